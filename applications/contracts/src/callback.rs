@@ -0,0 +1,297 @@
+//! `ContractModule`, routing the channel handshakes and packets of contract ports to the contracts
+//! bound to them.
+//!
+//! Contracts are called back by `Pallet::account_id` with the SCALE encoded arguments of the
+//! callback after its selector, so an ink! contract implements them as messages with these
+//! selectors, e.g. `#[ink(message, selector = 0x1BC00003)]` for `on_recv_packet`.
+use crate::{Config, ContractPorts, Event, OpeningPort, Pallet, LOG_TARGET};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use ibc::{
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::{ChannelError, PacketError},
+			handler::ModuleExtras,
+			msgs::acknowledgement::Acknowledgement,
+			packet::Packet,
+			timeout::TimeoutHeight,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleOutputBuilder},
+	},
+	signer::Signer,
+};
+use pallet_contracts::{CollectEvents, DebugInfo, Determinism};
+use scale_info::TypeInfo;
+use serde_json::json;
+use sp_runtime::traits::Zero;
+use sp_std::marker::PhantomData;
+
+/// Module id of `ContractModule`, which every port starting with `PORT_PREFIX` is routed to.
+pub const MODULE_ID: &str = "contracts";
+/// Prefix of the ports bound by contracts, followed by the name chosen by the contract.
+pub const PORT_PREFIX: &str = "contracts.";
+
+/// Callbacks into the contract bound to a port.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum Callback {
+	/// `on_chan_open(port_id, channel_id, version)`, when the channel version is agreed on, on
+	/// try or on ack. Reverting refuses the channel.
+	ChanOpen,
+	/// `on_chan_close(port_id, channel_id)`, when the counterparty closed the channel.
+	ChanClose,
+	/// `on_recv_packet(packet) -> Vec<u8>`, returning the acknowledgement. Reverting or
+	/// returning an empty acknowledgement writes an error acknowledgement.
+	RecvPacket,
+	/// `on_acknowledgement_packet(packet, acknowledgement)`
+	Acknowledgement,
+	/// `on_timeout_packet(packet)`
+	Timeout,
+}
+
+impl Callback {
+	/// Selector of the contract message.
+	pub fn selector(self) -> [u8; 4] {
+		match self {
+			Callback::ChanOpen => [0x1b, 0xc0, 0x00, 0x01],
+			Callback::ChanClose => [0x1b, 0xc0, 0x00, 0x02],
+			Callback::RecvPacket => [0x1b, 0xc0, 0x00, 0x03],
+			Callback::Acknowledgement => [0x1b, 0xc0, 0x00, 0x04],
+			Callback::Timeout => [0x1b, 0xc0, 0x00, 0x05],
+		}
+	}
+}
+
+/// A packet as passed to contracts, with ids as utf-8 bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct ContractPacket {
+	pub sequence: u64,
+	pub source_port: Vec<u8>,
+	pub source_channel: Vec<u8>,
+	pub destination_port: Vec<u8>,
+	pub destination_channel: Vec<u8>,
+	pub data: Vec<u8>,
+	/// Revision number and height after which the packet times out, if any
+	pub timeout_height: Option<(u64, u64)>,
+	/// Timestamp in nanoseconds after which the packet times out, zero if none
+	pub timeout_timestamp: u64,
+}
+
+impl From<&Packet> for ContractPacket {
+	fn from(packet: &Packet) -> Self {
+		Self {
+			sequence: packet.sequence.into(),
+			source_port: packet.port_on_a.as_str().as_bytes().to_vec(),
+			source_channel: packet.chan_on_a.as_str().as_bytes().to_vec(),
+			destination_port: packet.port_on_b.as_str().as_bytes().to_vec(),
+			destination_channel: packet.chan_on_b.as_str().as_bytes().to_vec(),
+			data: packet.data.clone(),
+			timeout_height: match packet.timeout_height_on_b {
+				TimeoutHeight::At(height) =>
+					Some((height.revision_number(), height.revision_height())),
+				TimeoutHeight::Never => None,
+			},
+			timeout_timestamp: packet.timeout_timestamp_on_b.nanoseconds(),
+		}
+	}
+}
+
+/// Error acknowledgement with `description`.
+pub fn error_ack(description: &str) -> Acknowledgement {
+	Acknowledgement::try_from(json!({ "error": description }).to_string().into_bytes())
+		.expect("a JSON acknowledgement is never empty")
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct ContractModule<T>(pub PhantomData<T>);
+
+impl<T: Config> ContractModule<T> {
+	/// Call the contract bound to `port_id` back with `input`, returning the output of the
+	/// message. A revert, a trap or running out of weight is reported by a `CallbackFailed`
+	/// event.
+	pub(crate) fn call_back(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		callback: Callback,
+		input: impl Encode,
+	) -> Result<Vec<u8>, String> {
+		let contract = <ContractPorts<T>>::get(port_id)
+			.ok_or_else(|| format!("port {} is not bound to a contract", port_id))?;
+		let mut data = callback.selector().to_vec();
+		input.encode_to(&mut data);
+
+		let result = pallet_contracts::Pallet::<T>::bare_call(
+			Pallet::<T>::account_id(),
+			contract.clone(),
+			Zero::zero(),
+			T::CallbackGasLimit::get(),
+			None,
+			data,
+			DebugInfo::Skip,
+			CollectEvents::Skip,
+			Determinism::Enforced,
+		)
+		.result;
+		match result {
+			Ok(output) if !output.did_revert() => Ok(output.data),
+			outcome => {
+				log::debug!(
+					target: LOG_TARGET,
+					"{:?} callback of {:?} failed: {:?}",
+					callback,
+					contract,
+					outcome
+				);
+				Pallet::<T>::deposit_event(Event::<T>::CallbackFailed {
+					contract,
+					port_id: port_id.clone(),
+					channel_id: channel_id.clone(),
+					callback,
+				});
+				Err(format!("{:?} callback failed", callback))
+			},
+		}
+	}
+
+	fn chan_open(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		version: &Version,
+	) -> Result<(), ChannelError> {
+		let input = (
+			port_id.as_str().as_bytes(),
+			channel_id.as_str().as_bytes(),
+			version.to_string().into_bytes(),
+		);
+		Self::call_back(port_id, channel_id, Callback::ChanOpen, input)
+			.map(|_| ())
+			.map_err(|description| ChannelError::AppModule { description })
+	}
+}
+
+impl<T: Config> Module for ContractModule<T> {
+	fn on_chan_open_init(
+		&mut self,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(ModuleExtras, Version), ChannelError> {
+		// only the contract bound to the port opens channels from it
+		if <OpeningPort<T>>::get().as_ref() != Some(port_id) {
+			return Err(ChannelError::AppModule {
+				description: format!("channels of {} are opened by its contract", port_id),
+			});
+		}
+		Ok((ModuleExtras::empty(), version.clone()))
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		counterparty_version: &Version,
+	) -> Result<(ModuleExtras, Version), ChannelError> {
+		Self::chan_open(port_id, channel_id, counterparty_version)?;
+		Ok((ModuleExtras::empty(), counterparty_version.clone()))
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<ModuleExtras, ChannelError> {
+		Self::chan_open(port_id, channel_id, counterparty_version)?;
+		Ok(ModuleExtras::empty())
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+	) -> Result<ModuleExtras, ChannelError> {
+		Ok(ModuleExtras::empty())
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+	) -> Result<ModuleExtras, ChannelError> {
+		Err(ChannelError::AppModule {
+			description: "contract channels cannot be closed".to_string(),
+		})
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<ModuleExtras, ChannelError> {
+		// the counterparty closed the channel whatever the contract answers
+		let input = (port_id.as_str().as_bytes(), channel_id.as_str().as_bytes());
+		let _ = Self::call_back(port_id, channel_id, Callback::ChanClose, input);
+		Ok(ModuleExtras::empty())
+	}
+
+	fn on_recv_packet(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		_relayer: &Signer,
+	) -> Acknowledgement {
+		let output = match Self::call_back(
+			&packet.port_on_b,
+			&packet.chan_on_b,
+			Callback::RecvPacket,
+			ContractPacket::from(packet),
+		) {
+			Ok(output) => output,
+			Err(description) => return error_ack(&description),
+		};
+		// ink! messages return a `Result<_, LangError>`
+		match <Result<Vec<u8>, u8>>::decode(&mut output.as_slice()) {
+			Ok(Ok(ack)) => Acknowledgement::try_from(ack)
+				.unwrap_or_else(|_| error_ack("empty acknowledgement")),
+			_ => error_ack("invalid acknowledgement"),
+		}
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &Acknowledgement,
+		_relayer: &Signer,
+	) -> Result<(), PacketError> {
+		// a failed callback is reported, the acknowledgement is still processed
+		let input = (ContractPacket::from(packet), acknowledgement.as_ref());
+		let _ =
+			Self::call_back(&packet.port_on_a, &packet.chan_on_a, Callback::Acknowledgement, input);
+		Ok(())
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		_relayer: &Signer,
+	) -> Result<(), PacketError> {
+		let input = ContractPacket::from(packet);
+		let _ = Self::call_back(&packet.port_on_a, &packet.chan_on_a, Callback::Timeout, input);
+		Ok(())
+	}
+}
@@ -0,0 +1,207 @@
+//! Ports, channels and packets of contracts.
+//!
+//! A contract binds `contracts.<name>` ports, then opens channels from them by dispatching a
+//! `MsgChannelOpenInit` to `Config::IbcRouter`, and sends packets over their channels. The rest
+//! of a handshake is driven by relayers, like for any other module.
+use crate::{
+	callback::PORT_PREFIX, contract_signer, Config, ContractPorts, Error, Event, OpeningPort,
+	Pallet, LOG_TARGET,
+};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec,
+	vec::Vec,
+};
+use core::str::FromStr;
+use frame_support::ensure;
+use ibc::{
+	core::{
+		ics04_channel::{
+			channel::Order,
+			context::{ChannelKeeper, ChannelReader},
+			handler::send_packet::send_packet,
+			packet::Packet,
+			timeout::TimeoutHeight,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+	},
+	events::IbcEvent,
+	handler::HandlerOutput,
+	timestamp::Timestamp,
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::channel::v1::{
+		Channel as RawChannel, Counterparty as RawCounterparty,
+		MsgChannelOpenInit as RawMsgChannelOpenInit, Order as RawOrder, State as RawState,
+	},
+};
+use pallet_ibc_utils::{context::ChannelContext, Router};
+use prost::Message;
+
+/// Type url of `MsgChannelOpenInit`.
+pub const MSG_CHANNEL_OPEN_INIT_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenInit";
+
+impl<T: Config> Pallet<T> {
+	/// The port `contracts.<name>`.
+	pub fn contract_port_id(name: &[u8]) -> Result<PortId, Error<T>> {
+		ensure!(!name.is_empty(), Error::<T>::InvalidPortName);
+		let name = core::str::from_utf8(name).map_err(|_| Error::<T>::InvalidPortName)?;
+		PortId::from_str(&format!("{}{}", PORT_PREFIX, name))
+			.map_err(|_| Error::<T>::InvalidPortName)
+	}
+
+	/// Bind the port `contracts.<name>` to `contract`, for good.
+	pub fn do_bind_port(contract: T::AccountId, name: &[u8]) -> Result<PortId, Error<T>> {
+		let port_id = Self::contract_port_id(name)?;
+		ensure!(!<ContractPorts<T>>::contains_key(&port_id), Error::<T>::PortAlreadyBound);
+
+		<ContractPorts<T>>::insert(&port_id, &contract);
+		Self::deposit_event(Event::PortBound { contract, port_id: port_id.clone() });
+		Ok(port_id)
+	}
+
+	/// The port `contracts.<name>`, checked to be bound to `contract`.
+	pub fn owned_port_id(contract: &T::AccountId, name: &[u8]) -> Result<PortId, Error<T>> {
+		let port_id = Self::contract_port_id(name)?;
+		ensure!(
+			<ContractPorts<T>>::get(&port_id).as_ref() == Some(contract),
+			Error::<T>::PortNotOwned
+		);
+		Ok(port_id)
+	}
+
+	/// Start opening a channel from the port `contracts.<name>` of `contract` to
+	/// `counterparty_port_id` over `connection_id`. Returns the id of the channel, in `Init`
+	/// state until relayers complete the handshake.
+	pub fn do_open_channel(
+		contract: T::AccountId,
+		name: &[u8],
+		connection_id: ConnectionId,
+		counterparty_port_id: PortId,
+		version: Version,
+		order: Order,
+	) -> Result<ChannelId, Error<T>> {
+		let port_id = Self::owned_port_id(&contract, name)?;
+		let ordering = match order {
+			Order::Unordered => RawOrder::Unordered,
+			Order::Ordered => RawOrder::Ordered,
+			_ => return Err(Error::<T>::OpenChannelFailed),
+		};
+
+		let ctx = ChannelContext::<T::IbcContext>::default();
+		let channel_id = ChannelReader::channel_counter(&ctx)
+			.map(ChannelId::new)
+			.map_err(|_| Error::<T>::OpenChannelFailed)?;
+		let msg = RawMsgChannelOpenInit {
+			port_id: port_id.to_string(),
+			channel: Some(RawChannel {
+				state: RawState::Init as i32,
+				ordering: ordering as i32,
+				counterparty: Some(RawCounterparty {
+					port_id: counterparty_port_id.to_string(),
+					channel_id: String::new(),
+				}),
+				connection_hops: vec![connection_id.to_string()],
+				version: version.to_string(),
+			}),
+			signer: contract_signer::<T>(&contract),
+		};
+		let message = Any {
+			type_url: MSG_CHANNEL_OPEN_INIT_TYPE_URL.to_string(),
+			value: msg.encode_to_vec(),
+		};
+
+		<OpeningPort<T>>::put(&port_id);
+		let dispatched = T::IbcRouter::dispatch(vec![message]);
+		<OpeningPort<T>>::kill();
+		dispatched.map_err(|error| {
+			log::error!(target: LOG_TARGET, "open_channel Error : {:?} ", error);
+			Error::<T>::OpenChannelFailed
+		})?;
+		// the failed messages of a delivery are only reported by events
+		ChannelReader::channel_end(&ctx, &port_id, &channel_id)
+			.map_err(|_| Error::<T>::OpenChannelFailed)?;
+
+		Self::deposit_event(Event::ChannelOpenInit {
+			contract,
+			port_id,
+			channel_id: channel_id.clone(),
+		});
+		Ok(channel_id)
+	}
+
+	/// Send `data` from the port `contracts.<name>` of `contract` over `channel_id`, timing out
+	/// at `timeout_height` or at `timeout_timestamp` nanoseconds on the counterparty, zero
+	/// meaning no timeout timestamp. Returns the packet sequence.
+	pub fn do_send_packet(
+		contract: T::AccountId,
+		name: &[u8],
+		channel_id: ChannelId,
+		data: Vec<u8>,
+		timeout_height: TimeoutHeight,
+		timeout_timestamp: u64,
+	) -> Result<u64, Error<T>> {
+		let port_on_a = Self::owned_port_id(&contract, name)?;
+		ensure!(
+			timeout_height != TimeoutHeight::Never || timeout_timestamp != 0,
+			Error::<T>::InvalidTimeout
+		);
+		let timeout_timestamp_on_b = Timestamp::from_nanoseconds(timeout_timestamp)
+			.map_err(|_| Error::<T>::InvalidTimeout)?;
+
+		let mut ctx = ChannelContext::<T::IbcContext>::default();
+		let channel_end = ChannelReader::channel_end(&ctx, &port_on_a, &channel_id)
+			.map_err(|_| Error::<T>::ChannelNotFound)?;
+		let port_on_b = channel_end.counterparty().port_id().clone();
+		let chan_on_b = channel_end
+			.counterparty()
+			.channel_id()
+			.cloned()
+			.ok_or(Error::<T>::ChannelNotFound)?;
+
+		let sequence = ChannelReader::get_next_sequence_send(&ctx, &port_on_a, &channel_id)
+			.map_err(|_| Error::<T>::SendPacketFailed)?;
+		let packet = Packet {
+			sequence,
+			port_on_a: port_on_a.clone(),
+			chan_on_a: channel_id.clone(),
+			port_on_b,
+			chan_on_b,
+			data,
+			timeout_height_on_b: timeout_height,
+			timeout_timestamp_on_b,
+		};
+		let HandlerOutput { events, .. } = send_packet(&ctx, packet.clone()).map_err(|error| {
+			log::error!(target: LOG_TARGET, "send_packet Error : {:?} ", error);
+			Error::<T>::SendPacketFailed
+		})?;
+
+		let commitment = ChannelReader::packet_commitment(
+			&ctx,
+			&packet.data,
+			&packet.timeout_height_on_b,
+			&packet.timeout_timestamp_on_b,
+		);
+		ctx.store_packet_commitment(port_on_a.clone(), channel_id.clone(), sequence, commitment)
+			.map_err(|_| Error::<T>::SendPacketFailed)?;
+		ctx.store_next_sequence_send(port_on_a.clone(), channel_id.clone(), sequence.increment())
+			.map_err(|_| Error::<T>::SendPacketFailed)?;
+
+		for event in events {
+			if let IbcEvent::SendPacket(send_packet) = event {
+				Self::deposit_event(Event::SendPacket(send_packet));
+			}
+		}
+		let sequence = u64::from(sequence);
+		Self::deposit_event(Event::PacketSent {
+			contract,
+			port_id: port_on_a,
+			channel_id,
+			sequence,
+		});
+		Ok(sequence)
+	}
+}
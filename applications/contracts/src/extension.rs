@@ -0,0 +1,188 @@
+//! `IbcExtension`, the chain extension through which contracts use IBC.
+//!
+//! Every function reads its SCALE encoded input from the input buffer and returns a status code,
+//! zero on success and `Pallet::status_code` of the error otherwise. On success, the functions
+//! with an output write it SCALE encoded to the output buffer. Ids are passed as utf-8 bytes.
+use crate::{Config, Error, Pallet, LOG_TARGET};
+use alloc::{string::String, vec::Vec};
+use codec::{Decode, Encode};
+use core::str::FromStr;
+use frame_support::{pallet_prelude::DispatchError, traits::Get};
+use ibc::{
+	core::ics04_channel::{channel::Order, timeout::TimeoutHeight, Version},
+	Height,
+};
+use pallet_contracts::chain_extension::{
+	ChainExtension, Environment, Ext, InitState, RegisteredChainExtension,
+	Result as ExtensionResult, RetVal,
+};
+use scale_info::TypeInfo;
+
+/// Id of the extension, in the high half of the function ids called by contracts.
+pub const EXTENSION_ID: u16 = 0x1bc0;
+
+/// Bind the port `contracts.<name>` to the calling contract.
+pub const BIND_PORT: u16 = 1;
+/// Start opening a channel from a port of the calling contract, returning the channel id.
+pub const OPEN_CHANNEL: u16 = 2;
+/// Send a packet from a port of the calling contract, returning the packet sequence.
+pub const SEND_PACKET: u16 = 3;
+/// Send an ICS-20 transfer out of the account of the calling contract.
+pub const TRANSFER: u16 = 4;
+
+/// Input of `BIND_PORT`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct BindPortInput {
+	pub port_name: Vec<u8>,
+}
+
+/// Input of `OPEN_CHANNEL`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct OpenChannelInput {
+	pub port_name: Vec<u8>,
+	pub connection_id: Vec<u8>,
+	pub counterparty_port_id: Vec<u8>,
+	pub version: Vec<u8>,
+	pub ordered: bool,
+}
+
+/// Input of `SEND_PACKET`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct SendPacketInput {
+	pub port_name: Vec<u8>,
+	pub channel_id: Vec<u8>,
+	pub data: Vec<u8>,
+	/// Revision number and height after which the packet times out, if any
+	pub timeout_height: Option<(u64, u64)>,
+	/// Timestamp in nanoseconds after which the packet times out, zero if none
+	pub timeout_timestamp: u64,
+}
+
+/// Input of `TRANSFER`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct TransferInput {
+	pub channel_id: Vec<u8>,
+	pub denom: Vec<u8>,
+	pub amount: u128,
+	pub receiver: Vec<u8>,
+	/// Revision number and height after which the transfer times out, if any
+	pub timeout_height: Option<(u64, u64)>,
+	/// Timestamp in nanoseconds after which the transfer times out, zero if none
+	pub timeout_timestamp: u64,
+	pub memo: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct IbcExtension;
+
+impl<T: Config> ChainExtension<T> for IbcExtension {
+	fn call<E: Ext<T = T>>(&mut self, env: Environment<E, InitState>) -> ExtensionResult<RetVal> {
+		let func_id = env.func_id();
+		let mut env = env.buf_in_buf_out();
+		let contract = env.ext().address().clone();
+		let db_weight = <T as frame_system::Config>::DbWeight::get();
+
+		let outcome = match func_id {
+			BIND_PORT => {
+				env.charge_weight(db_weight.reads_writes(1, 1))?;
+				let input: BindPortInput = env.read_as_unbounded(env.in_len())?;
+				Pallet::<T>::do_bind_port(contract, &input.port_name).map(|_| Vec::new())
+			},
+			OPEN_CHANNEL => {
+				// the handshake reads the connection and client, and writes the channel
+				env.charge_weight(db_weight.reads_writes(6, 6))?;
+				let input: OpenChannelInput = env.read_as_unbounded(env.in_len())?;
+				open_channel::<T>(contract, input).map(|channel_id| channel_id.encode())
+			},
+			SEND_PACKET => {
+				env.charge_weight(db_weight.reads_writes(6, 2))?;
+				let input: SendPacketInput = env.read_as_unbounded(env.in_len())?;
+				send_packet::<T>(contract, input).map(|sequence| sequence.encode())
+			},
+			TRANSFER => {
+				// the transfer moves the tokens then sends a packet
+				env.charge_weight(db_weight.reads_writes(10, 6))?;
+				let input: TransferInput = env.read_as_unbounded(env.in_len())?;
+				transfer::<T>(contract, input).map(|_| Vec::new())
+			},
+			_ => {
+				log::error!(target: LOG_TARGET, "unknown ibc extension function {}", func_id);
+				return Err(DispatchError::Other("unknown ibc extension function"));
+			},
+		};
+
+		match outcome {
+			Ok(output) => {
+				if !output.is_empty() {
+					env.write(&output, false, None)?;
+				}
+				Ok(RetVal::Converging(0))
+			},
+			Err(error) => Ok(RetVal::Converging(Pallet::<T>::status_code(error))),
+		}
+	}
+}
+
+impl<T: Config> RegisteredChainExtension<T> for IbcExtension {
+	const ID: u16 = EXTENSION_ID;
+}
+
+fn parse<T: Config, I: FromStr>(bytes: &[u8], error: Error<T>) -> Result<I, Error<T>> {
+	core::str::from_utf8(bytes).ok().and_then(|id| id.parse().ok()).ok_or(error)
+}
+
+fn utf8<T: Config>(bytes: Vec<u8>, error: Error<T>) -> Result<String, Error<T>> {
+	String::from_utf8(bytes).map_err(|_| error)
+}
+
+fn timeout_height<T: Config>(height: Option<(u64, u64)>) -> Result<TimeoutHeight, Error<T>> {
+	match height {
+		Some((revision_number, revision_height)) => Height::new(revision_number, revision_height)
+			.map(TimeoutHeight::At)
+			.map_err(|_| Error::<T>::InvalidTimeout),
+		None => Ok(TimeoutHeight::Never),
+	}
+}
+
+fn open_channel<T: Config>(
+	contract: T::AccountId,
+	input: OpenChannelInput,
+) -> Result<Vec<u8>, Error<T>> {
+	let connection_id = parse(&input.connection_id, Error::<T>::InvalidCounterparty)?;
+	let counterparty_port_id = parse(&input.counterparty_port_id, Error::<T>::InvalidCounterparty)?;
+	let version = Version::new(utf8(input.version, Error::<T>::InvalidCounterparty)?);
+	let order = if input.ordered { Order::Ordered } else { Order::Unordered };
+	Pallet::<T>::do_open_channel(
+		contract,
+		&input.port_name,
+		connection_id,
+		counterparty_port_id,
+		version,
+		order,
+	)
+	.map(|channel_id| channel_id.as_str().as_bytes().to_vec())
+}
+
+fn send_packet<T: Config>(contract: T::AccountId, input: SendPacketInput) -> Result<u64, Error<T>> {
+	Pallet::<T>::do_send_packet(
+		contract,
+		&input.port_name,
+		parse(&input.channel_id, Error::<T>::ChannelNotFound)?,
+		input.data,
+		timeout_height(input.timeout_height)?,
+		input.timeout_timestamp,
+	)
+}
+
+fn transfer<T: Config>(contract: T::AccountId, input: TransferInput) -> Result<(), Error<T>> {
+	Pallet::<T>::do_transfer(
+		contract,
+		parse(&input.channel_id, Error::<T>::ChannelNotFound)?,
+		utf8(input.denom, Error::<T>::TransferFailed)?,
+		input.amount,
+		utf8(input.receiver, Error::<T>::TransferFailed)?,
+		timeout_height(input.timeout_height)?,
+		input.timeout_timestamp,
+		utf8(input.memo, Error::<T>::TransferFailed)?,
+	)
+}
@@ -0,0 +1,157 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! IBC for the contracts of `pallet-contracts`, through a chain extension.
+//!
+//! A contract binds ports named `contracts.<name>`, opens channels from them, sends packets over
+//! them and transfers tokens with ICS-20, by calling `IbcExtension`. The runtime registers
+//! `ContractModule` under the module id `contracts`, which receives the handshakes, packets,
+//! acknowledgements and timeouts of every contract port and calls the contract bound to the port
+//! back with a weight limit of `Config::CallbackGasLimit`.
+
+extern crate alloc;
+
+pub use pallet::*;
+
+pub mod callback;
+pub mod channel;
+pub mod extension;
+pub mod transfer;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+use alloc::{format, string::String};
+use callback::Callback;
+use codec::Encode;
+use frame_support::{pallet_prelude::*, PalletId};
+use ibc::core::{
+	ics04_channel::events::SendPacket,
+	ics24_host::identifier::{ChannelId, PortId},
+};
+use sp_runtime::traits::AccountIdConversion;
+use sp_std::fmt::Debug;
+
+pub const LOG_TARGET: &str = "runtime::pallet-ibc-contracts";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config:
+		frame_system::Config
+		+ pallet_timestamp::Config
+		+ pallet_contracts::Config
+		+ Sync
+		+ Send
+		+ Debug
+	{
+		/// The aggregated event type of the runtime.
+		type RuntimeEvent: Parameter
+			+ Member
+			+ From<Event<Self>>
+			+ Debug
+			+ IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		type IbcContext: pallet_ibc_utils::traits::ChannelKeeperInterface
+			+ pallet_ibc_utils::traits::ChannelReaderInterface;
+
+		/// Dispatches the channel opening messages of contracts, i.e. `pallet_ibc`
+		type IbcRouter: pallet_ibc_utils::Router;
+
+		/// Dispatches the ICS-20 transfers of contracts, i.e. `pallet_ics20_transfer`
+		type TransferRouter: pallet_ibc_utils::Router;
+
+		/// Id of the account calling contracts back, which pays the storage deposits of the
+		/// callbacks
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Weight limit of every callback into a contract
+		#[pallet::constant]
+		type CallbackGasLimit: Get<Weight>;
+	}
+
+	#[pallet::storage]
+	/// key: port id, `contracts.<name>`
+	/// value: contract bound to the port
+	pub type ContractPorts<T: Config> = StorageMap<_, Blake2_128Concat, PortId, T::AccountId>;
+
+	#[pallet::storage]
+	/// Port of the channel a contract is opening, only set while its `MsgChannelOpenInit` is
+	/// handled.
+	pub type OpeningPort<T: Config> = StorageValue<_, PortId>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Send packet event
+		SendPacket(SendPacket),
+		/// A contract bound a port
+		PortBound { contract: T::AccountId, port_id: PortId },
+		/// A contract started opening a channel on one of its ports
+		ChannelOpenInit { contract: T::AccountId, port_id: PortId, channel_id: ChannelId },
+		/// A contract sent a packet
+		PacketSent { contract: T::AccountId, port_id: PortId, channel_id: ChannelId, sequence: u64 },
+		/// A contract sent an ICS-20 transfer
+		TransferSent { contract: T::AccountId, channel_id: ChannelId },
+		/// A contract reverted or ran out of weight in a callback
+		CallbackFailed {
+			contract: T::AccountId,
+			port_id: PortId,
+			channel_id: ChannelId,
+			callback: Callback,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The port name does not make a valid port id
+		InvalidPortName,
+		/// The port is already bound to a contract
+		PortAlreadyBound,
+		/// The port is not bound to the calling contract
+		PortNotOwned,
+		/// The connection or counterparty port id is invalid
+		InvalidCounterparty,
+		/// Opening the channel failed
+		OpenChannelFailed,
+		/// The channel does not exist on the port
+		ChannelNotFound,
+		/// A packet needs a timeout height, a timeout timestamp or both
+		InvalidTimeout,
+		/// Sending the packet failed
+		SendPacketFailed,
+		/// The transfer amount is zero
+		InvalidAmount,
+		/// Sending the ICS-20 transfer failed
+		TransferFailed,
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account calling contracts back.
+	pub fn account_id() -> T::AccountId {
+		T::PalletId::get().into_account_truncating()
+	}
+
+	/// Status code returned by the chain extension for `error`: the index of the error plus one,
+	/// zero being success.
+	pub fn status_code(error: Error<T>) -> u32 {
+		error.encode().first().map_or(u32::MAX, |index| u32::from(*index) + 1)
+	}
+}
+
+/// Signer of the messages of `contract`, the hex encoding of its account id as expected by the
+/// transfer pallet.
+pub fn contract_signer<T: Config>(contract: &T::AccountId) -> String {
+	format!("0x{}", hex::encode(contract.encode()))
+}
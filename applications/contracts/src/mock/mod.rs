@@ -0,0 +1,299 @@
+use super::*;
+use crate as pallet_ibc_contracts;
+pub use frame_support::{
+	construct_runtime, parameter_types,
+	traits::{ConstBool, ConstU128, ConstU16, ConstU32, ConstU64, Nothing},
+	weights::Weight,
+};
+use frame_system as system;
+use ibc_proto::google::protobuf::Any;
+use pallet_contracts::{Code, CollectEvents, DebugInfo, Determinism};
+use pallet_ibc_utils::module::Router;
+use sp_runtime::{
+	generic,
+	traits::{AccountIdLookup, BlakeTwo256, Convert, IdentifyAccount, Verify},
+	BuildStorage, MultiSignature, Perbill,
+};
+
+pub type Signature = MultiSignature;
+pub(crate) type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Timestamp: pallet_timestamp,
+		Balances: pallet_balances,
+		RandomnessCollectiveFlip: pallet_insecure_randomness_collective_flip,
+		Contracts: pallet_contracts,
+		Ibc: pallet_ibc,
+		IbcContracts: pallet_ibc_contracts,
+	}
+);
+
+/// A hash of some data used by the chain.
+pub type Hash = sp_core::H256;
+
+/// Index of a transaction in the chain.
+pub type Index = u32;
+/// An index to a block.
+pub type BlockNumber = u32;
+
+impl frame_system::Config for Test {
+	/// The basic call filter to use in dispatchable.
+	type BaseCallFilter = frame_support::traits::Everything;
+	/// Block & extrinsics weights: base values and limits.
+	type BlockWeights = ();
+	/// The maximum length of a block (in bytes).
+	type BlockLength = ();
+	/// The identifier used to distinguish between accounts.
+	type AccountId = AccountId;
+	/// The aggregated dispatch type that is available for extrinsics.
+	type RuntimeCall = RuntimeCall;
+	/// The lookup mechanism to get account ID from whatever is passed in dispatchers.
+	type Lookup = AccountIdLookup<AccountId, ()>;
+	/// The index type for storing how many extrinsics an account has signed.
+	type Index = Index;
+	/// The index type for blocks.
+	type BlockNumber = BlockNumber;
+	/// The type for hashing blocks and tries.
+	type Hash = Hash;
+	/// The hashing algorithm used.
+	type Hashing = BlakeTwo256;
+	/// The header type.
+	type Header = generic::Header<BlockNumber, BlakeTwo256>;
+	/// The ubiquitous event type.
+	type RuntimeEvent = RuntimeEvent;
+	/// The ubiquitous origin type.
+	type RuntimeOrigin = RuntimeOrigin;
+	/// Maximum number of block number to block hash mappings to keep (oldest pruned first).
+	type BlockHashCount = ();
+	/// The weight of database operations that the runtime can invoke.
+	type DbWeight = ();
+	/// Version of the runtime.
+	type Version = ();
+	/// Converts a module to the index of the module in `construct_runtime!`.
+	///
+	/// This type is being generated by `construct_runtime!`.
+	type PalletInfo = PalletInfo;
+	/// What to do if a new account is created.
+	type OnNewAccount = ();
+	/// What to do if an account is fully reaped from the system.
+	type OnKilledAccount = ();
+	/// The data to be stored in an account.
+	type AccountData = pallet_balances::AccountData<Balance>;
+	/// Weight information for the extrinsics of this pallet.
+	type SystemWeightInfo = ();
+	/// This is used as an identifier of the chain. 42 is the generic substrate prefix.
+	type SS58Prefix = ConstU16<42>;
+	/// The set code logic, just the default since we're not a parachain.
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+pub type Balance = u128;
+/// Type used for expressing timestamp.
+pub type Moment = u64;
+
+parameter_types! {
+	pub const MinimumPeriod: Moment = 3000;
+}
+
+impl pallet_timestamp::Config for Test {
+	/// A timestamp: milliseconds since the unix epoch.
+	type Moment = Moment;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	/// The type for recording an account's balance.
+	type Balance = Balance;
+	/// The ubiquitous event type.
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = frame_system::Pallet<Test>;
+	type WeightInfo = ();
+	type FreezeIdentifier = ();
+	type MaxFreezes = ();
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type RuntimeFreezeReason = RuntimeFreezeReason;
+	type MaxHolds = ConstU32<1>;
+}
+
+impl pallet_insecure_randomness_collective_flip::Config for Test {}
+
+parameter_types! {
+	pub MySchedule: pallet_contracts::Schedule<Test> = Default::default();
+	pub const DepositPerByte: Balance = 1;
+	pub const DepositPerItem: Balance = 2;
+	pub const DefaultDepositLimit: Balance = 10_000_000;
+	pub CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(0);
+}
+
+impl Convert<Weight, Balance> for Test {
+	fn convert(weight: Weight) -> Balance {
+		weight.ref_time().into()
+	}
+}
+
+impl pallet_contracts::Config for Test {
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type CallFilter = Nothing;
+	type CallStack = [pallet_contracts::Frame<Self>; 5];
+	type WeightPrice = Self;
+	type WeightInfo = ();
+	type ChainExtension = extension::IbcExtension;
+	type Schedule = MySchedule;
+	type DepositPerByte = DepositPerByte;
+	type DepositPerItem = DepositPerItem;
+	type DefaultDepositLimit = DefaultDepositLimit;
+	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+	type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+	type MaxStorageKeyLen = ConstU32<128>;
+	type UnsafeUnstableInterface = ConstBool<false>;
+	type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type Migrations = ();
+	type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
+	type MaxDelegateDependencies = ConstU32<32>;
+	type Debug = ();
+	type Environment = ();
+	type Xcm = ();
+}
+
+pub struct IbcModule;
+
+impl pallet_ibc_utils::module::AddModule for IbcModule {
+	fn add_module(router: Router) -> Router {
+		match router.add_route(
+			callback::MODULE_ID.parse().expect("never failed"),
+			callback::ContractModule::<Test>(std::marker::PhantomData::<Test>),
+		) {
+			Ok(ret) => ret,
+			Err(e) => panic!("add module failed by {}", e),
+		}
+	}
+}
+
+/// Records the transfers of contracts for the tests.
+pub struct RecordTransfer;
+impl pallet_ibc_utils::Router for RecordTransfer {
+	fn handles(type_url: &str) -> bool {
+		type_url == transfer::MSG_TRANSFER_TYPE_URL
+	}
+
+	fn dispatch(messages: Vec<Any>) -> frame_support::dispatch::DispatchResult {
+		for message in messages {
+			sp_io::storage::set(b"transfer", &message.value);
+		}
+		Ok(())
+	}
+}
+
+parameter_types! {
+	pub const IbcContractsPalletId: PalletId = PalletId(*b"ibc/ctrt");
+	pub CallbackGasLimit: Weight = Weight::from_parts(10_000_000_000, 1024 * 1024);
+}
+
+impl pallet_ibc_contracts::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type IbcContext = pallet_ibc::context::Context<Test>;
+	type IbcRouter = Ibc;
+	type TransferRouter = RecordTransfer;
+	type PalletId = IbcContractsPalletId;
+	type CallbackGasLimit = CallbackGasLimit;
+}
+
+parameter_types! {
+	pub const ExpectedBlockTime: u64 = 6;
+	pub const ChainVersion: u64 = 0;
+}
+
+impl pallet_ibc::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type TimeProvider = pallet_timestamp::Pallet<Test>;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	const IBC_COMMITMENT_PREFIX: &'static [u8] = b"Ibc";
+	type ChainVersion = ChainVersion;
+	type IbcModule = IbcModule;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type OnMisbehaviour = ();
+	type UpgradeModule = ();
+	type MaxPrunedPerBlock = ConstU32<10>;
+	type WasmClientGasLimit = ConstU64<10_000_000>;
+	type MaxWasmCodeSize = ConstU32<{ 3 * 1024 * 1024 }>;
+	type WeightInfo = ();
+}
+
+/// Account deploying the contracts of the tests.
+pub const ALICE: AccountId = AccountId::new([1u8; 32]);
+/// Weight limit of the contract calls of the tests.
+pub const GAS_LIMIT: Weight = Weight::from_parts(100_000_000_000, 3 * 1024 * 1024);
+
+/// Deploy the contract of `wat` from `ALICE`.
+pub fn instantiate(wat: &str) -> AccountId {
+	let code = wat::parse_str(wat).expect("valid wat");
+	Contracts::bare_instantiate(
+		ALICE,
+		0,
+		GAS_LIMIT,
+		None,
+		Code::Upload(code),
+		vec![],
+		vec![],
+		DebugInfo::Skip,
+		CollectEvents::Skip,
+	)
+	.result
+	.expect("the contract deploys")
+	.account_id
+}
+
+/// Call the contract at `contract` from `ALICE`, returning its output.
+pub fn call(contract: &AccountId) -> Vec<u8> {
+	Contracts::bare_call(
+		ALICE,
+		contract.clone(),
+		0,
+		GAS_LIMIT,
+		None,
+		vec![],
+		DebugInfo::Skip,
+		CollectEvents::Skip,
+		Determinism::Enforced,
+	)
+	.result
+	.expect("the contract runs")
+	.data
+}
+
+#[allow(dead_code)]
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000_000_000_000), (IbcContracts::account_id(), 1_000_000_000_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		callback::{Callback, ContractModule},
+		mock::{instantiate, new_test_ext, RuntimeEvent, System, Test},
+		ContractPorts, Event,
+	};
+	use core::{marker::PhantomData, str::FromStr};
+	use ibc::{
+		core::{
+			ics04_channel::{packet::Packet, timeout::TimeoutHeight},
+			ics24_host::identifier::{ChannelId, PortId},
+			ics26_routing::context::{Module, ModuleOutputBuilder},
+		},
+		signer::Signer,
+		timestamp::Timestamp,
+	};
+
+	/// Answers every call with the ink! encoding of `Ok(b"ack".to_vec())`.
+	const ACK_WAT: &str = r#"
+(module
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+	(data (i32.const 0) "\00\0cack")
+	(func (export "deploy"))
+	(func (export "call")
+		(call $seal_return (i32.const 0) (i32.const 0) (i32.const 5))
+	)
+)
+"#;
+
+	/// Reverts every call.
+	const REVERT_WAT: &str = r#"
+(module
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy"))
+	(func (export "call")
+		(call $seal_return (i32.const 1) (i32.const 0) (i32.const 0))
+	)
+)
+"#;
+
+	fn port_id() -> PortId {
+		PortId::from_str("contracts.demo").unwrap()
+	}
+
+	fn received_packet() -> Packet {
+		Packet {
+			sequence: 1u64.into(),
+			port_on_a: PortId::from_str("contracts.other").unwrap(),
+			chan_on_a: ChannelId::new(3),
+			port_on_b: port_id(),
+			chan_on_b: ChannelId::new(0),
+			data: b"ping".to_vec(),
+			timeout_height_on_b: TimeoutHeight::Never,
+			timeout_timestamp_on_b: Timestamp::from_nanoseconds(1).unwrap(),
+		}
+	}
+
+	fn relayer() -> Signer {
+		Signer::from_str("relayer").unwrap()
+	}
+
+	fn callback_failed() -> bool {
+		System::events().into_iter().any(|record| {
+			matches!(
+				record.event,
+				RuntimeEvent::IbcContracts(Event::CallbackFailed {
+					callback: Callback::RecvPacket,
+					..
+				})
+			)
+		})
+	}
+
+	#[test]
+	fn recv_packet_acknowledged_by_the_contract() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(ACK_WAT);
+			ContractPorts::<Test>::insert(port_id(), contract);
+
+			let ack = ContractModule::<Test>(PhantomData).on_recv_packet(
+				&mut ModuleOutputBuilder::new(),
+				&received_packet(),
+				&relayer(),
+			);
+			assert_eq!(ack.as_ref(), b"ack");
+			assert!(!callback_failed());
+		})
+	}
+
+	#[test]
+	fn reverted_recv_packet_writes_an_error_ack() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(REVERT_WAT);
+			ContractPorts::<Test>::insert(port_id(), contract);
+
+			let ack = ContractModule::<Test>(PhantomData).on_recv_packet(
+				&mut ModuleOutputBuilder::new(),
+				&received_packet(),
+				&relayer(),
+			);
+			assert!(ack.as_ref().starts_with(br#"{"error":"#));
+			assert!(callback_failed());
+		})
+	}
+
+	#[test]
+	fn packet_to_an_unbound_port_is_refused() {
+		new_test_ext().execute_with(|| {
+			let mut module = ContractModule::<Test>(PhantomData);
+			let ack = module.on_recv_packet(
+				&mut ModuleOutputBuilder::new(),
+				&received_packet(),
+				&relayer(),
+			);
+			assert!(ack.as_ref().starts_with(br#"{"error":"#));
+
+			// a failed timeout callback does not block the timeout
+			assert!(module
+				.on_timeout_packet(&mut ModuleOutputBuilder::new(), &received_packet(), &relayer())
+				.is_ok());
+		})
+	}
+}
@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		callback::ContractModule,
+		mock::{new_test_ext, AccountId, RuntimeEvent, System, Test, Timestamp},
+		ContractPorts, Error, Event, OpeningPort, Pallet,
+	};
+	use core::{marker::PhantomData, str::FromStr};
+	use frame_support::traits::BuildGenesisConfig;
+	use ibc::{
+		core::{
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				packet::Sequence,
+				timeout::TimeoutHeight,
+				Version,
+			},
+			ics24_host::identifier::{ChannelId, PortId},
+			ics26_routing::context::Module,
+		},
+		Height,
+	};
+	use pallet_ibc::{context::Context, localhost_client::localhost_connection_id};
+	use pallet_ibc_utils::traits::{ChannelKeeperInterface, ChannelReaderInterface};
+
+	fn contract() -> AccountId {
+		AccountId::new([7u8; 32])
+	}
+
+	fn other_contract() -> AccountId {
+		AccountId::new([8u8; 32])
+	}
+
+	fn port_id() -> PortId {
+		PortId::from_str("contracts.demo").unwrap()
+	}
+
+	fn version() -> Version {
+		Version::new("demo-1".to_string())
+	}
+
+	/// Genesis with the localhost connection, at block 5.
+	fn build_genesis() {
+		pallet_ibc::GenesisConfig::<Test>::default().build();
+		System::set_block_number(5);
+		Timestamp::set_timestamp(6_000);
+	}
+
+	fn contract_events() -> Vec<Event<Test>> {
+		System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::IbcContracts(event) => Some(event),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn bind_port_once() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(Pallet::<Test>::do_bind_port(contract(), b"demo"), Ok(port_id()));
+			assert_eq!(ContractPorts::<Test>::get(port_id()), Some(contract()));
+			assert_eq!(
+				Pallet::<Test>::do_bind_port(other_contract(), b"demo"),
+				Err(Error::<Test>::PortAlreadyBound)
+			);
+			assert_eq!(
+				Pallet::<Test>::do_bind_port(contract(), b""),
+				Err(Error::<Test>::InvalidPortName)
+			);
+			assert_eq!(
+				Pallet::<Test>::do_bind_port(contract(), b"de/mo"),
+				Err(Error::<Test>::InvalidPortName)
+			);
+			assert!(contract_events()
+				.contains(&Event::PortBound { contract: contract(), port_id: port_id() }));
+		})
+	}
+
+	#[test]
+	fn open_channel_from_a_bound_port_only() {
+		new_test_ext().execute_with(|| {
+			build_genesis();
+			Pallet::<Test>::do_bind_port(contract(), b"demo").unwrap();
+			let open_channel = |contract| {
+				Pallet::<Test>::do_open_channel(
+					contract,
+					b"demo",
+					localhost_connection_id(),
+					port_id(),
+					version(),
+					Order::Unordered,
+				)
+			};
+			assert_eq!(open_channel(other_contract()), Err(Error::<Test>::PortNotOwned));
+
+			assert_eq!(open_channel(contract()), Ok(ChannelId::new(0)));
+			let channel_end = <Context<Test> as ChannelReaderInterface>::channel_end(
+				&port_id(),
+				&ChannelId::new(0),
+			)
+			.unwrap();
+			assert_eq!(channel_end.state(), &State::Init);
+			assert_eq!(channel_end.version(), &version());
+			assert_eq!(OpeningPort::<Test>::get(), None);
+
+			// the handshake of a contract port is not started by anyone else
+			assert!(ContractModule::<Test>(PhantomData)
+				.on_chan_open_init(
+					Order::Unordered,
+					&[localhost_connection_id()],
+					&port_id(),
+					&ChannelId::new(1),
+					&Counterparty::new(port_id(), None),
+					&version(),
+				)
+				.is_err());
+		})
+	}
+
+	#[test]
+	fn send_packet_checks_the_port_timeout_and_channel() {
+		new_test_ext().execute_with(|| {
+			let send = |contract, timeout_height, timeout_timestamp| {
+				Pallet::<Test>::do_send_packet(
+					contract,
+					b"demo",
+					ChannelId::new(0),
+					b"ping".to_vec(),
+					timeout_height,
+					timeout_timestamp,
+				)
+			};
+			assert_eq!(send(contract(), TimeoutHeight::Never, 1), Err(Error::<Test>::PortNotOwned));
+
+			Pallet::<Test>::do_bind_port(contract(), b"demo").unwrap();
+			assert_eq!(
+				send(other_contract(), TimeoutHeight::Never, 1),
+				Err(Error::<Test>::PortNotOwned)
+			);
+			assert_eq!(
+				send(contract(), TimeoutHeight::Never, 0),
+				Err(Error::<Test>::InvalidTimeout)
+			);
+			assert_eq!(
+				send(contract(), TimeoutHeight::Never, 1),
+				Err(Error::<Test>::ChannelNotFound)
+			);
+		})
+	}
+
+	#[test]
+	fn send_packet_commits_the_packet() {
+		new_test_ext().execute_with(|| {
+			build_genesis();
+			Pallet::<Test>::do_bind_port(contract(), b"demo").unwrap();
+			// a channel of the port to itself over the localhost connection
+			<Context<Test> as ChannelKeeperInterface>::store_channel(
+				port_id(),
+				ChannelId::new(0),
+				ChannelEnd::new(
+					State::Open,
+					Order::Unordered,
+					Counterparty::new(port_id(), Some(ChannelId::new(0))),
+					vec![localhost_connection_id()],
+					version(),
+				),
+			)
+			.unwrap();
+			<Context<Test> as ChannelKeeperInterface>::store_next_sequence_send(
+				port_id(),
+				ChannelId::new(0),
+				Sequence::from(1),
+			)
+			.unwrap();
+
+			assert_eq!(
+				Pallet::<Test>::do_send_packet(
+					contract(),
+					b"demo",
+					ChannelId::new(0),
+					b"ping".to_vec(),
+					TimeoutHeight::At(Height::new(0, 100).unwrap()),
+					0,
+				),
+				Ok(1)
+			);
+
+			let mut hash_input = 0u64.to_be_bytes().to_vec();
+			hash_input.extend(0u64.to_be_bytes());
+			hash_input.extend(100u64.to_be_bytes());
+			hash_input.extend(sp_io::hashing::sha2_256(b"ping"));
+			let commitment = <Context<Test> as ChannelReaderInterface>::get_packet_commitment(
+				&port_id(),
+				&ChannelId::new(0),
+				&Sequence::from(1),
+			)
+			.unwrap();
+			assert_eq!(commitment.into_vec(), sp_io::hashing::sha2_256(&hash_input).to_vec());
+			assert_eq!(
+				<Context<Test> as ChannelReaderInterface>::get_next_sequence_send(
+					&port_id(),
+					&ChannelId::new(0)
+				)
+				.unwrap(),
+				Sequence::from(2)
+			);
+			assert!(contract_events().contains(&Event::PacketSent {
+				contract: contract(),
+				port_id: port_id(),
+				channel_id: ChannelId::new(0),
+				sequence: 1,
+			}));
+		})
+	}
+}
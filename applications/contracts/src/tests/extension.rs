@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{call, instantiate, new_test_ext, Test},
+		ContractPorts, Error, Pallet,
+	};
+	use codec::Decode;
+	use core::str::FromStr;
+	use ibc::core::ics24_host::identifier::PortId;
+
+	/// Binds the port `contracts.demo` through the extension, returning the status code.
+	const BIND_PORT_WAT: &str = r#"
+(module
+	(import "seal0" "call_chain_extension"
+		(func $call_chain_extension (param i32 i32 i32 i32 i32) (result i32)))
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+	;; BindPortInput { port_name: b"demo" }
+	(data (i32.const 0) "\10demo")
+	(func (export "deploy"))
+	(func (export "call")
+		;; length of the output buffer
+		(i32.store (i32.const 64) (i32.const 16))
+		(i32.store (i32.const 16)
+			(call $call_chain_extension
+				(i32.const 0x1bc00001) (i32.const 0) (i32.const 5) (i32.const 32) (i32.const 64)))
+		(call $seal_return (i32.const 0) (i32.const 16) (i32.const 4))
+	)
+)
+"#;
+
+	#[test]
+	fn contracts_bind_ports_through_the_extension() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(BIND_PORT_WAT);
+			let status = |output: Vec<u8>| u32::decode(&mut output.as_slice()).unwrap();
+
+			assert_eq!(status(call(&contract)), 0);
+			assert_eq!(
+				ContractPorts::<Test>::get(PortId::from_str("contracts.demo").unwrap()),
+				Some(contract.clone())
+			);
+
+			// binding the port again fails with the status code of the error
+			assert_eq!(
+				status(call(&contract)),
+				Pallet::<Test>::status_code(Error::<Test>::PortAlreadyBound)
+			);
+		})
+	}
+}
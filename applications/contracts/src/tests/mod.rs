@@ -0,0 +1,4 @@
+mod callback;
+mod channel;
+mod extension;
+mod transfer;
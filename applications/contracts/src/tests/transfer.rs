@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, AccountId, Test},
+		transfer::RawMsgTransfer,
+		Error, Pallet,
+	};
+	use ibc::core::{ics04_channel::timeout::TimeoutHeight, ics24_host::identifier::ChannelId};
+	use prost::Message;
+
+	fn contract() -> AccountId {
+		AccountId::new([7u8; 32])
+	}
+
+	fn transfer(amount: u128, timeout_timestamp: u64) -> Result<(), Error<Test>> {
+		Pallet::<Test>::do_transfer(
+			contract(),
+			ChannelId::new(2),
+			"uatom".to_string(),
+			amount,
+			"cosmos1receiver".to_string(),
+			TimeoutHeight::Never,
+			timeout_timestamp,
+			"{\"forward\":{}}".to_string(),
+		)
+	}
+
+	#[test]
+	fn transfer_from_the_contract_account() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(transfer(0, 1), Err(Error::<Test>::InvalidAmount));
+			assert_eq!(transfer(10, 0), Err(Error::<Test>::InvalidTimeout));
+			assert!(sp_io::storage::get(b"transfer").is_none());
+
+			assert_eq!(transfer(10, 1), Ok(()));
+			let msg =
+				RawMsgTransfer::decode(sp_io::storage::get(b"transfer").unwrap().as_ref()).unwrap();
+			assert_eq!(msg.source_port, "transfer");
+			assert_eq!(msg.source_channel, "channel-2");
+			assert_eq!(msg.sender, format!("0x{}", hex::encode([7u8; 32])));
+			assert_eq!(
+				msg.token.map(|coin| (coin.denom, coin.amount)),
+				Some(("uatom".into(), "10".into()))
+			);
+			assert_eq!(msg.memo, "{\"forward\":{}}");
+		})
+	}
+}
@@ -0,0 +1,86 @@
+//! ICS-20 transfers of contracts, sent by `Config::TransferRouter` out of the contract account.
+use crate::{contract_signer, Config, Error, Event, Pallet, LOG_TARGET};
+use alloc::{
+	string::{String, ToString},
+	vec,
+};
+use frame_support::ensure;
+use ibc::{
+	applications::transfer::PORT_ID_STR as TRANSFER_PORT_ID,
+	core::{ics04_channel::timeout::TimeoutHeight, ics24_host::identifier::ChannelId},
+};
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin, google::protobuf::Any,
+	ibc::core::client::v1::Height as RawHeight,
+};
+use pallet_ibc_utils::Router;
+use prost::Message;
+
+/// Type url of the ICS-20 `MsgTransfer`.
+pub const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+/// ICS-20 `MsgTransfer`, including the `memo` field.
+#[derive(Clone, PartialEq, Message)]
+pub struct RawMsgTransfer {
+	#[prost(string, tag = "1")]
+	pub source_port: String,
+	#[prost(string, tag = "2")]
+	pub source_channel: String,
+	#[prost(message, optional, tag = "3")]
+	pub token: Option<RawCoin>,
+	#[prost(string, tag = "4")]
+	pub sender: String,
+	#[prost(string, tag = "5")]
+	pub receiver: String,
+	#[prost(message, optional, tag = "6")]
+	pub timeout_height: Option<RawHeight>,
+	#[prost(uint64, tag = "7")]
+	pub timeout_timestamp: u64,
+	#[prost(string, tag = "8")]
+	pub memo: String,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Transfer `amount` of `denom` from `contract` to `receiver` over the transfer `channel_id`,
+	/// timing out at `timeout_height` or at `timeout_timestamp` nanoseconds on the counterparty.
+	#[allow(clippy::too_many_arguments)]
+	pub fn do_transfer(
+		contract: T::AccountId,
+		channel_id: ChannelId,
+		denom: String,
+		amount: u128,
+		receiver: String,
+		timeout_height: TimeoutHeight,
+		timeout_timestamp: u64,
+		memo: String,
+	) -> Result<(), Error<T>> {
+		ensure!(amount != 0, Error::<T>::InvalidAmount);
+		ensure!(
+			timeout_height != TimeoutHeight::Never || timeout_timestamp != 0,
+			Error::<T>::InvalidTimeout
+		);
+
+		let msg = RawMsgTransfer {
+			source_port: TRANSFER_PORT_ID.to_string(),
+			source_channel: channel_id.to_string(),
+			token: Some(RawCoin { denom, amount: amount.to_string() }),
+			sender: contract_signer::<T>(&contract),
+			receiver,
+			timeout_height: match timeout_height {
+				TimeoutHeight::At(height) => Some(height.into()),
+				TimeoutHeight::Never => None,
+			},
+			timeout_timestamp,
+			memo,
+		};
+		let message =
+			Any { type_url: MSG_TRANSFER_TYPE_URL.to_string(), value: msg.encode_to_vec() };
+		T::TransferRouter::dispatch(vec![message]).map_err(|error| {
+			log::error!(target: LOG_TARGET, "transfer Error : {:?} ", error);
+			Error::<T>::TransferFailed
+		})?;
+
+		Self::deposit_event(Event::TransferSent { contract, channel_id });
+		Ok(())
+	}
+}
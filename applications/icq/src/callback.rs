@@ -0,0 +1,187 @@
+use crate::{
+	controller::QueryResult,
+	packet::{decode_ack, decode_packet_data, error_ack, success_ack},
+	Config, Event, Pallet, LOG_TARGET,
+};
+use alloc::{format, string::ToString};
+use codec::{Decode, Encode};
+use ibc::{
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::{ChannelError, PacketError},
+			handler::ModuleExtras,
+			msgs::acknowledgement::Acknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleOutputBuilder},
+	},
+	signer::Signer,
+};
+use pallet_ibc_utils::traits::ChannelReaderInterface;
+use sp_std::marker::PhantomData;
+
+/// Port and module id of the host, answering queries.
+pub const HOST_PORT_ID: &str = "icqhost";
+/// Port and module id of the controller, sending queries.
+pub const CONTROLLER_PORT_ID: &str = "icqcontroller";
+/// Channel version of async-icq.
+pub const VERSION: &str = "icq-1";
+
+#[derive(Debug, Encode, Decode)]
+pub struct IcqModule<T>(pub PhantomData<T>);
+
+impl<T: Config> IcqModule<T> {
+	/// Channel checks of the opening handshake, on either port.
+	fn validate_channel(
+		order: Order,
+		port_id: &PortId,
+		version: &Version,
+	) -> Result<(), ChannelError> {
+		if order != Order::Unordered {
+			return Err(ChannelError::AppModule {
+				description: format!("{} channels must be unordered", VERSION),
+			});
+		}
+		if port_id.as_str() != HOST_PORT_ID && port_id.as_str() != CONTROLLER_PORT_ID {
+			return Err(ChannelError::AppModule {
+				description: format!("invalid port {}", port_id),
+			});
+		}
+		if *version != Version::new(VERSION.to_string()) {
+			return Err(ChannelError::AppModule {
+				description: format!("invalid version {}, expected {}", version, VERSION),
+			});
+		}
+		Ok(())
+	}
+}
+
+impl<T: Config> Module for IcqModule<T> {
+	fn on_chan_open_init(
+		&mut self,
+		order: Order,
+		_connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(ModuleExtras, Version), ChannelError> {
+		// an empty version is negotiated to the only version supported
+		let version =
+			if version.is_empty() { Version::new(VERSION.to_string()) } else { version.clone() };
+		Self::validate_channel(order, port_id, &version)?;
+		Ok((ModuleExtras::empty(), version))
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		order: Order,
+		_connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		counterparty_version: &Version,
+	) -> Result<(ModuleExtras, Version), ChannelError> {
+		Self::validate_channel(order, port_id, counterparty_version)?;
+		Ok((ModuleExtras::empty(), counterparty_version.clone()))
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<ModuleExtras, ChannelError> {
+		Self::validate_channel(Order::Unordered, port_id, counterparty_version)?;
+		Ok(ModuleExtras::empty())
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+	) -> Result<ModuleExtras, ChannelError> {
+		Ok(ModuleExtras::empty())
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+	) -> Result<ModuleExtras, ChannelError> {
+		Err(ChannelError::AppModule { description: "icq channels cannot be closed".to_string() })
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+	) -> Result<ModuleExtras, ChannelError> {
+		Ok(ModuleExtras::empty())
+	}
+
+	fn on_recv_packet(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		_relayer: &Signer,
+	) -> Acknowledgement {
+		if packet.port_on_b.as_str() != HOST_PORT_ID {
+			return error_ack("queries are only answered on the host port");
+		}
+		let answered = decode_packet_data(&packet.data)
+			.ok_or_else(|| "invalid icq packet data".to_string())
+			.and_then(|requests| {
+				let height = <T::IbcContext as ChannelReaderInterface>::host_height()
+					.map_err(|e| e.to_string())?
+					.revision_height();
+				Pallet::<T>::answer_queries(requests, height as i64)
+			});
+		let success = answered.is_ok();
+		Pallet::<T>::deposit_event(Event::<T>::QueriesAnswered {
+			channel_id: packet.chan_on_b.clone(),
+			sequence: packet.sequence.into(),
+			success,
+		});
+		match answered {
+			Ok(responses) => success_ack(responses),
+			Err(description) => {
+				log::debug!(target: LOG_TARGET, "on_recv_packet rejected query: {}", description);
+				error_ack(&description)
+			},
+		}
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &Acknowledgement,
+		_relayer: &Signer,
+	) -> Result<(), PacketError> {
+		let result = match decode_ack(acknowledgement.as_ref()) {
+			Ok(responses) =>
+				QueryResult::Responses(responses.into_iter().map(Into::into).collect()),
+			Err(error) => QueryResult::Error(error),
+		};
+		Pallet::<T>::on_query_result(&packet.chan_on_a, packet.sequence.into(), result);
+		Ok(())
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		_relayer: &Signer,
+	) -> Result<(), PacketError> {
+		Pallet::<T>::on_query_result(
+			&packet.chan_on_a,
+			packet.sequence.into(),
+			QueryResult::Timeout,
+		);
+		Ok(())
+	}
+}
@@ -0,0 +1,170 @@
+//! Controller side of async-icq: sending queries to a counterparty host and handing the results
+//! back to the pallet that sent them.
+//!
+//! Queries are sent from `CONTROLLER_PORT_ID` over an `icq-1` channel. The querier of every
+//! packet in flight is kept until its acknowledgement or timeout, which is then passed to
+//! `Config::OnQueryResult`.
+use crate::{
+	callback::{CONTROLLER_PORT_ID, VERSION},
+	packet::{encode_packet_data, QueryRequest, QueryResponse},
+	Config, Error, Event, Pallet, PendingQueries, LOG_TARGET,
+};
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use core::str::FromStr;
+use frame_support::{ensure, traits::Get};
+use ibc::{
+	core::{
+		ics04_channel::{
+			context::{ChannelKeeper, ChannelReader},
+			handler::send_packet::send_packet,
+			packet::Packet,
+			timeout::TimeoutHeight,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	events::IbcEvent,
+	handler::HandlerOutput,
+	timestamp::Timestamp,
+};
+use pallet_ibc_utils::context::ChannelContext;
+use scale_info::TypeInfo;
+
+/// Outcome of a query sent to a counterparty host.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum QueryResult {
+	/// One response per request, in the order of the requests
+	Responses(Vec<QueryResponse>),
+	/// The host rejected the query with this error acknowledgement
+	Error(Vec<u8>),
+	/// The query packet timed out before the host answered it
+	Timeout,
+}
+
+/// Receives the results of the queries sent with `Pallet::do_send_query`.
+pub trait OnQueryResult<AccountId> {
+	/// Called once for every query, with the account that sent it and the packet it was sent in.
+	fn on_query_result(
+		querier: &AccountId,
+		channel_id: &ChannelId,
+		sequence: u64,
+		result: &QueryResult,
+	);
+}
+
+impl<AccountId> OnQueryResult<AccountId> for () {
+	fn on_query_result(
+		_querier: &AccountId,
+		_channel_id: &ChannelId,
+		_sequence: u64,
+		_result: &QueryResult,
+	) {
+	}
+}
+
+impl<AccountId, A: OnQueryResult<AccountId>, B: OnQueryResult<AccountId>> OnQueryResult<AccountId>
+	for (A, B)
+{
+	fn on_query_result(
+		querier: &AccountId,
+		channel_id: &ChannelId,
+		sequence: u64,
+		result: &QueryResult,
+	) {
+		A::on_query_result(querier, channel_id, sequence, result);
+		B::on_query_result(querier, channel_id, sequence, result);
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Send `requests` to the host at the other end of `channel_id` in a single packet, timing
+	/// out at `timeout_timestamp` nanoseconds on the host. Returns the packet sequence.
+	pub fn do_send_query(
+		querier: T::AccountId,
+		channel_id: ChannelId,
+		requests: Vec<QueryRequest>,
+		timeout_timestamp: u64,
+	) -> Result<u64, Error<T>> {
+		ensure!(!requests.is_empty(), Error::<T>::EmptyQuery);
+		ensure!(
+			requests.len() <= T::MaxQueriesPerPacket::get() as usize,
+			Error::<T>::TooManyRequests
+		);
+		ensure!(timeout_timestamp != 0, Error::<T>::InvalidTimeout);
+		let timeout_timestamp_on_b = Timestamp::from_nanoseconds(timeout_timestamp)
+			.map_err(|_| Error::<T>::InvalidTimeout)?;
+
+		let mut ctx = ChannelContext::<T::IbcContext>::default();
+		let port_on_a = PortId::from_str(CONTROLLER_PORT_ID).expect("valid port id");
+		let channel_end = ChannelReader::channel_end(&ctx, &port_on_a, &channel_id)
+			.map_err(|_| Error::<T>::ChannelNotFound)?;
+		ensure!(
+			*channel_end.version() == Version::new(VERSION.into()),
+			Error::<T>::InvalidChannelVersion
+		);
+		let port_on_b = channel_end.counterparty().port_id().clone();
+		let chan_on_b = channel_end
+			.counterparty()
+			.channel_id()
+			.cloned()
+			.ok_or(Error::<T>::ChannelNotFound)?;
+
+		let sequence = ChannelReader::get_next_sequence_send(&ctx, &port_on_a, &channel_id)
+			.map_err(|_| Error::<T>::SendQueryFailed)?;
+		let packet = Packet {
+			sequence,
+			port_on_a: port_on_a.clone(),
+			chan_on_a: channel_id.clone(),
+			port_on_b,
+			chan_on_b,
+			data: encode_packet_data(requests.into_iter().map(Into::into).collect()),
+			timeout_height_on_b: TimeoutHeight::Never,
+			timeout_timestamp_on_b,
+		};
+		let HandlerOutput { events, .. } = send_packet(&ctx, packet.clone()).map_err(|error| {
+			log::error!(target: LOG_TARGET, "send_query Error : {:?} ", error);
+			Error::<T>::SendQueryFailed
+		})?;
+
+		let commitment = ChannelReader::packet_commitment(
+			&ctx,
+			&packet.data,
+			&packet.timeout_height_on_b,
+			&packet.timeout_timestamp_on_b,
+		);
+		ctx.store_packet_commitment(port_on_a.clone(), channel_id.clone(), sequence, commitment)
+			.map_err(|_| Error::<T>::SendQueryFailed)?;
+		ctx.store_next_sequence_send(port_on_a, channel_id.clone(), sequence.increment())
+			.map_err(|_| Error::<T>::SendQueryFailed)?;
+
+		for event in events {
+			if let IbcEvent::SendPacket(send_packet) = event {
+				Self::deposit_event(Event::SendPacket(send_packet));
+			}
+		}
+		let sequence = u64::from(sequence);
+		<PendingQueries<T>>::insert(&channel_id, sequence, &querier);
+		Self::deposit_event(Event::QuerySent { querier, channel_id, sequence });
+		Ok(sequence)
+	}
+
+	/// Hand the result of the query sent in packet `sequence` over to its querier.
+	pub(crate) fn on_query_result(channel_id: &ChannelId, sequence: u64, result: QueryResult) {
+		let Some(querier) = <PendingQueries<T>>::take(channel_id, sequence) else {
+			log::warn!(
+				target: LOG_TARGET,
+				"no pending query for {}/{}",
+				channel_id,
+				sequence
+			);
+			return;
+		};
+		T::OnQueryResult::on_query_result(&querier, channel_id, sequence, &result);
+		Self::deposit_event(Event::QueryResult {
+			channel_id: channel_id.clone(),
+			sequence,
+			result,
+		});
+	}
+}
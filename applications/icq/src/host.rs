@@ -0,0 +1,66 @@
+//! Host side of async-icq: answering the queries of counterparty controllers.
+//!
+//! A request reads a single storage key of this chain, with the path `STORAGE_QUERY_PATH` and
+//! the raw key as data. Only keys under a storage prefix allowed by governance, such as the
+//! prefix of one storage item, can be read. Like ibc-go, requests asking for a proof or a past
+//! height are rejected, and a single rejected request fails the whole packet.
+use crate::{
+	packet::{RequestQuery, ResponseQuery},
+	AllowedStoragePrefixes, Config, Pallet,
+};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use frame_support::traits::Get;
+
+/// ABCI query path of a raw storage read.
+pub const STORAGE_QUERY_PATH: &str = "/store/substrate/key";
+
+impl<T: Config> Pallet<T> {
+	/// Returns true if `key` is under a storage prefix allowed for queries.
+	pub fn is_queryable(key: &[u8]) -> bool {
+		<AllowedStoragePrefixes<T>>::iter_keys().any(|prefix| key.starts_with(&prefix))
+	}
+
+	/// Answer the requests of a query packet, failing on the first request that is not allowed.
+	pub(crate) fn answer_queries(
+		requests: Vec<RequestQuery>,
+		height: i64,
+	) -> Result<Vec<ResponseQuery>, String> {
+		if requests.is_empty() {
+			return Err("query packet carries no requests".to_string());
+		}
+		if requests.len() > T::MaxQueriesPerPacket::get() as usize {
+			return Err(format!("more than {} requests", T::MaxQueriesPerPacket::get()));
+		}
+		requests
+			.into_iter()
+			.enumerate()
+			.map(|(index, request)| {
+				Self::answer_query(request, height)
+					.map_err(|error| format!("request {}: {}", index, error))
+			})
+			.collect()
+	}
+
+	fn answer_query(request: RequestQuery, height: i64) -> Result<ResponseQuery, &'static str> {
+		if request.prove {
+			return Err("query proof not allowed");
+		}
+		if request.height != 0 {
+			return Err("query height not allowed");
+		}
+		if request.path != STORAGE_QUERY_PATH || !Self::is_queryable(&request.data) {
+			return Err("query path not allowed");
+		}
+		let value = sp_io::storage::get(&request.data).map(|value| value.to_vec());
+		Ok(ResponseQuery {
+			value: value.unwrap_or_default(),
+			key: request.data,
+			height,
+			..Default::default()
+		})
+	}
+}
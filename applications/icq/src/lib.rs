@@ -0,0 +1,210 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Async interchain queries, compatible with the ibc-go async-icq application.
+//!
+//! As a controller, the pallet sends ABCI queries to the host at the other end of an `icq-1`
+//! channel and passes the responses to `Config::OnQueryResult`, so other pallets read the state
+//! of a Cosmos chain without trusting an oracle. As a host, it answers the raw storage reads of
+//! counterparty controllers for the storage prefixes allowed by `AdminOrigin`.
+//!
+//! The runtime registers `IcqModule` under the module ids `icqhost` and `icqcontroller`, which
+//! are also the ports it is bound to.
+
+extern crate alloc;
+
+pub use pallet::*;
+
+pub mod callback;
+pub mod controller;
+pub mod host;
+pub mod packet;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+use controller::{OnQueryResult, QueryResult};
+use frame_support::{pallet_prelude::*, traits::BuildGenesisConfig};
+use frame_system::pallet_prelude::*;
+use ibc::core::{ics04_channel::events::SendPacket, ics24_host::identifier::ChannelId};
+use packet::QueryRequest;
+use sp_std::{fmt::Debug, vec::Vec};
+
+pub const LOG_TARGET: &str = "runtime::pallet-icq";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config:
+		frame_system::Config + pallet_timestamp::Config + Sync + Send + Debug
+	{
+		/// The aggregated event type of the runtime.
+		type RuntimeEvent: Parameter
+			+ Member
+			+ From<Event<Self>>
+			+ Debug
+			+ IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		type IbcContext: pallet_ibc_utils::traits::ChannelKeeperInterface
+			+ pallet_ibc_utils::traits::ChannelReaderInterface;
+
+		/// Receives the results of the queries sent by this chain
+		type OnQueryResult: OnQueryResult<Self::AccountId>;
+
+		/// Origin allowed to change the storage prefixes readable by counterparty controllers
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Maximum number of requests in a query packet, sent or received
+		#[pallet::constant]
+		type MaxQueriesPerPacket: Get<u32>;
+	}
+
+	#[pallet::storage]
+	/// key1: channel id
+	/// key2: sequence of the query packet
+	/// value: account that sent the query
+	pub type PendingQueries<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, ChannelId, Twox64Concat, u64, T::AccountId>;
+
+	#[pallet::storage]
+	/// key: storage prefix readable by counterparty controllers
+	/// value: ()
+	pub type AllowedStoragePrefixes<T: Config> = StorageMap<_, Blake2_128Concat, Vec<u8>, ()>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub allowed_storage_prefixes: Vec<Vec<u8>>,
+		#[serde(skip)]
+		pub _marker: PhantomData<T>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { allowed_storage_prefixes: Vec::new(), _marker: PhantomData }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for prefix in self.allowed_storage_prefixes.iter() {
+				<AllowedStoragePrefixes<T>>::insert(prefix, ());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Send packet event
+		SendPacket(SendPacket),
+		/// A query was sent to the counterparty host
+		QuerySent { querier: T::AccountId, channel_id: ChannelId, sequence: u64 },
+		/// The result of a query sent by this chain was received
+		QueryResult { channel_id: ChannelId, sequence: u64, result: QueryResult },
+		/// A query packet of a counterparty controller was answered or rejected
+		QueriesAnswered { channel_id: ChannelId, sequence: u64, success: bool },
+		/// A storage prefix was made readable by counterparty controllers
+		StoragePrefixAllowed { prefix: Vec<u8> },
+		/// A storage prefix is no longer readable by counterparty controllers
+		StoragePrefixDisallowed { prefix: Vec<u8> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A query needs at least one request
+		EmptyQuery,
+		/// A query has more than `MaxQueriesPerPacket` requests
+		TooManyRequests,
+		/// A query needs a non-zero timeout timestamp
+		InvalidTimeout,
+		/// The channel does not exist on the controller port
+		ChannelNotFound,
+		/// The channel was not opened with the async-icq version
+		InvalidChannelVersion,
+		/// Sending the query packet failed
+		SendQueryFailed,
+		/// An empty prefix would make the whole storage readable
+		EmptyStoragePrefix,
+		/// The storage prefix is not allowed
+		StoragePrefixNotAllowed,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Send queries to the host at the other end of an `icq-1` channel.
+		///
+		/// Parameters:
+		/// - `channel_id`: The channel of the controller port to send the query over.
+		/// - `requests`: The ABCI query path and data of every request.
+		/// - `timeout_timestamp`: Timestamp in nanoseconds on the host after which the query times
+		///   out.
+		///
+		/// Emits `QuerySent` event when successful.
+		#[pallet::call_index(0)]
+		// channel, connection, client and consensus states, host timestamp and next sequence
+		// read by `send_packet`, then the commitment, next sequence and pending query written
+		#[pallet::weight(T::DbWeight::get().reads_writes(6, 3))]
+		pub fn send_query(
+			origin: OriginFor<T>,
+			channel_id: ChannelId,
+			requests: Vec<QueryRequest>,
+			timeout_timestamp: u64,
+		) -> DispatchResult {
+			let querier = ensure_signed(origin)?;
+
+			Self::do_send_query(querier, channel_id, requests, timeout_timestamp)?;
+			Ok(())
+		}
+
+		/// Allow counterparty controllers to read the storage keys starting with `prefix`.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `prefix`: The storage prefix, e.g. the prefix of a storage item.
+		///
+		/// Emits `StoragePrefixAllowed` event when successful.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn allow_storage_prefix(origin: OriginFor<T>, prefix: Vec<u8>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(!prefix.is_empty(), Error::<T>::EmptyStoragePrefix);
+
+			<AllowedStoragePrefixes<T>>::insert(&prefix, ());
+			Self::deposit_event(Event::StoragePrefixAllowed { prefix });
+			Ok(())
+		}
+
+		/// Stop counterparty controllers from reading the storage keys starting with `prefix`.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `prefix`: A storage prefix previously allowed.
+		///
+		/// Emits `StoragePrefixDisallowed` event when successful.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn disallow_storage_prefix(origin: OriginFor<T>, prefix: Vec<u8>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(
+				<AllowedStoragePrefixes<T>>::take(&prefix).is_some(),
+				Error::<T>::StoragePrefixNotAllowed
+			);
+
+			Self::deposit_event(Event::StoragePrefixDisallowed { prefix });
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,175 @@
+use super::*;
+use crate as pallet_icq;
+use codec::Encode;
+pub use frame_support::{
+	construct_runtime, parameter_types,
+	traits::{ConstU16, ConstU32, ConstU64},
+};
+use frame_system as system;
+use ibc::core::ics24_host::identifier::ChannelId;
+use pallet_ibc_utils::module::Router;
+use sp_runtime::{
+	generic,
+	traits::{AccountIdLookup, BlakeTwo256, IdentifyAccount, Verify},
+	MultiSignature,
+};
+
+pub type Signature = MultiSignature;
+pub(crate) type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Timestamp: pallet_timestamp,
+		Ibc: pallet_ibc,
+		Icq: pallet_icq,
+	}
+);
+
+/// A hash of some data used by the chain.
+pub type Hash = sp_core::H256;
+
+/// Index of a transaction in the chain.
+pub type Index = u32;
+/// An index to a block.
+pub type BlockNumber = u32;
+
+impl frame_system::Config for Test {
+	/// The basic call filter to use in dispatchable.
+	type BaseCallFilter = frame_support::traits::Everything;
+	/// Block & extrinsics weights: base values and limits.
+	type BlockWeights = ();
+	/// The maximum length of a block (in bytes).
+	type BlockLength = ();
+	/// The identifier used to distinguish between accounts.
+	type AccountId = AccountId;
+	/// The aggregated dispatch type that is available for extrinsics.
+	type RuntimeCall = RuntimeCall;
+	/// The lookup mechanism to get account ID from whatever is passed in dispatchers.
+	type Lookup = AccountIdLookup<AccountId, ()>;
+	/// The index type for storing how many extrinsics an account has signed.
+	type Index = Index;
+	/// The index type for blocks.
+	type BlockNumber = BlockNumber;
+	/// The type for hashing blocks and tries.
+	type Hash = Hash;
+	/// The hashing algorithm used.
+	type Hashing = BlakeTwo256;
+	/// The header type.
+	type Header = generic::Header<BlockNumber, BlakeTwo256>;
+	/// The ubiquitous event type.
+	type RuntimeEvent = RuntimeEvent;
+	/// The ubiquitous origin type.
+	type RuntimeOrigin = RuntimeOrigin;
+	/// Maximum number of block number to block hash mappings to keep (oldest pruned first).
+	type BlockHashCount = ();
+	/// The weight of database operations that the runtime can invoke.
+	type DbWeight = ();
+	/// Version of the runtime.
+	type Version = ();
+	/// Converts a module to the index of the module in `construct_runtime!`.
+	///
+	/// This type is being generated by `construct_runtime!`.
+	type PalletInfo = PalletInfo;
+	/// What to do if a new account is created.
+	type OnNewAccount = ();
+	/// What to do if an account is fully reaped from the system.
+	type OnKilledAccount = ();
+	/// The data to be stored in an account.
+	type AccountData = ();
+	/// Weight information for the extrinsics of this pallet.
+	type SystemWeightInfo = ();
+	/// This is used as an identifier of the chain. 42 is the generic substrate prefix.
+	type SS58Prefix = ConstU16<42>;
+	/// The set code logic, just the default since we're not a parachain.
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+/// Type used for expressing timestamp.
+pub type Moment = u64;
+
+parameter_types! {
+	pub const MinimumPeriod: Moment = 3000;
+}
+
+impl pallet_timestamp::Config for Test {
+	/// A timestamp: milliseconds since the unix epoch.
+	type Moment = Moment;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+pub struct IbcModule;
+
+impl pallet_ibc_utils::module::AddModule for IbcModule {
+	fn add_module(router: Router) -> Router {
+		[callback::HOST_PORT_ID, callback::CONTROLLER_PORT_ID].into_iter().fold(
+			router,
+			|router, module_id| match router.add_route(
+				module_id.parse().expect("never failed"),
+				callback::IcqModule::<Test>(std::marker::PhantomData::<Test>),
+			) {
+				Ok(ret) => ret,
+				Err(e) => panic!("add module failed by {}", e),
+			},
+		)
+	}
+}
+
+/// Records the last query result for the tests.
+pub struct RecordQueryResult;
+impl OnQueryResult<AccountId> for RecordQueryResult {
+	fn on_query_result(
+		querier: &AccountId,
+		channel_id: &ChannelId,
+		sequence: u64,
+		result: &QueryResult,
+	) {
+		sp_io::storage::set(b"query_result", &(querier, channel_id, sequence, result).encode());
+	}
+}
+
+impl pallet_icq::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type IbcContext = pallet_ibc::context::Context<Test>;
+	type OnQueryResult = RecordQueryResult;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxQueriesPerPacket = ConstU32<4>;
+}
+
+parameter_types! {
+	pub const ExpectedBlockTime: u64 = 6;
+	pub const ChainVersion: u64 = 0;
+}
+
+impl pallet_ibc::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type TimeProvider = pallet_timestamp::Pallet<Test>;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	const IBC_COMMITMENT_PREFIX: &'static [u8] = b"Ibc";
+	type ChainVersion = ChainVersion;
+	type IbcModule = IbcModule;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type OnMisbehaviour = ();
+	type UpgradeModule = ();
+	type MaxPrunedPerBlock = ConstU32<10>;
+	type WasmClientGasLimit = ConstU64<10_000_000>;
+	type MaxWasmCodeSize = ConstU32<{ 3 * 1024 * 1024 }>;
+	type WeightInfo = ();
+}
+
+#[allow(dead_code)]
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
@@ -0,0 +1,176 @@
+//! Wire format of the ibc-go async-icq application.
+//!
+//! The packet data is the JSON encoding of `InterchainQueryPacketData`, whose `data` field holds
+//! a protobuf `CosmosQuery` made of ABCI `RequestQuery`s. A successful acknowledgement is a
+//! result acknowledgement wrapping the JSON encoding of `InterchainQueryPacketAck`, whose `data`
+//! field holds a protobuf `CosmosResponse` with one ABCI `ResponseQuery` per request.
+use alloc::{
+	string::{String, ToString},
+	vec::Vec,
+};
+use codec::{Decode, Encode};
+use ibc::core::ics04_channel::msgs::acknowledgement::Acknowledgement;
+use prost::Message;
+use scale_info::TypeInfo;
+use serde_json::{json, Value};
+use subtle_encoding::base64;
+
+/// ABCI `RequestQuery`.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct RequestQuery {
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+	#[prost(string, tag = "2")]
+	pub path: String,
+	#[prost(int64, tag = "3")]
+	pub height: i64,
+	#[prost(bool, tag = "4")]
+	pub prove: bool,
+}
+
+/// ABCI `ResponseQuery`, without the proof operations which are never requested.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct ResponseQuery {
+	#[prost(uint32, tag = "1")]
+	pub code: u32,
+	#[prost(string, tag = "3")]
+	pub log: String,
+	#[prost(string, tag = "4")]
+	pub info: String,
+	#[prost(int64, tag = "5")]
+	pub index: i64,
+	#[prost(bytes = "vec", tag = "6")]
+	pub key: Vec<u8>,
+	#[prost(bytes = "vec", tag = "7")]
+	pub value: Vec<u8>,
+	#[prost(int64, tag = "9")]
+	pub height: i64,
+	#[prost(string, tag = "10")]
+	pub codespace: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct CosmosQuery {
+	#[prost(message, repeated, tag = "1")]
+	pub requests: Vec<RequestQuery>,
+}
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct CosmosResponse {
+	#[prost(message, repeated, tag = "1")]
+	pub responses: Vec<ResponseQuery>,
+}
+
+/// A query sent to the counterparty host, e.g. `/cosmos.bank.v1beta1.Query/Balance` and the
+/// protobuf encoded request.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct QueryRequest {
+	pub path: Vec<u8>,
+	pub data: Vec<u8>,
+}
+
+/// The answer of the counterparty host to a `QueryRequest`, a non-zero `code` is an error.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct QueryResponse {
+	pub code: u32,
+	pub key: Vec<u8>,
+	pub value: Vec<u8>,
+	pub height: i64,
+}
+
+impl From<QueryRequest> for RequestQuery {
+	fn from(request: QueryRequest) -> Self {
+		Self {
+			data: request.data,
+			path: String::from_utf8_lossy(&request.path).into_owned(),
+			height: 0,
+			prove: false,
+		}
+	}
+}
+
+impl From<ResponseQuery> for QueryResponse {
+	fn from(response: ResponseQuery) -> Self {
+		Self {
+			code: response.code,
+			key: response.key,
+			value: response.value,
+			height: response.height,
+		}
+	}
+}
+
+/// JSON `InterchainQueryPacketData` carrying `requests`.
+pub fn encode_packet_data(requests: Vec<RequestQuery>) -> Vec<u8> {
+	let data = CosmosQuery { requests }.encode_to_vec();
+	json!({ "data": base64_string(&data), "memo": "" }).to_string().into_bytes()
+}
+
+/// The requests of a JSON `InterchainQueryPacketData`.
+pub fn decode_packet_data(data: &[u8]) -> Option<Vec<RequestQuery>> {
+	let data = base64_field(&serde_json::from_slice(data).ok()?, "data")?;
+	CosmosQuery::decode(data.as_slice()).ok().map(|query| query.requests)
+}
+
+/// Result acknowledgement carrying `responses`.
+pub fn success_ack(responses: Vec<ResponseQuery>) -> Acknowledgement {
+	let data = CosmosResponse { responses }.encode_to_vec();
+	let packet_ack = json!({ "data": base64_string(&data) }).to_string();
+	ack(json!({ "result": base64_string(packet_ack.as_bytes()) }))
+}
+
+/// Error acknowledgement with `description`.
+pub fn error_ack(description: &str) -> Acknowledgement {
+	ack(json!({ "error": description }))
+}
+
+/// The responses of a result acknowledgement, or the description of an error acknowledgement.
+pub fn decode_ack(ack: &[u8]) -> Result<Vec<ResponseQuery>, Vec<u8>> {
+	let ack: Value =
+		serde_json::from_slice(ack).map_err(|_| b"invalid acknowledgement".to_vec())?;
+	if let Some(error) = ack.get("error").and_then(Value::as_str) {
+		return Err(error.as_bytes().to_vec());
+	}
+	base64_field(&ack, "result")
+		.and_then(|packet_ack| serde_json::from_slice(&packet_ack).ok())
+		.and_then(|packet_ack: Value| base64_field(&packet_ack, "data"))
+		.and_then(|data| CosmosResponse::decode(data.as_slice()).ok())
+		.map(|response| response.responses)
+		.ok_or_else(|| b"invalid acknowledgement".to_vec())
+}
+
+fn ack(value: Value) -> Acknowledgement {
+	Acknowledgement::try_from(value.to_string().into_bytes())
+		.expect("a JSON acknowledgement is never empty")
+}
+
+fn base64_string(bytes: &[u8]) -> String {
+	String::from_utf8(base64::encode(bytes)).unwrap_or_default()
+}
+
+fn base64_field(value: &Value, field: &str) -> Option<Vec<u8>> {
+	base64::decode(value.get(field)?.as_str()?).ok()
+}
+
+#[test]
+fn test_packet_data_round_trip() {
+	let requests = vec![RequestQuery {
+		data: vec![1, 2, 3],
+		path: "/cosmos.bank.v1beta1.Query/Balance".to_string(),
+		height: 0,
+		prove: false,
+	}];
+	assert_eq!(decode_packet_data(&encode_packet_data(requests.clone())), Some(requests));
+	assert_eq!(decode_packet_data(br#"{"data":"not base64!","memo":""}"#), None);
+}
+
+#[test]
+fn test_ack_round_trip() {
+	let responses = vec![ResponseQuery { value: vec![7], height: 3, ..Default::default() }];
+	assert_eq!(decode_ack(success_ack(responses.clone()).as_ref()), Ok(responses));
+	assert_eq!(
+		decode_ack(error_ack("query not allowed").as_ref()),
+		Err(b"query not allowed".to_vec())
+	);
+	assert!(decode_ack(b"not json").is_err());
+}
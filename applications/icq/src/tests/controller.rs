@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		callback::{IcqModule, CONTROLLER_PORT_ID, HOST_PORT_ID},
+		controller::QueryResult,
+		mock::{new_test_ext, AccountId, Icq, RuntimeOrigin, Test},
+		packet::{error_ack, success_ack, QueryRequest, QueryResponse, ResponseQuery},
+		Error, PendingQueries,
+	};
+	use codec::Decode;
+	use core::{marker::PhantomData, str::FromStr};
+	use frame_support::assert_noop;
+	use ibc::{
+		core::{
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				packet::Packet,
+				timeout::TimeoutHeight,
+				Version,
+			},
+			ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+			ics26_routing::context::{Module, ModuleOutputBuilder},
+		},
+		signer::Signer,
+		timestamp::Timestamp,
+	};
+	use pallet_ibc_utils::traits::ChannelKeeperInterface;
+
+	fn querier() -> AccountId {
+		[7u8; 32].into()
+	}
+
+	fn requests() -> Vec<QueryRequest> {
+		vec![QueryRequest {
+			path: b"/cosmos.bank.v1beta1.Query/Balance".to_vec(),
+			data: vec![1, 2, 3],
+		}]
+	}
+
+	fn sent_packet(sequence: u64) -> Packet {
+		Packet {
+			sequence: sequence.into(),
+			port_on_a: PortId::from_str(CONTROLLER_PORT_ID).unwrap(),
+			chan_on_a: ChannelId::new(0),
+			port_on_b: PortId::from_str(HOST_PORT_ID).unwrap(),
+			chan_on_b: ChannelId::new(3),
+			data: vec![],
+			timeout_height_on_b: TimeoutHeight::Never,
+			timeout_timestamp_on_b: Timestamp::none(),
+		}
+	}
+
+	fn recorded_result() -> Option<(AccountId, ChannelId, u64, QueryResult)> {
+		sp_io::storage::get(b"query_result").map(|bytes| Decode::decode(&mut &bytes[..]).unwrap())
+	}
+
+	#[test]
+	fn send_query_checks_the_request_and_channel() {
+		new_test_ext().execute_with(|| {
+			let origin = RuntimeOrigin::signed(querier());
+			assert_noop!(
+				Icq::send_query(origin.clone(), ChannelId::new(0), vec![], 1),
+				Error::<Test>::EmptyQuery
+			);
+			assert_noop!(
+				Icq::send_query(origin.clone(), ChannelId::new(0), requests().repeat(5), 1),
+				Error::<Test>::TooManyRequests
+			);
+			assert_noop!(
+				Icq::send_query(origin.clone(), ChannelId::new(0), requests(), 0),
+				Error::<Test>::InvalidTimeout
+			);
+			assert_noop!(
+				Icq::send_query(origin.clone(), ChannelId::new(0), requests(), 1),
+				Error::<Test>::ChannelNotFound
+			);
+
+			<pallet_ibc::context::Context<Test> as ChannelKeeperInterface>::store_channel(
+				PortId::from_str(CONTROLLER_PORT_ID).unwrap(),
+				ChannelId::new(0),
+				ChannelEnd::new(
+					State::Open,
+					Order::Unordered,
+					Counterparty::new(
+						PortId::from_str(HOST_PORT_ID).unwrap(),
+						Some(ChannelId::new(3)),
+					),
+					vec![ConnectionId::default()],
+					Version::new("ics20-1".to_string()),
+				),
+			)
+			.unwrap();
+			assert_noop!(
+				Icq::send_query(origin, ChannelId::new(0), requests(), 1),
+				Error::<Test>::InvalidChannelVersion
+			);
+		})
+	}
+
+	#[test]
+	fn acknowledgement_hands_the_responses_to_the_querier() {
+		new_test_ext().execute_with(|| {
+			PendingQueries::<Test>::insert(ChannelId::new(0), 1, querier());
+			let response =
+				ResponseQuery { value: b"100uatom".to_vec(), height: 9, ..Default::default() };
+
+			IcqModule::<Test>(PhantomData)
+				.on_acknowledgement_packet(
+					&mut ModuleOutputBuilder::new(),
+					&sent_packet(1),
+					&success_ack(vec![response]),
+					&Signer::from_str("relayer").unwrap(),
+				)
+				.unwrap();
+
+			assert_eq!(
+				recorded_result(),
+				Some((
+					querier(),
+					ChannelId::new(0),
+					1,
+					QueryResult::Responses(vec![QueryResponse {
+						code: 0,
+						key: vec![],
+						value: b"100uatom".to_vec(),
+						height: 9,
+					}])
+				))
+			);
+			assert!(!PendingQueries::<Test>::contains_key(ChannelId::new(0), 1));
+		})
+	}
+
+	#[test]
+	fn error_acknowledgement_and_timeout_are_handed_over() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = IcqModule::<Test>(PhantomData);
+			let relayer = Signer::from_str("relayer").unwrap();
+			PendingQueries::<Test>::insert(ChannelId::new(0), 1, querier());
+			PendingQueries::<Test>::insert(ChannelId::new(0), 2, querier());
+
+			ctx.on_acknowledgement_packet(
+				&mut ModuleOutputBuilder::new(),
+				&sent_packet(1),
+				&error_ack("query path not allowed"),
+				&relayer,
+			)
+			.unwrap();
+			assert_eq!(
+				recorded_result().map(|(_, _, _, result)| result),
+				Some(QueryResult::Error(b"query path not allowed".to_vec()))
+			);
+
+			ctx.on_timeout_packet(&mut ModuleOutputBuilder::new(), &sent_packet(2), &relayer)
+				.unwrap();
+			assert_eq!(
+				recorded_result().map(|(_, _, sequence, result)| (sequence, result)),
+				Some((2, QueryResult::Timeout))
+			);
+			assert_eq!(PendingQueries::<Test>::iter().count(), 0);
+		})
+	}
+}
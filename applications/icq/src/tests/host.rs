@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		callback::{IcqModule, CONTROLLER_PORT_ID, HOST_PORT_ID, VERSION},
+		host::STORAGE_QUERY_PATH,
+		mock::{new_test_ext, Icq, RuntimeOrigin, System, Test},
+		packet::{decode_ack, encode_packet_data, RequestQuery},
+		AllowedStoragePrefixes, Error,
+	};
+	use core::{marker::PhantomData, str::FromStr};
+	use frame_support::{assert_noop, assert_ok};
+	use ibc::{
+		core::{
+			ics04_channel::{
+				channel::{Counterparty, Order},
+				packet::Packet,
+				timeout::TimeoutHeight,
+				Version,
+			},
+			ics05_port::context::PortReader,
+			ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+			ics26_routing::context::{Module, ModuleId, ModuleOutputBuilder},
+		},
+		signer::Signer,
+		timestamp::Timestamp,
+	};
+	use sp_runtime::DispatchError;
+
+	const PREFIX: &[u8] = b"icq:allowed:";
+
+	fn read(key: &[u8]) -> RequestQuery {
+		RequestQuery {
+			data: key.to_vec(),
+			path: STORAGE_QUERY_PATH.to_string(),
+			height: 0,
+			prove: false,
+		}
+	}
+
+	fn query_packet(port_on_b: &str, requests: Vec<RequestQuery>) -> Packet {
+		Packet {
+			sequence: 1.into(),
+			port_on_a: PortId::from_str(CONTROLLER_PORT_ID).unwrap(),
+			chan_on_a: ChannelId::new(0),
+			port_on_b: PortId::from_str(port_on_b).unwrap(),
+			chan_on_b: ChannelId::new(1),
+			data: encode_packet_data(requests),
+			timeout_height_on_b: TimeoutHeight::Never,
+			timeout_timestamp_on_b: Timestamp::none(),
+		}
+	}
+
+	fn recv(packet: &Packet) -> Result<Vec<crate::packet::ResponseQuery>, Vec<u8>> {
+		let ack = IcqModule::<Test>(PhantomData).on_recv_packet(
+			&mut ModuleOutputBuilder::new(),
+			packet,
+			&Signer::from_str("relayer").unwrap(),
+		);
+		decode_ack(ack.as_ref())
+	}
+
+	#[test]
+	fn answers_reads_of_allowed_prefixes() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(5);
+			assert_ok!(Icq::allow_storage_prefix(RuntimeOrigin::root(), PREFIX.to_vec()));
+			sp_io::storage::set(b"icq:allowed:balance", b"42");
+
+			let responses = recv(&query_packet(
+				HOST_PORT_ID,
+				vec![read(b"icq:allowed:balance"), read(b"icq:allowed:missing")],
+			))
+			.unwrap();
+
+			assert_eq!(responses.len(), 2);
+			assert_eq!(responses[0].code, 0);
+			assert_eq!(responses[0].key, b"icq:allowed:balance".to_vec());
+			assert_eq!(responses[0].value, b"42".to_vec());
+			assert_eq!(responses[0].height, 5);
+			assert!(responses[1].value.is_empty());
+		})
+	}
+
+	#[test]
+	fn rejects_the_packet_on_any_disallowed_request() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Icq::allow_storage_prefix(RuntimeOrigin::root(), PREFIX.to_vec()));
+			sp_io::storage::set(b"icq:secret", b"1");
+
+			let disallowed =
+				query_packet(HOST_PORT_ID, vec![read(b"icq:allowed:balance"), read(b"icq:secret")]);
+			assert_eq!(recv(&disallowed), Err(b"request 1: query path not allowed".to_vec()));
+
+			let proof = query_packet(
+				HOST_PORT_ID,
+				vec![RequestQuery { prove: true, ..read(b"icq:allowed:balance") }],
+			);
+			assert_eq!(recv(&proof), Err(b"request 0: query proof not allowed".to_vec()));
+
+			let too_many = query_packet(HOST_PORT_ID, vec![read(b"icq:allowed:balance"); 5]);
+			assert!(recv(&too_many).is_err());
+
+			let on_controller =
+				query_packet(CONTROLLER_PORT_ID, vec![read(b"icq:allowed:balance")]);
+			assert!(recv(&on_controller).is_err());
+		})
+	}
+
+	#[test]
+	fn admin_manages_allowed_prefixes() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Icq::allow_storage_prefix(RuntimeOrigin::signed([1u8; 32].into()), PREFIX.to_vec()),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Icq::allow_storage_prefix(RuntimeOrigin::root(), vec![]),
+				Error::<Test>::EmptyStoragePrefix
+			);
+
+			assert_ok!(Icq::allow_storage_prefix(RuntimeOrigin::root(), PREFIX.to_vec()));
+			assert!(AllowedStoragePrefixes::<Test>::contains_key(PREFIX));
+			assert!(crate::Pallet::<Test>::is_queryable(b"icq:allowed:balance"));
+
+			assert_ok!(Icq::disallow_storage_prefix(RuntimeOrigin::root(), PREFIX.to_vec()));
+			assert!(!crate::Pallet::<Test>::is_queryable(b"icq:allowed:balance"));
+			assert_noop!(
+				Icq::disallow_storage_prefix(RuntimeOrigin::root(), PREFIX.to_vec()),
+				Error::<Test>::StoragePrefixNotAllowed
+			);
+		})
+	}
+
+	#[test]
+	fn channel_handshake_requires_unordered_icq_channels() {
+		let mut ctx = IcqModule::<Test>(PhantomData);
+		let host = PortId::from_str(HOST_PORT_ID).unwrap();
+		let counterparty = Counterparty::new(PortId::from_str(CONTROLLER_PORT_ID).unwrap(), None);
+		let icq = Version::new(VERSION.to_string());
+
+		let (_, version) = ctx
+			.on_chan_open_try(
+				Order::Unordered,
+				&[ConnectionId::default()],
+				&host,
+				&ChannelId::new(0),
+				&counterparty,
+				&icq,
+			)
+			.unwrap();
+		assert_eq!(version, icq);
+
+		assert!(ctx
+			.on_chan_open_try(
+				Order::Ordered,
+				&[ConnectionId::default()],
+				&host,
+				&ChannelId::new(0),
+				&counterparty,
+				&icq,
+			)
+			.is_err());
+		assert!(ctx
+			.on_chan_open_try(
+				Order::Unordered,
+				&[ConnectionId::default()],
+				&PortId::transfer(),
+				&ChannelId::new(0),
+				&counterparty,
+				&icq,
+			)
+			.is_err());
+		assert!(ctx.on_chan_close_init(&host, &ChannelId::new(0)).is_err());
+	}
+
+	#[test]
+	fn icq_ports_are_bound_to_their_modules() {
+		new_test_ext().execute_with(|| {
+			let ctx = pallet_ibc::context::Context::<Test>::new();
+			for port in [HOST_PORT_ID, CONTROLLER_PORT_ID] {
+				assert_eq!(
+					ctx.lookup_module_by_port(&PortId::from_str(port).unwrap()).unwrap(),
+					ModuleId::from_str(port).unwrap()
+				);
+			}
+			assert!(ctx.lookup_module_by_port(&PortId::from_str("icqunknown").unwrap()).is_err());
+		})
+	}
+}
@@ -1,7 +1,22 @@
-use crate::Config;
-use alloc::string::ToString;
+use crate::{
+	escrow::{escrowed_denom_on_recv, escrowed_denom_on_send},
+	memo::dispatch_memo_hooks,
+	utils::{is_success_ack, DecodedTransfer},
+	v2::{is_v2, VERSION_V2},
+	Config, Event, Pallet, LOG_TARGET,
+};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
 use codec::{Decode, Encode};
+use frame_support::storage::{with_transaction, TransactionOutcome};
 use ibc::{
+	applications::transfer::{
+		acknowledgement::TokenTransferAcknowledgement, context::TokenTransferReader,
+		error::TokenTransferError, VERSION,
+	},
 	core::{
 		ics04_channel::{
 			channel::{Counterparty, Order},
@@ -17,11 +32,84 @@ use ibc::{
 	signer::Signer,
 };
 
+use pallet_ibc_utils::module::ChannelUpgradeModule;
+use sp_runtime::DispatchError;
 use sp_std::marker::PhantomData;
 
 #[derive(Debug, Encode, Decode)]
 pub struct IbcTransferModule<T>(pub PhantomData<T>);
 
+impl<T: Config> IbcTransferModule<T> {
+	/// Denom and amount released from the source channel escrow when `packet` is refunded.
+	fn refunded_escrow(packet: &Packet, transfer: &DecodedTransfer) -> Option<(Vec<u8>, u128)> {
+		escrowed_denom_on_send(&packet.port_on_a, &packet.chan_on_a, &transfer.denom)
+			.map(|denom| (denom, transfer.amount))
+	}
+
+	/// Receive pipeline shared by `ics20-1` and `ics20-2` packets: checks that receiving is
+	/// enabled, credits the packet funds with `credit` and runs the memo hooks, rolling both back
+	/// and acknowledging with an error if either fails.
+	///
+	/// The module output is buffered and only merged into `output` once the transaction commits,
+	/// so no event is reported for a rolled back receive.
+	pub(crate) fn recv_with_memo_hooks(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		credit: impl FnOnce(&mut Self, &mut ModuleOutputBuilder) -> Acknowledgement,
+	) -> Acknowledgement {
+		if !self.is_receive_enabled() {
+			return TokenTransferAcknowledgement::Error(
+				TokenTransferError::ReceiveDisabled.to_string(),
+			)
+			.into();
+		}
+
+		let mut buffered = ModuleOutputBuilder::new();
+		let committed = with_transaction(|| {
+			let ack = credit(self, &mut buffered);
+			if !is_success_ack(ack.as_ref()) {
+				return TransactionOutcome::Rollback(Ok(Err(ack)));
+			}
+
+			match dispatch_memo_hooks::<T>(packet) {
+				Ok(()) => TransactionOutcome::Commit(Ok(Ok(ack))),
+				Err(error) => TransactionOutcome::Rollback(Ok(Err(
+					TokenTransferAcknowledgement::Error(format!("memo hook failed: {:?}", error))
+						.into(),
+				))),
+			}
+		})
+		.unwrap_or_else(|error: DispatchError| {
+			Err(TokenTransferAcknowledgement::Error(format!("memo hook failed: {:?}", error))
+				.into())
+		});
+
+		match committed {
+			Ok(ack) => {
+				output.merge(buffered);
+				ack
+			},
+			Err(ack) => ack,
+		}
+	}
+
+	pub(crate) fn ensure_escrow(
+		channel_id: &ChannelId,
+		denom: &[u8],
+		amount: u128,
+	) -> Result<(), String> {
+		let total = Pallet::<T>::total_escrow(channel_id, denom);
+		if total < amount {
+			return Err(format!(
+				"insufficient escrow on {}: {} outstanding, {} requested",
+				channel_id, total, amount
+			));
+		}
+		Ok(())
+	}
+}
+
 impl<T: Config> Module for IbcTransferModule<T> {
 	fn on_chan_open_init(
 		&mut self,
@@ -32,6 +120,10 @@ impl<T: Config> Module for IbcTransferModule<T> {
 		counterparty: &Counterparty,
 		version: &Version,
 	) -> Result<(ModuleExtras, Version), ChannelError> {
+		if is_v2(version) {
+			self.validate_channel_v2(order, port_id)?;
+			return Ok((ModuleExtras::empty(), version.clone()));
+		}
 		ibc::applications::transfer::context::on_chan_open_init(
 			self,
 			order,
@@ -53,6 +145,10 @@ impl<T: Config> Module for IbcTransferModule<T> {
 		counterparty: &Counterparty,
 		counterparty_version: &Version,
 	) -> Result<(ModuleExtras, Version), ChannelError> {
+		if is_v2(counterparty_version) {
+			self.validate_channel_v2(order, port_id)?;
+			return Ok((ModuleExtras::empty(), Version::new(VERSION_V2.to_string())));
+		}
 		ibc::applications::transfer::context::on_chan_open_try(
 			self,
 			order,
@@ -71,6 +167,10 @@ impl<T: Config> Module for IbcTransferModule<T> {
 		channel_id: &ChannelId,
 		counterparty_version: &Version,
 	) -> Result<ModuleExtras, ChannelError> {
+		if is_v2(counterparty_version) {
+			self.validate_open_ack_v2(port_id, channel_id)?;
+			return Ok(ModuleExtras::empty());
+		}
 		ibc::applications::transfer::context::on_chan_open_ack(
 			self,
 			port_id,
@@ -112,7 +212,48 @@ impl<T: Config> Module for IbcTransferModule<T> {
 		packet: &Packet,
 		relayer: &Signer,
 	) -> Acknowledgement {
-		ibc::applications::transfer::context::on_recv_packet(self, output, packet, relayer)
+		if self.is_v2_channel(&packet.port_on_b, &packet.chan_on_b) {
+			return self.on_recv_packet_v2(output, packet);
+		}
+
+		let transfer = DecodedTransfer::from_packet(packet);
+		// tokens returning to this chain are released from the destination channel escrow
+		let unescrow = transfer.as_ref().and_then(|transfer| {
+			escrowed_denom_on_recv(&packet.port_on_a, &packet.chan_on_a, &transfer.denom)
+				.map(|denom| (denom, transfer.amount))
+		});
+
+		let checked = match &unescrow {
+			Some((denom, amount)) => Self::ensure_escrow(&packet.chan_on_b, denom, *amount),
+			None => Ok(()),
+		};
+		let ack = match checked {
+			Ok(()) => self.recv_with_memo_hooks(output, packet, |ctx, output| {
+				ibc::applications::transfer::context::on_recv_packet(ctx, output, packet, relayer)
+			}),
+			Err(description) => TokenTransferAcknowledgement::Error(description).into(),
+		};
+		let success = is_success_ack(ack.as_ref());
+
+		if let (true, Some((denom, amount))) = (success, unescrow) {
+			if let Err(error) =
+				Pallet::<T>::decrease_total_escrow(&packet.chan_on_b, &denom, amount)
+			{
+				log::error!(target: LOG_TARGET, "on_recv_packet total escrow: {:?}", error);
+			}
+		}
+
+		if let Some(DecodedTransfer { sender, receiver, denom, amount }) = transfer {
+			Pallet::<T>::deposit_event(Event::<T>::RecvPacket {
+				sender,
+				receiver,
+				denom,
+				amount,
+				success,
+			});
+		}
+
+		ack
 	}
 
 	fn on_acknowledgement_packet(
@@ -122,6 +263,22 @@ impl<T: Config> Module for IbcTransferModule<T> {
 		acknowledgement: &Acknowledgement,
 		relayer: &Signer,
 	) -> Result<(), PacketError> {
+		if self.is_v2_channel(&packet.port_on_a, &packet.chan_on_a) {
+			return self.on_acknowledgement_packet_v2(packet, acknowledgement);
+		}
+
+		let transfer = DecodedTransfer::from_packet(packet);
+		let success = is_success_ack(acknowledgement.as_ref());
+		// an error acknowledgement refunds the sender out of the source channel escrow
+		let refund = match (&transfer, success) {
+			(Some(transfer), false) => Self::refunded_escrow(packet, transfer),
+			_ => None,
+		};
+		if let Some((denom, amount)) = &refund {
+			Self::ensure_escrow(&packet.chan_on_a, denom, *amount)
+				.map_err(|description| PacketError::AppModule { description })?;
+		}
+
 		ibc::applications::transfer::context::on_acknowledgement_packet(
 			self,
 			output,
@@ -129,7 +286,24 @@ impl<T: Config> Module for IbcTransferModule<T> {
 			acknowledgement,
 			relayer,
 		)
-		.map_err(|e| PacketError::AppModule { description: e.to_string() })
+		.map_err(|e| PacketError::AppModule { description: e.to_string() })?;
+
+		if let Some((denom, amount)) = refund {
+			Pallet::<T>::decrease_total_escrow(&packet.chan_on_a, &denom, amount)
+				.map_err(|e| PacketError::AppModule { description: format!("{:?}", e) })?;
+		}
+
+		if let Some(DecodedTransfer { sender, receiver, denom, amount }) = transfer {
+			Pallet::<T>::deposit_event(Event::<T>::AckStatus {
+				sender,
+				receiver,
+				denom,
+				amount,
+				success,
+			});
+		}
+
+		Ok(())
 	}
 
 	fn on_timeout_packet(
@@ -138,7 +312,101 @@ impl<T: Config> Module for IbcTransferModule<T> {
 		packet: &Packet,
 		relayer: &Signer,
 	) -> Result<(), PacketError> {
+		if self.is_v2_channel(&packet.port_on_a, &packet.chan_on_a) {
+			return self.on_timeout_packet_v2(packet);
+		}
+
+		let transfer = DecodedTransfer::from_packet(packet);
+		let refund = transfer.as_ref().and_then(|transfer| Self::refunded_escrow(packet, transfer));
+		if let Some((denom, amount)) = &refund {
+			Self::ensure_escrow(&packet.chan_on_a, denom, *amount)
+				.map_err(|description| PacketError::AppModule { description })?;
+		}
+
 		ibc::applications::transfer::context::on_timeout_packet(self, output, packet, relayer)
-			.map_err(|e| PacketError::AppModule { description: e.to_string() })
+			.map_err(|e| PacketError::AppModule { description: e.to_string() })?;
+
+		if let Some((denom, amount)) = refund {
+			Pallet::<T>::decrease_total_escrow(&packet.chan_on_a, &denom, amount)
+				.map_err(|e| PacketError::AppModule { description: format!("{:?}", e) })?;
+		}
+
+		if let Some(DecodedTransfer { sender, denom, amount, .. }) = transfer {
+			Pallet::<T>::deposit_event(Event::<T>::Timeout {
+				refund_receiver: sender,
+				denom,
+				amount,
+			});
+		}
+
+		Ok(())
+	}
+}
+
+impl<T: Config> IbcTransferModule<T> {
+	/// Checks of an upgrade of a transfer channel, which stays unordered and may switch between
+	/// `ics20-1` and `ics20-2`.
+	fn validate_upgrade(
+		port_id: &PortId,
+		order: Order,
+		version: &Version,
+	) -> Result<Version, ChannelError> {
+		if !is_v2(version) && *version != Version::new(VERSION.to_string()) {
+			return Err(ChannelError::AppModule {
+				description: format!("unsupported transfer version {}", version),
+			});
+		}
+		Self(PhantomData).validate_channel_v2(order, port_id)?;
+		Ok(version.clone())
+	}
+}
+
+impl<T: Config> ChannelUpgradeModule for IbcTransferModule<T> {
+	fn handles(port_id: &PortId) -> bool {
+		*port_id == PortId::transfer()
+	}
+
+	fn on_chan_upgrade_init(
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		order: Order,
+		_connection_hops: &[ConnectionId],
+		version: &Version,
+	) -> Result<Version, ChannelError> {
+		Self::validate_upgrade(port_id, order, version)
+	}
+
+	fn on_chan_upgrade_try(
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		order: Order,
+		_connection_hops: &[ConnectionId],
+		counterparty_version: &Version,
+	) -> Result<Version, ChannelError> {
+		Self::validate_upgrade(port_id, order, counterparty_version)
+	}
+
+	fn on_chan_upgrade_ack(
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), ChannelError> {
+		Self::validate_upgrade(port_id, Order::Unordered, counterparty_version).map(|_| ())
+	}
+
+	fn on_chan_upgrade_open(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		version: &Version,
+	) {
+		log::info!(
+			target: LOG_TARGET,
+			"transfer channel {}/{} upgraded to {}",
+			port_id,
+			channel_id,
+			version
+		);
 	}
 }
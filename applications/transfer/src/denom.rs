@@ -1,9 +1,13 @@
+use crate::{AssetIdByName, AssetNameById, Config, DenomTrace, Pallet};
 use alloc::vec::Vec;
 use codec::{Decode, Encode};
 use ibc::applications::transfer::denom::PrefixedDenom as IbcPrefixedDenom;
 
+/// Prefix of the denomination of a voucher, followed by the hex-encoded hash of its trace.
+pub const IBC_DENOM_PREFIX: &[u8] = b"ibc/";
+
 /// A type that contains the base denomination for ICS20 and the source tracing information path.
-#[derive(Clone, Debug, Decode, Encode, scale_info::TypeInfo)]
+#[derive(Clone, Debug, PartialEq, Eq, Decode, Encode, scale_info::TypeInfo)]
 pub struct PrefixedDenom {
 	/// A series of `{port-id}/{channel-id}`s for tracing the source of the token.
 	pub trace_path: Vec<u8>,
@@ -19,3 +23,86 @@ impl From<IbcPrefixedDenom> for PrefixedDenom {
 		}
 	}
 }
+
+impl PrefixedDenom {
+	/// Full `{trace_path}/{base_denom}`, or the base denomination alone for a native token.
+	pub fn full_path(&self) -> Vec<u8> {
+		if self.trace_path.is_empty() {
+			self.base_denom.clone()
+		} else {
+			[self.trace_path.as_slice(), b"/", self.base_denom.as_slice()].concat()
+		}
+	}
+
+	/// `ibc/{hash}` voucher denomination under which the trace is stored in `DenomTrace`.
+	pub fn trace_hash(&self) -> Option<Vec<u8>> {
+		let full_path = self.full_path();
+		let full_path = core::str::from_utf8(&full_path).ok()?;
+		crate::utils::derive_ibc_denom_with_path(full_path)
+			.ok()
+			.map(|denom| denom.into_bytes())
+	}
+
+	/// Name of the asset of this denomination in `AssetIdByName`: the `ibc/{hash}` voucher
+	/// denomination of a traced token, so that tokens of different origins sharing a base
+	/// denomination are different assets, or the base denomination of an untraced token.
+	pub fn asset_name(&self) -> Option<Vec<u8>> {
+		if self.trace_path.is_empty() {
+			Some(self.base_denom.clone())
+		} else {
+			self.trace_hash()
+		}
+	}
+}
+
+/// A page of denomination traces, ordered by storage key.
+#[derive(Clone, Debug, PartialEq, Eq, Decode, Encode, scale_info::TypeInfo)]
+pub struct DenomTracePage {
+	/// `(ibc/{hash}, trace)` pairs.
+	pub traces: Vec<(Vec<u8>, PrefixedDenom)>,
+	/// Denomination to pass as `start_after` to fetch the next page, `None` on the last page.
+	pub next: Option<Vec<u8>>,
+}
+
+/// Normalize a voucher denomination given either as `ibc/{hash}` or as the bare hash.
+fn ibc_denom(denom: &[u8]) -> Vec<u8> {
+	if denom.starts_with(IBC_DENOM_PREFIX) {
+		denom.to_vec()
+	} else {
+		[IBC_DENOM_PREFIX, denom].concat()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Full trace of a voucher denomination, given as `ibc/{hash}` or as the bare hash.
+	pub fn denom_trace(denom: impl AsRef<[u8]>) -> Option<PrefixedDenom> {
+		<DenomTrace<T>>::get(ibc_denom(denom.as_ref()))
+	}
+
+	/// At most `limit` denomination traces following `start_after`, or from the first one.
+	pub fn denom_traces(start_after: Option<Vec<u8>>, limit: u32) -> DenomTracePage {
+		let mut iter = match start_after {
+			Some(denom) =>
+				<DenomTrace<T>>::iter_from(<DenomTrace<T>>::hashed_key_for(ibc_denom(&denom))),
+			None => <DenomTrace<T>>::iter(),
+		};
+		let traces: Vec<_> = iter.by_ref().take(limit as usize).collect();
+		let next = match iter.next() {
+			Some(_) => traces.last().map(|(denom, _)| denom.clone()),
+			None => None,
+		};
+		DenomTracePage { traces, next }
+	}
+
+	/// Asset id of a denomination, either a voucher `ibc/{hash}` or the base denomination of an
+	/// untraced token.
+	pub fn asset_id_by_denom(denom: impl AsRef<[u8]>) -> Option<T::AssetId> {
+		<AssetIdByName<T>>::try_get(denom.as_ref()).ok()
+	}
+
+	/// Denomination of an asset id, the `ibc/{hash}` of a voucher or the base denomination of an
+	/// untraced token.
+	pub fn denom_by_asset_id(asset_id: T::AssetId) -> Option<Vec<u8>> {
+		<AssetNameById<T>>::get(asset_id)
+	}
+}
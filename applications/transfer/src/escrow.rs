@@ -0,0 +1,181 @@
+//! Accounting of the tokens escrowed by the ICS20 module on each channel.
+//!
+//! Tokens are escrowed when they leave this chain as the source chain and are released again on
+//! refund (error acknowledgement or timeout) or when the vouchers come back from the counterparty.
+//! `TotalEscrow` tracks the outstanding amount per (channel, denom), so a counterparty can never
+//! unescrow more than it was sent.
+use crate::*;
+use alloc::{format, vec::Vec};
+use ibc::core::ics24_host::identifier::{ChannelId, PortId};
+#[cfg(any(feature = "try-runtime", feature = "std", test))]
+use {
+	crate::utils::get_channel_escrow_address,
+	core::str::FromStr,
+	ibc::applications::transfer::{denom::PrefixedDenom, PORT_ID_STR},
+	sp_runtime::traits::IdentifyAccount,
+};
+#[cfg(any(feature = "try-runtime", test))]
+use {
+	frame_support::traits::{fungibles::Inspect, Currency},
+	pallet_ibc_utils::AssetIdAndNameProvider,
+	sp_runtime::traits::UniqueSaturatedInto,
+};
+
+/// The `{port_id}/{channel_id}/` trace prefix added by a hop over the given channel end.
+fn trace_prefix(port_id: &PortId, channel_id: &ChannelId) -> Vec<u8> {
+	format!("{}/{}/", port_id, channel_id).into_bytes()
+}
+
+/// Returns the denomination escrowed on this chain when `denom` is sent over
+/// `source_port/source_channel`, or `None` if the token is a voucher that gets burned instead.
+pub fn escrowed_denom_on_send(
+	source_port: &PortId,
+	source_channel: &ChannelId,
+	denom: &[u8],
+) -> Option<Vec<u8>> {
+	if denom.starts_with(&trace_prefix(source_port, source_channel)) {
+		None
+	} else {
+		Some(denom.to_vec())
+	}
+}
+
+/// Returns the denomination released from escrow on this chain when `denom` is received from
+/// `source_port/source_channel`, or `None` if the token gets minted as a voucher instead.
+pub fn escrowed_denom_on_recv(
+	source_port: &PortId,
+	source_channel: &ChannelId,
+	denom: &[u8],
+) -> Option<Vec<u8>> {
+	denom
+		.strip_prefix(trace_prefix(source_port, source_channel).as_slice())
+		.map(|d| d.to_vec())
+}
+
+impl<T: Config> Pallet<T> {
+	/// Total amount of `denom` currently held in escrow for `channel_id`.
+	pub fn total_escrow(channel_id: &ChannelId, denom: impl AsRef<[u8]>) -> u128 {
+		<TotalEscrow<T>>::get(channel_id, denom.as_ref())
+	}
+
+	pub(crate) fn increase_total_escrow(
+		channel_id: &ChannelId,
+		denom: &[u8],
+		amount: u128,
+	) -> Result<(), Error<T>> {
+		<TotalEscrow<T>>::try_mutate(channel_id, denom, |total| {
+			*total = total.checked_add(amount).ok_or(Error::<T>::EscrowOverflow)?;
+			Ok(())
+		})
+	}
+
+	/// Fails without touching storage if more than the outstanding escrow would be released.
+	pub(crate) fn decrease_total_escrow(
+		channel_id: &ChannelId,
+		denom: &[u8],
+		amount: u128,
+	) -> Result<(), Error<T>> {
+		<TotalEscrow<T>>::try_mutate_exists(channel_id, denom, |total| {
+			let remaining = total
+				.unwrap_or_default()
+				.checked_sub(amount)
+				.ok_or(Error::<T>::InsufficientEscrow)?;
+			*total = if remaining == 0 { None } else { Some(remaining) };
+			Ok(())
+		})
+	}
+
+	/// Escrow account of `channel_id` on the transfer port.
+	#[cfg(any(feature = "try-runtime", feature = "std", test))]
+	fn escrow_account(channel_id: &ChannelId) -> Result<T::AccountId, &'static str> {
+		let port_id = PortId::from_str(PORT_ID_STR).map_err(|_| "invalid transfer port")?;
+		Ok(get_channel_escrow_address(&port_id, channel_id)
+			.ok()
+			.and_then(|signer| T::AccountIdConversion::try_from(signer).ok())
+			.ok_or("invalid escrow account")?
+			.into_account())
+	}
+
+	/// Asset holding the escrowed `denom`, `None` for the native token.
+	///
+	/// A traced denom resolves through the hash of its full path rather than its base denom, so
+	/// that tokens of different origins sharing a base denom are told apart.
+	#[cfg(any(feature = "try-runtime", test))]
+	fn escrowed_asset(denom: &[u8]) -> Result<Option<T::AssetId>, &'static str> {
+		let denom = core::str::from_utf8(denom)
+			.ok()
+			.and_then(|denom| PrefixedDenom::from_str(denom).ok())
+			.map(crate::denom::PrefixedDenom::from)
+			.ok_or("invalid escrowed denom")?;
+
+		if denom.trace_path.is_empty() && T::NATIVE_TOKEN_NAME == denom.base_denom.as_slice() {
+			return Ok(None);
+		}
+		denom
+			.asset_name()
+			.and_then(|name| T::AssetIdByName::try_get_asset_id(name).ok())
+			.map(Some)
+			.ok_or("escrowed denom is not registered")
+	}
+
+	/// Balance of `asset`, or of the native token, held by the escrow account of `channel_id`.
+	#[cfg(any(feature = "try-runtime", test))]
+	fn escrow_balance(
+		channel_id: &ChannelId,
+		asset: Option<T::AssetId>,
+	) -> Result<u128, &'static str> {
+		let escrow = Self::escrow_account(channel_id)?;
+		Ok(match asset {
+			None => T::Currency::free_balance(&escrow).unique_saturated_into(),
+			Some(asset_id) =>
+				<T::Fungibles as Inspect<T::AccountId>>::balance(asset_id, &escrow).into(),
+		})
+	}
+
+	/// Checks that every escrow account holds at least the amount recorded in `TotalEscrow`.
+	///
+	/// Denoms escrowed in the same asset on a channel are summed, as they share one balance.
+	#[cfg(any(feature = "try-runtime", test))]
+	pub fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+		let mut totals: Vec<(ChannelId, Option<T::AssetId>, u128)> = Vec::new();
+		for (channel_id, denom, total) in <TotalEscrow<T>>::iter() {
+			let asset = Self::escrowed_asset(&denom).map_err(|error| {
+				log::error!(target: LOG_TARGET, "escrow of {:?} on {}: {}", denom, channel_id, error);
+				error
+			})?;
+			match totals.iter_mut().find(|(c, a, _)| *c == channel_id && *a == asset) {
+				Some((_, _, sum)) =>
+					*sum = sum.checked_add(total).ok_or("total escrow overflows on a channel")?,
+				None => totals.push((channel_id, asset, total)),
+			}
+		}
+
+		for (channel_id, asset, total) in totals {
+			let balance = Self::escrow_balance(&channel_id, asset.clone())?;
+			if balance < total {
+				log::error!(
+					target: LOG_TARGET,
+					"escrow of {:?} on {} holds {} but {} is outstanding",
+					asset,
+					channel_id,
+					balance,
+					total
+				);
+				return Err("escrow balance below outstanding total escrow".into());
+			}
+		}
+		Ok(())
+	}
+
+	/// Checks that the native token is an un-traced denom and that escrow accounts are derivable.
+	#[cfg(feature = "std")]
+	pub fn do_integrity_test() -> Result<(), &'static str> {
+		let native = core::str::from_utf8(T::NATIVE_TOKEN_NAME)
+			.map_err(|_| "native token name is not utf-8")?;
+		let native = PrefixedDenom::from_str(native).map_err(|_| "invalid native token name")?;
+		if !native.trace_path.is_empty() {
+			return Err("native token name must not carry a trace path");
+		}
+		Self::escrow_account(&ChannelId::new(0)).map(|_| ())
+	}
+}
@@ -24,6 +24,13 @@ use sp_runtime::{
 };
 use sp_std::str::FromStr;
 
+/// Name of the asset of `amt` in `AssetIdByName`, see `PrefixedDenom::asset_name`.
+fn asset_name(amt: &PrefixedCoin) -> Result<Vec<u8>, TokenTransferError> {
+	crate::denom::PrefixedDenom::from(amt.denom.clone())
+		.asset_name()
+		.ok_or(TokenTransferError::InvalidToken)
+}
+
 impl<T: Config> BankKeeper for IbcTransferModule<T> {
 	type AccountId = <Self as TokenTransferContext>::AccountId;
 
@@ -33,19 +40,16 @@ impl<T: Config> BankKeeper for IbcTransferModule<T> {
 		to: &Self::AccountId,
 		amt: &PrefixedCoin,
 	) -> Result<(), TokenTransferError> {
-		let is_native_asset = amt.denom.trace_path.is_empty();
+		// an un-traced denom other than the native token is an asset registered in
+		// `AssetIdByName`, e.g. one arriving over XCM
+		let is_native_asset = amt.denom.trace_path.is_empty() &&
+			amt.denom.base_denom.as_str().as_bytes() == T::NATIVE_TOKEN_NAME;
 		match is_native_asset {
 			// transfer native token
 			true => {
-				let amount = U256::from(amt.amount).low_u128().checked_into().unwrap(); // TODO: FIX IN THE FUTURE
-				let native_token_name = T::NATIVE_TOKEN_NAME;
-				let ibc_token_name = amt.denom.base_denom.as_str().as_bytes();
-
-				// assert native token name equal want to send ibc token name
-				assert_eq!(
-					native_token_name, ibc_token_name,
-					"send ibc token name is not native token name"
-				);
+				let amount = crate::utils::amount_to_u128(U256::from(amt.amount))
+					.and_then(|amount| amount.checked_into())
+					.ok_or(TokenTransferError::InvalidToken)?;
 
 				<T::Currency as Currency<T::AccountId>>::transfer(
 					&from.clone().into_account(),
@@ -70,10 +74,12 @@ impl<T: Config> BankKeeper for IbcTransferModule<T> {
 			},
 			// transfer non-native token
 			false => {
-				let amount = U256::from(amt.amount).low_u128().into();
-				let denom = amt.denom.base_denom.as_str();
+				let amount = crate::utils::amount_to_u128(U256::from(amt.amount))
+					.ok_or(TokenTransferError::InvalidToken)?
+					.into();
+				let denom = asset_name(amt)?;
 				// look cross chain asset have register in host chain
-				match T::AssetIdByName::try_get_asset_id(denom) {
+				match T::AssetIdByName::try_get_asset_id(&denom) {
 					Ok(token_id) => {
 						<T::Fungibles as Mutate<T::AccountId>>::transfer(
 							token_id,
@@ -110,17 +116,22 @@ impl<T: Config> BankKeeper for IbcTransferModule<T> {
 		account: &Self::AccountId,
 		amt: &PrefixedCoin,
 	) -> Result<(), TokenTransferError> {
-		let amount = U256::from(amt.amount).low_u128().into();
-		let denom = amt.denom.base_denom.as_str();
-		let denom_trace_hash =
-			crate::utils::derive_ibc_denom_with_path(&format!("{}", amt.denom.trace_path.clone()))?
-				.as_bytes()
-				.to_vec();
+		let amount = crate::utils::amount_to_u128(U256::from(amt.amount))
+			.ok_or(TokenTransferError::InvalidToken)?
+			.into();
+		let denom = asset_name(amt)?;
+		let denom_trace_hash = crate::utils::derive_ibc_denom_with_path(&format!("{}", amt.denom))?
+			.as_bytes()
+			.to_vec();
 		let denom_trace = crate::denom::PrefixedDenom::from(amt.denom.clone());
 		// insert denom trace hash, and demo_trace
-		<DenomTrace<T>>::insert(denom_trace_hash, denom_trace);
+		<DenomTrace<T>>::insert(denom_trace_hash.clone(), denom_trace);
+		Pallet::<T>::deposit_event(Event::<T>::DenomTrace {
+			trace_hash: denom_trace_hash,
+			denom: format!("{}", amt.denom).as_bytes().to_vec(),
+		});
 		// look cross chain asset have register in host chain
-		match T::AssetIdByName::try_get_asset_id(denom) {
+		match T::AssetIdByName::try_get_asset_id(&denom) {
 			Ok(token_id) => {
 				<T::Fungibles as Mutate<T::AccountId>>::mint_into(
 					token_id.clone(),
@@ -152,10 +163,12 @@ impl<T: Config> BankKeeper for IbcTransferModule<T> {
 		account: &Self::AccountId,
 		amt: &PrefixedCoin,
 	) -> Result<(), TokenTransferError> {
-		let amount = U256::from(amt.amount).low_u128().into();
-		let denom = amt.denom.base_denom.as_str();
+		let amount = crate::utils::amount_to_u128(U256::from(amt.amount))
+			.ok_or(TokenTransferError::InvalidToken)?
+			.into();
+		let denom = asset_name(amt)?;
 		// look cross chain asset have register in host chain
-		match T::AssetIdByName::try_get_asset_id(denom) {
+		match T::AssetIdByName::try_get_asset_id(&denom) {
 			Ok(token_id) => {
 				<T::Fungibles as Mutate<T::AccountId>>::burn_from(
 					token_id.clone(),
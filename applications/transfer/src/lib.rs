@@ -10,8 +10,13 @@ pub use pallet::*;
 pub mod callback;
 pub mod context_channel;
 pub mod denom;
+pub mod escrow;
 pub mod impls;
+pub mod memo;
+pub mod migrations;
+pub mod runtime_api;
 pub mod utils;
+pub mod v2;
 
 #[cfg(test)]
 mod tests;
@@ -19,32 +24,35 @@ mod tests;
 #[cfg(test)]
 mod mock;
 
-use crate::callback::IbcTransferModule;
+use crate::{callback::IbcTransferModule, utils::DecodedTransfer};
 use alloc::string::String;
-use frame_support::traits::BuildGenesisConfig;
 use frame_support::{
 	pallet_prelude::*,
 	traits::{
 		fungibles::Mutate,
 		tokens::{AssetId, Balance as AssetBalance},
-		Currency,
+		BuildGenesisConfig, Currency,
 	},
 };
 use frame_system::pallet_prelude::*;
 use ibc::{
-	applications::transfer::msgs::transfer::MsgTransfer,
-	core::ics04_channel::events::SendPacket,
+	applications::transfer::{error::TokenTransferError, msgs::transfer::MsgTransfer},
+	core::{ics04_channel::events::SendPacket, ics24_host::identifier::ChannelId},
 	events::IbcEvent,
 	handler::{HandlerOutput, HandlerOutputBuilder},
 	signer::Signer,
 };
 use ibc_proto::google::protobuf::Any;
 use pallet_ibc_utils::{AssetIdAndNameProvider, Router};
+use prost::Message;
 use sp_runtime::traits::IdentifyAccount;
 use sp_std::{fmt::Debug, vec::Vec};
 
 pub const LOG_TARGET: &str = "runtime::pallet-ics20-transfer";
 
+/// Type url of the ICS-20 `MsgTransfer`, shared by single and multi-denom transfers.
+pub const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
 type BalanceOf<T> =
 	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
@@ -52,7 +60,11 @@ type BalanceOf<T> =
 pub mod pallet {
 	use super::*;
 
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(_);
 
@@ -97,6 +109,13 @@ pub mod pallet {
 		type IbcContext: pallet_ibc_utils::traits::ChannelKeeperInterface
 			+ pallet_ibc_utils::traits::ChannelReaderInterface;
 
+		/// Hooks run for the memo actions of received packets, keyed by action name
+		type MemoHooks: memo::MemoHooks<Self::AccountId>;
+
+		/// Maximum length in bytes of the memo of an outbound transfer
+		#[pallet::constant]
+		type MaxMemoLength: Get<u32>;
+
 		// The native token name
 		const NATIVE_TOKEN_NAME: &'static [u8];
 	}
@@ -104,15 +123,34 @@ pub mod pallet {
 	type AssetName = Vec<u8>;
 
 	#[pallet::storage]
-	/// (asset name) => asset id
+	/// (asset name) => asset id, vouchers are named by their `ibc/{hash}` denomination and
+	/// untraced tokens by their base denomination
 	pub type AssetIdByName<T: Config> =
 		StorageMap<_, Twox64Concat, AssetName, T::AssetId, ValueQuery>;
 
 	#[pallet::storage]
-	// key: denom trace hash
+	/// asset id => (asset name), reverse index of `AssetIdByName`
+	pub type AssetNameById<T: Config> = StorageMap<_, Twox64Concat, T::AssetId, AssetName>;
+
+	#[pallet::storage]
+	// key: denom trace hash, `ibc/{hash}` of the full `{trace_path}/{base_denom}`
 	// value: denom trace
 	pub type DenomTrace<T: Config> = StorageMap<_, Blake2_128Concat, Vec<u8>, denom::PrefixedDenom>;
 
+	#[pallet::storage]
+	/// key1: channel id
+	/// key2: escrowed denom
+	/// value: total amount held in the channel escrow account
+	pub type TotalEscrow<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ChannelId,
+		Blake2_128Concat,
+		Vec<u8>,
+		u128,
+		ValueQuery,
+	>;
+
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub asset_id_by_name: Vec<(String, T::AssetId)>,
@@ -130,6 +168,7 @@ pub mod pallet {
 		fn build(&self) {
 			for (token_id, id) in self.asset_id_by_name.iter() {
 				<AssetIdByName<T>>::insert(token_id.as_bytes(), id);
+				<AssetNameById<T>>::insert(id, token_id.as_bytes().to_vec());
 			}
 		}
 	}
@@ -143,6 +182,40 @@ pub mod pallet {
 		SendPacket(SendPacket),
 		// unsupported event
 		UnsupportedEvent,
+		/// An outbound ICS20 transfer was committed
+		Transfer {
+			sender: Vec<u8>,
+			receiver: Vec<u8>,
+			denom: Vec<u8>,
+			amount: u128,
+		},
+		/// A denomination trace was recorded for a received voucher
+		DenomTrace {
+			trace_hash: Vec<u8>,
+			denom: Vec<u8>,
+		},
+		/// An inbound ICS20 packet was processed
+		RecvPacket {
+			sender: Vec<u8>,
+			receiver: Vec<u8>,
+			denom: Vec<u8>,
+			amount: u128,
+			success: bool,
+		},
+		/// An acknowledgement was received for an outbound ICS20 packet
+		AckStatus {
+			sender: Vec<u8>,
+			receiver: Vec<u8>,
+			denom: Vec<u8>,
+			amount: u128,
+			success: bool,
+		},
+		/// An outbound ICS20 packet timed out and the sender was refunded
+		Timeout {
+			refund_receiver: Vec<u8>,
+			denom: Vec<u8>,
+			amount: u128,
+		},
 		/// Transfer native token  event
 		TransferNativeToken(T::AccountIdConversion, T::AccountIdConversion, BalanceOf<T>),
 		/// Transfer non-native token event
@@ -168,6 +241,39 @@ pub mod pallet {
 		WrongAssetId,
 		///
 		DecodeStringFailed,
+		/// Token amount does not fit in a u128
+		InvalidAmount,
+		/// Sending tokens is disabled
+		SendDisabled,
+		/// Sender or receiver is not a valid account
+		InvalidAccount,
+		/// Send transfer failed
+		SendTransferFailed,
+		/// Total escrow of a denom on a channel overflowed
+		EscrowOverflow,
+		/// Released amount exceeds the total escrow of a denom on a channel
+		InsufficientEscrow,
+		/// Packet data is not valid ICS20 packet data
+		InvalidPacketData,
+		/// Memo exceeds the maximum memo length
+		MemoTooLong,
+		/// Multi-denom transfer over a channel that did not negotiate ics20-2
+		InvalidChannelVersion,
+		/// Requested packet forwarding is not supported
+		UnsupportedForwarding,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		#[cfg(feature = "std")]
+		fn integrity_test() {
+			Self::do_integrity_test().expect("invalid ics20 escrow configuration");
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_try_state()
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -212,10 +318,18 @@ impl<T: Config> AssetIdAndNameProvider<T::AssetId> for Pallet<T> {
 	}
 
 	fn try_get_asset_name(asset_id: T::AssetId) -> Result<Vec<u8>, Self::Err> {
-		let token_id = <AssetIdByName<T>>::iter().find(|p| p.1 == asset_id).map(|p| p.0);
-		match token_id {
-			Some(id) => Ok(id),
-			_ => Err(Error::<T>::WrongAssetId),
+		<AssetNameById<T>>::get(asset_id).ok_or(Error::<T>::WrongAssetId)
+	}
+}
+
+impl<T: Config> From<TokenTransferError> for Error<T> {
+	fn from(error: TokenTransferError) -> Self {
+		match error {
+			TokenTransferError::InvalidToken => Error::<T>::InvalidTokenId,
+			TokenTransferError::SendDisabled => Error::<T>::SendDisabled,
+			TokenTransferError::ParseAccountFailure | TokenTransferError::Signer(_) =>
+				Error::<T>::InvalidAccount,
+			_ => Error::<T>::SendTransferFailed,
 		}
 	}
 }
@@ -224,6 +338,10 @@ impl<T: Config> pallet_ibc_utils::Router for Pallet<T>
 where
 	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
 {
+	fn handles(type_url: &str) -> bool {
+		type_url == MSG_TRANSFER_TYPE_URL
+	}
+
 	fn dispatch(messages: Vec<Any>) -> DispatchResult {
 		let mut ctx = IbcTransferModule(PhantomData::<T>);
 
@@ -235,39 +353,84 @@ where
 
 		for message in messages {
 			let mut handle_out = HandlerOutputBuilder::new();
+			let raw_msg = v2::MsgTransferV2::decode(message.value.as_slice())
+				.map_err(|_| Error::<T>::ParserMsgTransferError)?;
+			ensure!(
+				raw_msg.memo.len() <= T::MaxMemoLength::get() as usize,
+				Error::<T>::MemoTooLong
+			);
+			// a non-empty `tokens` list makes it a multi-denom ics20-2 transfer
+			if !raw_msg.tokens.is_empty() {
+				frame_support::storage::with_storage_layer(|| {
+					Self::send_transfer_v2(&mut ctx, raw_msg)
+				})?;
+				continue;
+			}
 			let msg_transfer =
 				MsgTransfer::try_from(message).map_err(|_| Error::<T>::ParserMsgTransferError)?;
-			let result = ibc::applications::transfer::relay::send_transfer::send_transfer(
+			let transfer = DecodedTransfer::new(
+				&msg_transfer.sender,
+				&msg_transfer.receiver,
+				&msg_transfer.token,
+			)
+			.ok_or(Error::<T>::InvalidAmount)?;
+			let escrowed_denom = escrow::escrowed_denom_on_send(
+				&msg_transfer.port_on_a,
+				&msg_transfer.chan_on_a,
+				&transfer.denom,
+			);
+			let source_channel = msg_transfer.chan_on_a.clone();
+
+			ibc::applications::transfer::relay::send_transfer::send_transfer(
 				&mut ctx,
 				&mut handle_out,
 				msg_transfer,
-			);
-			match result {
-				Ok(_value) => {
-					log::trace!(target: LOG_TARGET, "raw_transfer Successful!");
-				},
-				Err(error) => {
-					log::trace!(target: LOG_TARGET, "raw_transfer Error : {:?} ", error);
-				},
+			)
+			.map_err(|error| {
+				log::error!(target: LOG_TARGET, "raw_transfer Error : {:?} ", error);
+				Error::<T>::from(error)
+			})?;
+
+			if let Some(denom) = escrowed_denom {
+				Self::increase_total_escrow(&source_channel, &denom, transfer.amount)?;
 			}
 
 			let HandlerOutput::<()> { result: _, log, events } = handle_out.with_result(());
 
 			log::trace!(target: LOG_TARGET, "raw_transfer log : {:?} ", log);
 
-			// deposit events about send packet event and ics20 transfer event
-			for event in events {
-				log::trace!(target: LOG_TARGET, "raw_transfer event : {:?} ", event);
-				match event {
-					IbcEvent::SendPacket(ref send_packet) => {
-						Self::deposit_event(Event::SendPacket(send_packet.clone()));
-					},
-					_ => {
-						Self::deposit_event(Event::UnsupportedEvent);
-					},
-				}
-			}
+			Self::deposit_send_events(events);
+
+			let DecodedTransfer { sender, receiver, denom, amount } = transfer;
+			Self::deposit_event(Event::Transfer { sender, receiver, denom, amount });
 		}
 		Ok(())
 	}
 }
+
+impl<T: Config> Pallet<T> {
+	/// Weight of sending a single `MsgTransfer`: the channel, connection, client and consensus
+	/// states, host timestamp and next sequence read by `send_packet`, the asset id, total escrow
+	/// and the two balances read, then the commitment, next sequence, packets in flight, total
+	/// escrow and the two balances written.
+	pub fn send_transfer_weight() -> Weight {
+		T::DbWeight::get().reads_writes(10, 6)
+	}
+
+	/// Deposit the events of a sent packet, the ics20 transfer event is surfaced separately as
+	/// `Event::Transfer`.
+	pub(crate) fn deposit_send_events(events: Vec<IbcEvent>) {
+		for event in events {
+			log::trace!(target: LOG_TARGET, "raw_transfer event : {:?} ", event);
+			match event {
+				IbcEvent::SendPacket(ref send_packet) => {
+					Self::deposit_event(Event::SendPacket(send_packet.clone()));
+				},
+				IbcEvent::AppModule(_) => {},
+				_ => {
+					Self::deposit_event(Event::UnsupportedEvent);
+				},
+			}
+		}
+	}
+}
@@ -0,0 +1,165 @@
+//! Receiver-side hooks driven by the `memo` of incoming ICS20 packets.
+//!
+//! A memo that is a JSON object is read as a set of actions keyed by name, e.g.
+//! `{"stake": {"validator": "..."}}`. Each action with a hook registered in
+//! [`Config::MemoHooks`](crate::Config::MemoHooks) runs after the funds have landed on the
+//! receiver. Actions without a hook are left for other middleware, and a memo that is not a JSON
+//! object is treated as plain text.
+use crate::{
+	escrow::escrowed_denom_on_recv,
+	utils::DecodedTransfer,
+	v2::{decode_transfers, PacketDataV2},
+	*,
+};
+use alloc::{format, string::String, vec::Vec};
+use frame_support::dispatch::DispatchResult;
+use ibc::{core::ics04_channel::packet::Packet, signer::Signer};
+use prost::Message;
+use serde_json::Value;
+use sp_runtime::traits::IdentifyAccount;
+
+/// An ICS20 transfer whose funds have landed on this chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceivedTransfer<AccountId> {
+	/// Account credited by the transfer.
+	pub receiver: AccountId,
+	/// Sender on the counterparty chain.
+	pub sender: Vec<u8>,
+	/// Denomination as known on this chain, i.e. including the trace of the receiving hop.
+	pub denom: Vec<u8>,
+	pub amount: u128,
+}
+
+/// A runtime action run for the memo action named [`MemoHook::ACTION`].
+pub trait MemoHook<AccountId> {
+	/// Name of the memo action handled by this hook.
+	const ACTION: &'static str;
+
+	/// Called with the arguments of the action once the funds have landed. An error rolls the
+	/// whole packet back and acknowledges it with an error.
+	fn on_recv(transfer: &ReceivedTransfer<AccountId>, args: &Value) -> DispatchResult;
+}
+
+/// A registry of [`MemoHook`]s keyed by their action name, usually a tuple of hooks.
+pub trait MemoHooks<AccountId> {
+	/// Runs the hook registered for `action`, or returns `None` if there is none.
+	fn on_recv(
+		action: &str,
+		transfer: &ReceivedTransfer<AccountId>,
+		args: &Value,
+	) -> Option<DispatchResult>;
+}
+
+impl<AccountId> MemoHooks<AccountId> for () {
+	fn on_recv(
+		_action: &str,
+		_transfer: &ReceivedTransfer<AccountId>,
+		_args: &Value,
+	) -> Option<DispatchResult> {
+		None
+	}
+}
+
+macro_rules! impl_memo_hooks_for_tuples {
+	($($hook:ident),+) => {
+		impl<AccountId, $($hook: MemoHook<AccountId>),+> MemoHooks<AccountId> for ($($hook,)+) {
+			fn on_recv(
+				action: &str,
+				transfer: &ReceivedTransfer<AccountId>,
+				args: &Value,
+			) -> Option<DispatchResult> {
+				$(
+					if action == $hook::ACTION {
+						return Some($hook::on_recv(transfer, args))
+					}
+				)+
+				None
+			}
+		}
+	};
+}
+
+impl_memo_hooks_for_tuples!(A);
+impl_memo_hooks_for_tuples!(A, B);
+impl_memo_hooks_for_tuples!(A, B, C);
+impl_memo_hooks_for_tuples!(A, B, C, D);
+impl_memo_hooks_for_tuples!(A, B, C, D, E);
+impl_memo_hooks_for_tuples!(A, B, C, D, E, F);
+impl_memo_hooks_for_tuples!(A, B, C, D, E, F, G);
+impl_memo_hooks_for_tuples!(A, B, C, D, E, F, G, H);
+
+/// Runs the hooks registered for the memo actions of `packet`, whose funds must already have been
+/// credited to the receiver.
+///
+/// `packet` carries either `ics20-1` JSON or `ics20-2` protobuf packet data. Each token of an
+/// `ics20-2` packet is handed to the hooks in turn.
+pub fn dispatch_memo_hooks<T: Config>(packet: &Packet) -> DispatchResult {
+	let (memo, receiver, transfers) = match decode_memo_packet(packet) {
+		Some(decoded) => decoded,
+		None => return Ok(()),
+	};
+	let actions = match serde_json::from_str::<Value>(&memo) {
+		Ok(Value::Object(actions)) => actions,
+		_ => return Ok(()),
+	};
+
+	let transfers = received_transfers::<T>(packet, &receiver, transfers)?;
+	for (action, args) in actions.iter() {
+		for transfer in &transfers {
+			if let Some(result) = T::MemoHooks::on_recv(action, transfer, args) {
+				log::trace!(target: LOG_TARGET, "memo hook {} : {:?}", action, result);
+				result?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Memo, receiver and tokens of `ics20-1` or `ics20-2` packet data.
+fn decode_memo_packet(packet: &Packet) -> Option<(String, String, Vec<DecodedTransfer>)> {
+	if let Ok(data) = serde_json::from_slice::<Value>(&packet.data) {
+		let field = |name: &str| data.get(name).and_then(Value::as_str).map(String::from);
+		return Some((
+			field("memo")?,
+			field("receiver").unwrap_or_default(),
+			DecodedTransfer::from_packet(packet).into_iter().collect(),
+		));
+	}
+	let data = PacketDataV2::decode(packet.data.as_slice()).ok()?;
+	Some((data.memo, data.receiver, decode_transfers(packet)))
+}
+
+fn received_transfers<T: Config>(
+	packet: &Packet,
+	receiver: &str,
+	transfers: Vec<DecodedTransfer>,
+) -> Result<Vec<ReceivedTransfer<T::AccountId>>, Error<T>> {
+	ensure!(!transfers.is_empty(), Error::<T>::InvalidPacketData);
+	let receiver = receiver
+		.parse::<Signer>()
+		.ok()
+		.and_then(|signer| T::AccountIdConversion::try_from(signer).ok())
+		.ok_or(Error::<T>::InvalidAccount)?
+		.into_account();
+
+	Ok(transfers
+		.into_iter()
+		.map(|transfer| {
+			let denom =
+				escrowed_denom_on_recv(&packet.port_on_a, &packet.chan_on_a, &transfer.denom)
+					.unwrap_or_else(|| {
+						let mut denom =
+							format!("{}/{}/", packet.port_on_b, packet.chan_on_b).into_bytes();
+						denom.extend_from_slice(&transfer.denom);
+						denom
+					});
+			ReceivedTransfer {
+				receiver: receiver.clone(),
+				sender: transfer.sender,
+				denom,
+				amount: transfer.amount,
+			}
+		})
+		.collect())
+}
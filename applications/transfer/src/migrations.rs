@@ -0,0 +1,136 @@
+//! Storage migrations of the ICS20 transfer pallet.
+use crate::{AssetIdByName, AssetNameById, Config, DenomTrace, Pallet, LOG_TARGET};
+use core::marker::PhantomData;
+use frame_support::{
+	traits::{Get, OnRuntimeUpgrade, StorageVersion},
+	weights::Weight,
+};
+use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
+
+pub mod v1 {
+	use super::*;
+
+	/// Maximum number of denom traces re-keyed by the migration, which runs within a single
+	/// block.
+	pub const MAX_DENOM_TRACES: u32 = 1_000;
+
+	/// Migrates the storage from version 0 to 1:
+	/// - re-keys `DenomTrace` by the hash of the full `{trace_path}/{base_denom}` of the voucher,
+	///   rather than the hash of its trace path alone which collides for vouchers of different base
+	///   denominations received over the same channel;
+	/// - names the asset of a voucher by its `ibc/{hash}` denomination, rather than its base
+	///   denomination, when a single denom trace has that base denomination;
+	/// - fills the `AssetNameById` reverse index of the existing `AssetIdByName` entries.
+	///
+	/// The upgrade fails if the storage holds more than `MAX_DENOM_TRACES` denom traces, so that
+	/// the new runtime never runs on storage of version 0.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() != 0 {
+				log::info!(target: LOG_TARGET, "MigrateToV1 should be removed, storage is up to date");
+				return T::DbWeight::get().reads(1);
+			}
+
+			let count = <DenomTrace<T>>::iter_keys().take(MAX_DENOM_TRACES as usize + 1).count();
+			if count > MAX_DENOM_TRACES as usize {
+				panic!(
+					"more than {} denom traces to migrate, refusing to run on storage of version 0",
+					MAX_DENOM_TRACES
+				);
+			}
+
+			let traces = <DenomTrace<T>>::drain().collect::<Vec<_>>();
+			let mut reads = 1 + 2 * traces.len() as u64;
+			let mut writes = 1 + traces.len() as u64;
+			let mut vouchers = BTreeMap::<Vec<u8>, Vec<Vec<u8>>>::new();
+			for (old_key, trace) in traces {
+				match trace.trace_hash() {
+					Some(key) => {
+						vouchers.entry(trace.base_denom.clone()).or_default().push(key.clone());
+						<DenomTrace<T>>::insert(key, trace);
+						writes += 1;
+					},
+					None => log::warn!(
+						target: LOG_TARGET,
+						"dropping undecodable denom trace stored under {:?}",
+						old_key
+					),
+				}
+			}
+
+			// the asset registered under a base denomination shared by several traces cannot be
+			// told apart, it is left to governance to register each of them
+			for (base_denom, hashes) in vouchers {
+				reads += 1;
+				let Ok(asset_id) = <AssetIdByName<T>>::try_get(&base_denom) else { continue };
+				match &hashes[..] {
+					[hash] => {
+						<AssetIdByName<T>>::insert(hash, asset_id.clone());
+						<AssetNameById<T>>::insert(asset_id, hash.clone());
+						writes += 2;
+					},
+					_ => log::warn!(
+						target: LOG_TARGET,
+						"{} denom traces share the registered base denom {:?}, none is named after it",
+						hashes.len(),
+						base_denom
+					),
+				}
+			}
+
+			for (name, asset_id) in <AssetIdByName<T>>::iter() {
+				reads += 2;
+				if !<AssetNameById<T>>::contains_key(&asset_id) {
+					<AssetNameById<T>>::insert(asset_id, name);
+					writes += 1;
+				}
+			}
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+			log::info!(target: LOG_TARGET, "migrated storage to version 1");
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+			use codec::Encode;
+			let traces = <DenomTrace<T>>::iter().count() as u32;
+			frame_support::ensure!(
+				traces <= MAX_DENOM_TRACES,
+				"too many denom traces to migrate in a single block"
+			);
+			Ok(traces.encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			use codec::Decode;
+			frame_support::ensure!(
+				StorageVersion::get::<Pallet<T>>() == 1,
+				"storage version was not updated to 1"
+			);
+			let traces = u32::decode(&mut state.as_slice())
+				.map_err(|_| "cannot decode the number of denom traces")?;
+			// traces whose old keys collided were already lost, re-keying cannot add any
+			frame_support::ensure!(
+				<DenomTrace<T>>::iter().count() as u32 <= traces,
+				"denom traces were added by the migration"
+			);
+			for (key, trace) in <DenomTrace<T>>::iter() {
+				frame_support::ensure!(
+					trace.trace_hash() == Some(key),
+					"denom trace is not keyed by the hash of its full path"
+				);
+			}
+			for (_, asset_id) in <AssetIdByName<T>>::iter() {
+				frame_support::ensure!(
+					<AssetNameById<T>>::contains_key(asset_id),
+					"asset id is missing from the reverse index"
+				);
+			}
+			Ok(())
+		}
+	}
+}
@@ -1,11 +1,13 @@
+pub mod relayer;
+
 use super::*;
 use crate as pallet_ics20_transfer;
 use codec::Encode;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU8, KeyOwnerProofSystem,
-		Randomness, StorageInfo,
+		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64, ConstU8,
+		KeyOwnerProofSystem, Randomness, StorageInfo,
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
@@ -17,11 +19,12 @@ use frame_system as system;
 use frame_system::EnsureRoot;
 use pallet_assets::AssetsCallback;
 use pallet_ibc_utils::module::Router;
+use pallet_ics20_transfer::memo::{MemoHook, ReceivedTransfer};
 use sp_io::storage;
 use sp_runtime::{
 	generic,
 	traits::{AccountIdLookup, BlakeTwo256, IdentifyAccount, Verify},
-	MultiSignature,
+	DispatchError, DispatchResult, MultiSignature,
 };
 
 pub type Signature = MultiSignature;
@@ -235,9 +238,38 @@ impl pallet_ics20_transfer::Config for Test {
 	type AssetIdByName = Ics20Transfer;
 	type IbcContext = pallet_ibc::context::Context<Test>;
 	type AccountIdConversion = pallet_ics20_transfer::impls::IbcAccount;
+	type MemoHooks = (RecordMemoHook, FailingMemoHook);
+	type MaxMemoLength = ConstU32<256>;
 	const NATIVE_TOKEN_NAME: &'static [u8] = b"DEMO";
 }
 
+/// Records the amount of the transfer for the memo action `record`.
+pub struct RecordMemoHook;
+impl MemoHook<AccountId> for RecordMemoHook {
+	const ACTION: &'static str = "record";
+
+	fn on_recv(
+		transfer: &ReceivedTransfer<AccountId>,
+		_args: &serde_json::Value,
+	) -> DispatchResult {
+		storage::set(b"memo_record", &transfer.amount.encode());
+		Ok(())
+	}
+}
+
+/// Always fails for the memo action `fail`.
+pub struct FailingMemoHook;
+impl MemoHook<AccountId> for FailingMemoHook {
+	const ACTION: &'static str = "fail";
+
+	fn on_recv(
+		_transfer: &ReceivedTransfer<AccountId>,
+		_args: &serde_json::Value,
+	) -> DispatchResult {
+		Err(DispatchError::Other("memo hook failed"))
+	}
+}
+
 pub type AssetBalance = u128;
 pub type AssetId = u32;
 
@@ -253,6 +285,12 @@ impl pallet_ibc::Config for Test {
 	const IBC_COMMITMENT_PREFIX: &'static [u8] = b"Ibc";
 	type ChainVersion = ChainVersion;
 	type IbcModule = IbcModule;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type OnMisbehaviour = ();
+	type UpgradeModule = crate::callback::IbcTransferModule<Test>;
+	type MaxPrunedPerBlock = ConstU32<10>;
+	type WasmClientGasLimit = ConstU64<10_000_000>;
+	type MaxWasmCodeSize = ConstU32<{ 3 * 1024 * 1024 }>;
 	type WeightInfo = ();
 }
 
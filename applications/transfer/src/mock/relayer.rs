@@ -0,0 +1,626 @@
+//! Two chains of the mock runtime, connected by an in-process relayer.
+//!
+//! Each chain tracks the other with an ibc-rs mock client, which accepts any proof, so the relayer
+//! submits the messages of a real relayer with dummy proofs at the latest height of the source.
+use super::{
+	AccountId, Balances, Ibc, Ics20Transfer, RuntimeEvent, RuntimeOrigin, System, Test, Timestamp,
+	DOLLARS, MILLISECS_PER_BLOCK,
+};
+use crate::{
+	utils::derive_ibc_denom_with_path, v2::MsgTransferV2, AssetIdByName, AssetNameById,
+	MSG_TRANSFER_TYPE_URL,
+};
+use frame_support::assert_ok;
+use ibc::{
+	applications::transfer::{PORT_ID_STR, VERSION},
+	core::{
+		ics02_client::{
+			context::ClientReader,
+			msgs::{create_client::MsgCreateClient, update_client::MsgUpdateClient},
+		},
+		ics03_connection::{
+			connection::ConnectionEnd,
+			context::ConnectionReader,
+			version::{get_compatible_versions, Version as ConnectionVersion},
+		},
+		ics04_channel::{
+			channel::ChannelEnd, context::ChannelReader, events::SendPacket, packet::Packet,
+		},
+		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+	},
+	events::IbcEvent,
+	mock::{
+		client_state::{client_type as mock_client_type, MockClientState},
+		consensus_state::MockConsensusState,
+		header::MockHeader,
+	},
+	tx_msg::Msg,
+	Height,
+};
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin,
+	google::protobuf::Any,
+	ibc::{
+		applications::transfer::v1::MsgTransfer as RawMsgTransfer,
+		core::{
+			channel::v1::{
+				Channel as RawChannel, Counterparty as RawChannelCounterparty,
+				MsgAcknowledgement as RawMsgAcknowledgement,
+				MsgChannelCloseInit as RawMsgChannelCloseInit,
+				MsgChannelOpenAck as RawMsgChannelOpenAck,
+				MsgChannelOpenConfirm as RawMsgChannelOpenConfirm,
+				MsgChannelOpenInit as RawMsgChannelOpenInit,
+				MsgChannelOpenTry as RawMsgChannelOpenTry, MsgRecvPacket as RawMsgRecvPacket,
+				MsgTimeout as RawMsgTimeout, Order as RawOrder, State as RawChannelState,
+			},
+			commitment::v1::MerklePrefix,
+			connection::v1::{
+				Counterparty as RawConnectionCounterparty,
+				MsgConnectionOpenAck as RawMsgConnectionOpenAck,
+				MsgConnectionOpenConfirm as RawMsgConnectionOpenConfirm,
+				MsgConnectionOpenInit as RawMsgConnectionOpenInit,
+				MsgConnectionOpenTry as RawMsgConnectionOpenTry,
+			},
+		},
+	},
+};
+use pallet_ibc::{
+	channel::allow_timeout::{
+		MSG_CHANNEL_OPEN_INIT_TYPE_URL, MSG_CHANNEL_OPEN_TRY_TYPE_URL, MSG_RECV_PACKET_TYPE_URL,
+		MSG_TIMEOUT_TYPE_URL,
+	},
+	context::Context,
+	redundant_relay::MSG_ACKNOWLEDGEMENT_TYPE_URL,
+	ChannelCounter, ClientCounter, ConnectionCounter,
+};
+use prost::Message;
+use sp_runtime::BuildStorage;
+use std::str::FromStr;
+
+const MSG_CONNECTION_OPEN_INIT_TYPE_URL: &str = "/ibc.core.connection.v1.MsgConnectionOpenInit";
+const MSG_CONNECTION_OPEN_TRY_TYPE_URL: &str = "/ibc.core.connection.v1.MsgConnectionOpenTry";
+const MSG_CONNECTION_OPEN_ACK_TYPE_URL: &str = "/ibc.core.connection.v1.MsgConnectionOpenAck";
+const MSG_CONNECTION_OPEN_CONFIRM_TYPE_URL: &str =
+	"/ibc.core.connection.v1.MsgConnectionOpenConfirm";
+const MSG_CHANNEL_OPEN_ACK_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenAck";
+const MSG_CHANNEL_OPEN_CONFIRM_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenConfirm";
+const MSG_CHANNEL_CLOSE_INIT_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelCloseInit";
+
+pub const ALICE: AccountId = AccountId::new([1u8; 32]);
+pub const BOB: AccountId = AccountId::new([2u8; 32]);
+pub const RELAYER: AccountId = AccountId::new([3u8; 32]);
+pub const INITIAL_BALANCE: u128 = 1_000 * DOLLARS;
+/// Asset of the `DEMO` vouchers, the native token of the counterparty.
+pub const DEMO_VOUCHER_ID: u32 = 1;
+
+/// The ICS-20 signer of `account`.
+pub fn signer(account: &AccountId) -> String {
+	format!("0x{}", hex::encode(account))
+}
+
+fn any(type_url: &str, message: impl Message) -> Any {
+	Any { type_url: type_url.to_string(), value: message.encode_to_vec() }
+}
+
+fn proof() -> Vec<u8> {
+	vec![1]
+}
+
+fn transfer_port() -> PortId {
+	PortId::from_str(PORT_ID_STR).unwrap()
+}
+
+/// The packet committed by `event`.
+fn sent_packet(event: &SendPacket) -> Packet {
+	Packet {
+		sequence: *event.sequence(),
+		port_on_a: event.src_port_id().clone(),
+		chan_on_a: event.src_channel_id().clone(),
+		port_on_b: event.dst_port_id().clone(),
+		chan_on_b: event.dst_channel_id().clone(),
+		data: event.packet_data().to_vec(),
+		timeout_height_on_b: *event.timeout_height(),
+		timeout_timestamp_on_b: *event.timeout_timestamp(),
+	}
+}
+
+/// A chain of the mock runtime and the identifiers of its end of the transfer path.
+pub struct Chain {
+	ext: sp_io::TestExternalities,
+	/// Client of the counterparty.
+	pub client_id: ClientId,
+	pub connection_id: ConnectionId,
+	pub channel_id: ChannelId,
+}
+
+impl Chain {
+	/// A chain where `ALICE` and `BOB` hold native tokens and the asset of `DEMO` vouchers exists.
+	fn new() -> Self {
+		let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(ALICE, INITIAL_BALANCE), (BOB, INITIAL_BALANCE)],
+		}
+		.assimilate_storage(&mut storage)
+		.unwrap();
+		pallet_assets::GenesisConfig::<Test, pallet_assets::Instance1> {
+			assets: vec![(DEMO_VOUCHER_ID, ALICE, true, 1)],
+			metadata: vec![],
+			accounts: vec![],
+		}
+		.assimilate_storage(&mut storage)
+		.unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(storage);
+		ext.execute_with(|| {
+			System::set_block_number(1);
+			Timestamp::set_timestamp(MILLISECS_PER_BLOCK);
+		});
+		Self {
+			ext,
+			client_id: ClientId::default(),
+			connection_id: ConnectionId::default(),
+			channel_id: ChannelId::default(),
+		}
+	}
+
+	pub fn execute_with<R>(&mut self, execute: impl FnOnce() -> R) -> R {
+		self.ext.execute_with(execute)
+	}
+
+	pub fn height(&mut self) -> Height {
+		self.execute_with(|| ClientReader::host_height(&Context::<Test>::new()).unwrap())
+	}
+
+	/// Header of the current block, as tracked by the client of the counterparty.
+	fn header(&mut self) -> MockHeader {
+		self.execute_with(|| {
+			let ctx = Context::<Test>::new();
+			MockHeader::new(ClientReader::host_height(&ctx).unwrap())
+				.with_timestamp(ClientReader::host_timestamp(&ctx).unwrap())
+		})
+	}
+
+	/// Latest height of the counterparty known to its client.
+	fn client_height(&mut self) -> Height {
+		let client_id = self.client_id.clone();
+		self.execute_with(|| {
+			ClientReader::client_state(&Context::<Test>::new(), &client_id)
+				.unwrap()
+				.latest_height()
+		})
+	}
+
+	pub fn connection_end(&mut self) -> ConnectionEnd {
+		let connection_id = self.connection_id.clone();
+		self.execute_with(|| {
+			ConnectionReader::connection_end(&Context::<Test>::new(), &connection_id).unwrap()
+		})
+	}
+
+	pub fn channel_end(&mut self) -> ChannelEnd {
+		let channel_id = self.channel_id.clone();
+		self.execute_with(|| {
+			ChannelReader::channel_end(&Context::<Test>::new(), &transfer_port(), &channel_id)
+				.unwrap()
+		})
+	}
+
+	pub fn next_block(&mut self) {
+		self.execute_with(|| {
+			let number = System::block_number() + 1;
+			System::set_block_number(number);
+			Timestamp::set_timestamp(MILLISECS_PER_BLOCK * u64::from(number));
+		})
+	}
+
+	/// Deliver `messages` in the current block and move on to the next one, returning the ibc
+	/// events. Panics if one of the messages fails.
+	fn deliver(&mut self, messages: Vec<Any>) -> Vec<IbcEvent> {
+		let events = self.execute_with(|| {
+			let first = System::events().len();
+			assert_ok!(Ibc::deliver(RuntimeOrigin::signed(RELAYER), messages));
+			System::events()[first..]
+				.iter()
+				.flat_map(|record| match &record.event {
+					RuntimeEvent::Ibc(pallet_ibc::Event::IbcEvents { events }) => events.clone(),
+					RuntimeEvent::Ibc(pallet_ibc::Event::IbcErrors { errors }) => {
+						panic!("ibc errors: {:?}", errors)
+					},
+					_ => vec![],
+				})
+				.collect()
+		});
+		self.next_block();
+		events
+	}
+
+	/// Send `amount` of `denom` from `sender` to `receiver` on the counterparty over the transfer
+	/// channel, returning the sent packet.
+	pub fn transfer(
+		&mut self,
+		sender: &AccountId,
+		receiver: &str,
+		denom: &str,
+		amount: u128,
+		timeout_height: Height,
+	) -> Packet {
+		let message = RawMsgTransfer {
+			source_port: PORT_ID_STR.to_string(),
+			source_channel: self.channel_id.to_string(),
+			token: Some(RawCoin { denom: denom.to_string(), amount: amount.to_string() }),
+			sender: signer(sender),
+			receiver: receiver.to_string(),
+			timeout_height: Some(timeout_height.into()),
+			timeout_timestamp: 0,
+			..Default::default()
+		};
+		self.send(sender, any(MSG_TRANSFER_TYPE_URL, message))
+	}
+
+	/// `MsgTransfer` of the `tokens` from `sender` to `receiver` in a single `ics20-2` packet.
+	pub fn transfer_v2_message(
+		&self,
+		sender: &AccountId,
+		receiver: &str,
+		tokens: &[(&str, u128)],
+		timeout_height: Height,
+	) -> Any {
+		let message = MsgTransferV2 {
+			source_port: PORT_ID_STR.to_string(),
+			source_channel: self.channel_id.to_string(),
+			sender: signer(sender),
+			receiver: receiver.to_string(),
+			timeout_height: Some(timeout_height.into()),
+			timeout_timestamp: 0,
+			memo: String::new(),
+			tokens: tokens
+				.iter()
+				.map(|(denom, amount)| RawCoin {
+					denom: denom.to_string(),
+					amount: amount.to_string(),
+				})
+				.collect(),
+			forwarding: None,
+		};
+		any(MSG_TRANSFER_TYPE_URL, message)
+	}
+
+	/// Submit the transfer `message` signed by `sender`, returning the sent packet.
+	pub fn send(&mut self, sender: &AccountId, message: Any) -> Packet {
+		let packet = self.execute_with(|| {
+			let first = System::events().len();
+			assert_ok!(Ics20Transfer::raw_transfer(
+				RuntimeOrigin::signed(sender.clone()),
+				vec![message]
+			));
+			System::events()[first..]
+				.iter()
+				.find_map(|record| match &record.event {
+					RuntimeEvent::Ics20Transfer(crate::Event::SendPacket(event)) =>
+						Some(sent_packet(event)),
+					_ => None,
+				})
+				.expect("the transfer sends a packet")
+		});
+		self.next_block();
+		packet
+	}
+
+	/// Close the transfer channel on this end.
+	pub fn close_channel(&mut self) {
+		self.deliver(vec![any(
+			MSG_CHANNEL_CLOSE_INIT_TYPE_URL,
+			RawMsgChannelCloseInit {
+				port_id: PORT_ID_STR.to_string(),
+				channel_id: self.channel_id.to_string(),
+				signer: signer(&RELAYER),
+			},
+		)]);
+	}
+
+	pub fn balance(&mut self, account: &AccountId) -> u128 {
+		self.execute_with(|| Balances::free_balance(account))
+	}
+}
+
+/// Create on `host` a client of `counterparty` at its current height.
+fn create_client(host: &mut Chain, counterparty: &mut Chain) {
+	let header = counterparty.header();
+	host.client_id = host
+		.execute_with(|| ClientId::new(mock_client_type(), ClientCounter::<Test>::get()))
+		.unwrap();
+	host.deliver(vec![MsgCreateClient::new(
+		MockClientState::new(header).into(),
+		MockConsensusState::new(header).into(),
+		signer(&RELAYER).parse().unwrap(),
+	)
+	.to_any()]);
+}
+
+/// Update the client of `src` on `dst` to the current height of `src`, if it is behind, and
+/// return that height to prove the state of `src` at.
+fn update_client(dst: &mut Chain, src: &mut Chain) -> Height {
+	let header = src.header();
+	if dst.client_height() < header.height {
+		dst.deliver(vec![MsgUpdateClient {
+			client_id: dst.client_id.clone(),
+			header: header.into(),
+			signer: signer(&RELAYER).parse().unwrap(),
+		}
+		.to_any()]);
+	}
+	header.height
+}
+
+fn connection_counterparty(chain: &Chain) -> RawConnectionCounterparty {
+	RawConnectionCounterparty {
+		client_id: chain.client_id.to_string(),
+		connection_id: chain.connection_id.to_string(),
+		prefix: Some(MerklePrefix {
+			key_prefix: <Test as pallet_ibc::Config>::IBC_COMMITMENT_PREFIX.to_vec(),
+		}),
+	}
+}
+
+fn transfer_channel(
+	state: RawChannelState,
+	counterparty_channel_id: String,
+	connection_id: &ConnectionId,
+	version: &str,
+) -> RawChannel {
+	RawChannel {
+		state: state as i32,
+		ordering: RawOrder::Unordered as i32,
+		counterparty: Some(RawChannelCounterparty {
+			port_id: PORT_ID_STR.to_string(),
+			channel_id: counterparty_channel_id,
+		}),
+		connection_hops: vec![connection_id.to_string()],
+		version: version.to_string(),
+	}
+}
+
+/// Relay `packet` from `src` to `dst` and its acknowledgement back, returning the
+/// acknowledgement.
+fn relay_packet(src: &mut Chain, dst: &mut Chain, packet: &Packet) -> Vec<u8> {
+	let proof_height = update_client(dst, src);
+	let events = dst.deliver(vec![any(
+		MSG_RECV_PACKET_TYPE_URL,
+		RawMsgRecvPacket {
+			packet: Some(packet.clone().into()),
+			proof_commitment: proof(),
+			proof_height: Some(proof_height.into()),
+			signer: signer(&RELAYER),
+		},
+	)]);
+	let acknowledgement = events
+		.iter()
+		.find_map(|event| match event {
+			IbcEvent::WriteAcknowledgement(event) => {
+				let acknowledgement: &[u8] = event.acknowledgement().as_ref();
+				Some(acknowledgement.to_vec())
+			},
+			_ => None,
+		})
+		.expect("the packet is acknowledged");
+
+	let proof_height = update_client(src, dst);
+	src.deliver(vec![any(
+		MSG_ACKNOWLEDGEMENT_TYPE_URL,
+		RawMsgAcknowledgement {
+			packet: Some(packet.clone().into()),
+			acknowledgement: acknowledgement.clone(),
+			proof_acked: proof(),
+			proof_height: Some(proof_height.into()),
+			signer: signer(&RELAYER),
+		},
+	)]);
+	acknowledgement
+}
+
+/// Chains `a` and `b`, and the relayer between them.
+pub struct Relayer {
+	pub a: Chain,
+	pub b: Chain,
+}
+
+impl Relayer {
+	pub fn new() -> Self {
+		Self { a: Chain::new(), b: Chain::new() }
+	}
+
+	/// Chains with an open transfer channel between them.
+	pub fn connected() -> Self {
+		Self::connected_with_version(VERSION)
+	}
+
+	/// Chains with a transfer channel of `version` open between them.
+	pub fn connected_with_version(version: &str) -> Self {
+		let mut relayer = Self::new();
+		relayer.create_clients();
+		relayer.open_connection();
+		relayer.open_channel_with_version(version);
+		relayer
+	}
+
+	pub fn create_clients(&mut self) {
+		create_client(&mut self.a, &mut self.b);
+		create_client(&mut self.b, &mut self.a);
+	}
+
+	#[allow(deprecated)]
+	pub fn open_connection(&mut self) {
+		let Self { a, b } = self;
+
+		a.connection_id = a.execute_with(|| ConnectionId::new(ConnectionCounter::<Test>::get()));
+		a.deliver(vec![any(
+			MSG_CONNECTION_OPEN_INIT_TYPE_URL,
+			RawMsgConnectionOpenInit {
+				client_id: a.client_id.to_string(),
+				counterparty: Some(RawConnectionCounterparty {
+					connection_id: String::new(),
+					..connection_counterparty(b)
+				}),
+				version: Some(ConnectionVersion::default().into()),
+				delay_period: 0,
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		let proof_height = update_client(b, a);
+		let consensus_height = a.client_height();
+		b.connection_id = b.execute_with(|| ConnectionId::new(ConnectionCounter::<Test>::get()));
+		b.deliver(vec![any(
+			MSG_CONNECTION_OPEN_TRY_TYPE_URL,
+			RawMsgConnectionOpenTry {
+				client_id: b.client_id.to_string(),
+				previous_connection_id: String::new(),
+				client_state: Some(MockClientState::new(MockHeader::new(consensus_height)).into()),
+				counterparty: Some(connection_counterparty(a)),
+				delay_period: 0,
+				counterparty_versions: get_compatible_versions()
+					.into_iter()
+					.map(Into::into)
+					.collect(),
+				proof_init: proof(),
+				proof_height: Some(proof_height.into()),
+				proof_consensus: proof(),
+				consensus_height: Some(consensus_height.into()),
+				proof_client: proof(),
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		let proof_height = update_client(a, b);
+		let consensus_height = b.client_height();
+		a.deliver(vec![any(
+			MSG_CONNECTION_OPEN_ACK_TYPE_URL,
+			RawMsgConnectionOpenAck {
+				connection_id: a.connection_id.to_string(),
+				counterparty_connection_id: b.connection_id.to_string(),
+				proof_try: proof(),
+				proof_height: Some(proof_height.into()),
+				proof_consensus: proof(),
+				consensus_height: Some(consensus_height.into()),
+				client_state: Some(MockClientState::new(MockHeader::new(consensus_height)).into()),
+				proof_client: proof(),
+				version: Some(ConnectionVersion::default().into()),
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		let proof_height = update_client(b, a);
+		b.deliver(vec![any(
+			MSG_CONNECTION_OPEN_CONFIRM_TYPE_URL,
+			RawMsgConnectionOpenConfirm {
+				connection_id: b.connection_id.to_string(),
+				proof_ack: proof(),
+				proof_height: Some(proof_height.into()),
+				signer: signer(&RELAYER),
+			},
+		)]);
+	}
+
+	pub fn open_channel(&mut self) {
+		self.open_channel_with_version(VERSION)
+	}
+
+	#[allow(deprecated)]
+	pub fn open_channel_with_version(&mut self, version: &str) {
+		let Self { a, b } = self;
+
+		a.channel_id = a.execute_with(|| ChannelId::new(ChannelCounter::<Test>::get()));
+		a.deliver(vec![any(
+			MSG_CHANNEL_OPEN_INIT_TYPE_URL,
+			RawMsgChannelOpenInit {
+				port_id: PORT_ID_STR.to_string(),
+				channel: Some(transfer_channel(
+					RawChannelState::Init,
+					String::new(),
+					&a.connection_id,
+					version,
+				)),
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		let proof_height = update_client(b, a);
+		b.channel_id = b.execute_with(|| ChannelId::new(ChannelCounter::<Test>::get()));
+		b.deliver(vec![any(
+			MSG_CHANNEL_OPEN_TRY_TYPE_URL,
+			RawMsgChannelOpenTry {
+				port_id: PORT_ID_STR.to_string(),
+				previous_channel_id: String::new(),
+				channel: Some(transfer_channel(
+					RawChannelState::Tryopen,
+					a.channel_id.to_string(),
+					&b.connection_id,
+					version,
+				)),
+				counterparty_version: version.to_string(),
+				proof_init: proof(),
+				proof_height: Some(proof_height.into()),
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		let proof_height = update_client(a, b);
+		a.deliver(vec![any(
+			MSG_CHANNEL_OPEN_ACK_TYPE_URL,
+			RawMsgChannelOpenAck {
+				port_id: PORT_ID_STR.to_string(),
+				channel_id: a.channel_id.to_string(),
+				counterparty_channel_id: b.channel_id.to_string(),
+				counterparty_version: version.to_string(),
+				proof_try: proof(),
+				proof_height: Some(proof_height.into()),
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		let proof_height = update_client(b, a);
+		b.deliver(vec![any(
+			MSG_CHANNEL_OPEN_CONFIRM_TYPE_URL,
+			RawMsgChannelOpenConfirm {
+				port_id: PORT_ID_STR.to_string(),
+				channel_id: b.channel_id.to_string(),
+				proof_ack: proof(),
+				proof_height: Some(proof_height.into()),
+				signer: signer(&RELAYER),
+			},
+		)]);
+
+		// vouchers of `a`'s native token are registered under the hash of their trace on `b`
+		let trace = format!("{}/{}/DEMO", PORT_ID_STR, b.channel_id);
+		let name = derive_ibc_denom_with_path(&trace).unwrap().into_bytes();
+		b.execute_with(|| {
+			AssetIdByName::<Test>::insert(&name, DEMO_VOUCHER_ID);
+			AssetNameById::<Test>::insert(DEMO_VOUCHER_ID, name);
+		});
+	}
+
+	/// Relay `packet` sent by `a` and its acknowledgement, returning the acknowledgement.
+	pub fn relay_from_a(&mut self, packet: &Packet) -> Vec<u8> {
+		relay_packet(&mut self.a, &mut self.b, packet)
+	}
+
+	/// Relay `packet` sent by `b` and its acknowledgement, returning the acknowledgement.
+	pub fn relay_from_b(&mut self, packet: &Packet) -> Vec<u8> {
+		relay_packet(&mut self.b, &mut self.a, packet)
+	}
+
+	/// Time out `packet` sent by `a`, proving `b` is past its timeout height.
+	pub fn time_out_from_a(&mut self, packet: &Packet) {
+		let proof_height = update_client(&mut self.a, &mut self.b);
+		self.a.deliver(vec![any(
+			MSG_TIMEOUT_TYPE_URL,
+			RawMsgTimeout {
+				packet: Some(packet.clone().into()),
+				proof_unreceived: proof(),
+				proof_height: Some(proof_height.into()),
+				next_sequence_recv: u64::from(packet.sequence),
+				signer: signer(&RELAYER),
+			},
+		)]);
+	}
+}
@@ -0,0 +1,27 @@
+//! Runtime API of the ICS20 transfer pallet.
+//!
+//! A runtime implements it by forwarding to the matching `Pallet` functions, e.g.
+//! `Ics20TransferApi::denom_trace` to `pallet_ics20_transfer::Pallet::<Runtime>::denom_trace`.
+use crate::denom::{DenomTracePage, PrefixedDenom};
+use alloc::vec::Vec;
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+	/// Queries of the ICS20 denomination traces and the asset registry.
+	pub trait Ics20TransferApi<AssetId>
+	where
+		AssetId: Codec,
+	{
+		/// Full trace of a voucher denomination, given as `ibc/{hash}` or as the bare hash.
+		fn denom_trace(denom: Vec<u8>) -> Option<PrefixedDenom>;
+
+		/// At most `limit` denomination traces following `start_after`, or from the first one.
+		fn denom_traces(start_after: Option<Vec<u8>>, limit: u32) -> DenomTracePage;
+
+		/// Asset id of a voucher `ibc/{hash}` denomination or of a registered asset name.
+		fn asset_id_by_denom(denom: Vec<u8>) -> Option<AssetId>;
+
+		/// Registered asset name of an asset id.
+		fn denom_by_asset_id(asset_id: AssetId) -> Option<Vec<u8>>;
+	}
+}
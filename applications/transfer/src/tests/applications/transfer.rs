@@ -89,3 +89,95 @@ pub mod test_util {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::test_util::{get_dummy_msg_transfer, get_dummy_substrate_account};
+	use crate::{
+		callback::IbcTransferModule,
+		impls::IbcAccount,
+		mock::{new_test_ext, AccountId, Assets, Ics20Transfer, RuntimeOrigin, System, Test},
+		AssetIdByName, DenomTrace, Error,
+	};
+	use core::marker::PhantomData;
+	use frame_support::{assert_noop, assert_ok, traits::fungibles::Inspect};
+	use ibc::{
+		applications::transfer::{context::BankKeeper, BaseCoin, PrefixedCoin},
+		core::ics04_channel::timeout::TimeoutHeight,
+		tx_msg::Msg,
+	};
+	use ibc_proto::google::protobuf::Any;
+	use primitive_types::U256;
+	use sp_runtime::traits::IdentifyAccount;
+
+	#[test]
+	fn raw_transfer_rejects_malformed_message() {
+		new_test_ext().execute_with(|| {
+			let message = Any {
+				type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
+				value: vec![0xff, 0xff],
+			};
+
+			assert_noop!(
+				Ics20Transfer::raw_transfer(
+					RuntimeOrigin::signed(AccountId::new([1u8; 32])),
+					vec![message]
+				),
+				Error::<Test>::ParserMsgTransferError
+			);
+		})
+	}
+
+	#[test]
+	fn raw_transfer_fails_without_channel() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let message = get_dummy_msg_transfer(TimeoutHeight::Never, None).to_any();
+
+			// no channel exists, so `send_transfer` fails and the extrinsic must fail too
+			assert_noop!(
+				Ics20Transfer::raw_transfer(
+					RuntimeOrigin::signed(AccountId::new([1u8; 32])),
+					vec![message]
+				),
+				Error::<Test>::SendTransferFailed
+			);
+			assert!(System::events().is_empty());
+		})
+	}
+
+	#[test]
+	fn amounts_above_u128_are_rejected_for_assets() {
+		new_test_ext().execute_with(|| {
+			let account = IbcAccount::try_from(
+				get_dummy_substrate_account().parse::<ibc::signer::Signer>().unwrap(),
+			)
+			.unwrap();
+			let owner = account.clone().into_account();
+			assert_ok!(Assets::force_create(
+				RuntimeOrigin::root(),
+				1,
+				owner.clone().into(),
+				true,
+				1
+			));
+			AssetIdByName::<Test>::insert(b"uatom".to_vec(), 1);
+
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			let coin = |amount: U256| -> PrefixedCoin {
+				BaseCoin { denom: "uatom".parse().unwrap(), amount: amount.into() }.into()
+			};
+			let overflow = coin(U256::from(u128::MAX) + 1);
+
+			// the amount must not be truncated to its low 128 bits
+			assert!(ctx.mint_coins(&account, &overflow).is_err());
+			assert!(ctx.burn_coins(&account, &overflow).is_err());
+			assert!(ctx.send_coins(&account, &account, &overflow).is_err());
+			assert_eq!(Assets::total_issuance(1), 0);
+			assert_eq!(DenomTrace::<Test>::iter().count(), 0);
+
+			assert_ok!(ctx.mint_coins(&account, &coin(U256::from(10))));
+			assert_eq!(Assets::balance(1, &owner), 10);
+		})
+	}
+}
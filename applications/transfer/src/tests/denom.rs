@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		denom::PrefixedDenom,
+		mock::{new_test_ext, Test},
+		utils::derive_ibc_denom_with_path,
+		DenomTrace, GenesisConfig, Pallet,
+	};
+	use frame_support::traits::BuildGenesisConfig;
+	use pallet_ibc_utils::AssetIdAndNameProvider;
+
+	fn insert_trace(trace_path: &str, base_denom: &str) -> Vec<u8> {
+		let hash = derive_ibc_denom_with_path(&format!("{}/{}", trace_path, base_denom))
+			.unwrap()
+			.into_bytes();
+		DenomTrace::<Test>::insert(
+			&hash,
+			PrefixedDenom {
+				trace_path: trace_path.as_bytes().to_vec(),
+				base_denom: base_denom.as_bytes().to_vec(),
+			},
+		);
+		hash
+	}
+
+	#[test]
+	fn denom_trace_by_hash() {
+		new_test_ext().execute_with(|| {
+			let hash = insert_trace("transfer/channel-0", "uatom");
+			let expected = PrefixedDenom {
+				trace_path: b"transfer/channel-0".to_vec(),
+				base_denom: b"uatom".to_vec(),
+			};
+
+			assert_eq!(Pallet::<Test>::denom_trace(&hash), Some(expected.clone()));
+			// the bare hash resolves as well
+			assert_eq!(Pallet::<Test>::denom_trace(&hash[4..]), Some(expected));
+			assert_eq!(Pallet::<Test>::denom_trace(b"ibc/UNKNOWN"), None);
+		})
+	}
+
+	#[test]
+	fn denom_traces_are_paginated() {
+		new_test_ext().execute_with(|| {
+			for base_denom in ["uatom", "uosmo", "ujuno", "uakt", "uscrt"] {
+				insert_trace("transfer/channel-0", base_denom);
+			}
+
+			let mut seen = Vec::new();
+			let mut start_after = None;
+			loop {
+				let page = Pallet::<Test>::denom_traces(start_after, 2);
+				assert!(page.traces.len() <= 2);
+				seen.extend(page.traces.into_iter().map(|(denom, _)| denom));
+				match page.next {
+					Some(next) => start_after = Some(next),
+					None => break,
+				}
+			}
+
+			seen.sort();
+			seen.dedup();
+			assert_eq!(seen.len(), 5);
+			assert!(Pallet::<Test>::denom_traces(None, 0).traces.is_empty());
+		})
+	}
+
+	#[test]
+	fn asset_id_and_denom_resolve_both_ways() {
+		new_test_ext().execute_with(|| {
+			let hash = insert_trace("transfer/channel-0", "uatom");
+			let other = insert_trace("transfer/channel-1", "uatom");
+			GenesisConfig::<Test> {
+				asset_id_by_name: vec![
+					("uatom".to_string(), 7),
+					(String::from_utf8(hash.clone()).unwrap(), 9),
+				],
+			}
+			.build();
+
+			assert_eq!(Pallet::<Test>::asset_id_by_denom(b"uatom"), Some(7));
+			assert_eq!(Pallet::<Test>::asset_id_by_denom(&hash), Some(9));
+			// vouchers sharing the base denom of another origin are a different asset
+			assert_eq!(Pallet::<Test>::asset_id_by_denom(&other), None);
+			assert_eq!(Pallet::<Test>::asset_id_by_denom(b"ibc/UNKNOWN"), None);
+
+			assert_eq!(Pallet::<Test>::denom_by_asset_id(7), Some(b"uatom".to_vec()));
+			assert_eq!(Pallet::<Test>::denom_by_asset_id(9), Some(hash));
+			assert_eq!(Pallet::<Test>::try_get_asset_name(7), Ok(b"uatom".to_vec()));
+			assert!(Pallet::<Test>::try_get_asset_name(8).is_err());
+		})
+	}
+}
@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		escrow::{escrowed_denom_on_recv, escrowed_denom_on_send},
+		impls::IbcAccount,
+		mock::{new_test_ext, Assets, Balances, RuntimeOrigin, Test, DOLLARS},
+		utils::get_channel_escrow_address,
+		AssetIdByName, DenomTrace, Error, Pallet, TotalEscrow,
+	};
+	use frame_support::{
+		assert_noop, assert_ok,
+		traits::{fungibles::Mutate, Currency},
+	};
+	use ibc::core::ics24_host::identifier::{ChannelId, PortId};
+	use sp_runtime::{traits::IdentifyAccount, AccountId32};
+
+	fn escrow_account(channel_id: &ChannelId) -> AccountId32 {
+		IbcAccount::try_from(get_channel_escrow_address(&PortId::transfer(), channel_id).unwrap())
+			.unwrap()
+			.into_account()
+	}
+
+	/// Records the trace of the voucher `denom`, as done when it is first received.
+	/// Records the trace of `denom`, returning its `ibc/{hash}` denomination.
+	fn record_trace(denom: &str) -> Vec<u8> {
+		let trace = crate::denom::PrefixedDenom::from(
+			denom.parse::<ibc::applications::transfer::denom::PrefixedDenom>().unwrap(),
+		);
+		let hash = trace.trace_hash().unwrap();
+		DenomTrace::<Test>::insert(&hash, trace);
+		hash
+	}
+
+	#[test]
+	fn escrowed_denom_follows_source_tracing() {
+		let port_id = PortId::transfer();
+		let channel_id = ChannelId::new(0);
+
+		// native and foreign-sourced tokens are escrowed when sent
+		assert_eq!(escrowed_denom_on_send(&port_id, &channel_id, b"DEMO"), Some(b"DEMO".to_vec()));
+		assert_eq!(
+			escrowed_denom_on_send(&port_id, &channel_id, b"transfer/channel-1/uatom"),
+			Some(b"transfer/channel-1/uatom".to_vec())
+		);
+		// vouchers received over the same channel are burned instead
+		assert_eq!(
+			escrowed_denom_on_send(&port_id, &channel_id, b"transfer/channel-0/uatom"),
+			None
+		);
+
+		// tokens prefixed by the counterparty channel end come back out of escrow
+		assert_eq!(
+			escrowed_denom_on_recv(&port_id, &channel_id, b"transfer/channel-0/DEMO"),
+			Some(b"DEMO".to_vec())
+		);
+		assert_eq!(escrowed_denom_on_recv(&port_id, &channel_id, b"uatom"), None);
+	}
+
+	#[test]
+	fn total_escrow_increase_and_decrease() {
+		new_test_ext().execute_with(|| {
+			let channel_id = ChannelId::new(0);
+
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", 10));
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 10);
+
+			assert_noop!(
+				Pallet::<Test>::decrease_total_escrow(&channel_id, b"DEMO", 11),
+				Error::<Test>::InsufficientEscrow
+			);
+			assert_ok!(Pallet::<Test>::decrease_total_escrow(&channel_id, b"DEMO", 10));
+			assert!(!TotalEscrow::<Test>::contains_key(&channel_id, b"DEMO".to_vec()));
+		})
+	}
+
+	#[test]
+	fn total_escrow_overflow_is_rejected() {
+		new_test_ext().execute_with(|| {
+			let channel_id = ChannelId::new(0);
+
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", u128::MAX));
+			assert_noop!(
+				Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", 1),
+				Error::<Test>::EscrowOverflow
+			);
+		})
+	}
+
+	#[test]
+	fn try_state_checks_escrow_balance() {
+		new_test_ext().execute_with(|| {
+			let channel_id = ChannelId::new(0);
+			let escrow = escrow_account(&channel_id);
+
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", 10 * DOLLARS));
+			assert!(Pallet::<Test>::do_try_state().is_err());
+
+			Balances::make_free_balance_be(&escrow, 10 * DOLLARS);
+			assert_ok!(Pallet::<Test>::do_try_state());
+		})
+	}
+
+	#[test]
+	fn try_state_checks_each_origin_against_its_own_asset() {
+		new_test_ext().execute_with(|| {
+			let channel_id = ChannelId::new(0);
+			let escrow = escrow_account(&channel_id);
+			// vouchers of `uatom` from two different origins are different assets
+			let (first, second) = ("transfer/channel-1/uatom", "transfer/channel-2/uatom");
+			for (asset_id, denom) in [(1, first), (2, second)] {
+				assert_ok!(Assets::force_create(
+					RuntimeOrigin::root(),
+					asset_id,
+					escrow.clone().into(),
+					true,
+					1
+				));
+				AssetIdByName::<Test>::insert(record_trace(denom), asset_id);
+			}
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, first.as_bytes(), 10));
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, second.as_bytes(), 10));
+
+			assert_ok!(Assets::mint_into(1, &escrow, 20));
+			assert!(Pallet::<Test>::do_try_state().is_err());
+			assert_ok!(Assets::mint_into(2, &escrow, 10));
+			assert_ok!(Pallet::<Test>::do_try_state());
+		})
+	}
+
+	#[test]
+	fn try_state_rejects_escrowed_denom_without_trace() {
+		new_test_ext().execute_with(|| {
+			let channel_id = ChannelId::new(0);
+			AssetIdByName::<Test>::insert(b"uatom".to_vec(), 1);
+
+			// the base denom alone does not identify a traced token
+			assert_ok!(Pallet::<Test>::increase_total_escrow(
+				&channel_id,
+				b"transfer/channel-1/uatom",
+				10
+			));
+			assert!(Pallet::<Test>::do_try_state().is_err());
+		})
+	}
+
+	#[test]
+	fn integrity_test_accepts_mock_configuration() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Pallet::<Test>::do_integrity_test());
+		})
+	}
+}
@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		callback::IbcTransferModule,
+		memo::dispatch_memo_hooks,
+		mock::{new_test_ext, AccountId, Assets, Ics20Transfer, RuntimeOrigin, Test},
+		tests::applications::transfer::test_util::{
+			get_dummy_msg_transfer, get_dummy_substrate_account, get_dummy_transfer_packet,
+		},
+		utils::{derive_ibc_denom_with_path, is_success_ack},
+		AssetIdByName, DenomTrace, Error,
+	};
+	use codec::Decode;
+	use core::marker::PhantomData;
+	use frame_support::{assert_noop, assert_ok, traits::fungibles::Inspect};
+	use ibc::core::{
+		ics04_channel::{packet::Packet, timeout::TimeoutHeight},
+		ics24_host::identifier::{ChannelId, PortId},
+		ics26_routing::context::{Module, ModuleOutputBuilder},
+	};
+	use ibc_proto::{
+		google::protobuf::Any, ibc::applications::transfer::v1::MsgTransfer as RawMsgTransfer,
+	};
+	use prost::Message;
+	use sp_io::storage;
+
+	fn packet_with_memo(memo: Option<&str>) -> Packet {
+		let msg = get_dummy_msg_transfer(TimeoutHeight::Never, None);
+		let mut packet = get_dummy_transfer_packet(msg, 1.into());
+		if let Some(memo) = memo {
+			let mut data: serde_json::Value = serde_json::from_slice(&packet.data).unwrap();
+			data["memo"] = serde_json::Value::String(memo.to_string());
+			packet.data = serde_json::to_vec(&data).unwrap();
+		}
+		packet
+	}
+
+	/// Registers the asset backing the `uatom` vouchers minted by the dummy packets.
+	fn register_uatom() {
+		assert_ok!(Assets::force_create(
+			RuntimeOrigin::root(),
+			1,
+			AccountId::new([1u8; 32]).into(),
+			true,
+			1
+		));
+		let voucher = format!("{}/{}/uatom", PortId::default(), ChannelId::default());
+		AssetIdByName::<Test>::insert(
+			derive_ibc_denom_with_path(&voucher).unwrap().into_bytes(),
+			1,
+		);
+	}
+
+	/// Receives `packet` through the transfer module, returning the acknowledgement and the
+	/// number of events reported in the module output.
+	fn recv(packet: &Packet) -> (Vec<u8>, usize) {
+		let mut output = ModuleOutputBuilder::new();
+		let relayer = get_dummy_substrate_account().parse().unwrap();
+		let ack =
+			IbcTransferModule::<Test>(PhantomData).on_recv_packet(&mut output, packet, &relayer);
+		(ack.as_ref().to_vec(), output.with_result(()).events.len())
+	}
+
+	fn recorded_amount() -> Option<u128> {
+		storage::get(b"memo_record").map(|value| u128::decode(&mut &value[..]).unwrap())
+	}
+
+	#[test]
+	fn packet_without_memo_runs_no_hook() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(dispatch_memo_hooks::<Test>(&packet_with_memo(None)));
+			assert_ok!(dispatch_memo_hooks::<Test>(&packet_with_memo(Some("plain text memo"))));
+			assert_eq!(recorded_amount(), None);
+		})
+	}
+
+	#[test]
+	fn memo_action_runs_registered_hook() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(dispatch_memo_hooks::<Test>(&packet_with_memo(Some(r#"{"record":{}}"#))));
+			assert_eq!(recorded_amount(), Some(10));
+		})
+	}
+
+	#[test]
+	fn unknown_memo_action_is_ignored() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(dispatch_memo_hooks::<Test>(&packet_with_memo(Some(r#"{"forward":{}}"#))));
+			assert_eq!(recorded_amount(), None);
+		})
+	}
+
+	#[test]
+	fn failing_memo_hook_returns_error() {
+		new_test_ext().execute_with(|| {
+			assert!(dispatch_memo_hooks::<Test>(&packet_with_memo(Some(r#"{"fail":{}}"#))).is_err());
+		})
+	}
+
+	#[test]
+	fn raw_transfer_rejects_long_memo() {
+		new_test_ext().execute_with(|| {
+			let message = Any {
+				type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
+				value: RawMsgTransfer { memo: "x".repeat(257), ..Default::default() }
+					.encode_to_vec(),
+			};
+
+			assert_noop!(
+				Ics20Transfer::raw_transfer(
+					RuntimeOrigin::signed(AccountId::new([1u8; 32])),
+					vec![message]
+				),
+				Error::<Test>::MemoTooLong
+			);
+		})
+	}
+
+	#[test]
+	fn recv_runs_memo_hook_after_minting() {
+		new_test_ext().execute_with(|| {
+			register_uatom();
+
+			let (ack, events) = recv(&packet_with_memo(Some(r#"{"record":{}}"#)));
+			assert!(is_success_ack(&ack));
+			assert!(events > 0);
+			assert_eq!(Assets::total_issuance(1), 10);
+			assert_eq!(recorded_amount(), Some(10));
+		})
+	}
+
+	#[test]
+	fn failing_memo_hook_rolls_back_recv() {
+		new_test_ext().execute_with(|| {
+			register_uatom();
+
+			// the vouchers were minted before the hook failed
+			let (ack, events) = recv(&packet_with_memo(Some(r#"{"fail":{}}"#)));
+			assert!(!is_success_ack(&ack));
+			assert_eq!(events, 0);
+			assert_eq!(Assets::total_issuance(1), 0);
+			assert_eq!(DenomTrace::<Test>::iter().count(), 0);
+		})
+	}
+}
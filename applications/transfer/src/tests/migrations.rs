@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		denom::PrefixedDenom,
+		migrations::v1::{MigrateToV1, MAX_DENOM_TRACES},
+		mock::{new_test_ext, Ics20Transfer, Test},
+		utils::derive_ibc_denom_with_path,
+		AssetIdByName, AssetNameById, DenomTrace,
+	};
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	fn trace(trace_path: &str, base_denom: &str) -> PrefixedDenom {
+		PrefixedDenom {
+			trace_path: trace_path.as_bytes().to_vec(),
+			base_denom: base_denom.as_bytes().to_vec(),
+		}
+	}
+
+	fn hash(path: &str) -> Vec<u8> {
+		derive_ibc_denom_with_path(path).unwrap().into_bytes()
+	}
+
+	#[test]
+	fn migrate_denom_traces_to_v1() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(0).put::<Ics20Transfer>();
+			// version 0 keyed the traces by the hash of the trace path alone
+			DenomTrace::<Test>::insert(
+				hash("transfer/channel-0"),
+				trace("transfer/channel-0", "uatom"),
+			);
+			DenomTrace::<Test>::insert(
+				hash("transfer/channel-1/transfer/channel-7"),
+				trace("transfer/channel-1/transfer/channel-7", "uosmo"),
+			);
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get::<Ics20Transfer>(), 1);
+			assert_eq!(DenomTrace::<Test>::get(hash("transfer/channel-0")), None);
+			assert_eq!(
+				DenomTrace::<Test>::get(hash("transfer/channel-0/uatom")),
+				Some(trace("transfer/channel-0", "uatom"))
+			);
+			assert_eq!(
+				DenomTrace::<Test>::get(hash("transfer/channel-1/transfer/channel-7/uosmo")),
+				Some(trace("transfer/channel-1/transfer/channel-7", "uosmo"))
+			);
+			assert_eq!(DenomTrace::<Test>::iter().count(), 2);
+		})
+	}
+
+	#[test]
+	fn migrate_asset_name_index_to_v1() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(0).put::<Ics20Transfer>();
+			AssetIdByName::<Test>::insert(b"uatom".to_vec(), 7);
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+
+			assert_eq!(AssetNameById::<Test>::get(7), Some(b"uatom".to_vec()));
+		})
+	}
+
+	#[test]
+	fn migrate_voucher_asset_names_to_v1() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(0).put::<Ics20Transfer>();
+			DenomTrace::<Test>::insert(
+				hash("transfer/channel-0"),
+				trace("transfer/channel-0", "uatom"),
+			);
+			DenomTrace::<Test>::insert(
+				hash("transfer/channel-1"),
+				trace("transfer/channel-1", "uosmo"),
+			);
+			DenomTrace::<Test>::insert(
+				hash("transfer/channel-2"),
+				trace("transfer/channel-2", "uosmo"),
+			);
+			AssetIdByName::<Test>::insert(b"uatom".to_vec(), 7);
+			AssetIdByName::<Test>::insert(b"uosmo".to_vec(), 8);
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+
+			let uatom = hash("transfer/channel-0/uatom");
+			assert_eq!(AssetIdByName::<Test>::try_get(&uatom), Ok(7));
+			assert_eq!(AssetNameById::<Test>::get(7), Some(uatom));
+			// two origins share `uosmo`, neither is given its asset
+			assert!(!AssetIdByName::<Test>::contains_key(hash("transfer/channel-1/uosmo")));
+			assert!(!AssetIdByName::<Test>::contains_key(hash("transfer/channel-2/uosmo")));
+			assert_eq!(AssetNameById::<Test>::get(8), Some(b"uosmo".to_vec()));
+		})
+	}
+
+	#[test]
+	#[should_panic(expected = "refusing to run on storage of version 0")]
+	fn migration_to_v1_is_bounded() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(0).put::<Ics20Transfer>();
+			for channel in 0..=MAX_DENOM_TRACES {
+				let path = format!("transfer/channel-{}", channel);
+				DenomTrace::<Test>::insert(hash(&path), trace(&path, "uatom"));
+			}
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+		})
+	}
+
+	#[test]
+	fn migration_to_v1_runs_once() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(1).put::<Ics20Transfer>();
+			DenomTrace::<Test>::insert(hash("custom"), trace("transfer/channel-0", "uatom"));
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+
+			assert!(DenomTrace::<Test>::contains_key(hash("custom")));
+		})
+	}
+
+	#[test]
+	fn trace_hash_of_native_denom() {
+		assert_eq!(trace("", "uatom").trace_hash(), Some(hash("uatom")));
+		assert_eq!(
+			trace("transfer/channel-0", "uatom").trace_hash(),
+			Some(hash("transfer/channel-0/uatom"))
+		);
+	}
+}
@@ -1,2 +1,9 @@
 mod applications;
+mod denom;
+mod escrow;
+mod memo;
+mod migrations;
+mod relay;
+mod route_table;
 mod routing;
+mod v2;
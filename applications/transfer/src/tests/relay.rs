@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		impls::IbcAccount,
+		mock::{
+			relayer::{signer, Chain, Relayer, ALICE, BOB, DEMO_VOUCHER_ID, INITIAL_BALANCE},
+			Assets, RuntimeEvent, System, Test, DOLLARS,
+		},
+		utils::{get_channel_escrow_address, is_success_ack},
+		DenomTrace, Event, Pallet,
+	};
+	use frame_support::traits::fungibles::Inspect;
+	use ibc::core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{channel::State as ChannelState, context::ChannelReader, packet::Packet},
+		ics24_host::identifier::PortId,
+	};
+	use pallet_ibc::context::Context;
+	use sp_runtime::{traits::IdentifyAccount, AccountId32};
+
+	const AMOUNT: u128 = 100 * DOLLARS;
+
+	fn escrow_account(chain: &Chain) -> AccountId32 {
+		IbcAccount::try_from(
+			get_channel_escrow_address(&PortId::transfer(), &chain.channel_id).unwrap(),
+		)
+		.unwrap()
+		.into_account()
+	}
+
+	fn total_escrow(chain: &mut Chain) -> u128 {
+		let channel_id = chain.channel_id.clone();
+		chain.execute_with(|| Pallet::<Test>::total_escrow(&channel_id, b"DEMO"))
+	}
+
+	fn vouchers(chain: &mut Chain, account: &AccountId32) -> u128 {
+		chain.execute_with(|| Assets::balance(DEMO_VOUCHER_ID, account))
+	}
+
+	fn is_committed(chain: &mut Chain, packet: &Packet) -> bool {
+		chain.execute_with(|| {
+			ChannelReader::get_packet_commitment(
+				&Context::<Test>::new(),
+				&packet.port_on_a,
+				&packet.chan_on_a,
+				&packet.sequence,
+			)
+			.is_ok()
+		})
+	}
+
+	fn transfer_events(chain: &mut Chain) -> Vec<Event<Test>> {
+		chain.execute_with(|| {
+			System::events()
+				.into_iter()
+				.filter_map(|record| match record.event {
+					RuntimeEvent::Ics20Transfer(event) => Some(event),
+					_ => None,
+				})
+				.collect()
+		})
+	}
+
+	/// Sends `AMOUNT` of the native token of `a` from `ALICE` to `receiver` on `b`.
+	fn send_demo(relayer: &mut Relayer, receiver: &str) -> Packet {
+		let timeout_height = relayer.b.height().add(100);
+		relayer.a.transfer(&ALICE, receiver, "DEMO", AMOUNT, timeout_height)
+	}
+
+	#[test]
+	fn handshake_opens_a_transfer_channel() {
+		let mut relayer = Relayer::new();
+
+		relayer.create_clients();
+		assert_eq!(relayer.a.client_id.to_string(), "9999-mock-0");
+		assert_eq!(relayer.b.client_id.to_string(), "9999-mock-0");
+
+		relayer.open_connection();
+		let (connection_a, connection_b) = (relayer.a.connection_end(), relayer.b.connection_end());
+		assert_eq!(connection_a.state(), &ConnectionState::Open);
+		assert_eq!(connection_b.state(), &ConnectionState::Open);
+		assert_eq!(connection_a.counterparty().connection_id(), Some(&relayer.b.connection_id));
+		assert_eq!(connection_b.counterparty().connection_id(), Some(&relayer.a.connection_id));
+
+		relayer.open_channel();
+		let (channel_a, channel_b) = (relayer.a.channel_end(), relayer.b.channel_end());
+		assert_eq!(channel_a.state(), &ChannelState::Open);
+		assert_eq!(channel_b.state(), &ChannelState::Open);
+		assert_eq!(channel_a.counterparty().channel_id(), Some(&relayer.b.channel_id));
+		assert_eq!(channel_b.counterparty().channel_id(), Some(&relayer.a.channel_id));
+	}
+
+	#[test]
+	fn transfer_is_received_and_acknowledged() {
+		let mut relayer = Relayer::connected();
+
+		let packet = send_demo(&mut relayer, &signer(&BOB));
+		assert_eq!(relayer.a.balance(&ALICE), INITIAL_BALANCE - AMOUNT);
+		assert_eq!(relayer.a.balance(&escrow_account(&relayer.a)), AMOUNT);
+		assert_eq!(total_escrow(&mut relayer.a), AMOUNT);
+		assert!(is_committed(&mut relayer.a, &packet));
+
+		let acknowledgement = relayer.relay_from_a(&packet);
+		assert!(is_success_ack(&acknowledgement));
+
+		// `BOB` holds vouchers of the `DEMO` of `a`
+		let voucher = format!("transfer/{}/DEMO", relayer.b.channel_id);
+		assert_eq!(vouchers(&mut relayer.b, &BOB), AMOUNT);
+		let traced = relayer.b.execute_with(|| {
+			DenomTrace::<Test>::iter_values().any(|denom| denom.full_path() == voucher.as_bytes())
+		});
+		assert!(traced);
+		assert!(transfer_events(&mut relayer.b).contains(&Event::RecvPacket {
+			sender: signer(&ALICE).into_bytes(),
+			receiver: signer(&BOB).into_bytes(),
+			denom: b"DEMO".to_vec(),
+			amount: AMOUNT,
+			success: true,
+		}));
+
+		// the tokens stay escrowed on `a` once acknowledged
+		assert!(!is_committed(&mut relayer.a, &packet));
+		assert_eq!(total_escrow(&mut relayer.a), AMOUNT);
+		assert!(transfer_events(&mut relayer.a)
+			.iter()
+			.any(|event| matches!(event, Event::AckStatus { success: true, .. })));
+	}
+
+	#[test]
+	fn vouchers_return_to_the_source_chain() {
+		let mut relayer = Relayer::connected();
+		let packet = send_demo(&mut relayer, &signer(&BOB));
+		relayer.relay_from_a(&packet);
+
+		let voucher = format!("transfer/{}/DEMO", relayer.b.channel_id);
+		let timeout_height = relayer.a.height().add(100);
+		let receiver = signer(&ALICE);
+		let packet = relayer.b.transfer(&BOB, &receiver, &voucher, 40 * DOLLARS, timeout_height);
+		// the vouchers are burned on `b`
+		assert_eq!(vouchers(&mut relayer.b, &BOB), 60 * DOLLARS);
+		assert_eq!(
+			relayer.b.execute_with(|| Assets::total_issuance(DEMO_VOUCHER_ID)),
+			60 * DOLLARS
+		);
+
+		let acknowledgement = relayer.relay_from_b(&packet);
+		assert!(is_success_ack(&acknowledgement));
+
+		// and released from the escrow on `a`
+		assert_eq!(relayer.a.balance(&ALICE), INITIAL_BALANCE - 60 * DOLLARS);
+		assert_eq!(relayer.a.balance(&escrow_account(&relayer.a)), 60 * DOLLARS);
+		assert_eq!(total_escrow(&mut relayer.a), 60 * DOLLARS);
+		assert!(!is_committed(&mut relayer.b, &packet));
+	}
+
+	#[test]
+	fn error_acknowledgement_refunds_the_sender() {
+		let mut relayer = Relayer::connected();
+
+		// `b` cannot credit a receiver which is not an account
+		let packet = send_demo(&mut relayer, "cosmos1receiver");
+		let acknowledgement = relayer.relay_from_a(&packet);
+		assert!(!is_success_ack(&acknowledgement));
+
+		assert_eq!(relayer.b.execute_with(|| Assets::total_issuance(DEMO_VOUCHER_ID)), 0);
+		assert!(transfer_events(&mut relayer.b)
+			.iter()
+			.any(|event| matches!(event, Event::RecvPacket { success: false, .. })));
+
+		assert_eq!(relayer.a.balance(&ALICE), INITIAL_BALANCE);
+		assert_eq!(relayer.a.balance(&escrow_account(&relayer.a)), 0);
+		assert_eq!(total_escrow(&mut relayer.a), 0);
+		assert!(!is_committed(&mut relayer.a, &packet));
+		assert!(transfer_events(&mut relayer.a)
+			.iter()
+			.any(|event| matches!(event, Event::AckStatus { success: false, .. })));
+	}
+
+	#[test]
+	fn timed_out_transfer_is_refunded() {
+		let mut relayer = Relayer::connected();
+
+		let timeout_height = relayer.b.height().add(1);
+		let packet = relayer.a.transfer(&ALICE, &signer(&BOB), "DEMO", AMOUNT, timeout_height);
+		assert_eq!(total_escrow(&mut relayer.a), AMOUNT);
+
+		// the packet is never relayed before `b` reaches the timeout height
+		relayer.b.next_block();
+		relayer.time_out_from_a(&packet);
+
+		assert_eq!(relayer.a.balance(&ALICE), INITIAL_BALANCE);
+		assert_eq!(relayer.a.balance(&escrow_account(&relayer.a)), 0);
+		assert_eq!(total_escrow(&mut relayer.a), 0);
+		assert!(!is_committed(&mut relayer.a, &packet));
+		assert!(transfer_events(&mut relayer.a).contains(&Event::Timeout {
+			refund_receiver: signer(&ALICE).into_bytes(),
+			denom: b"DEMO".to_vec(),
+			amount: AMOUNT,
+		}));
+
+		assert_eq!(vouchers(&mut relayer.b, &BOB), 0);
+		assert!(transfer_events(&mut relayer.b).is_empty());
+	}
+}
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, Ibc, Ics20Transfer},
+		MSG_TRANSFER_TYPE_URL,
+	};
+	use frame_support::assert_noop;
+	use pallet_ibc_utils::Router;
+	use sp_runtime::DispatchError;
+
+	type Routers = (Ibc, (Ics20Transfer, ()));
+
+	#[test]
+	fn type_urls_resolve_to_their_route() {
+		assert_eq!(<Routers as Router>::ROUTES, 2);
+		assert_eq!(Routers::route("/ibc.core.client.v1.MsgCreateClient"), Some(0));
+		assert_eq!(Routers::route("/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeTry"), Some(0));
+		assert_eq!(Routers::route(MSG_TRANSFER_TYPE_URL), Some(1));
+		assert_eq!(Routers::route("/ibc.applications.interchain_accounts.v1.MsgSend"), None);
+		assert!(!Routers::handles("/cosmos.bank.v1beta1.MsgSend"));
+	}
+
+	#[test]
+	fn route_order_decides_overlapping_type_urls() {
+		type Reversed = (Ics20Transfer, (Ibc, ()));
+		assert_eq!(Reversed::route(MSG_TRANSFER_TYPE_URL), Some(0));
+		assert_eq!(Reversed::route("/ibc.core.client.v1.MsgUpdateClient"), Some(1));
+	}
+
+	#[test]
+	fn unknown_routes_are_rejected() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Routers::dispatch_to(2, vec![]),
+				DispatchError::Other("unknown ibc route")
+			);
+		});
+	}
+}
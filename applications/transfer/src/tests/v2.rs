@@ -0,0 +1,347 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		callback::IbcTransferModule,
+		impls::IbcAccount,
+		mock::{
+			new_test_ext,
+			relayer::{signer, Relayer, ALICE, BOB, DEMO_VOUCHER_ID, INITIAL_BALANCE},
+			AccountId, Assets, Balances, Ics20Transfer, RuntimeOrigin, Test, DOLLARS,
+		},
+		tests::applications::transfer::test_util::get_dummy_substrate_account,
+		utils::{get_channel_escrow_address, is_success_ack},
+		v2::{Denom, Hop, MsgTransferV2, PacketDataV2, Token, VERSION_V2},
+		Error, Pallet,
+	};
+	use codec::Decode;
+	use core::marker::PhantomData;
+	use frame_support::{
+		assert_noop, assert_ok,
+		traits::{fungibles::Inspect, Currency},
+	};
+	use ibc::{
+		core::{
+			ics04_channel::{
+				channel::{Counterparty, Order},
+				context::ChannelReader,
+				msgs::acknowledgement::Acknowledgement,
+				packet::Packet,
+				timeout::TimeoutHeight,
+				Version,
+			},
+			ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+			ics26_routing::context::{Module, ModuleOutputBuilder},
+		},
+		signer::Signer,
+		timestamp::Timestamp,
+	};
+	use ibc_proto::ibc::applications::transfer::v1::MsgTransfer as RawMsgTransfer;
+	use pallet_ibc::context::Context;
+	use prost::Message;
+	use sp_io::storage;
+	use sp_runtime::traits::IdentifyAccount;
+
+	fn packet(tokens: &[(&str, u128)]) -> Packet {
+		packet_with_memo(tokens, "")
+	}
+
+	fn packet_with_memo(tokens: &[(&str, u128)], memo: &str) -> Packet {
+		let data = PacketDataV2 {
+			tokens: tokens
+				.iter()
+				.map(|(denom, amount)| Token {
+					denom: Denom::from_path(denom),
+					amount: amount.to_string(),
+				})
+				.collect(),
+			sender: get_dummy_substrate_account(),
+			receiver: get_dummy_substrate_account(),
+			memo: memo.to_string(),
+			forwarding: None,
+		};
+		Packet {
+			sequence: 1.into(),
+			port_on_a: PortId::transfer(),
+			chan_on_a: ChannelId::new(0),
+			port_on_b: PortId::transfer(),
+			chan_on_b: ChannelId::new(1),
+			data: data.encode_to_vec(),
+			timeout_height_on_b: TimeoutHeight::Never,
+			timeout_timestamp_on_b: Timestamp::none(),
+		}
+	}
+
+	fn account(address: Signer) -> AccountId {
+		IbcAccount::try_from(address).unwrap().into_account()
+	}
+
+	fn sender() -> AccountId {
+		account(get_dummy_substrate_account().parse().unwrap())
+	}
+
+	fn escrow_account(channel_id: &ChannelId) -> AccountId {
+		account(get_channel_escrow_address(&PortId::transfer(), channel_id).unwrap())
+	}
+
+	fn recv(ctx: &mut IbcTransferModule<Test>, packet: &Packet) -> Acknowledgement {
+		ctx.on_recv_packet_v2(&mut ModuleOutputBuilder::new(), packet)
+	}
+
+	/// `DEMO` returning over channel-1, where `escrowed` was escrowed by an earlier send.
+	fn escrow_demo(escrowed: u128) -> ChannelId {
+		let channel_id = ChannelId::new(1);
+		Balances::make_free_balance_be(&escrow_account(&channel_id), 30 * DOLLARS);
+		assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", escrowed));
+		channel_id
+	}
+
+	#[test]
+	fn denom_path_round_trip() {
+		let denom = Denom::from_path("transfer/channel-1/transfer/channel-7/uatom").unwrap();
+		assert_eq!(denom.base, "uatom");
+		assert_eq!(
+			denom.trace,
+			vec![
+				Hop { port_id: "transfer".to_string(), channel_id: "channel-1".to_string() },
+				Hop { port_id: "transfer".to_string(), channel_id: "channel-7".to_string() },
+			]
+		);
+		assert_eq!(denom.path(), "transfer/channel-1/transfer/channel-7/uatom");
+
+		let native = Denom::from_path("DEMO").unwrap();
+		assert!(native.trace.is_empty());
+		assert_eq!(native.path(), "DEMO");
+	}
+
+	#[test]
+	fn v1_msg_transfer_decodes_without_tokens() {
+		let raw = RawMsgTransfer {
+			source_port: "transfer".to_string(),
+			source_channel: "channel-0".to_string(),
+			memo: "memo".to_string(),
+			..Default::default()
+		};
+		let msg = MsgTransferV2::decode(raw.encode_to_vec().as_slice()).unwrap();
+		assert_eq!(msg.source_channel, "channel-0");
+		assert_eq!(msg.memo, "memo");
+		assert!(msg.tokens.is_empty());
+	}
+
+	#[test]
+	fn channel_handshake_negotiates_v2() {
+		let mut ctx = IbcTransferModule::<Test>(PhantomData);
+		let counterparty = Counterparty::new(PortId::transfer(), None);
+		let v2 = Version::new(VERSION_V2.to_string());
+
+		let (_, version) = ctx
+			.on_chan_open_init(
+				Order::Unordered,
+				&[ConnectionId::default()],
+				&PortId::transfer(),
+				&ChannelId::new(0),
+				&counterparty,
+				&v2,
+			)
+			.unwrap();
+		assert_eq!(version, v2);
+
+		let (_, version) = ctx
+			.on_chan_open_try(
+				Order::Unordered,
+				&[ConnectionId::default()],
+				&PortId::transfer(),
+				&ChannelId::new(0),
+				&counterparty,
+				&v2,
+			)
+			.unwrap();
+		assert_eq!(version, v2);
+
+		assert!(ctx
+			.on_chan_open_init(
+				Order::Ordered,
+				&[ConnectionId::default()],
+				&PortId::transfer(),
+				&ChannelId::new(0),
+				&counterparty,
+				&v2,
+			)
+			.is_err());
+	}
+
+	#[test]
+	fn refund_releases_all_tokens() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			let channel_id = ChannelId::new(0);
+			let sender = sender();
+			Balances::make_free_balance_be(&escrow_account(&channel_id), 30 * DOLLARS);
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", 20 * DOLLARS));
+
+			assert_ok!(ctx
+				.on_timeout_packet_v2(&packet(&[("DEMO", 10 * DOLLARS), ("DEMO", 10 * DOLLARS)])));
+
+			assert_eq!(Balances::free_balance(&sender), 20 * DOLLARS);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 0);
+		})
+	}
+
+	#[test]
+	fn failed_refund_is_rolled_back() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			let channel_id = ChannelId::new(0);
+			let sender = sender();
+			Balances::make_free_balance_be(&escrow_account(&channel_id), 30 * DOLLARS);
+			assert_ok!(Pallet::<Test>::increase_total_escrow(&channel_id, b"DEMO", 10 * DOLLARS));
+
+			// the second token was never escrowed, so the first refund must not persist
+			assert!(ctx
+				.on_timeout_packet_v2(&packet(&[("DEMO", 10 * DOLLARS), ("uatom", 5)]))
+				.is_err());
+
+			assert_eq!(Balances::free_balance(&sender), 0);
+			assert_eq!(Balances::free_balance(&escrow_account(&channel_id)), 30 * DOLLARS);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 10 * DOLLARS);
+		})
+	}
+
+	#[test]
+	fn failed_recv_returns_error_ack_without_writes() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			let channel_id = escrow_demo(10 * DOLLARS);
+			let receiver = sender();
+
+			let ack = recv(
+				&mut ctx,
+				&packet(&[
+					("transfer/channel-0/DEMO", 10 * DOLLARS),
+					("transfer/channel-0/DEMO", 10 * DOLLARS),
+				]),
+			);
+			assert!(!is_success_ack(ack.as_ref()));
+			assert_eq!(Balances::free_balance(&receiver), 0);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 10 * DOLLARS);
+
+			let ack = recv(&mut ctx, &packet(&[("transfer/channel-0/DEMO", 10 * DOLLARS)]));
+			assert!(is_success_ack(ack.as_ref()));
+			assert_eq!(Balances::free_balance(&receiver), 10 * DOLLARS);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 0);
+		})
+	}
+
+	#[test]
+	fn recv_runs_memo_hooks() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			let channel_id = escrow_demo(10 * DOLLARS);
+
+			let memo = r#"{"record":{}}"#;
+			let ack = recv(
+				&mut ctx,
+				&packet_with_memo(&[("transfer/channel-0/DEMO", 10 * DOLLARS)], memo),
+			);
+			assert!(is_success_ack(ack.as_ref()));
+			assert_eq!(
+				storage::get(b"memo_record").map(|value| u128::decode(&mut &value[..]).unwrap()),
+				Some(10 * DOLLARS)
+			);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 0);
+		})
+	}
+
+	#[test]
+	fn failing_memo_hook_rolls_back_recv() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			let channel_id = escrow_demo(10 * DOLLARS);
+
+			let memo = r#"{"fail":{}}"#;
+			let ack = recv(
+				&mut ctx,
+				&packet_with_memo(&[("transfer/channel-0/DEMO", 10 * DOLLARS)], memo),
+			);
+			assert!(!is_success_ack(ack.as_ref()));
+			assert_eq!(Balances::free_balance(&sender()), 0);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 10 * DOLLARS);
+		})
+	}
+
+	#[test]
+	fn open_ack_requires_proposed_v2() {
+		let v2 = Version::new(VERSION_V2.to_string());
+
+		// the counterparty cannot switch an `ics20-1` channel to `ics20-2`
+		let mut relayer = Relayer::connected();
+		let channel_id = relayer.a.channel_id.clone();
+		relayer.a.execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			assert!(ctx.on_chan_open_ack(&PortId::transfer(), &channel_id, &v2).is_err());
+			assert!(ctx.on_chan_open_ack(&PortId::transfer(), &ChannelId::new(9), &v2).is_err());
+		});
+
+		let mut relayer = Relayer::connected_with_version(VERSION_V2);
+		let channel_id = relayer.a.channel_id.clone();
+		relayer.a.execute_with(|| {
+			let mut ctx = IbcTransferModule::<Test>(PhantomData);
+			assert_ok!(ctx.on_chan_open_ack(&PortId::transfer(), &channel_id, &v2));
+		});
+	}
+
+	#[test]
+	fn send_commits_packet_and_escrows_tokens() {
+		let mut relayer = Relayer::connected_with_version(VERSION_V2);
+		let timeout_height = relayer.b.height().add(100);
+		let message = relayer.a.transfer_v2_message(
+			&ALICE,
+			&signer(&BOB),
+			&[("DEMO", 10 * DOLLARS), ("DEMO", 5 * DOLLARS)],
+			timeout_height,
+		);
+		let packet = relayer.a.send(&ALICE, message);
+
+		let channel_id = relayer.a.channel_id.clone();
+		relayer.a.execute_with(|| {
+			let ctx = Context::<Test>::new();
+			let port_id = PortId::transfer();
+			assert!(ChannelReader::get_packet_commitment(
+				&ctx,
+				&port_id,
+				&channel_id,
+				&packet.sequence
+			)
+			.is_ok());
+			assert_eq!(
+				ChannelReader::get_next_sequence_send(&ctx, &port_id, &channel_id).unwrap(),
+				packet.sequence.increment()
+			);
+			assert_eq!(Balances::free_balance(&ALICE), INITIAL_BALANCE - 15 * DOLLARS);
+			assert_eq!(Pallet::<Test>::total_escrow(&channel_id, b"DEMO"), 15 * DOLLARS);
+		});
+
+		let acknowledgement = relayer.relay_from_a(&packet);
+		assert!(is_success_ack(&acknowledgement));
+		assert_eq!(relayer.b.execute_with(|| Assets::balance(DEMO_VOUCHER_ID, &BOB)), 15 * DOLLARS);
+	}
+
+	#[test]
+	fn send_is_refused_on_closed_channel() {
+		let mut relayer = Relayer::connected_with_version(VERSION_V2);
+		relayer.a.close_channel();
+
+		let timeout_height = relayer.b.height().add(100);
+		let message = relayer.a.transfer_v2_message(
+			&ALICE,
+			&signer(&BOB),
+			&[("DEMO", 10 * DOLLARS)],
+			timeout_height,
+		);
+		relayer.a.execute_with(|| {
+			assert_noop!(
+				Ics20Transfer::raw_transfer(RuntimeOrigin::signed(ALICE), vec![message]),
+				Error::<Test>::SendTransferFailed
+			);
+		});
+	}
+}
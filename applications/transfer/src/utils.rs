@@ -1,12 +1,62 @@
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use ibc::{
-	applications::transfer::{error::TokenTransferError, VERSION},
-	core::ics24_host::identifier::{ChannelId as IbcChannelId, PortId},
+	applications::transfer::{
+		error::TokenTransferError, packet::PacketData, PrefixedCoin, VERSION,
+	},
+	core::{
+		ics04_channel::packet::Packet,
+		ics24_host::identifier::{ChannelId as IbcChannelId, PortId},
+	},
 	signer::Signer,
 };
+use primitive_types::U256;
 use scale_info::prelude::format;
 use sha2::{Digest, Sha256};
 
+/// The sender, receiver, denomination and amount of an ICS20 transfer, decoded into the shape
+/// carried by the pallet events.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedTransfer {
+	pub sender: Vec<u8>,
+	pub receiver: Vec<u8>,
+	pub denom: Vec<u8>,
+	pub amount: u128,
+}
+
+impl DecodedTransfer {
+	/// Returns `None` if the token amount does not fit in a `u128`.
+	pub fn new(sender: &Signer, receiver: &Signer, token: &PrefixedCoin) -> Option<Self> {
+		Some(Self {
+			sender: sender.as_ref().as_bytes().to_vec(),
+			receiver: receiver.as_ref().as_bytes().to_vec(),
+			denom: format!("{}", token.denom).as_bytes().to_vec(),
+			amount: amount_to_u128(U256::from(token.amount))?,
+		})
+	}
+
+	/// Decode the ICS20 `PacketData` carried by `packet`.
+	pub fn from_packet(packet: &Packet) -> Option<Self> {
+		let data = serde_json::from_slice::<PacketData>(&packet.data).ok()?;
+		Self::new(&data.sender, &data.receiver, &data.token)
+	}
+}
+
+/// Convert an ICS20 amount into a `u128`, returning `None` on overflow instead of truncating.
+pub fn amount_to_u128(amount: U256) -> Option<u128> {
+	if amount > U256::from(u128::MAX) {
+		None
+	} else {
+		Some(amount.low_u128())
+	}
+}
+
+/// Returns true if `ack` is an ICS20 success acknowledgement, i.e. `{"result": ...}`.
+pub fn is_success_ack(ack: &[u8]) -> bool {
+	serde_json::from_slice::<serde_json::Value>(ack)
+		.map(|value| value.get("result").is_some())
+		.unwrap_or(false)
+}
+
 /// In ICS20 fungible token transfer, get the escrow address by channel ID and port ID
 ///
 /// Parameters:
@@ -50,6 +100,20 @@ pub fn derive_ibc_denom_with_path(transfer_path: &str) -> Result<String, TokenTr
 	Ok(format!("ibc/{}", denom_hex))
 }
 
+#[test]
+fn test_amount_to_u128() {
+	assert_eq!(amount_to_u128(U256::from(10u64)), Some(10));
+	assert_eq!(amount_to_u128(U256::from(u128::MAX)), Some(u128::MAX));
+	assert_eq!(amount_to_u128(U256::from(u128::MAX) + 1), None);
+}
+
+#[test]
+fn test_is_success_ack() {
+	assert!(is_success_ack(br#"{"result":"AQ=="}"#));
+	assert!(!is_success_ack(br#"{"error":"insufficient funds"}"#));
+	assert!(!is_success_ack(b"not json"));
+}
+
 #[test]
 fn test_get_channel_escrow_address() {
 	let signer = get_channel_escrow_address(&PortId::default(), &IbcChannelId::default()).unwrap();
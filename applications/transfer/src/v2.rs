@@ -0,0 +1,538 @@
+//! ICS20 v2 (`ics20-2`) fungible token transfers.
+//!
+//! A v2 packet moves several tokens at once and may carry forwarding hops. Channels negotiate
+//! `ics20-2` during the opening handshake, channels opened with `ics20-1` keep using the v1
+//! packet format. The escrow, mint and burn of all the tokens of a packet, as well as their
+//! refund on timeout or error acknowledgement, run inside a single storage transaction so a
+//! packet is either applied as a whole or not at all.
+//!
+//! Packet data and messages follow the protobuf encoding of ibc-go. Forwarding hops are carried
+//! on send, but packets asking this chain to forward tokens further are rejected with an error
+//! acknowledgement, which refunds the sender.
+use crate::{
+	callback::IbcTransferModule,
+	escrow::{escrowed_denom_on_recv, escrowed_denom_on_send},
+	utils::{is_success_ack, DecodedTransfer},
+	Config, Error, Event, Pallet, LOG_TARGET,
+};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use core::str::FromStr;
+use frame_support::{
+	dispatch::DispatchResult,
+	ensure,
+	storage::{with_transaction, TransactionOutcome},
+};
+use ibc::{
+	applications::transfer::{
+		acknowledgement::TokenTransferAcknowledgement,
+		context::{BankKeeper, TokenTransferReader},
+		denom::PrefixedDenom,
+		error::TokenTransferError,
+		PrefixedCoin,
+	},
+	core::{
+		ics04_channel::{
+			channel::Order,
+			context::ChannelReader,
+			error::{ChannelError, PacketError},
+			msgs::acknowledgement::Acknowledgement,
+			packet::Packet,
+			timeout::TimeoutHeight,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, PortId},
+		ics26_routing::context::ModuleOutputBuilder,
+	},
+	signer::Signer,
+	timestamp::Timestamp,
+	Height,
+};
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin, ibc::core::client::v1::Height as RawHeight,
+};
+use pallet_ibc_utils::packet::send_packet;
+use primitive_types::U256;
+use prost::Message;
+use sp_runtime::DispatchError;
+
+pub const VERSION_V2: &str = "ics20-2";
+
+/// A `port_id/channel_id` pair a token travelled through.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct Hop {
+	#[prost(string, tag = "1")]
+	pub port_id: String,
+	#[prost(string, tag = "2")]
+	pub channel_id: String,
+}
+
+/// A denomination split into its base denomination and the hops it travelled through, the most
+/// recent hop first.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct Denom {
+	#[prost(string, tag = "1")]
+	pub base: String,
+	#[prost(message, repeated, tag = "3")]
+	pub trace: Vec<Hop>,
+}
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct Token {
+	#[prost(message, optional, tag = "1")]
+	pub denom: Option<Denom>,
+	#[prost(string, tag = "2")]
+	pub amount: String,
+}
+
+/// Forwarding hops of a packet and the memo delivered at the final destination.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct ForwardingPacketData {
+	#[prost(string, tag = "1")]
+	pub destination_memo: String,
+	#[prost(message, repeated, tag = "2")]
+	pub hops: Vec<Hop>,
+}
+
+/// `FungibleTokenPacketDataV2`
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct PacketDataV2 {
+	#[prost(message, repeated, tag = "1")]
+	pub tokens: Vec<Token>,
+	#[prost(string, tag = "2")]
+	pub sender: String,
+	#[prost(string, tag = "3")]
+	pub receiver: String,
+	#[prost(string, tag = "4")]
+	pub memo: String,
+	#[prost(message, optional, tag = "5")]
+	pub forwarding: Option<ForwardingPacketData>,
+}
+
+/// Forwarding requested by a `MsgTransfer`.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct Forwarding {
+	#[prost(bool, tag = "1")]
+	pub unwind: bool,
+	#[prost(message, repeated, tag = "2")]
+	pub hops: Vec<Hop>,
+}
+
+/// `MsgTransfer` including the v2 `tokens` and `forwarding` fields. A message with a non-empty
+/// `tokens` list is a v2 transfer, the v1 `token` field is ignored.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgTransferV2 {
+	#[prost(string, tag = "1")]
+	pub source_port: String,
+	#[prost(string, tag = "2")]
+	pub source_channel: String,
+	#[prost(string, tag = "4")]
+	pub sender: String,
+	#[prost(string, tag = "5")]
+	pub receiver: String,
+	#[prost(message, optional, tag = "6")]
+	pub timeout_height: Option<RawHeight>,
+	#[prost(uint64, tag = "7")]
+	pub timeout_timestamp: u64,
+	#[prost(string, tag = "8")]
+	pub memo: String,
+	#[prost(message, repeated, tag = "9")]
+	pub tokens: Vec<RawCoin>,
+	#[prost(message, optional, tag = "10")]
+	pub forwarding: Option<Forwarding>,
+}
+
+impl Denom {
+	/// The `{port}/{channel}/.../{base}` path of the denomination, as used by v1 packets and
+	/// the escrow accounting.
+	pub fn path(&self) -> String {
+		let mut path = String::new();
+		for hop in &self.trace {
+			path.push_str(&format!("{}/{}/", hop.port_id, hop.channel_id));
+		}
+		path.push_str(&self.base);
+		path
+	}
+
+	/// Split a denomination path into its base denomination and trace hops.
+	pub fn from_path(path: &str) -> Option<Self> {
+		let denom = PrefixedDenom::from_str(path).ok()?;
+		let trace_path = denom.trace_path.to_string();
+		let segments: Vec<&str> = trace_path.split('/').filter(|s| !s.is_empty()).collect();
+		let trace = segments
+			.chunks_exact(2)
+			.map(|hop| Hop { port_id: hop[0].to_string(), channel_id: hop[1].to_string() })
+			.collect();
+		Some(Self { base: denom.base_denom.as_str().to_string(), trace })
+	}
+}
+
+/// Returns true if `version` is the ICS20 v2 channel version.
+pub fn is_v2(version: &Version) -> bool {
+	*version == Version::new(VERSION_V2.to_string())
+}
+
+/// Denomination path and amount of a packet token, `None` if either is malformed or zero.
+fn decode_token(token: &Token) -> Option<(String, u128)> {
+	let denom = token.denom.as_ref()?.path();
+	let amount = token.amount.parse::<u128>().ok().filter(|amount| *amount > 0)?;
+	Some((denom, amount))
+}
+
+fn coin(denom: &str, amount: u128) -> Result<PrefixedCoin, TokenTransferError> {
+	Ok(PrefixedCoin { denom: PrefixedDenom::from_str(denom)?, amount: U256::from(amount).into() })
+}
+
+fn parse_account<T: Config>(address: &str) -> Option<T::AccountIdConversion> {
+	address.parse::<Signer>().ok()?.try_into().ok()
+}
+
+/// Decode the tokens of a v2 packet into the shape carried by the pallet events.
+pub fn decode_transfers(packet: &Packet) -> Vec<DecodedTransfer> {
+	let data = match PacketDataV2::decode(packet.data.as_slice()) {
+		Ok(data) => data,
+		Err(_) => return Vec::new(),
+	};
+	data.tokens
+		.iter()
+		.filter_map(decode_token)
+		.map(|(denom, amount)| DecodedTransfer {
+			sender: data.sender.as_bytes().to_vec(),
+			receiver: data.receiver.as_bytes().to_vec(),
+			denom: denom.into_bytes(),
+			amount,
+		})
+		.collect()
+}
+
+/// Run `f` in a storage transaction that is rolled back if it returns an error.
+fn atomically<R>(f: impl FnOnce() -> Result<R, String>) -> Result<R, String> {
+	with_transaction(|| match f() {
+		Ok(result) => TransactionOutcome::Commit(Ok(Ok(result))),
+		Err(description) => TransactionOutcome::Rollback(Ok(Err(description))),
+	})
+	.unwrap_or_else(|error: DispatchError| Err(format!("{:?}", error)))
+}
+
+impl<T: Config> IbcTransferModule<T> {
+	/// Returns true if the channel end was opened with the ICS20 v2 version.
+	pub(crate) fn is_v2_channel(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
+		ChannelReader::channel_end(self, port_id, channel_id)
+			.map(|channel_end| is_v2(channel_end.version()))
+			.unwrap_or(false)
+	}
+
+	/// Channel checks of the opening handshake for an `ics20-2` channel.
+	pub(crate) fn validate_channel_v2(
+		&self,
+		order: Order,
+		port_id: &PortId,
+	) -> Result<(), ChannelError> {
+		if order != Order::Unordered {
+			return Err(ChannelError::AppModule {
+				description: format!("{} channels must be unordered", VERSION_V2),
+			});
+		}
+		let bound_port = self
+			.get_port()
+			.map_err(|e| ChannelError::AppModule { description: e.to_string() })?;
+		if port_id != &bound_port {
+			return Err(ChannelError::AppModule {
+				description: format!("invalid port {}, expected {}", port_id, bound_port),
+			});
+		}
+		Ok(())
+	}
+
+	/// Checks of the acknowledgement of an `ics20-2` channel: the counterparty may only select
+	/// `ics20-2` if this end proposed it when opening the channel.
+	pub(crate) fn validate_open_ack_v2(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), ChannelError> {
+		let channel_end = ChannelReader::channel_end(self, port_id, channel_id)?;
+		if !is_v2(channel_end.version()) {
+			return Err(ChannelError::AppModule {
+				description: format!(
+					"counterparty selected {} but {} was proposed",
+					VERSION_V2,
+					channel_end.version()
+				),
+			});
+		}
+		self.validate_channel_v2(*channel_end.ordering(), port_id)
+	}
+
+	/// Releases or mints every token of the packet for the receiver.
+	fn recv_tokens_v2(&mut self, packet: &Packet) -> Result<(), String> {
+		let data = PacketDataV2::decode(packet.data.as_slice())
+			.map_err(|_| "invalid ics20-2 packet data".to_string())?;
+		if data.forwarding.map_or(false, |forwarding| !forwarding.hops.is_empty()) {
+			return Err("forwarding is not supported".to_string());
+		}
+		if data.tokens.is_empty() {
+			return Err("packet carries no tokens".to_string());
+		}
+		let receiver = parse_account::<T>(&data.receiver)
+			.ok_or_else(|| format!("invalid receiver {}", data.receiver))?;
+
+		for token in &data.tokens {
+			let (denom, amount) =
+				decode_token(token).ok_or_else(|| "invalid packet token".to_string())?;
+			// tokens returning to this chain are released from the destination channel escrow
+			match escrowed_denom_on_recv(&packet.port_on_a, &packet.chan_on_a, denom.as_bytes()) {
+				Some(local_denom) => {
+					Self::ensure_escrow(&packet.chan_on_b, &local_denom, amount)?;
+					let escrow = self
+						.get_channel_escrow_address(&packet.port_on_b, &packet.chan_on_b)
+						.map_err(|e| e.to_string())?;
+					let local_denom = String::from_utf8(local_denom)
+						.map_err(|_| "invalid packet token".to_string())?;
+					self.send_coins(
+						&escrow,
+						&receiver,
+						&coin(&local_denom, amount).map_err(|e| e.to_string())?,
+					)
+					.map_err(|e| e.to_string())?;
+					Pallet::<T>::decrease_total_escrow(
+						&packet.chan_on_b,
+						local_denom.as_bytes(),
+						amount,
+					)
+					.map_err(|e| format!("{:?}", e))?;
+				},
+				None => {
+					let voucher = format!("{}/{}/{}", packet.port_on_b, packet.chan_on_b, denom);
+					self.mint_coins(&receiver, &coin(&voucher, amount).map_err(|e| e.to_string())?)
+						.map_err(|e| e.to_string())?;
+				},
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns every token of the packet to the sender.
+	fn refund_tokens_v2(&mut self, packet: &Packet) -> Result<(), String> {
+		let data = PacketDataV2::decode(packet.data.as_slice())
+			.map_err(|_| "invalid ics20-2 packet data".to_string())?;
+		let sender = parse_account::<T>(&data.sender)
+			.ok_or_else(|| format!("invalid sender {}", data.sender))?;
+
+		for token in &data.tokens {
+			let (denom, amount) =
+				decode_token(token).ok_or_else(|| "invalid packet token".to_string())?;
+			let refund = coin(&denom, amount).map_err(|e| e.to_string())?;
+			match escrowed_denom_on_send(&packet.port_on_a, &packet.chan_on_a, denom.as_bytes()) {
+				Some(escrowed) => {
+					Self::ensure_escrow(&packet.chan_on_a, &escrowed, amount)?;
+					let escrow = self
+						.get_channel_escrow_address(&packet.port_on_a, &packet.chan_on_a)
+						.map_err(|e| e.to_string())?;
+					self.send_coins(&escrow, &sender, &refund).map_err(|e| e.to_string())?;
+					Pallet::<T>::decrease_total_escrow(&packet.chan_on_a, &escrowed, amount)
+						.map_err(|e| format!("{:?}", e))?;
+				},
+				// burned vouchers are minted back
+				None => self.mint_coins(&sender, &refund).map_err(|e| e.to_string())?,
+			}
+		}
+		Ok(())
+	}
+
+	pub(crate) fn on_recv_packet_v2(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+	) -> Acknowledgement {
+		let ack =
+			self.recv_with_memo_hooks(output, packet, |ctx, _| match ctx.recv_tokens_v2(packet) {
+				Ok(()) => TokenTransferAcknowledgement::success().into(),
+				Err(description) => {
+					log::error!(target: LOG_TARGET, "on_recv_packet_v2: {}", description);
+					TokenTransferAcknowledgement::Error(description).into()
+				},
+			});
+		let success = is_success_ack(ack.as_ref());
+
+		for DecodedTransfer { sender, receiver, denom, amount } in decode_transfers(packet) {
+			Pallet::<T>::deposit_event(Event::<T>::RecvPacket {
+				sender,
+				receiver,
+				denom,
+				amount,
+				success,
+			});
+		}
+
+		ack
+	}
+
+	pub(crate) fn on_acknowledgement_packet_v2(
+		&mut self,
+		packet: &Packet,
+		acknowledgement: &Acknowledgement,
+	) -> Result<(), PacketError> {
+		let success = is_success_ack(acknowledgement.as_ref());
+		if !success {
+			atomically(|| self.refund_tokens_v2(packet))
+				.map_err(|description| PacketError::AppModule { description })?;
+		}
+
+		for DecodedTransfer { sender, receiver, denom, amount } in decode_transfers(packet) {
+			Pallet::<T>::deposit_event(Event::<T>::AckStatus {
+				sender,
+				receiver,
+				denom,
+				amount,
+				success,
+			});
+		}
+
+		Ok(())
+	}
+
+	pub(crate) fn on_timeout_packet_v2(&mut self, packet: &Packet) -> Result<(), PacketError> {
+		atomically(|| self.refund_tokens_v2(packet))
+			.map_err(|description| PacketError::AppModule { description })?;
+
+		for DecodedTransfer { sender, denom, amount, .. } in decode_transfers(packet) {
+			Pallet::<T>::deposit_event(Event::<T>::Timeout {
+				refund_receiver: sender,
+				denom,
+				amount,
+			});
+		}
+
+		Ok(())
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Send the tokens of a v2 `MsgTransfer` in a single `ics20-2` packet.
+	///
+	/// All tokens are escrowed or burned before the packet goes through the ICS04 send handler.
+	/// The router runs it in its own storage layer, so nothing is written if any of them or the
+	/// send fails.
+	pub(crate) fn send_transfer_v2(
+		ctx: &mut IbcTransferModule<T>,
+		msg: MsgTransferV2,
+	) -> DispatchResult {
+		let port_on_a =
+			PortId::from_str(&msg.source_port).map_err(|_| Error::<T>::ParserMsgTransferError)?;
+		let chan_on_a = ChannelId::from_str(&msg.source_channel)
+			.map_err(|_| Error::<T>::ParserMsgTransferError)?;
+		ensure!(ctx.is_send_enabled(), Error::<T>::SendDisabled);
+
+		let channel_end = ChannelReader::channel_end(ctx, &port_on_a, &chan_on_a)
+			.map_err(|_| Error::<T>::SendTransferFailed)?;
+		ensure!(is_v2(channel_end.version()), Error::<T>::InvalidChannelVersion);
+		let port_on_b = channel_end.counterparty().port_id().clone();
+		let chan_on_b = channel_end
+			.counterparty()
+			.channel_id()
+			.cloned()
+			.ok_or(Error::<T>::SendTransferFailed)?;
+
+		let timeout_height_on_b = match msg.timeout_height {
+			Some(RawHeight { revision_number, revision_height })
+				if revision_number != 0 || revision_height != 0 =>
+				TimeoutHeight::At(
+					Height::new(revision_number, revision_height)
+						.map_err(|_| Error::<T>::ParserMsgTransferError)?,
+				),
+			_ => TimeoutHeight::Never,
+		};
+		ensure!(
+			timeout_height_on_b != TimeoutHeight::Never || msg.timeout_timestamp != 0,
+			Error::<T>::ParserMsgTransferError
+		);
+		let timeout_timestamp_on_b = Timestamp::from_nanoseconds(msg.timeout_timestamp)
+			.map_err(|_| Error::<T>::ParserMsgTransferError)?;
+
+		let forwarding_hops = match msg.forwarding {
+			Some(Forwarding { unwind: true, .. }) =>
+				return Err(Error::<T>::UnsupportedForwarding.into()),
+			Some(Forwarding { hops, .. }) => hops,
+			None => Vec::new(),
+		};
+		// with forwarding, the memo is only delivered at the final destination
+		let (memo, forwarding) = if forwarding_hops.is_empty() {
+			(msg.memo, None)
+		} else {
+			(
+				String::new(),
+				Some(ForwardingPacketData { destination_memo: msg.memo, hops: forwarding_hops }),
+			)
+		};
+
+		let sender = parse_account::<T>(&msg.sender).ok_or(Error::<T>::InvalidAccount)?;
+		let mut tokens = Vec::with_capacity(msg.tokens.len());
+		for RawCoin { denom, amount } in msg.tokens {
+			let amount = amount
+				.parse::<u128>()
+				.ok()
+				.filter(|amount| *amount > 0)
+				.ok_or(Error::<T>::InvalidAmount)?;
+			let denom = Denom::from_path(&denom).ok_or(Error::<T>::InvalidTokenId)?;
+			tokens.push(Token { denom: Some(denom), amount: amount.to_string() });
+		}
+
+		for (denom, amount) in tokens.iter().filter_map(decode_token) {
+			let token = coin(&denom, amount).map_err(Error::<T>::from)?;
+			match escrowed_denom_on_send(&port_on_a, &chan_on_a, denom.as_bytes()) {
+				Some(escrowed) => {
+					let escrow = ctx
+						.get_channel_escrow_address(&port_on_a, &chan_on_a)
+						.map_err(Error::<T>::from)?;
+					ctx.send_coins(&sender, &escrow, &token).map_err(Error::<T>::from)?;
+					Self::increase_total_escrow(&chan_on_a, &escrowed, amount)?;
+				},
+				None => ctx.burn_coins(&sender, &token).map_err(Error::<T>::from)?,
+			}
+		}
+
+		let data =
+			PacketDataV2 { tokens, sender: msg.sender, receiver: msg.receiver, memo, forwarding };
+		let transfers: Vec<(Vec<u8>, u128)> = data
+			.tokens
+			.iter()
+			.filter_map(decode_token)
+			.map(|(denom, amount)| (denom.into_bytes(), amount))
+			.collect();
+		let (sender, receiver) =
+			(data.sender.as_bytes().to_vec(), data.receiver.as_bytes().to_vec());
+
+		let sequence = ChannelReader::get_next_sequence_send(ctx, &port_on_a, &chan_on_a)
+			.map_err(|_| Error::<T>::SendTransferFailed)?;
+		let packet = Packet {
+			sequence,
+			port_on_a,
+			chan_on_a,
+			port_on_b,
+			chan_on_b,
+			data: data.encode_to_vec(),
+			timeout_height_on_b,
+			timeout_timestamp_on_b,
+		};
+		let events = send_packet(ctx, packet).map_err(|error| {
+			log::error!(target: LOG_TARGET, "send_transfer_v2 Error : {:?} ", error);
+			Error::<T>::SendTransferFailed
+		})?;
+
+		Self::deposit_send_events(events);
+		for (denom, amount) in transfers {
+			Self::deposit_event(Event::Transfer {
+				sender: sender.clone(),
+				receiver: receiver.clone(),
+				denom,
+				amount,
+			});
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,249 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Bridge between XCM assets and ICS-20 transfers, on top of `pallet_ics20_transfer`.
+//!
+//! `Ics20AssetTransactor` is the XCM asset transactor of the assets `pallet_ics20_transfer`
+//! transfers: the native token through `Currency` and every other denomination through the
+//! `Fungibles` registered in `AssetIdByName`, under the base denomination of a native asset or the
+//! `ibc/{hash}` of the full trace path of a voucher. `Config::DenomLocations` converts between
+//! their XCM locations and ICS-20 denominations, so tokens arriving over XCM, e.g. DOT, can be
+//! sent to a counterparty with `Call::transfer` and vouchers received over ICS-20 can be reserve
+//! transferred to sibling parachains with `pallet_xcm`.
+//!
+//! A remote account transfers the tokens it holds on this chain by sending the XCM built by
+//! `message::transfer_message`, which dispatches `Call::transfer` from its sovereign account.
+
+extern crate alloc;
+
+pub use pallet::*;
+
+pub mod location;
+pub mod message;
+pub mod transactor;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod mock;
+
+use alloc::{
+	boxed::Box,
+	format,
+	string::{String, ToString},
+	vec,
+};
+use codec::Encode;
+use frame_support::pallet_prelude::*;
+use frame_system::pallet_prelude::*;
+use ibc::{applications::transfer::PORT_ID_STR, core::ics24_host::identifier::ChannelId};
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin, google::protobuf::Any,
+	ibc::core::client::v1::Height as RawHeight,
+};
+use location::DenomLocations;
+use pallet_ibc_utils::{AssetIdAndNameProvider, Router};
+use pallet_ics20_transfer::{denom::PrefixedDenom, MSG_TRANSFER_TYPE_URL};
+use prost::Message;
+use sp_std::{fmt::Debug, vec::Vec};
+use xcm::{latest::prelude::*, VersionedAsset};
+
+pub const LOG_TARGET: &str = "runtime::pallet-ics20-xcm";
+
+/// ICS-20 `MsgTransfer`, including the `memo` field.
+#[derive(Clone, PartialEq, Message)]
+pub struct RawMsgTransfer {
+	#[prost(string, tag = "1")]
+	pub source_port: String,
+	#[prost(string, tag = "2")]
+	pub source_channel: String,
+	#[prost(message, optional, tag = "3")]
+	pub token: Option<RawCoin>,
+	#[prost(string, tag = "4")]
+	pub sender: String,
+	#[prost(string, tag = "5")]
+	pub receiver: String,
+	#[prost(message, optional, tag = "6")]
+	pub timeout_height: Option<RawHeight>,
+	#[prost(uint64, tag = "7")]
+	pub timeout_timestamp: u64,
+	#[prost(string, tag = "8")]
+	pub memo: String,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_ics20_transfer::Config {
+		/// The aggregated event type of the runtime.
+		type RuntimeEvent: Parameter
+			+ Member
+			+ From<Event<Self>>
+			+ Debug
+			+ IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Conversion between the XCM locations of assets and their ICS-20 denominations, e.g.
+		/// `location::Ics20Locations`
+		type DenomLocations: DenomLocations;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An asset held on this chain was sent over ICS-20
+		TransferSent {
+			sender: T::AccountId,
+			channel_id: ChannelId,
+			denom: Vec<u8>,
+			amount: u128,
+			memo: Vec<u8>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The asset is of an unsupported XCM version
+		BadVersion,
+		/// The asset location has no ICS-20 denomination
+		AssetNotFound,
+		/// The asset is not fungible or its amount is zero
+		InvalidAmount,
+		/// The receiver is not valid utf-8
+		InvalidReceiver,
+		/// The memo is not valid utf-8
+		InvalidMemo,
+		/// A transfer needs a timeout height, a timeout timestamp or both
+		InvalidTimeout,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T>
+	where
+		u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+	{
+		/// Send an asset held on this chain to `receiver` over the ICS-20 channel `channel_id`.
+		///
+		/// The origin must be signed, usually the sovereign account of a remote account
+		/// dispatching this call with the XCM of `message::transfer_message`.
+		///
+		/// Parameters:
+		/// - `asset`: The location and amount of the asset, as seen from this chain.
+		/// - `channel_id`: The transfer channel to send the asset over.
+		/// - `receiver`: The account of the receiver on the counterparty chain.
+		/// - `timeout_height`: The revision number and height on the counterparty after which the
+		///   transfer times out, if any.
+		/// - `timeout_timestamp`: The timestamp in nanoseconds on the counterparty after which the
+		///   transfer times out, zero if none.
+		/// - `memo`: The memo of the transfer.
+		///
+		/// Emits `TransferSent` when successful.
+		#[pallet::call_index(0)]
+		// the `MsgTransfer` sent by the transfer pallet, then the `TransferSent` event
+		#[pallet::weight(
+			pallet_ics20_transfer::Pallet::<T>::send_transfer_weight()
+				.saturating_add(T::DbWeight::get().writes(1))
+		)]
+		#[allow(clippy::too_many_arguments)]
+		pub fn transfer(
+			origin: OriginFor<T>,
+			asset: Box<VersionedAsset>,
+			channel_id: ChannelId,
+			receiver: Vec<u8>,
+			timeout_height: Option<(u64, u64)>,
+			timeout_timestamp: u64,
+			memo: Vec<u8>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let asset: Asset = (*asset).try_into().map_err(|()| Error::<T>::BadVersion)?;
+
+			Self::do_transfer(
+				sender,
+				asset,
+				channel_id,
+				receiver,
+				timeout_height,
+				timeout_timestamp,
+				memo,
+			)
+		}
+	}
+}
+
+/// An asset `pallet_ics20_transfer` moves with `send_coins`, `mint_coins` and `burn_coins`.
+pub enum Ics20Asset<AssetId> {
+	/// The native token, moved with `Currency`
+	Native,
+	/// An asset of `Fungibles`, registered in `AssetIdByName` under the `asset_name` of its denom
+	Fungible(AssetId),
+}
+
+impl<T: Config> Pallet<T> {
+	/// The asset `denom` is held in on this chain, if any.
+	pub fn ics20_asset(denom: &PrefixedDenom) -> Option<Ics20Asset<T::AssetId>> {
+		if denom.trace_path.is_empty() && denom.base_denom == T::NATIVE_TOKEN_NAME {
+			return Some(Ics20Asset::Native)
+		}
+		let name = denom.asset_name()?;
+		T::AssetIdByName::try_get_asset_id(name).ok().map(Ics20Asset::Fungible)
+	}
+
+	/// Transfer `asset` from `sender` to `receiver` over the transfer `channel_id`, timing out at
+	/// `timeout_height` or at `timeout_timestamp` nanoseconds on the counterparty.
+	#[allow(clippy::too_many_arguments)]
+	pub fn do_transfer(
+		sender: T::AccountId,
+		asset: Asset,
+		channel_id: ChannelId,
+		receiver: Vec<u8>,
+		timeout_height: Option<(u64, u64)>,
+		timeout_timestamp: u64,
+		memo: Vec<u8>,
+	) -> DispatchResult
+	where
+		u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+	{
+		let amount = match asset.fun {
+			Fungible(amount) if amount != 0 => amount,
+			_ => return Err(Error::<T>::InvalidAmount.into()),
+		};
+		let denom = T::DenomLocations::denom(&asset.id.0).ok_or(Error::<T>::AssetNotFound)?;
+		let denom = denom.full_path();
+		ensure!(timeout_height.is_some() || timeout_timestamp != 0, Error::<T>::InvalidTimeout);
+
+		let msg = RawMsgTransfer {
+			source_port: PORT_ID_STR.to_string(),
+			source_channel: channel_id.to_string(),
+			token: Some(RawCoin {
+				denom: String::from_utf8(denom.clone()).map_err(|_| Error::<T>::AssetNotFound)?,
+				amount: amount.to_string(),
+			}),
+			sender: account_signer::<T>(&sender),
+			receiver: String::from_utf8(receiver).map_err(|_| Error::<T>::InvalidReceiver)?,
+			timeout_height: timeout_height.map(|(revision_number, revision_height)| RawHeight {
+				revision_number,
+				revision_height,
+			}),
+			timeout_timestamp,
+			memo: String::from_utf8(memo.clone()).map_err(|_| Error::<T>::InvalidMemo)?,
+		};
+		let message =
+			Any { type_url: MSG_TRANSFER_TYPE_URL.to_string(), value: msg.encode_to_vec() };
+		<pallet_ics20_transfer::Pallet<T> as Router>::dispatch(vec![message])?;
+
+		Self::deposit_event(Event::TransferSent { sender, channel_id, denom, amount, memo });
+		Ok(())
+	}
+}
+
+/// Signer of the transfers of `account`, the hex encoding of its account id as expected by the
+/// transfer pallet.
+pub fn account_signer<T: Config>(account: &T::AccountId) -> String {
+	format!("0x{}", hex::encode(account.encode()))
+}
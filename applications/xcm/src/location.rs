@@ -0,0 +1,87 @@
+//! Conversion between the XCM locations of assets and their ICS-20 denominations.
+//!
+//! With `Ics20Locations`, the native token of this chain is `Here`, a voucher is
+//! `PalletInstance(<index of pallet_ics20_transfer>)/GeneralKey(<hash of its denom trace>)`,
+//! and the tokens of other consensus systems, e.g. DOT, are registered by the runtime with the
+//! un-traced denomination they are sent with over ICS-20.
+use alloc::vec::Vec;
+use frame_support::traits::{Get, PalletInfoAccess};
+use pallet_ics20_transfer::denom::{PrefixedDenom, IBC_DENOM_PREFIX};
+use sp_std::marker::PhantomData;
+use xcm::latest::prelude::*;
+
+/// Conversion between the XCM locations of assets and their ICS-20 denominations.
+pub trait DenomLocations {
+	/// Denomination of the asset at `location`, as seen from this chain.
+	fn denom(location: &Location) -> Option<PrefixedDenom>;
+
+	/// Location of the asset of `denom`, as seen from this chain.
+	fn location(denom: &PrefixedDenom) -> Option<Location>;
+}
+
+/// The 32 bytes hash of the denom trace of the voucher `denom`, keying it in `DenomTrace`.
+pub fn trace_key(denom: &PrefixedDenom) -> Option<[u8; 32]> {
+	let trace_hash = denom.trace_hash()?;
+	let hash = trace_hash.strip_prefix(IBC_DENOM_PREFIX)?;
+	hex::decode(hash).ok()?.try_into().ok()
+}
+
+/// `DenomLocations` of the native token, the vouchers of `pallet_ics20_transfer` and the
+/// `RegisteredAssets`, pairs of a location and its un-traced denomination.
+pub struct Ics20Locations<T, RegisteredAssets>(PhantomData<(T, RegisteredAssets)>);
+
+impl<T, RegisteredAssets> Ics20Locations<T, RegisteredAssets>
+where
+	T: pallet_ics20_transfer::Config,
+{
+	fn pallet_index() -> Option<u8> {
+		<pallet_ics20_transfer::Pallet<T> as PalletInfoAccess>::index().try_into().ok()
+	}
+
+	fn native_denom() -> PrefixedDenom {
+		PrefixedDenom { trace_path: Vec::new(), base_denom: T::NATIVE_TOKEN_NAME.to_vec() }
+	}
+}
+
+impl<T, RegisteredAssets> DenomLocations for Ics20Locations<T, RegisteredAssets>
+where
+	T: pallet_ics20_transfer::Config,
+	RegisteredAssets: Get<Vec<(Location, Vec<u8>)>>,
+{
+	fn denom(location: &Location) -> Option<PrefixedDenom> {
+		if location == &Location::here() {
+			return Some(Self::native_denom())
+		}
+		if let Some((_, base_denom)) = RegisteredAssets::get()
+			.into_iter()
+			.find(|(registered, _)| registered == location)
+		{
+			return Some(PrefixedDenom { trace_path: Vec::new(), base_denom })
+		}
+		match location.unpack() {
+			(0, [PalletInstance(index), GeneralKey { length: 32, data }])
+				if Some(*index) == Self::pallet_index() =>
+				pallet_ics20_transfer::Pallet::<T>::denom_trace(hex::encode_upper(data)),
+			_ => None,
+		}
+	}
+
+	fn location(denom: &PrefixedDenom) -> Option<Location> {
+		if !denom.trace_path.is_empty() {
+			return Some(Location::new(
+				0,
+				[
+					PalletInstance(Self::pallet_index()?),
+					GeneralKey { length: 32, data: trace_key(denom)? },
+				],
+			))
+		}
+		if denom.base_denom == T::NATIVE_TOKEN_NAME {
+			return Some(Location::here())
+		}
+		RegisteredAssets::get()
+			.into_iter()
+			.find(|(_, base_denom)| base_denom == &denom.base_denom)
+			.map(|(location, _)| location)
+	}
+}
@@ -0,0 +1,30 @@
+//! The XCM a remote account sends to this chain to transfer tokens it holds here over ICS-20.
+use alloc::{vec, vec::Vec};
+use frame_support::weights::Weight;
+use xcm::latest::prelude::*;
+
+/// XCM dispatching the SCALE encoded `transfer_call`, a `Call::transfer` of this pallet as a
+/// call of the runtime of this chain, from the sovereign account of the sender.
+///
+/// The sender pays for the execution with `fees` held by its sovereign account, up to
+/// `weight_limit`, and what is left of them is deposited back to `refund_to`. The memo and the
+/// rest of the ICS-20 transfer are the arguments of the call.
+pub fn transfer_message(
+	fees: Asset,
+	weight_limit: WeightLimit,
+	transfer_call: Vec<u8>,
+	require_weight_at_most: Weight,
+	refund_to: Location,
+) -> Xcm<()> {
+	Xcm(vec![
+		WithdrawAsset(fees.clone().into()),
+		BuyExecution { fees, weight_limit },
+		Transact {
+			origin_kind: OriginKind::SovereignAccount,
+			require_weight_at_most,
+			call: transfer_call.into(),
+		},
+		RefundSurplus,
+		DepositAsset { assets: Wild(AllCounted(1)), beneficiary: refund_to },
+	])
+}
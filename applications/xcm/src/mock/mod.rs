@@ -0,0 +1,151 @@
+//! `xcm-simulator` network of a relay chain and two parachains running the XCM bridge.
+pub mod msg_queue;
+pub mod para;
+pub mod relay;
+
+use core::str::FromStr;
+use frame_support::traits::BuildGenesisConfig;
+use ibc::{
+	applications::transfer::PORT_ID_STR,
+	core::{
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, Order, State},
+			packet::Sequence,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+};
+use pallet_ibc::{context::Context, localhost_client::localhost_connection_id};
+use pallet_ibc_utils::traits::ChannelKeeperInterface;
+use sp_runtime::{AccountId32, BuildStorage};
+use xcm_simulator::{decl_test_network, decl_test_parachain, decl_test_relay_chain};
+
+pub const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+pub const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+pub const PARA_A: u32 = 1;
+pub const PARA_B: u32 = 2;
+pub const INITIAL_BALANCE: u128 = 1_000_000_000;
+
+decl_test_parachain! {
+	pub struct ParaA {
+		Runtime = para::Runtime,
+		XcmpMessageHandler = para::MsgQueue,
+		DmpMessageHandler = para::MsgQueue,
+		new_ext = para_ext(PARA_A),
+	}
+}
+
+decl_test_parachain! {
+	pub struct ParaB {
+		Runtime = para::Runtime,
+		XcmpMessageHandler = para::MsgQueue,
+		DmpMessageHandler = para::MsgQueue,
+		new_ext = para_ext(PARA_B),
+	}
+}
+
+decl_test_relay_chain! {
+	pub struct Relay {
+		Runtime = relay::Runtime,
+		RuntimeCall = relay::RuntimeCall,
+		RuntimeEvent = relay::RuntimeEvent,
+		XcmConfig = relay::XcmConfig,
+		MessageQueue = relay::MessageQueue,
+		System = relay::System,
+		new_ext = relay_ext(),
+	}
+}
+
+decl_test_network! {
+	pub struct MockNet {
+		relay_chain = Relay,
+		parachains = vec![
+			(PARA_A, ParaA),
+			(PARA_B, ParaB),
+		],
+	}
+}
+
+/// The `transfer/channel-0` port and channel, open to itself over the localhost connection.
+pub fn open_transfer_channel() {
+	let port_id = PortId::from_str(PORT_ID_STR).unwrap();
+	<Context<para::Runtime> as ChannelKeeperInterface>::store_channel(
+		port_id.clone(),
+		ChannelId::new(0),
+		ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(port_id.clone(), Some(ChannelId::new(0))),
+			vec![localhost_connection_id()],
+			Version::new("ics20-1".to_string()),
+		),
+	)
+	.unwrap();
+	<Context<para::Runtime> as ChannelKeeperInterface>::store_next_sequence_send(
+		port_id,
+		ChannelId::new(0),
+		Sequence::from(1),
+	)
+	.unwrap();
+}
+
+/// Parachain `para_id`, with the DOT and `uatom` assets registered for ICS-20 and a transfer
+/// channel over the localhost connection.
+pub fn para_ext(para_id: u32) -> sp_io::TestExternalities {
+	use para::{
+		uatom_voucher, Runtime, System, Timestamp, DOT, DOT_ID, SIBLING_UATOM, SIBLING_UATOM_ID,
+		UATOM_ID,
+	};
+
+	let mut storage = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> { balances: vec![(ALICE, INITIAL_BALANCE)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	pallet_assets::GenesisConfig::<Runtime> {
+		assets: [DOT_ID, UATOM_ID, SIBLING_UATOM_ID]
+			.into_iter()
+			.map(|asset_id| (asset_id, ALICE, true, 1))
+			.collect(),
+		metadata: vec![],
+		accounts: vec![],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	// the `uatom` vouchers are registered under the hash of their trace
+	let uatom = uatom_voucher().asset_name().unwrap();
+	let asset_id_by_name =
+		[(DOT.to_vec(), DOT_ID), (uatom, UATOM_ID), (SIBLING_UATOM.to_vec(), SIBLING_UATOM_ID)]
+			.into_iter()
+			.map(|(name, asset_id)| (String::from_utf8(name).unwrap(), asset_id))
+			.collect();
+	pallet_ics20_transfer::GenesisConfig::<Runtime> { asset_id_by_name }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		msg_queue::ParachainId::<Runtime>::put(
+			polkadot_parachain_primitives::primitives::Id::from(para_id),
+		);
+		pallet_ibc::GenesisConfig::<Runtime>::default().build();
+		Timestamp::set_timestamp(6_000);
+		open_transfer_channel();
+	});
+	ext
+}
+
+/// Relay chain, with the DOT of `ALICE`.
+pub fn relay_ext() -> sp_io::TestExternalities {
+	use relay::{Runtime, System};
+
+	let mut storage = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> { balances: vec![(ALICE, INITIAL_BALANCE)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
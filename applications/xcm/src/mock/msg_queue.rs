@@ -0,0 +1,100 @@
+//! Minimal XCMP and DMP queue of the mock parachains, executing messages as they arrive.
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use codec::{Decode, Encode};
+	use frame_support::pallet_prelude::*;
+	use polkadot_parachain_primitives::primitives::{
+		DmpMessageHandler, Id as ParaId, XcmpMessageFormat, XcmpMessageHandler,
+	};
+	use sp_std::vec::Vec;
+	use xcm::{latest::prelude::*, VersionedXcm};
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		type XcmExecutor: ExecuteXcm<Self::RuntimeCall>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {}
+
+	#[pallet::storage]
+	/// value: id of this parachain
+	pub type ParachainId<T: Config> = StorageValue<_, ParaId, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A message from `origin` was executed
+		Executed { origin: Location, outcome: Outcome },
+		/// A message was not decoded
+		BadFormat,
+	}
+
+	impl<T: Config> Get<ParaId> for Pallet<T> {
+		fn get() -> ParaId {
+			ParachainId::<T>::get()
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		fn execute(origin: Location, xcm: VersionedXcm<T::RuntimeCall>, max_weight: Weight) {
+			let mut id = xcm.using_encoded(sp_io::hashing::blake2_256);
+			let Ok(xcm) = Xcm::try_from(xcm) else { return Self::deposit_event(Event::BadFormat) };
+			let outcome = T::XcmExecutor::prepare_and_execute(
+				origin.clone(),
+				xcm,
+				&mut id,
+				max_weight,
+				Weight::zero(),
+			);
+			Self::deposit_event(Event::Executed { origin, outcome });
+		}
+	}
+
+	impl<T: Config> XcmpMessageHandler for Pallet<T> {
+		fn handle_xcmp_messages<'a, I: Iterator<Item = (ParaId, u32, &'a [u8])>>(
+			iter: I,
+			max_weight: Weight,
+		) -> Weight {
+			for (sender, _sent_at, mut data) in iter {
+				if XcmpMessageFormat::decode(&mut data).is_err() {
+					Self::deposit_event(Event::BadFormat);
+					continue
+				}
+				let origin = Location::new(1, [Parachain(sender.into())]);
+				while !data.is_empty() {
+					match VersionedXcm::<T::RuntimeCall>::decode(&mut data) {
+						Ok(xcm) => Self::execute(origin.clone(), xcm, max_weight),
+						Err(_) => {
+							Self::deposit_event(Event::BadFormat);
+							break
+						},
+					}
+				}
+			}
+			max_weight
+		}
+	}
+
+	impl<T: Config> DmpMessageHandler for Pallet<T> {
+		fn handle_dmp_messages(
+			iter: impl Iterator<Item = (u32, Vec<u8>)>,
+			limit: Weight,
+		) -> Weight {
+			for (_sent_at, data) in iter {
+				match VersionedXcm::<T::RuntimeCall>::decode(&mut &data[..]) {
+					Ok(xcm) => Self::execute(Location::parent(), xcm, limit),
+					Err(_) => Self::deposit_event(Event::BadFormat),
+				}
+			}
+			limit
+		}
+	}
+}
@@ -0,0 +1,316 @@
+//! Mock parachain with IBC, ICS-20 transfers and the XCM bridge, run as both `ParaA` and `ParaB`.
+use super::{msg_queue, ParachainXcmRouter};
+use crate::{self as pallet_ics20_xcm, location::Ics20Locations, transactor::Ics20AssetTransactor};
+use frame_support::{
+	construct_runtime, parameter_types,
+	traits::{
+		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64, ContainsPair, Everything,
+		Nothing, PalletInfoAccess,
+	},
+	weights::Weight,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use pallet_ibc_utils::module::Router;
+use pallet_ics20_transfer::denom::PrefixedDenom;
+use polkadot_parachain_primitives::primitives::Sibling;
+use sp_runtime::{
+	generic,
+	traits::{AccountIdLookup, BlakeTwo256},
+	AccountId32,
+};
+use xcm::latest::prelude::*;
+use xcm_builder::{
+	AccountId32Aliases, AllowUnpaidExecutionFrom, DescribeAllTerminal, DescribeFamily,
+	EnsureXcmOrigin, FixedWeightBounds, FrameTransactionalProcessor, HashedDescription,
+	ParentIsPreset, SiblingParachainConvertsVia, SignedAccountId32AsNative, SignedToAccountId32,
+	SovereignSignedViaLocation,
+};
+use xcm_executor::XcmExecutor;
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+pub type AssetId = u32;
+/// Type used for expressing timestamp.
+pub type Moment = u64;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+type Block = frame_system::mocking::MockBlock<Runtime>;
+
+construct_runtime!(
+	pub enum Runtime where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		Assets: pallet_assets,
+		Timestamp: pallet_timestamp,
+		MsgQueue: msg_queue,
+		PolkadotXcm: pallet_xcm,
+		Ibc: pallet_ibc,
+		Ics20Transfer: pallet_ics20_transfer,
+		IbcXcm: pallet_ics20_xcm,
+	}
+);
+
+/// Index of a transaction in the chain.
+pub type Index = u32;
+/// An index to a block.
+pub type BlockNumber = u32;
+
+impl frame_system::Config for Runtime {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type AccountId = AccountId;
+	type RuntimeCall = RuntimeCall;
+	type Lookup = AccountIdLookup<AccountId, ()>;
+	type Index = Index;
+	type BlockNumber = BlockNumber;
+	type Hash = sp_core::H256;
+	type Hashing = BlakeTwo256;
+	type Header = generic::Header<BlockNumber, BlakeTwo256>;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type BlockHashCount = ();
+	type DbWeight = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Runtime {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = Balance;
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type FreezeIdentifier = ();
+	type MaxFreezes = ();
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type RuntimeFreezeReason = RuntimeFreezeReason;
+	type MaxHolds = ConstU32<1>;
+}
+
+impl pallet_assets::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type AssetIdParameter = AssetId;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = ConstU128<1>;
+	type AssetAccountDeposit = ConstU128<1>;
+	type MetadataDepositBase = ConstU128<1>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type RemoveItemsLimit = ConstU32<5>;
+	type WeightInfo = ();
+	type CallbackHandle = ();
+}
+
+impl pallet_timestamp::Config for Runtime {
+	type Moment = Moment;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<3000>;
+	type WeightInfo = ();
+}
+
+impl msg_queue::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type XcmExecutor = XcmExecutor<XcmConfig>;
+}
+
+parameter_types! {
+	pub const RelayNetwork: NetworkId = NetworkId::Kusama;
+	pub UniversalLocation: InteriorLocation =
+		[GlobalConsensus(RelayNetwork::get()), Parachain(MsgQueue::get().into())].into();
+	pub const UnitWeightCost: Weight = Weight::from_parts(10, 10);
+	pub const MaxInstructions: u32 = 100;
+	pub const MaxAssetsIntoHolding: u32 = 64;
+	/// DOT, and the vouchers of `ParaA` for `transfer/channel-0/uatom` on `ParaB`
+	pub RegisteredAssets: Vec<(Location, Vec<u8>)> = vec![
+		(Location::parent(), DOT.to_vec()),
+		(sibling_voucher_location(), SIBLING_UATOM.to_vec()),
+	];
+}
+
+pub type LocationToAccountId = (
+	ParentIsPreset<AccountId>,
+	SiblingParachainConvertsVia<Sibling, AccountId>,
+	AccountId32Aliases<RelayNetwork, AccountId>,
+	HashedDescription<AccountId, DescribeFamily<DescribeAllTerminal>>,
+);
+
+pub type XcmOriginToCallOrigin = (
+	SovereignSignedViaLocation<LocationToAccountId, RuntimeOrigin>,
+	SignedAccountId32AsNative<RelayNetwork, RuntimeOrigin>,
+);
+
+pub type LocalOriginToLocation = SignedToAccountId32<RuntimeOrigin, AccountId, RelayNetwork>;
+
+pub type XcmRouter = ParachainXcmRouter<MsgQueue>;
+
+/// Accepts the assets located under their origin as reserve assets.
+pub struct ReserveOfOrigin;
+impl ContainsPair<Asset, Location> for ReserveOfOrigin {
+	fn contains(asset: &Asset, origin: &Location) -> bool {
+		asset.id.0.starts_with(origin)
+	}
+}
+
+pub struct XcmConfig;
+impl xcm_executor::Config for XcmConfig {
+	type RuntimeCall = RuntimeCall;
+	type XcmSender = XcmRouter;
+	type AssetTransactor = Ics20AssetTransactor<Runtime, LocationToAccountId>;
+	type OriginConverter = XcmOriginToCallOrigin;
+	type IsReserve = ReserveOfOrigin;
+	type IsTeleporter = ();
+	type UniversalLocation = UniversalLocation;
+	type Barrier = AllowUnpaidExecutionFrom<Everything>;
+	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+	type Trader = ();
+	type ResponseHandler = ();
+	type AssetTrap = ();
+	type AssetLocker = ();
+	type AssetExchanger = ();
+	type AssetClaims = ();
+	type SubscriptionService = ();
+	type PalletInstancesInfo = AllPalletsWithSystem;
+	type MaxAssetsIntoHolding = MaxAssetsIntoHolding;
+	type FeeManager = ();
+	type MessageExporter = ();
+	type UniversalAliases = Nothing;
+	type CallDispatcher = RuntimeCall;
+	type SafeCallFilter = Everything;
+	type Aliasers = Nothing;
+	type TransactionalProcessor = FrameTransactionalProcessor;
+}
+
+impl pallet_xcm::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type SendXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+	type XcmRouter = XcmRouter;
+	type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+	type XcmExecuteFilter = Everything;
+	type XcmExecutor = XcmExecutor<XcmConfig>;
+	type XcmTeleportFilter = Nothing;
+	type XcmReserveTransferFilter = Everything;
+	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+	type UniversalLocation = UniversalLocation;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+	type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
+	type Currency = Balances;
+	type CurrencyMatcher = ();
+	type TrustedLockers = ();
+	type SovereignAccountOf = LocationToAccountId;
+	type MaxLockers = ConstU32<8>;
+	type MaxRemoteLockConsumers = ConstU32<0>;
+	type RemoteLockConsumerIdentifier = ();
+	type WeightInfo = pallet_xcm::TestWeightInfo;
+	type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+pub struct IbcModule;
+
+impl pallet_ibc_utils::module::AddModule for IbcModule {
+	fn add_module(router: Router) -> Router {
+		match router.add_route(
+			ibc::applications::transfer::MODULE_ID_STR.parse().expect("never failed"),
+			pallet_ics20_transfer::callback::IbcTransferModule::<Runtime>(
+				std::marker::PhantomData::<Runtime>,
+			),
+		) {
+			Ok(ret) => ret,
+			Err(e) => panic!("add module failed by {}", e),
+		}
+	}
+}
+
+parameter_types! {
+	pub const ExpectedBlockTime: u64 = 6;
+	pub const ChainVersion: u64 = 0;
+}
+
+impl pallet_ibc::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type TimeProvider = pallet_timestamp::Pallet<Runtime>;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	const IBC_COMMITMENT_PREFIX: &'static [u8] = b"Ibc";
+	type ChainVersion = ChainVersion;
+	type IbcModule = IbcModule;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type OnMisbehaviour = ();
+	type UpgradeModule = ();
+	type MaxPrunedPerBlock = ConstU32<10>;
+	type WasmClientGasLimit = ConstU64<10_000_000>;
+	type MaxWasmCodeSize = ConstU32<{ 3 * 1024 * 1024 }>;
+	type WeightInfo = ();
+}
+
+impl pallet_ics20_transfer::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type AssetId = AssetId;
+	type AssetBalance = Balance;
+	type Fungibles = Assets;
+	type AssetIdByName = Ics20Transfer;
+	type IbcContext = pallet_ibc::context::Context<Runtime>;
+	type AccountIdConversion = pallet_ics20_transfer::impls::IbcAccount;
+	type MemoHooks = ();
+	type MaxMemoLength = ConstU32<256>;
+	const NATIVE_TOKEN_NAME: &'static [u8] = b"DEMO";
+}
+
+impl pallet_ics20_xcm::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type DenomLocations = Ics20Locations<Runtime, RegisteredAssets>;
+}
+
+/// Base denomination of DOT, asset `DOT_ID`.
+pub const DOT: &[u8] = b"dot";
+pub const DOT_ID: AssetId = 1;
+/// Base denomination of the `uatom` vouchers of `uatom_voucher`, registered under their trace hash
+/// as asset `UATOM_ID`.
+pub const UATOM: &[u8] = b"uatom";
+pub const UATOM_ID: AssetId = 2;
+/// Base denomination of the `uatom` vouchers reserve transferred from `ParaA`, asset
+/// `SIBLING_UATOM_ID`.
+pub const SIBLING_UATOM: &[u8] = b"uatom-para-a";
+pub const SIBLING_UATOM_ID: AssetId = 3;
+
+/// The `uatom` voucher received over the channel `channel-0`.
+pub fn uatom_voucher() -> PrefixedDenom {
+	PrefixedDenom { trace_path: b"transfer/channel-0".to_vec(), base_denom: UATOM.to_vec() }
+}
+
+/// Location of the `uatom` voucher of `ParaA`, as seen from a sibling.
+pub fn sibling_voucher_location() -> Location {
+	let trace_key = crate::location::trace_key(&uatom_voucher()).expect("a voucher has a trace");
+	Location::new(
+		1,
+		[
+			Parachain(super::PARA_A),
+			PalletInstance(<Ics20Transfer as PalletInfoAccess>::index() as u8),
+			GeneralKey { length: 32, data: trace_key },
+		],
+	)
+}
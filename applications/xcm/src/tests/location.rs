@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		location::{DenomLocations, Ics20Locations},
+		mock::{
+			para::{
+				sibling_voucher_location, uatom_voucher, Assets, Balances, Ics20Transfer,
+				LocationToAccountId, RegisteredAssets, Runtime, DOT, DOT_ID, SIBLING_UATOM,
+				UATOM_ID,
+			},
+			para_ext, ALICE, INITIAL_BALANCE, PARA_A,
+		},
+		transactor::Ics20AssetTransactor,
+		Ics20Asset, Pallet,
+	};
+	use frame_support::traits::{fungibles::Inspect, Currency, PalletInfoAccess};
+	use pallet_ics20_transfer::{denom::PrefixedDenom, DenomTrace};
+	use xcm::latest::prelude::*;
+	use xcm_executor::traits::TransactAsset;
+
+	type Locations = Ics20Locations<Runtime, RegisteredAssets>;
+	type Transactor = Ics20AssetTransactor<Runtime, LocationToAccountId>;
+
+	fn un_traced(base_denom: &[u8]) -> PrefixedDenom {
+		PrefixedDenom { trace_path: vec![], base_denom: base_denom.to_vec() }
+	}
+
+	fn alice() -> Location {
+		AccountId32 { network: None, id: ALICE.into() }.into()
+	}
+
+	#[test]
+	fn native_and_registered_locations() {
+		para_ext(PARA_A).execute_with(|| {
+			assert_eq!(Locations::denom(&Location::here()), Some(un_traced(b"DEMO")));
+			assert_eq!(Locations::location(&un_traced(b"DEMO")), Some(Location::here()));
+
+			assert_eq!(Locations::denom(&Location::parent()), Some(un_traced(DOT)));
+			assert_eq!(Locations::location(&un_traced(DOT)), Some(Location::parent()));
+			assert_eq!(
+				Locations::denom(&sibling_voucher_location()),
+				Some(un_traced(SIBLING_UATOM))
+			);
+
+			assert_eq!(Locations::denom(&Location::new(1, [Parachain(3)])), None);
+			assert_eq!(Locations::location(&un_traced(b"unknown")), None);
+		})
+	}
+
+	#[test]
+	fn voucher_locations_key_the_denom_trace() {
+		para_ext(PARA_A).execute_with(|| {
+			let voucher = uatom_voucher();
+			let location = Locations::location(&voucher).unwrap();
+			let index = <Ics20Transfer as PalletInfoAccess>::index() as u8;
+			assert!(matches!(
+				location.unpack(),
+				(0, [PalletInstance(pallet_index), GeneralKey { length: 32, .. }])
+					if *pallet_index == index
+			));
+
+			// the voucher is only known once its trace was recorded
+			assert_eq!(Locations::denom(&location), None);
+			DenomTrace::<Runtime>::insert(voucher.trace_hash().unwrap(), voucher.clone());
+			assert_eq!(Locations::denom(&location), Some(voucher));
+		})
+	}
+
+	#[test]
+	fn transactor_mints_and_burns() {
+		para_ext(PARA_A).execute_with(|| {
+			let dot: Asset = (Parent, 500u128).into();
+			assert_eq!(Transactor::deposit_asset(&dot, &alice(), None), Ok(()));
+			assert_eq!(Assets::balance(DOT_ID, &ALICE), 500);
+			assert_eq!(Assets::total_issuance(DOT_ID), 500);
+
+			let withdrawn = Transactor::withdraw_asset(&(Parent, 200u128).into(), &alice(), None);
+			assert!(withdrawn.is_ok());
+			assert_eq!(Assets::balance(DOT_ID, &ALICE), 300);
+			assert_eq!(Assets::total_issuance(DOT_ID), 300);
+
+			// the native token goes through `Currency`
+			let native: Asset = (Here, 100u128).into();
+			assert!(Transactor::withdraw_asset(&native, &alice(), None).is_ok());
+			assert_eq!(Balances::free_balance(&ALICE), INITIAL_BALANCE - 100);
+
+			// vouchers of unknown traces are left to the next transactor
+			let voucher = Locations::location(&uatom_voucher()).unwrap();
+			assert_eq!(
+				Transactor::deposit_asset(&(voucher, 100u128).into(), &alice(), None),
+				Err(XcmError::AssetNotFound)
+			);
+			assert_eq!(Assets::total_issuance(UATOM_ID), 0);
+		})
+	}
+
+	#[test]
+	fn vouchers_resolve_by_trace_hash() {
+		para_ext(PARA_A).execute_with(|| {
+			assert!(matches!(
+				Pallet::<Runtime>::ics20_asset(&uatom_voucher()),
+				Some(Ics20Asset::Fungible(UATOM_ID))
+			));
+			assert!(matches!(
+				Pallet::<Runtime>::ics20_asset(&un_traced(b"DEMO")),
+				Some(Ics20Asset::Native)
+			));
+
+			// `uatom` of another origin is not the asset of the `uatom` vouchers
+			let other = PrefixedDenom {
+				trace_path: b"transfer/channel-1".to_vec(),
+				base_denom: uatom_voucher().base_denom,
+			};
+			assert!(Pallet::<Runtime>::ics20_asset(&other).is_none());
+			assert!(
+				Pallet::<Runtime>::ics20_asset(&un_traced(&uatom_voucher().base_denom)).is_none()
+			);
+		})
+	}
+}
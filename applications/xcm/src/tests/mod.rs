@@ -0,0 +1,2 @@
+mod location;
+mod transfer;
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		location::{DenomLocations, Ics20Locations},
+		message::transfer_message,
+		mock::{
+			para::{
+				self, uatom_voucher, Assets, Ics20Transfer, LocationToAccountId, PolkadotXcm,
+				RegisteredAssets, Runtime, RuntimeEvent, System, DOT, DOT_ID, SIBLING_UATOM_ID,
+				UATOM_ID,
+			},
+			para_ext, relay, MockNet, ParaA, ParaB, Relay, ALICE, BOB, PARA_A, PARA_B,
+		},
+		Error, Event, Pallet,
+	};
+	use codec::Encode;
+	use core::str::FromStr;
+	use frame_support::{
+		assert_ok,
+		traits::fungibles::{Inspect, Mutate},
+		weights::Weight,
+	};
+	use ibc::{
+		applications::transfer::PORT_ID_STR,
+		core::{
+			ics04_channel::packet::Sequence,
+			ics24_host::identifier::{ChannelId, PortId},
+		},
+	};
+	use pallet_ibc::context::Context;
+	use pallet_ibc_utils::traits::ChannelReaderInterface;
+	use pallet_ics20_transfer::DenomTrace;
+	use sp_runtime::DispatchError;
+	use xcm::{latest::prelude::*, VersionedXcm};
+	use xcm_executor::traits::ConvertLocation;
+	use xcm_simulator::TestExt;
+
+	const MEMO: &[u8] = br#"{"wasm":{"contract":"cosmos1contract"}}"#;
+	const RECEIVER: &[u8] = b"cosmos1receiver";
+	/// Far enough in the future for the localhost client.
+	const TIMEOUT_TIMESTAMP: u64 = 1_000_000_000_000_000_000;
+
+	/// `ALICE` of the relay chain, as seen from a parachain.
+	fn relay_alice() -> Location {
+		Location::new(1, [AccountId32 { network: Some(NetworkId::Kusama), id: ALICE.into() }])
+	}
+
+	fn sovereign_account(location: &Location) -> para::AccountId {
+		LocationToAccountId::convert_location(location).unwrap()
+	}
+
+	fn transfer_call(asset: Asset) -> para::RuntimeCall {
+		para::RuntimeCall::IbcXcm(crate::Call::transfer {
+			asset: Box::new(asset.into()),
+			channel_id: ChannelId::new(0),
+			receiver: RECEIVER.to_vec(),
+			timeout_height: None,
+			timeout_timestamp: TIMEOUT_TIMESTAMP,
+			memo: MEMO.to_vec(),
+		})
+	}
+
+	fn bridge_events() -> Vec<Event<Runtime>> {
+		System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::IbcXcm(event) => Some(event),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn dot_from_the_relay_chain_sent_over_ics20_with_a_memo() {
+		MockNet::reset();
+
+		Relay::execute_with(|| {
+			assert_ok!(relay::XcmPallet::limited_reserve_transfer_assets(
+				relay::RuntimeOrigin::signed(ALICE),
+				Box::new(Parachain(PARA_A).into()),
+				Box::new(relay_alice().into()),
+				Box::new((Here, 1_000u128).into()),
+				0,
+				Unlimited,
+			));
+		});
+		ParaA::execute_with(|| {
+			assert_eq!(Assets::balance(DOT_ID, &sovereign_account(&relay_alice())), 1_000);
+		});
+
+		// `ALICE` sends the DOT she holds on the parachain to a cosmos account
+		let message = transfer_message(
+			(Parent, 10u128).into(),
+			Unlimited,
+			transfer_call((Parent, 400u128).into()).encode(),
+			Weight::from_parts(1_000_000_000, 64 * 1024),
+			relay_alice(),
+		);
+		Relay::execute_with(|| {
+			assert_ok!(relay::XcmPallet::send(
+				relay::RuntimeOrigin::signed(ALICE),
+				Box::new(Parachain(PARA_A).into()),
+				Box::new(VersionedXcm::V4(message)),
+			));
+		});
+
+		ParaA::execute_with(|| {
+			let sender = sovereign_account(&relay_alice());
+			// the fees are deposited back
+			assert_eq!(Assets::balance(DOT_ID, &sender), 600);
+			assert_eq!(Ics20Transfer::total_escrow(&ChannelId::new(0), DOT), 400);
+			assert!(bridge_events().contains(&Event::TransferSent {
+				sender,
+				channel_id: ChannelId::new(0),
+				denom: DOT.to_vec(),
+				amount: 400,
+				memo: MEMO.to_vec(),
+			}));
+			assert!(<Context<Runtime> as ChannelReaderInterface>::get_packet_commitment(
+				&PortId::from_str(PORT_ID_STR).unwrap(),
+				&ChannelId::new(0),
+				&Sequence::from(1),
+			)
+			.is_ok());
+		});
+	}
+
+	#[test]
+	fn vouchers_reserve_transferred_to_a_sibling() {
+		MockNet::reset();
+
+		let voucher =
+			Ics20Locations::<Runtime, RegisteredAssets>::location(&uatom_voucher()).unwrap();
+		ParaA::execute_with(|| {
+			// `ALICE` received `uatom` vouchers over ICS-20
+			DenomTrace::<Runtime>::insert(uatom_voucher().trace_hash().unwrap(), uatom_voucher());
+			assert_ok!(Assets::mint_into(UATOM_ID, &ALICE, 1_000));
+
+			assert_ok!(PolkadotXcm::limited_reserve_transfer_assets(
+				para::RuntimeOrigin::signed(ALICE),
+				Box::new((Parent, Parachain(PARA_B)).into()),
+				Box::new(AccountId32 { network: None, id: BOB.into() }.into()),
+				Box::new((voucher, 400u128).into()),
+				0,
+				Unlimited,
+			));
+			assert_eq!(Assets::balance(UATOM_ID, &ALICE), 600);
+			// the vouchers are held by the sovereign account of the sibling
+			let sibling = sovereign_account(&Location::new(1, [Parachain(PARA_B)]));
+			assert_eq!(Assets::balance(UATOM_ID, &sibling), 400);
+		});
+
+		ParaB::execute_with(|| {
+			assert_eq!(Assets::balance(SIBLING_UATOM_ID, &BOB), 400);
+		});
+	}
+
+	#[test]
+	fn transfer_checks_the_asset_and_timeout() {
+		para_ext(PARA_A).execute_with(|| {
+			let transfer = |asset: Asset, timeout_timestamp| {
+				Pallet::<Runtime>::do_transfer(
+					ALICE,
+					asset,
+					ChannelId::new(0),
+					RECEIVER.to_vec(),
+					None,
+					timeout_timestamp,
+					MEMO.to_vec(),
+				)
+			};
+			let error = |error: Error<Runtime>| Err::<(), _>(DispatchError::from(error));
+
+			assert_eq!(
+				transfer((Parent, 0u128).into(), TIMEOUT_TIMESTAMP),
+				error(Error::InvalidAmount)
+			);
+			assert_eq!(
+				transfer((Parachain(3), 100u128).into(), TIMEOUT_TIMESTAMP),
+				error(Error::AssetNotFound)
+			);
+			assert_eq!(transfer((Parent, 100u128).into(), 0), error(Error::InvalidTimeout));
+		})
+	}
+}
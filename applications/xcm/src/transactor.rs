@@ -0,0 +1,106 @@
+//! `Ics20AssetTransactor`, the XCM asset transactor of the assets of `pallet_ics20_transfer`.
+//!
+//! Withdrawing an asset burns it and depositing it mints it, like the transfer pallet does with
+//! vouchers: the tokens of a reserve transfer out of this chain are held by the sovereign
+//! account of the destination, and the tokens of a reserve transfer into this chain are backed
+//! by the sovereign account of this chain on their reserve.
+use crate::{location::DenomLocations, Config, Ics20Asset, Pallet, LOG_TARGET};
+use frame_support::traits::{
+	fungibles::Mutate,
+	tokens::{Fortitude, Precision},
+	Currency, ExistenceRequirement, WithdrawReasons,
+};
+use sp_runtime::traits::CheckedConversion;
+use sp_std::marker::PhantomData;
+use xcm::latest::prelude::*;
+use xcm_executor::{
+	traits::{ConvertLocation, TransactAsset},
+	AssetsInHolding,
+};
+
+/// Transacts the assets `Config::DenomLocations` has a denomination for, held by the accounts
+/// `AccountIdConverter` converts locations into.
+pub struct Ics20AssetTransactor<T, AccountIdConverter>(PhantomData<(T, AccountIdConverter)>);
+
+impl<T, AccountIdConverter> Ics20AssetTransactor<T, AccountIdConverter>
+where
+	T: Config,
+	AccountIdConverter: ConvertLocation<T::AccountId>,
+{
+	/// The asset and amount of `what`, `AssetNotFound` letting the next transactor try it.
+	fn matches(what: &Asset) -> Result<(Ics20Asset<T::AssetId>, u128), XcmError> {
+		let Fungible(amount) = what.fun else { return Err(XcmError::AssetNotFound) };
+		let asset = T::DenomLocations::denom(&what.id.0)
+			.and_then(|denom| Pallet::<T>::ics20_asset(&denom))
+			.ok_or(XcmError::AssetNotFound)?;
+		Ok((asset, amount))
+	}
+
+	fn account_id(who: &Location) -> Result<T::AccountId, XcmError> {
+		AccountIdConverter::convert_location(who)
+			.ok_or(XcmError::FailedToTransactAsset("AccountIdConversionFailed"))
+	}
+}
+
+impl<T, AccountIdConverter> TransactAsset for Ics20AssetTransactor<T, AccountIdConverter>
+where
+	T: Config,
+	AccountIdConverter: ConvertLocation<T::AccountId>,
+{
+	fn deposit_asset(what: &Asset, who: &Location, _context: Option<&XcmContext>) -> XcmResult {
+		let (asset, amount) = Self::matches(what)?;
+		let who = Self::account_id(who)?;
+		match asset {
+			Ics20Asset::Native => {
+				let amount = amount.checked_into().ok_or(XcmError::Overflow)?;
+				let _ = <T::Currency as Currency<T::AccountId>>::deposit_creating(&who, amount);
+			},
+			Ics20Asset::Fungible(asset_id) => {
+				<T::Fungibles as Mutate<T::AccountId>>::mint_into(asset_id, &who, amount.into())
+					.map_err(|error| {
+						log::debug!(target: LOG_TARGET, "deposit of {:?} failed: {:?}", what, error);
+						XcmError::FailedToTransactAsset(error.into())
+					})?;
+			},
+		}
+		Ok(())
+	}
+
+	fn withdraw_asset(
+		what: &Asset,
+		who: &Location,
+		_maybe_context: Option<&XcmContext>,
+	) -> Result<AssetsInHolding, XcmError> {
+		let (asset, amount) = Self::matches(what)?;
+		let who = Self::account_id(who)?;
+		match asset {
+			Ics20Asset::Native => {
+				let amount = amount.checked_into().ok_or(XcmError::Overflow)?;
+				let _ = <T::Currency as Currency<T::AccountId>>::withdraw(
+					&who,
+					amount,
+					WithdrawReasons::TRANSFER,
+					ExistenceRequirement::AllowDeath,
+				)
+				.map_err(|error| {
+					log::debug!(target: LOG_TARGET, "withdrawal of {:?} failed: {:?}", what, error);
+					XcmError::FailedToTransactAsset(error.into())
+				})?;
+			},
+			Ics20Asset::Fungible(asset_id) => {
+				<T::Fungibles as Mutate<T::AccountId>>::burn_from(
+					asset_id,
+					&who,
+					amount.into(),
+					Precision::Exact,
+					Fortitude::Polite,
+				)
+				.map_err(|error| {
+					log::debug!(target: LOG_TARGET, "withdrawal of {:?} failed: {:?}", what, error);
+					XcmError::FailedToTransactAsset(error.into())
+				})?;
+			},
+		}
+		Ok(what.clone().into())
+	}
+}
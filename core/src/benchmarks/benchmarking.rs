@@ -14,10 +14,11 @@ use alloc::boxed::Box;
 use ibc::core::{
 	ics02_client::{
 		client_state::ClientState,
-		context::ClientKeeper,
+		context::{ClientKeeper, ClientReader},
 		height::Height,
 		msgs::{
 			create_client::{MsgCreateClient, TYPE_URL as CREATE_CLIENT_TYPE_URL},
+			misbehaviour::TYPE_URL as MISBEHAVIOUR_TYPE_URL,
 			update_client::TYPE_URL as UPDATE_CLIENT_TYPE_URL,
 		},
 	},
@@ -54,7 +55,7 @@ use ibc_proto::protobuf::Protobuf;
 use scale_info::prelude::string::ToString;
 use sp_std::vec;
 
-use super::utils::TIMESTAMP;
+use super::utils::{ED25519_PUBLIC, ED25519_SIGNATURE, MAX_MISBEHAVIOUR_SIGNATURES, TIMESTAMP};
 
 benchmarks! {
 	where_clause {
@@ -143,6 +144,39 @@ benchmarks! {
 
 	}
 
+	// Freeze a client with misbehaviour evidence. The light client verifies every commit signature
+	// of the evidence on top of it, which is benchmarked by `ed25519_verify`.
+	misbehaviour_mock {
+		let mut ctx = crate::context::Context::<T>::new();
+		let now: <T as pallet_timestamp::Config>::Moment = TIMESTAMP.saturating_mul(1000);
+		pallet_timestamp::Pallet::<T>::set_timestamp(now);
+		let height = Height::new(0, 1).unwrap();
+		let (mock_cl_state, mock_cs_state) = super::utils::create_mock_state(height);
+		let client_id = ClientId::new(mock_client_state::client_type(), 0).unwrap();
+		ctx.store_client_type(client_id.clone(), mock_client_state::client_type()).unwrap();
+		ctx.store_client_state(client_id.clone(), Box::new(mock_cl_state)).unwrap();
+		ctx.store_consensus_state(client_id.clone(), height, Box::new(mock_cs_state)).unwrap();
+
+		let value = super::utils::create_mock_misbehaviour(client_id.clone(), Height::new(0, 2).unwrap());
+
+		let msg = Any { type_url: MISBEHAVIOUR_TYPE_URL.to_string(), value };
+		let caller: T::AccountId = whitelisted_caller();
+	}: deliver(RawOrigin::Signed(caller), vec![msg])
+	verify {
+		assert!(ctx.client_state(&client_id).unwrap().is_frozen());
+	}
+
+	// Verify `s` ed25519 signatures, like the commit signatures of misbehaviour evidence.
+	ed25519_verify {
+		let s in 1 .. MAX_MISBEHAVIOUR_SIGNATURES;
+		let public = sp_core::ed25519::Public::from_raw(ED25519_PUBLIC);
+		let signature = sp_core::ed25519::Signature::from_raw(ED25519_SIGNATURE);
+	}: {
+		for _ in 0..s {
+			assert!(sp_io::crypto::ed25519_verify(&signature, &[], &public));
+		}
+	}
+
 	// misbehaviours client
 	conn_open_init_mock {
 		let mut ctx = crate::context::Context::<T>::new();
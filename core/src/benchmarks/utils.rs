@@ -21,7 +21,10 @@ use crate::{
 use alloc::vec::Vec;
 use ibc::{
 	core::{
-		ics02_client::msgs::{update_client::MsgUpdateClient, upgrade_client::MsgUpgradeClient},
+		ics02_client::msgs::{
+			misbehaviour::MsgSubmitMisbehaviour, update_client::MsgUpdateClient,
+			upgrade_client::MsgUpgradeClient,
+		},
 		ics03_connection::msgs::{
 			conn_open_ack::MsgConnectionOpenAck, conn_open_confirm::MsgConnectionOpenConfirm,
 			conn_open_try::MsgConnectionOpenTry,
@@ -36,6 +39,7 @@ use ibc::{
 	},
 	mock::{
 		client_state::MockClientState, consensus_state::MockConsensusState, header::MockHeader,
+		misbehaviour::Misbehaviour as MockMisbehaviour,
 	},
 	timestamp::Timestamp,
 	Height,
@@ -44,6 +48,23 @@ use ibc_proto::protobuf::Protobuf;
 
 pub const TIMESTAMP: u64 = 1650894363;
 
+/// Upper bound of the commit signatures carried by the two headers of misbehaviour evidence.
+pub const MAX_MISBEHAVIOUR_SIGNATURES: u32 = 300;
+
+/// Public key of the first ed25519 test vector of RFC 8032.
+pub const ED25519_PUBLIC: [u8; 32] = [
+	0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64, 0x07, 0x3a,
+	0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68, 0xf7, 0x07, 0x51, 0x1a,
+];
+
+/// Signature of the empty message in the first ed25519 test vector of RFC 8032.
+pub const ED25519_SIGNATURE: [u8; 64] = [
+	0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e, 0x82, 0x8a,
+	0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65, 0x22, 0x49, 0x01, 0x55,
+	0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e, 0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b,
+	0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24, 0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+];
+
 pub fn create_mock_state(height: Height) -> (MockClientState, MockConsensusState) {
 	let mock_header = MockHeader {
 		height,
@@ -91,6 +112,26 @@ pub fn create_mock_upgrade_client(client_id: ClientId, height: Height) -> Vec<u8
 	msg
 }
 
+pub fn create_mock_misbehaviour(client_id: ClientId, height: Height) -> Vec<u8> {
+	let header = |timestamp: u64| MockHeader {
+		height,
+		timestamp: Timestamp::from_nanoseconds(timestamp.saturating_mul(1000)).unwrap(),
+	};
+	let misbehaviour = MockMisbehaviour {
+		client_id: client_id.clone(),
+		header1: header(TIMESTAMP),
+		header2: header(TIMESTAMP + 1),
+	};
+
+	MsgSubmitMisbehaviour {
+		client_id,
+		misbehaviour: misbehaviour.into(),
+		signer: crate::tests::common::get_dummy_account_id(),
+	}
+	.encode_vec()
+	.unwrap()
+}
+
 pub fn create_conn_open_try<T: Config>(
 	block_height: Height,
 	host_chain_height: Height,
@@ -27,7 +27,9 @@ use ibc::{
 use pallet_ibc_utils::traits::{ChannelKeeperInterface, ChannelReaderInterface};
 use sp_std::{boxed::Box, vec::Vec};
 
+pub mod allow_timeout;
 pub mod impls;
+pub mod upgrade;
 
 impl<T: Config> ChannelReader for Context<T>
 where
@@ -70,7 +72,7 @@ where
 		port_id: &PortId,
 		channel_id: &ChannelId,
 	) -> Result<Sequence, PacketError> {
-		<Context<T> as ChannelReaderInterface>::get_next_sequence_ack(port_id, channel_id)
+		<Context<T> as ChannelReaderInterface>::get_next_sequence_send(port_id, channel_id)
 	}
 
 	fn get_next_sequence_recv(
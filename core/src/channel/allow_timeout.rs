@@ -0,0 +1,431 @@
+//! `ORDERED_ALLOW_TIMEOUT` channels, ordered channels which do not close when a packet times out.
+//!
+//! ibc-rs 0.28 only knows ordered and unordered channels, so these channels are opened and
+//! stored as ordered, and recorded in `OrderedAllowTimeoutChannels`. Sending, receiving and
+//! acknowledging packets follow the ordered semantics of ibc-rs. A timed out packet is handled
+//! by this pallet on both ends instead, as ibc-rs would close the channel:
+//!
+//! - the receiving end skips the sequence when the packet is relayed after its timeout, moving
+//!   `NextSequenceRecv` past it and storing `TIMEOUT_RECEIPT` as its acknowledgement commitment;
+//! - the sending end times the packet out with a proof of its timeout receipt once the counterparty
+//!   skipped it, keeping the channel open and moving `NextSequenceAck` past the packet. A proof of
+//!   the counterparty `NextSequenceRecv` is refused: the counterparty may still skip the sequence
+//!   and acknowledge a later packet, which this end would then reject.
+//!
+//! Each of these messages is handled in a storage transaction, rolled back with the changes of
+//! the module callback if it fails.
+//!
+//! Counterparties prove the channel ends of these channels as ordered, so the handshake only
+//! verifies against chains storing them the same way.
+use crate::{
+	context::Context, Channels, Config, Connections, Error, Event, NextSequenceAck,
+	NextSequenceRecv, OrderedAllowTimeoutChannels, PacketCommitment as PacketCommitStore, Pallet,
+};
+use alloc::{boxed::Box, string::ToString};
+use core::str::FromStr;
+use frame_support::{
+	ensure,
+	storage::{with_transaction, TransactionOutcome},
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::{
+	core::{
+		ics02_client::{
+			client_state::ClientState, consensus_state::ConsensusState, context::ClientReader,
+		},
+		ics03_connection::connection::ConnectionEnd,
+		ics04_channel::{
+			channel::{ChannelEnd, State},
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			context::{ChannelKeeper, ChannelReader},
+			events::TimeoutPacket,
+			packet::{Packet, Sequence},
+		},
+		ics05_port::context::PortReader,
+		ics23_commitment::commitment::CommitmentProofBytes,
+		ics24_host::{
+			identifier::{ChannelId, PortId},
+			path::{ChannelEndsPath, CommitmentsPath, ConnectionsPath, SeqAcksPath, SeqRecvsPath},
+		},
+		ics26_routing::context::{ModuleOutputBuilder, Router},
+	},
+	events::IbcEvent,
+	signer::Signer,
+	Height,
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::{
+		channel::v1::{
+			MsgChannelOpenInit as RawMsgChannelOpenInit, MsgChannelOpenTry as RawMsgChannelOpenTry,
+			MsgRecvPacket as RawMsgRecvPacket, MsgTimeout as RawMsgTimeout, Packet as RawPacket,
+		},
+		client::v1::Height as RawHeight,
+	},
+};
+use pallet_ibc_utils::traits::{ChannelKeeperInterface, ChannelReaderInterface};
+use prost::Message;
+use sp_runtime::DispatchError;
+use sp_std::{vec, vec::Vec};
+
+pub const MSG_CHANNEL_OPEN_INIT_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenInit";
+pub const MSG_CHANNEL_OPEN_TRY_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenTry";
+pub const MSG_RECV_PACKET_TYPE_URL: &str = "/ibc.core.channel.v1.MsgRecvPacket";
+pub const MSG_TIMEOUT_TYPE_URL: &str = "/ibc.core.channel.v1.MsgTimeout";
+
+/// `Order::ORDERED_ALLOW_TIMEOUT` of the channel protos.
+pub const ORDER_ORDERED_ALLOW_TIMEOUT: i32 = 3;
+/// `Order::ORDERED` of the channel protos.
+const ORDER_ORDERED: i32 = 2;
+
+/// Acknowledgement stored by the receiving end for a skipped packet, hashed into its commitment.
+pub const TIMEOUT_RECEIPT: &[u8] = b"ibc-timeout-receipt";
+
+/// Packet message on an `ORDERED_ALLOW_TIMEOUT` channel handled by this pallet.
+enum AllowTimeoutMessage {
+	/// A packet relayed after its timeout, to skip
+	Skip(ChannelEndsPath, ChannelEnd, RawMsgRecvPacket),
+	/// A timeout of a packet, keeping the channel open
+	Timeout(ChannelEndsPath, ChannelEnd, RawMsgTimeout),
+}
+
+fn decode<T: Config, M: Message + Default>(msg: &Any) -> Result<M, Error<T>> {
+	M::decode(msg.value.as_slice()).map_err(|_| Error::<T>::InvalidPacket)
+}
+
+fn proof_height<T: Config>(height: Option<RawHeight>) -> Result<Height, Error<T>> {
+	height
+		.and_then(|height| Height::new(height.revision_number, height.revision_height).ok())
+		.ok_or(Error::<T>::InvalidHeight)
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Returns true if the channel was opened with the `ORDERED_ALLOW_TIMEOUT` ordering.
+	pub fn is_ordered_allow_timeout(port_id: &PortId, channel_id: &ChannelId) -> bool {
+		<OrderedAllowTimeoutChannels<T>>::contains_key(ChannelEndsPath(
+			port_id.clone(),
+			channel_id.clone(),
+		))
+	}
+
+	/// Commitment of `TIMEOUT_RECEIPT`, stored in place of an acknowledgement commitment.
+	pub fn timeout_receipt_commitment() -> AcknowledgementCommitment {
+		<Context<T> as ChannelReaderInterface>::hash(TIMEOUT_RECEIPT).into()
+	}
+
+	fn allow_timeout_channel(
+		port_id: &str,
+		channel_id: &str,
+	) -> Option<(ChannelEndsPath, ChannelEnd)> {
+		let path =
+			ChannelEndsPath(PortId::from_str(port_id).ok()?, ChannelId::from_str(channel_id).ok()?);
+		if !<OrderedAllowTimeoutChannels<T>>::contains_key(&path) {
+			return None;
+		}
+		<Channels<T>>::get(&path).map(|channel_end| (path, channel_end))
+	}
+
+	/// Rewrite the ordering of a handshake message opening an `ORDERED_ALLOW_TIMEOUT` channel
+	/// to ordered, and return the channel to record once ibc-rs opened it.
+	pub(crate) fn allow_timeout_hook(msg: Any) -> (Any, Option<ChannelEndsPath>) {
+		let next_channel = |port_id: &str| {
+			let port_id = PortId::from_str(port_id).ok()?;
+			let channel_id = ChannelId::new(<crate::ChannelCounter<T>>::get());
+			Some(ChannelEndsPath(port_id, channel_id))
+		};
+
+		match msg.type_url.as_str() {
+			MSG_CHANNEL_OPEN_INIT_TYPE_URL => {
+				match RawMsgChannelOpenInit::decode(msg.value.as_slice()) {
+					Ok(mut raw)
+						if raw.channel.as_ref().map(|c| c.ordering) ==
+							Some(ORDER_ORDERED_ALLOW_TIMEOUT) =>
+					{
+						raw.channel.as_mut().map(|channel| channel.ordering = ORDER_ORDERED);
+						let opened = next_channel(&raw.port_id);
+						(Any { type_url: msg.type_url, value: raw.encode_to_vec() }, opened)
+					},
+					_ => (msg, None),
+				}
+			},
+			MSG_CHANNEL_OPEN_TRY_TYPE_URL => {
+				match RawMsgChannelOpenTry::decode(msg.value.as_slice()) {
+					Ok(mut raw)
+						if raw.channel.as_ref().map(|c| c.ordering) ==
+							Some(ORDER_ORDERED_ALLOW_TIMEOUT) =>
+					{
+						raw.channel.as_mut().map(|channel| channel.ordering = ORDER_ORDERED);
+						let opened = next_channel(&raw.port_id);
+						(Any { type_url: msg.type_url, value: raw.encode_to_vec() }, opened)
+					},
+					_ => (msg, None),
+				}
+			},
+			_ => (msg, None),
+		}
+	}
+
+	/// Record the `ORDERED_ALLOW_TIMEOUT` channel opened by a handshake message, if ibc-rs
+	/// stored it.
+	pub(crate) fn after_allow_timeout_hook(opened: Option<ChannelEndsPath>) {
+		if let Some(path) = opened {
+			if <Channels<T>>::contains_key(&path) {
+				<OrderedAllowTimeoutChannels<T>>::insert(path, ());
+			}
+		}
+	}
+
+	fn advance_next_sequence_ack(path: &ChannelEndsPath, sequence: u64) {
+		<NextSequenceAck<T>>::mutate(SeqAcksPath(path.0.clone(), path.1.clone()), |next| {
+			if next.map_or(true, |next| u64::from(next) <= sequence) {
+				*next = Some(Sequence::from(sequence + 1));
+			}
+		});
+	}
+
+	/// The packet message on an `ORDERED_ALLOW_TIMEOUT` channel which ibc-rs cannot handle
+	/// without closing the channel: a packet relayed after its timeout to the receiving end, or
+	/// any timeout of a packet.
+	fn allow_timeout_message(ctx: &Context<T>, msg: &Any) -> Option<AllowTimeoutMessage> {
+		match msg.type_url.as_str() {
+			MSG_RECV_PACKET_TYPE_URL => {
+				let raw: RawMsgRecvPacket = decode::<T, _>(msg).ok()?;
+				let packet = raw.packet.as_ref()?;
+				let (path, channel_end) = Self::allow_timeout_channel(
+					&packet.destination_port,
+					&packet.destination_channel,
+				)?;
+				if !Self::timed_out_on_host(ctx, packet) {
+					return None;
+				}
+				Some(AllowTimeoutMessage::Skip(path, channel_end, raw))
+			},
+			MSG_TIMEOUT_TYPE_URL => {
+				let raw: RawMsgTimeout = decode::<T, _>(msg).ok()?;
+				let packet = raw.packet.as_ref()?;
+				let (path, channel_end) =
+					Self::allow_timeout_channel(&packet.source_port, &packet.source_channel)?;
+				Some(AllowTimeoutMessage::Timeout(path, channel_end, raw))
+			},
+			_ => None,
+		}
+	}
+
+	/// Handle `msg` in a storage transaction if ibc-rs cannot handle it without closing its
+	/// `ORDERED_ALLOW_TIMEOUT` channel, returning the events to emit. `None` otherwise.
+	pub(crate) fn deliver_allow_timeout(
+		ctx: &mut Context<T>,
+		msg: &Any,
+	) -> Option<Result<Vec<IbcEvent>, DispatchError>> {
+		let message = Self::allow_timeout_message(ctx, msg)?;
+		Some(with_transaction(|| {
+			let result = match message {
+				AllowTimeoutMessage::Skip(path, channel_end, raw) =>
+					Self::skip_timed_out_packet(ctx, path, channel_end, raw).map(|()| vec![]),
+				AllowTimeoutMessage::Timeout(path, channel_end, raw) =>
+					Self::timeout_packet(ctx, path, channel_end, raw),
+			};
+			match result {
+				Ok(events) => TransactionOutcome::Commit(Ok(events)),
+				Err(error) => TransactionOutcome::Rollback(Err(error.into())),
+			}
+		}))
+	}
+
+	fn timed_out_on_host(ctx: &Context<T>, packet: &RawPacket) -> bool {
+		let height_reached = match (&packet.timeout_height, ChannelReader::host_height(ctx)) {
+			(Some(timeout), Ok(host)) if timeout.revision_height != 0 =>
+				(host.revision_number(), host.revision_height()) >=
+					(timeout.revision_number, timeout.revision_height),
+			_ => false,
+		};
+		let timestamp_reached = packet.timeout_timestamp != 0 &&
+			ClientReader::host_timestamp(ctx)
+				.map_or(false, |host| host.nanoseconds() >= packet.timeout_timestamp);
+		height_reached || timestamp_reached
+	}
+
+	/// The connection and light client of the counterparty of `channel_end`.
+	fn counterparty_client(
+		channel_end: &ChannelEnd,
+	) -> Result<(ConnectionEnd, Box<dyn ClientState>), Error<T>> {
+		ensure!(channel_end.state_matches(&State::Open), Error::<T>::ChannelNotOpen);
+		let connection_id =
+			channel_end.connection_hops().first().ok_or(Error::<T>::InvalidPacket)?;
+		let connection_end = <Connections<T>>::get(ConnectionsPath(connection_id.clone()))
+			.ok_or(Error::<T>::InvalidConnectionId)?;
+		let client_state =
+			<Context<T> as ChannelReaderInterface>::client_state(connection_end.client_id())
+				.map_err(|_| Error::<T>::ClientNotFound)?;
+		Ok((connection_end, client_state))
+	}
+
+	/// Ensure the packet is sent between the two ends of `channel_end`.
+	fn ensure_counterparty(
+		channel_end: &ChannelEnd,
+		port_id: &str,
+		channel_id: &str,
+	) -> Result<(), Error<T>> {
+		let counterparty = channel_end.counterparty();
+		ensure!(
+			counterparty.port_id().as_str() == port_id &&
+				counterparty.channel_id().map(|id| id.to_string()) ==
+					Some(channel_id.to_string()),
+			Error::<T>::InvalidPacket
+		);
+		Ok(())
+	}
+
+	/// Skip the sequence of a packet relayed after its timeout, leaving the timeout receipt
+	/// for the sending end to prove.
+	fn skip_timed_out_packet(
+		ctx: &Context<T>,
+		path: ChannelEndsPath,
+		channel_end: ChannelEnd,
+		raw: RawMsgRecvPacket,
+	) -> Result<(), Error<T>> {
+		let raw_packet = raw.packet.ok_or(Error::<T>::InvalidPacket)?;
+		Self::ensure_counterparty(
+			&channel_end,
+			&raw_packet.source_port,
+			&raw_packet.source_channel,
+		)?;
+		let packet = Packet::try_from(raw_packet).map_err(|_| Error::<T>::InvalidPacket)?;
+		let commitment = ChannelReader::packet_commitment(
+			ctx,
+			&packet.data,
+			&packet.timeout_height_on_b,
+			&packet.timeout_timestamp_on_b,
+		);
+		let next_sequence_recv =
+			<NextSequenceRecv<T>>::get(SeqRecvsPath(path.0.clone(), path.1.clone()))
+				.map_or(1, u64::from);
+		ensure!(
+			u64::from(packet.sequence) == next_sequence_recv,
+			Error::<T>::PacketSequenceMismatch
+		);
+
+		let (connection_end, client_state) = Self::counterparty_client(&channel_end)?;
+		let proof_height = proof_height::<T>(raw.proof_height)?;
+		let consensus_state = <Context<T> as ChannelReaderInterface>::client_consensus_state(
+			connection_end.client_id(),
+			&proof_height,
+		)
+		.map_err(|_| Error::<T>::ConsensusStateNotFound)?;
+		let proof = CommitmentProofBytes::try_from(raw.proof_commitment)
+			.map_err(|_| Error::<T>::InvalidProof)?;
+		client_state
+			.verify_packet_data(
+				ctx,
+				proof_height,
+				&connection_end,
+				&proof,
+				consensus_state.root(),
+				&packet.port_on_a,
+				&packet.chan_on_a,
+				packet.sequence,
+				commitment,
+			)
+			.map_err(|_| Error::<T>::InvalidProof)?;
+
+		<NextSequenceRecv<T>>::insert(
+			SeqRecvsPath(path.0.clone(), path.1.clone()),
+			Sequence::from(next_sequence_recv + 1),
+		);
+		<Context<T> as ChannelKeeperInterface>::store_packet_acknowledgement(
+			path.0.clone(),
+			path.1.clone(),
+			packet.sequence,
+			Self::timeout_receipt_commitment(),
+		)
+		.map_err(|_| Error::<T>::InvalidPacket)?;
+		Self::deposit_event(Event::PacketReceiveSkipped {
+			port_id: path.0,
+			channel_id: path.1,
+			sequence: next_sequence_recv,
+		});
+		Ok(())
+	}
+
+	/// Time out a packet, keeping the channel open. The counterparty proves the timeout receipt
+	/// of the packet, stored when it skipped it.
+	fn timeout_packet(
+		ctx: &mut Context<T>,
+		path: ChannelEndsPath,
+		channel_end: ChannelEnd,
+		raw: RawMsgTimeout,
+	) -> Result<Vec<IbcEvent>, Error<T>> {
+		let raw_packet = raw.packet.ok_or(Error::<T>::InvalidPacket)?;
+		Self::ensure_counterparty(
+			&channel_end,
+			&raw_packet.destination_port,
+			&raw_packet.destination_channel,
+		)?;
+		let packet = Packet::try_from(raw_packet).map_err(|_| Error::<T>::InvalidPacket)?;
+		let sequence = u64::from(packet.sequence);
+		let next_sequence_ack =
+			<NextSequenceAck<T>>::get(SeqAcksPath(path.0.clone(), path.1.clone()))
+				.map_or(1, u64::from);
+		ensure!(sequence == next_sequence_ack, Error::<T>::PacketSequenceMismatch);
+
+		let commitment_path = CommitmentsPath {
+			port_id: path.0.clone(),
+			channel_id: path.1.clone(),
+			sequence: packet.sequence,
+		};
+		let stored: PacketCommitment = <PacketCommitStore<T>>::get(&commitment_path)
+			.ok_or(Error::<T>::PacketCommitmentMismatch)?;
+		let commitment = ChannelReader::packet_commitment(
+			&*ctx,
+			&packet.data,
+			&packet.timeout_height_on_b,
+			&packet.timeout_timestamp_on_b,
+		);
+		ensure!(stored == commitment, Error::<T>::PacketCommitmentMismatch);
+
+		let (connection_end, client_state) = Self::counterparty_client(&channel_end)?;
+		let proof_height = proof_height::<T>(raw.proof_height)?;
+		let consensus_state = <Context<T> as ChannelReaderInterface>::client_consensus_state(
+			connection_end.client_id(),
+			&proof_height,
+		)
+		.map_err(|_| Error::<T>::ConsensusStateNotFound)?;
+		let proof = CommitmentProofBytes::try_from(raw.proof_unreceived)
+			.map_err(|_| Error::<T>::InvalidProof)?;
+		client_state
+			.verify_packet_acknowledgement(
+				&*ctx,
+				proof_height,
+				&connection_end,
+				&proof,
+				consensus_state.root(),
+				&packet.port_on_b,
+				&packet.chan_on_b,
+				packet.sequence,
+				Self::timeout_receipt_commitment(),
+			)
+			.map_err(|_| Error::<T>::InvalidProof)?;
+
+		let relayer = Signer::from_str(&raw.signer).map_err(|_| Error::<T>::InvalidPacket)?;
+		let module_id = ctx
+			.lookup_module_by_port(&packet.port_on_a)
+			.map_err(|_| Error::<T>::InvalidModuleId)?;
+		let module = ctx.router.get_route_mut(&module_id).ok_or(Error::<T>::InvalidModuleId)?;
+		module
+			.on_timeout_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer)
+			.map_err(|_| Error::<T>::ModuleCallbackFailed)?;
+
+		ctx.delete_packet_commitment(&path.0, &path.1, &packet.sequence)
+			.map_err(|_| Error::<T>::PacketCommitmentMismatch)?;
+		Self::advance_next_sequence_ack(&path, sequence);
+		Self::deposit_event(Event::SkippedPacketTimedOut {
+			port_id: path.0,
+			channel_id: path.1,
+			sequence,
+		});
+		Ok(vec![IbcEvent::TimeoutPacket(TimeoutPacket::new(packet, *channel_end.ordering()))])
+	}
+}
@@ -1,7 +1,8 @@
 use crate::{
-	context::Context, Acknowledgements, ChannelCounter, Channels, ChannelsConnection,
-	ClientProcessedHeights, ClientProcessedTimes, Config, NextSequenceAck, NextSequenceRecv,
-	NextSequenceSend, PacketCommitment as PacketCommitStore, PacketReceipt,
+	context::Context, localhost_client::LOCALHOST_CLIENT_ID, status::ClientStatus,
+	Acknowledgements, ChannelCounter, Channels, ChannelsConnection, ClientProcessedHeights,
+	ClientProcessedTimes, Config, InFlightPackets, NextSequenceAck, NextSequenceRecv,
+	NextSequenceSend, PacketCommitment as PacketCommitStore, PacketReceipt, Pallet,
 };
 use alloc::{format, string::ToString, vec};
 use core::time::Duration;
@@ -62,11 +63,17 @@ where
 	}
 
 	/// Returns the ClientState for the given identifier `client_id`. Necessary dependency towards
-	/// proof verification.
+	/// proof verification, which is refused unless the client is active.
 	fn client_state(client_id: &ClientId) -> Result<Box<dyn ClientState>, ChannelError> {
 		let context = Context::<T>::new();
-		ClientReader::client_state(&context, client_id)
-			.map_err(|e| ChannelError::Connection(ConnectionError::Client(e)))
+		let client_state = ClientReader::client_state(&context, client_id)
+			.map_err(|e| ChannelError::Connection(ConnectionError::Client(e)))?;
+		match Pallet::<T>::client_status(client_id) {
+			ClientStatus::Active => Ok(client_state),
+			status => Err(ChannelError::Other {
+				description: format!("client {} is not active: {:?}", client_id, status),
+			}),
+		}
 	}
 
 	fn client_consensus_state(
@@ -82,6 +89,14 @@ where
 		port_id: &PortId,
 		channel_id: &ChannelId,
 	) -> Result<Sequence, PacketError> {
+		if <Pallet<T>>::is_flushing(port_id, channel_id) {
+			return Err(PacketError::Channel(ChannelError::Other {
+				description: format!(
+					"channel {}/{} is flushing for an upgrade and cannot send packets",
+					port_id, channel_id
+				),
+			}));
+		}
 		<NextSequenceSend<T>>::get(SeqSendsPath(port_id.clone(), channel_id.clone())).ok_or(
 			PacketError::MissingNextSendSeq {
 				port_id: port_id.clone(),
@@ -184,6 +199,11 @@ where
 		client_id: &ClientId,
 		height: &Height,
 	) -> Result<Timestamp, ChannelError> {
+		// the localhost client processes every height as soon as it is reached
+		if client_id.as_str() == LOCALHOST_CLIENT_ID {
+			return ClientReader::host_timestamp(&Context::<T>::new())
+				.map_err(|e| ChannelError::Connection(ConnectionError::Client(e)));
+		}
 		let time = <ClientProcessedTimes<T>>::get(client_id, height).ok_or(
 			ChannelError::ProcessedTimeNotFound { client_id: client_id.clone(), height: *height },
 		)?;
@@ -195,6 +215,9 @@ where
 	/// Returns the height when the client state for the given [`ClientId`] was updated with a
 	/// header for the given [`Height`]
 	fn client_update_height(client_id: &ClientId, height: &Height) -> Result<Height, ChannelError> {
+		if client_id.as_str() == LOCALHOST_CLIENT_ID {
+			return Ok(*height);
+		}
 		<ClientProcessedHeights<T>>::get(client_id, height).ok_or(
 			ChannelError::ProcessedHeightNotFound { client_id: client_id.clone(), height: *height },
 		)
@@ -222,10 +245,14 @@ impl<T: Config> ChannelKeeperInterface for Context<T> {
 		sequence: Sequence,
 		commitment: PacketCommitment,
 	) -> Result<(), PacketError> {
-		<PacketCommitStore<T>>::insert(
-			CommitmentsPath { port_id, channel_id, sequence },
-			commitment,
-		);
+		let path = CommitmentsPath { port_id: port_id.clone(), channel_id, sequence };
+		if !<PacketCommitStore<T>>::contains_key(&path) {
+			<InFlightPackets<T>>::mutate(
+				ChannelEndsPath(port_id, path.channel_id.clone()),
+				|count| *count += 1,
+			);
+		}
+		<PacketCommitStore<T>>::insert(path, commitment);
 
 		Ok(())
 	}
@@ -235,11 +262,17 @@ impl<T: Config> ChannelKeeperInterface for Context<T> {
 		channel_id: &ChannelId,
 		seq: &Sequence,
 	) -> Result<(), PacketError> {
-		<PacketCommitStore<T>>::remove(CommitmentsPath {
+		let path = CommitmentsPath {
 			port_id: port_id.clone(),
 			channel_id: channel_id.clone(),
 			sequence: seq.clone(),
-		});
+		};
+		if <PacketCommitStore<T>>::take(path).is_some() {
+			<InFlightPackets<T>>::mutate(
+				ChannelEndsPath(port_id.clone(), channel_id.clone()),
+				|count| *count = count.saturating_sub(1),
+			);
+		}
 
 		Ok(())
 	}
@@ -250,7 +283,11 @@ impl<T: Config> ChannelKeeperInterface for Context<T> {
 		sequence: Sequence,
 		receipt: Receipt,
 	) -> Result<(), PacketError> {
-		<PacketReceipt<T>>::insert(ReceiptsPath { port_id, channel_id, sequence }, receipt);
+		let path = ReceiptsPath { port_id, channel_id, sequence };
+		if !<PacketReceipt<T>>::contains_key(&path) {
+			<Pallet<T>>::note_packet_state_stored(&path.port_id, &path.channel_id, sequence, true);
+		}
+		<PacketReceipt<T>>::insert(path, receipt);
 
 		Ok(())
 	}
@@ -261,7 +298,11 @@ impl<T: Config> ChannelKeeperInterface for Context<T> {
 		sequence: Sequence,
 		ack_commitment: AcknowledgementCommitment,
 	) -> Result<(), PacketError> {
-		<Acknowledgements<T>>::insert(AcksPath { port_id, channel_id, sequence }, ack_commitment);
+		let path = AcksPath { port_id, channel_id, sequence };
+		if !<Acknowledgements<T>>::contains_key(&path) {
+			<Pallet<T>>::note_packet_state_stored(&path.port_id, &path.channel_id, sequence, false);
+		}
+		<Acknowledgements<T>>::insert(path, ack_commitment);
 
 		Ok(())
 	}
@@ -271,11 +312,14 @@ impl<T: Config> ChannelKeeperInterface for Context<T> {
 		channel_id: &ChannelId,
 		sequence: &Sequence,
 	) -> Result<(), PacketError> {
-		<Acknowledgements<T>>::remove(AcksPath {
+		let path = AcksPath {
 			port_id: port_id.clone(),
 			channel_id: channel_id.clone(),
 			sequence: sequence.clone(),
-		});
+		};
+		if <Acknowledgements<T>>::take(path).is_some() {
+			<Pallet<T>>::note_packet_state_removed(port_id, channel_id, *sequence, false);
+		}
 
 		Ok(())
 	}
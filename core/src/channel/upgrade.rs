@@ -0,0 +1,841 @@
+//! Channel upgrades between two chains running this pallet, changing the ordering, connection hops
+//! or version of an open channel without closing it.
+//!
+//! Both chains' `AdminOrigin` propose the same upgrade with `chan_upgrade_init`, which commits it
+//! under `channelUpgrades/upgrades/ports/{port}/channels/{channel}`. A relayer then submits
+//! `MsgChannelUpgradeTry` on one end, proving the upgrade committed by the counterparty, and
+//! `MsgChannelUpgradeAck` on the other, proving that the counterparty tried it. From then on each
+//! end stops sending packets and flushes the packets in flight, i.e. waits for them to be
+//! acknowledged or timed out. Once flushed, the `Try` end switches to the upgraded fields with
+//! `MsgChannelUpgradeConfirm`, proving that the counterparty acknowledged the upgrade, and the
+//! `Ack` end with `MsgChannelUpgradeOpen`, proving that the counterparty channel end was
+//! upgraded. The last sequence the counterparty sent before flushing is only taken from these
+//! proofs.
+//!
+//! `MsgChannelUpgradeTimeout` proves that the counterparty channel end was not upgraded by the
+//! upgrade timeout, and `MsgChannelUpgradeCancel` proves an error receipt written by the
+//! counterparty under `channelUpgrades/upgradeError/ports/{port}/channels/{channel}`. Both
+//! restore the channel, as does `chan_upgrade_cancel`, and write an error receipt in turn.
+//!
+//! This is not the channel upgrade protocol of ibc-go and does not interoperate with it: the
+//! messages have their own layouts under the `/pallet_ibc.channel.upgrade.v1.` type URLs, the
+//! committed upgrades are SCALE encoded and the channel end has no `FLUSHING` state. Both ends
+//! of the channel must run this pallet, so the counterparty is tracked by an 08-wasm client (or
+//! the mock client in tests) which proves the paths above. Each message is handled in a storage
+//! transaction and is fully checked, proofs included, before calling the module bound to the port.
+#[cfg(any(test, feature = "mock-client"))]
+use crate::MOCK_CLIENT_TYPE;
+use crate::{
+	context::Context, wasm_client::client_state::WasmClientState, ChannelUpgradeSequences,
+	ChannelUpgrades, Channels, ChannelsConnection, ClientStates, Config, Connections, Error, Event,
+	InFlightPackets, NextSequenceAck, NextSequenceRecv, NextSequenceSend,
+	OrderedAllowTimeoutChannels, Pallet, UpgradeCommitments, UpgradeErrorReceipts,
+	WASM_CLIENT_TYPE,
+};
+use alloc::string::{String, ToString};
+use codec::{Decode, Encode};
+use core::{fmt, str::FromStr};
+use frame_support::{
+	ensure,
+	storage::{with_transaction, TransactionOutcome},
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::{
+	core::{
+		ics02_client::{client_state::ClientState, consensus_state::ConsensusState},
+		ics03_connection::connection::{ConnectionEnd, State as ConnectionState},
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, Order, State},
+			packet::Sequence,
+			Version,
+		},
+		ics23_commitment::commitment::CommitmentProofBytes,
+		ics24_host::{
+			identifier::{ChannelId, ConnectionId, PortId},
+			path::{
+				ChannelEndsPath, ClientStatePath, ConnectionsPath, SeqAcksPath, SeqRecvsPath,
+				SeqSendsPath,
+			},
+		},
+	},
+	Height,
+};
+use ibc_proto::{
+	google::protobuf::Any, ibc::core::client::v1::Height as RawHeight, protobuf::Protobuf,
+};
+use pallet_ibc_utils::{module::ChannelUpgradeModule, traits::ChannelReaderInterface};
+use prost::Message;
+use scale_info::TypeInfo;
+use sp_runtime::DispatchError;
+use sp_std::{vec, vec::Vec};
+
+/// Type URL prefix of the channel upgrade messages, distinct from ibc-go's
+/// `/ibc.core.channel.v1.` as the layouts differ.
+pub const CHANNEL_UPGRADE_TYPE_URL_PREFIX: &str = "/pallet_ibc.channel.upgrade.v1.";
+pub const MSG_CHANNEL_UPGRADE_TRY_TYPE_URL: &str =
+	"/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeTry";
+pub const MSG_CHANNEL_UPGRADE_ACK_TYPE_URL: &str =
+	"/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeAck";
+pub const MSG_CHANNEL_UPGRADE_CONFIRM_TYPE_URL: &str =
+	"/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeConfirm";
+pub const MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL: &str =
+	"/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeOpen";
+pub const MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL: &str =
+	"/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeTimeout";
+pub const MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL: &str =
+	"/pallet_ibc.channel.upgrade.v1.MsgChannelUpgradeCancel";
+
+/// The fields of a channel which can be upgraded.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct UpgradeFields {
+	pub ordering: Order,
+	pub connection_hops: Vec<ConnectionId>,
+	pub version: Version,
+}
+
+/// Height or timestamp of the counterparty chain after which the upgrade can be timed out.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct UpgradeTimeout {
+	pub height: Option<Height>,
+	/// nanoseconds since the unix epoch, 0 if unset
+	pub timestamp: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum UpgradeState {
+	/// Proposed by this chain, waiting for the counterparty
+	Init,
+	/// Flushing after `MsgChannelUpgradeTry`, to be confirmed
+	TryFlushing,
+	/// Flushing after `MsgChannelUpgradeAck`, to be opened
+	AckFlushing,
+}
+
+/// An upgrade of a channel in progress.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct ChannelUpgrade {
+	pub fields: UpgradeFields,
+	pub timeout: UpgradeTimeout,
+	pub sequence: u64,
+	pub state: UpgradeState,
+	/// Last sequence sent on this end before flushing
+	pub latest_sequence_send: u64,
+	/// Last sequence sent on the counterparty end before flushing, as proven by
+	/// `MsgChannelUpgradeAck` or `MsgChannelUpgradeConfirm`
+	pub counterparty_latest_sequence_send: u64,
+}
+
+/// The upgrade a channel end commits for the counterparty to prove, SCALE encoded.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct UpgradeCommitment {
+	pub fields: UpgradeFields,
+	pub sequence: u64,
+	pub state: UpgradeState,
+	pub latest_sequence_send: u64,
+}
+
+impl From<&ChannelUpgrade> for UpgradeCommitment {
+	fn from(upgrade: &ChannelUpgrade) -> Self {
+		Self {
+			fields: upgrade.fields.clone(),
+			sequence: upgrade.sequence,
+			state: upgrade.state,
+			latest_sequence_send: upgrade.latest_sequence_send,
+		}
+	}
+}
+
+/// Path of the upgrade committed by a channel end.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct ChannelUpgradePath(pub PortId, pub ChannelId);
+
+impl From<&ChannelEndsPath> for ChannelUpgradePath {
+	fn from(path: &ChannelEndsPath) -> Self {
+		Self(path.0.clone(), path.1.clone())
+	}
+}
+
+impl fmt::Display for ChannelUpgradePath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "channelUpgrades/upgrades/ports/{}/channels/{}", self.0, self.1)
+	}
+}
+
+/// Path of the sequence of the last upgrade of a channel end which was cancelled or timed out.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct ChannelUpgradeErrorPath(pub PortId, pub ChannelId);
+
+impl From<&ChannelEndsPath> for ChannelUpgradeErrorPath {
+	fn from(path: &ChannelEndsPath) -> Self {
+		Self(path.0.clone(), path.1.clone())
+	}
+}
+
+impl fmt::Display for ChannelUpgradeErrorPath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "channelUpgrades/upgradeError/ports/{}/channels/{}", self.0, self.1)
+	}
+}
+
+/// Proves the upgrade committed by the counterparty in the `Init` state.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgChannelUpgradeTry {
+	#[prost(string, tag = "1")]
+	pub port_id: String,
+	#[prost(string, tag = "2")]
+	pub channel_id: String,
+	#[prost(uint64, tag = "3")]
+	pub counterparty_upgrade_sequence: u64,
+	#[prost(bytes = "vec", tag = "4")]
+	pub proof_upgrade: Vec<u8>,
+	#[prost(message, optional, tag = "5")]
+	pub proof_height: Option<RawHeight>,
+	#[prost(string, tag = "6")]
+	pub signer: String,
+}
+
+/// Proves the upgrade committed by the counterparty in the `TryFlushing` state, having sent
+/// `counterparty_latest_sequence_send` before flushing.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgChannelUpgradeAck {
+	#[prost(string, tag = "1")]
+	pub port_id: String,
+	#[prost(string, tag = "2")]
+	pub channel_id: String,
+	#[prost(uint64, tag = "3")]
+	pub counterparty_upgrade_sequence: u64,
+	#[prost(uint64, tag = "4")]
+	pub counterparty_latest_sequence_send: u64,
+	#[prost(bytes = "vec", tag = "5")]
+	pub proof_upgrade: Vec<u8>,
+	#[prost(message, optional, tag = "6")]
+	pub proof_height: Option<RawHeight>,
+	#[prost(string, tag = "7")]
+	pub signer: String,
+}
+
+/// Same layout as `MsgChannelUpgradeAck`, proving the upgrade committed by the counterparty in
+/// the `AckFlushing` state.
+pub type MsgChannelUpgradeConfirm = MsgChannelUpgradeAck;
+
+/// Also the layout of `MsgChannelUpgradeTimeout`, with a proof of the counterparty channel end
+/// before the upgrade.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgChannelUpgradeOpen {
+	#[prost(string, tag = "1")]
+	pub port_id: String,
+	#[prost(string, tag = "2")]
+	pub channel_id: String,
+	#[prost(bytes = "vec", tag = "3")]
+	pub proof_channel: Vec<u8>,
+	#[prost(message, optional, tag = "4")]
+	pub proof_height: Option<RawHeight>,
+	#[prost(string, tag = "5")]
+	pub signer: String,
+}
+
+pub type MsgChannelUpgradeTimeout = MsgChannelUpgradeOpen;
+
+/// Proves an error receipt of the counterparty for an upgrade sequence at least the one of the
+/// upgrade in progress.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgChannelUpgradeCancel {
+	#[prost(string, tag = "1")]
+	pub port_id: String,
+	#[prost(string, tag = "2")]
+	pub channel_id: String,
+	#[prost(uint64, tag = "3")]
+	pub error_receipt_sequence: u64,
+	#[prost(bytes = "vec", tag = "4")]
+	pub proof_error_receipt: Vec<u8>,
+	#[prost(message, optional, tag = "5")]
+	pub proof_height: Option<RawHeight>,
+	#[prost(string, tag = "6")]
+	pub signer: String,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Record the upgrade of the channel in progress and commit it for the counterparty.
+	fn store_upgrade(path: &ChannelEndsPath, upgrade: ChannelUpgrade) {
+		<UpgradeCommitments<T>>::insert(
+			ChannelUpgradePath::from(path),
+			UpgradeCommitment::from(&upgrade),
+		);
+		<ChannelUpgrades<T>>::insert(path, upgrade);
+	}
+
+	/// Drop the upgrade of the channel in progress and its commitment.
+	pub(crate) fn remove_upgrade(path: &ChannelEndsPath) {
+		<ChannelUpgrades<T>>::remove(path);
+		<UpgradeCommitments<T>>::remove(ChannelUpgradePath::from(path));
+	}
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Returns true if sending packets on the channel is paused by a flushing upgrade.
+	pub fn is_flushing(port_id: &PortId, channel_id: &ChannelId) -> bool {
+		<ChannelUpgrades<T>>::get(ChannelEndsPath(port_id.clone(), channel_id.clone()))
+			.map_or(false, |upgrade| upgrade.state != UpgradeState::Init)
+	}
+
+	fn open_connection(connection_id: &ConnectionId) -> Result<ConnectionEnd, Error<T>> {
+		<Connections<T>>::get(ConnectionsPath(connection_id.clone()))
+			.filter(|connection_end| connection_end.state_matches(&ConnectionState::Open))
+			.ok_or(Error::<T>::InvalidUpgrade)
+	}
+
+	fn upgrade_of(
+		port_id: &str,
+		channel_id: &str,
+	) -> Result<(ChannelEndsPath, ChannelEnd, ChannelUpgrade), Error<T>> {
+		let port_id = PortId::from_str(port_id).map_err(|_| Error::<T>::InvalidPortId)?;
+		let channel_id =
+			ChannelId::from_str(channel_id).map_err(|_| Error::<T>::InvalidChannelId)?;
+		let path = ChannelEndsPath(port_id, channel_id);
+		let channel_end = <Channels<T>>::get(&path).ok_or(Error::<T>::ChannelNotFound)?;
+		let upgrade = <ChannelUpgrades<T>>::get(&path).ok_or(Error::<T>::NoUpgradeInProgress)?;
+		Ok((path, channel_end, upgrade))
+	}
+
+	/// Propose an upgrade of the open channel, returning its upgrade sequence.
+	pub fn do_chan_upgrade_init(
+		port_id: PortId,
+		channel_id: ChannelId,
+		fields: UpgradeFields,
+		timeout: UpgradeTimeout,
+	) -> Result<u64, Error<T>> {
+		let path = ChannelEndsPath(port_id.clone(), channel_id.clone());
+		let channel_end = <Channels<T>>::get(&path).ok_or(Error::<T>::ChannelNotFound)?;
+		ensure!(channel_end.state_matches(&State::Open), Error::<T>::ChannelNotOpen);
+		ensure!(!<ChannelUpgrades<T>>::contains_key(&path), Error::<T>::UpgradeInProgress);
+		ensure!(
+			timeout.height.is_some() || timeout.timestamp != 0,
+			Error::<T>::InvalidUpgradeTimeout
+		);
+		match fields.connection_hops.as_slice() {
+			[connection_id] => Self::open_connection(connection_id)?,
+			_ => return Err(Error::<T>::InvalidUpgrade),
+		};
+		ensure!(
+			&fields.ordering != channel_end.ordering() ||
+				&fields.connection_hops != channel_end.connection_hops() ||
+				&fields.version != channel_end.version(),
+			Error::<T>::InvalidUpgrade
+		);
+
+		let version = T::UpgradeModule::on_chan_upgrade_init(
+			&port_id,
+			&channel_id,
+			fields.ordering,
+			&fields.connection_hops,
+			&fields.version,
+		)
+		.map_err(|_| Error::<T>::UpgradeRefused)?;
+		let sequence = <ChannelUpgradeSequences<T>>::mutate(&path, |sequence| {
+			*sequence += 1;
+			*sequence
+		});
+		Self::store_upgrade(
+			&path,
+			ChannelUpgrade {
+				fields: UpgradeFields { version, ..fields },
+				timeout,
+				sequence,
+				state: UpgradeState::Init,
+				latest_sequence_send: 0,
+				counterparty_latest_sequence_send: 0,
+			},
+		);
+
+		Ok(sequence)
+	}
+
+	/// Abandon the upgrade of the channel, which keeps its current fields.
+	pub fn do_chan_upgrade_cancel(port_id: PortId, channel_id: ChannelId) -> Result<u64, Error<T>> {
+		let path = ChannelEndsPath(port_id, channel_id);
+		let upgrade = <ChannelUpgrades<T>>::get(&path).ok_or(Error::<T>::NoUpgradeInProgress)?;
+		Self::restore_channel(&path, upgrade.sequence);
+		Ok(upgrade.sequence)
+	}
+
+	/// Drop the upgrade and write an error receipt for the counterparty to cancel it too.
+	fn restore_channel(path: &ChannelEndsPath, sequence: u64) {
+		Self::remove_upgrade(path);
+		<UpgradeErrorReceipts<T>>::insert(ChannelUpgradeErrorPath::from(path), sequence);
+		T::UpgradeModule::on_chan_upgrade_restore(&path.0, &path.1);
+	}
+
+	/// Stop sending packets and record the sequences to flush on both ends.
+	fn start_flushing(
+		path: &ChannelEndsPath,
+		mut upgrade: ChannelUpgrade,
+		state: UpgradeState,
+		counterparty_latest_sequence_send: u64,
+	) {
+		let next_sequence_send =
+			<NextSequenceSend<T>>::get(SeqSendsPath(path.0.clone(), path.1.clone()))
+				.map_or(1, u64::from);
+		upgrade.state = state;
+		upgrade.latest_sequence_send = next_sequence_send.saturating_sub(1);
+		upgrade.counterparty_latest_sequence_send = counterparty_latest_sequence_send;
+		Self::store_upgrade(path, upgrade);
+	}
+
+	fn proof_height(proof_height: Option<RawHeight>) -> Result<Height, Error<T>> {
+		proof_height
+			.and_then(|height| Height::new(height.revision_number, height.revision_height).ok())
+			.ok_or(Error::<T>::InvalidHeight)
+	}
+
+	/// The channel end of the counterparty of `channel_end`.
+	fn counterparty_path(channel_end: &ChannelEnd) -> Result<ChannelEndsPath, Error<T>> {
+		let counterparty = channel_end.counterparty();
+		let channel_id = counterparty.channel_id().ok_or(Error::<T>::InvalidChannelId)?;
+		Ok(ChannelEndsPath(counterparty.port_id().clone(), channel_id.clone()))
+	}
+
+	/// Verify the proof of `value` being stored under `path` on the counterparty of
+	/// `connection_end` at `proof_height`.
+	///
+	/// The light clients of ibc-rs 0.28 only verify the paths of ICS-24, so the proof is checked by
+	/// the contract of 08-wasm clients. No chain tracked by a tendermint client writes these
+	/// paths, such clients are refused.
+	fn verify_counterparty_membership(
+		connection_end: &ConnectionEnd,
+		proof: Vec<u8>,
+		proof_height: Height,
+		path: String,
+		value: Vec<u8>,
+	) -> Result<(), Error<T>> {
+		let proof = CommitmentProofBytes::try_from(proof).map_err(|_| Error::<T>::InvalidProof)?;
+		let client_id = connection_end.client_id();
+		// refuses clients which are not active
+		let client_state = <Context<T> as ChannelReaderInterface>::client_state(client_id)
+			.map_err(|_| Error::<T>::ClientNotFound)?;
+		let consensus_state = <Context<T> as ChannelReaderInterface>::client_consensus_state(
+			client_id,
+			&proof_height,
+		)
+		.map_err(|_| Error::<T>::ConsensusStateNotFound)?;
+		let prefix = connection_end.counterparty().prefix();
+		let root = consensus_state.root();
+
+		match client_state.client_type().as_str() {
+			WASM_CLIENT_TYPE => {
+				let data = <ClientStates<T>>::get(ClientStatePath(client_id.clone()))
+					.ok_or(Error::<T>::ClientNotFound)?;
+				let client_state: WasmClientState = Protobuf::<Any>::decode_vec(&data)
+					.map_err(|_| Error::<T>::InvalidClientState)?;
+				client_state
+					.with_host(Self::wasm_host())
+					.verify_membership(prefix, &proof, root, path, value)
+					.map_err(|_| Error::<T>::InvalidProof)?;
+			},
+			// the mock client accepts every proof, like ibc-rs does for channel ends
+			#[cfg(any(test, feature = "mock-client"))]
+			MOCK_CLIENT_TYPE => {},
+			_ => return Err(Error::<T>::UnknownClientType),
+		}
+		Ok(())
+	}
+
+	/// Verify the proof of the counterparty of `channel_end` committing `upgrade` in `state`,
+	/// after sending `latest_sequence_send` before flushing.
+	fn verify_counterparty_upgrade(
+		channel_end: &ChannelEnd,
+		upgrade: &ChannelUpgrade,
+		state: UpgradeState,
+		latest_sequence_send: u64,
+		proof: Vec<u8>,
+		proof_height: Option<RawHeight>,
+	) -> Result<(), Error<T>> {
+		let proof_height = Self::proof_height(proof_height)?;
+		let connection_id =
+			channel_end.connection_hops().first().ok_or(Error::<T>::InvalidUpgrade)?;
+		let connection_end = Self::open_connection(connection_id)?;
+		let upgraded_connection_id =
+			upgrade.fields.connection_hops.first().ok_or(Error::<T>::InvalidUpgrade)?;
+		let counterparty_connection_id = Self::open_connection(upgraded_connection_id)?
+			.counterparty()
+			.connection_id()
+			.cloned()
+			.ok_or(Error::<T>::InvalidUpgrade)?;
+		let expected = UpgradeCommitment {
+			fields: UpgradeFields {
+				ordering: upgrade.fields.ordering,
+				connection_hops: vec![counterparty_connection_id],
+				version: upgrade.fields.version.clone(),
+			},
+			sequence: upgrade.sequence,
+			state,
+			latest_sequence_send,
+		};
+		let counterparty_path = Self::counterparty_path(channel_end)?;
+
+		Self::verify_counterparty_membership(
+			&connection_end,
+			proof,
+			proof_height,
+			ChannelUpgradePath::from(&counterparty_path).to_string(),
+			expected.encode(),
+		)
+	}
+
+	fn chan_upgrade_try(msg: MsgChannelUpgradeTry) -> Result<(), Error<T>> {
+		let (path, channel_end, upgrade) = Self::upgrade_of(&msg.port_id, &msg.channel_id)?;
+		ensure!(upgrade.state == UpgradeState::Init, Error::<T>::InvalidUpgradeState);
+		ensure!(
+			msg.counterparty_upgrade_sequence == upgrade.sequence,
+			Error::<T>::UpgradeSequenceMismatch
+		);
+		Self::verify_counterparty_upgrade(
+			&channel_end,
+			&upgrade,
+			UpgradeState::Init,
+			0,
+			msg.proof_upgrade,
+			msg.proof_height,
+		)?;
+
+		let version = T::UpgradeModule::on_chan_upgrade_try(
+			&path.0,
+			&path.1,
+			upgrade.fields.ordering,
+			&upgrade.fields.connection_hops,
+			&upgrade.fields.version,
+		)
+		.map_err(|_| Error::<T>::UpgradeRefused)?;
+		ensure!(version == upgrade.fields.version, Error::<T>::UpgradeFieldsMismatch);
+
+		// the counterparty starts flushing on `MsgChannelUpgradeAck`, its latest sequence is
+		// proven by `MsgChannelUpgradeConfirm`
+		let sequence = upgrade.sequence;
+		Self::start_flushing(&path, upgrade, UpgradeState::TryFlushing, 0);
+		Self::deposit_event(Event::ChannelUpgradeTry {
+			port_id: path.0,
+			channel_id: path.1,
+			upgrade_sequence: sequence,
+		});
+		Ok(())
+	}
+
+	fn chan_upgrade_ack(msg: MsgChannelUpgradeAck) -> Result<(), Error<T>> {
+		let (path, channel_end, upgrade) = Self::upgrade_of(&msg.port_id, &msg.channel_id)?;
+		ensure!(upgrade.state == UpgradeState::Init, Error::<T>::InvalidUpgradeState);
+		ensure!(
+			msg.counterparty_upgrade_sequence == upgrade.sequence,
+			Error::<T>::UpgradeSequenceMismatch
+		);
+		Self::verify_counterparty_upgrade(
+			&channel_end,
+			&upgrade,
+			UpgradeState::TryFlushing,
+			msg.counterparty_latest_sequence_send,
+			msg.proof_upgrade,
+			msg.proof_height,
+		)?;
+
+		T::UpgradeModule::on_chan_upgrade_ack(&path.0, &path.1, &upgrade.fields.version)
+			.map_err(|_| Error::<T>::UpgradeRefused)?;
+
+		let sequence = upgrade.sequence;
+		Self::start_flushing(
+			&path,
+			upgrade,
+			UpgradeState::AckFlushing,
+			msg.counterparty_latest_sequence_send,
+		);
+		Self::deposit_event(Event::ChannelUpgradeAck {
+			port_id: path.0,
+			channel_id: path.1,
+			upgrade_sequence: sequence,
+		});
+		Ok(())
+	}
+
+	/// Ensure the packets sent on this end before flushing were acknowledged or timed out and, on
+	/// ordered channels, that those of the counterparty were received.
+	///
+	/// Packets in flight are counted by `InFlightPackets`, which `migrations::v2::MigrateToV2`
+	/// seeds with the commitments stored before the counter existed.
+	fn ensure_flushed(
+		path: &ChannelEndsPath,
+		channel_end: &ChannelEnd,
+		upgrade: &ChannelUpgrade,
+	) -> Result<(), Error<T>> {
+		ensure!(<InFlightPackets<T>>::get(path) == 0, Error::<T>::FlushInProgress);
+		if channel_end.ordering() == &Order::Ordered {
+			let next_sequence_ack =
+				<NextSequenceAck<T>>::get(SeqAcksPath(path.0.clone(), path.1.clone()))
+					.map_or(1, u64::from);
+			let next_sequence_recv =
+				<NextSequenceRecv<T>>::get(SeqRecvsPath(path.0.clone(), path.1.clone()))
+					.map_or(1, u64::from);
+			ensure!(
+				next_sequence_ack > upgrade.latest_sequence_send &&
+					next_sequence_recv > upgrade.counterparty_latest_sequence_send,
+				Error::<T>::FlushInProgress
+			);
+		}
+		Ok(())
+	}
+
+	/// Switch the channel to the upgraded fields.
+	fn open_upgraded_channel(
+		path: ChannelEndsPath,
+		channel_end: ChannelEnd,
+		upgrade: ChannelUpgrade,
+	) {
+		let ChannelEndsPath(port_id, channel_id) = path.clone();
+		let fields = upgrade.fields;
+
+		if &fields.connection_hops != channel_end.connection_hops() {
+			for connection_id in channel_end.connection_hops() {
+				<ChannelsConnection<T>>::mutate(connection_id, |channels| {
+					if let Some(channels) = channels {
+						channels
+							.retain(|channel| channel != &(port_id.clone(), channel_id.clone()));
+					}
+				});
+			}
+			for connection_id in fields.connection_hops.iter() {
+				<ChannelsConnection<T>>::append(
+					connection_id,
+					(port_id.clone(), channel_id.clone()),
+				);
+			}
+		}
+		if fields.ordering == Order::Ordered && channel_end.ordering() != &Order::Ordered {
+			<NextSequenceRecv<T>>::insert(
+				SeqRecvsPath(port_id.clone(), channel_id.clone()),
+				Sequence::from(upgrade.counterparty_latest_sequence_send + 1),
+			);
+			<NextSequenceAck<T>>::insert(
+				SeqAcksPath(port_id.clone(), channel_id.clone()),
+				Sequence::from(upgrade.latest_sequence_send + 1),
+			);
+		}
+
+		<Channels<T>>::insert(
+			&path,
+			ChannelEnd::new(
+				State::Open,
+				fields.ordering,
+				channel_end.counterparty().clone(),
+				fields.connection_hops.clone(),
+				fields.version.clone(),
+			),
+		);
+		if fields.ordering != Order::Ordered {
+			<OrderedAllowTimeoutChannels<T>>::remove(&path);
+		}
+		Self::remove_upgrade(&path);
+		T::UpgradeModule::on_chan_upgrade_open(
+			&port_id,
+			&channel_id,
+			fields.ordering,
+			&fields.connection_hops,
+			&fields.version,
+		);
+		Self::deposit_event(Event::ChannelUpgradeOpen {
+			port_id,
+			channel_id,
+			upgrade_sequence: upgrade.sequence,
+		});
+	}
+
+	fn chan_upgrade_confirm(msg: MsgChannelUpgradeConfirm) -> Result<(), Error<T>> {
+		let (path, channel_end, mut upgrade) = Self::upgrade_of(&msg.port_id, &msg.channel_id)?;
+		ensure!(upgrade.state == UpgradeState::TryFlushing, Error::<T>::InvalidUpgradeState);
+		ensure!(
+			msg.counterparty_upgrade_sequence == upgrade.sequence,
+			Error::<T>::UpgradeSequenceMismatch
+		);
+		Self::verify_counterparty_upgrade(
+			&channel_end,
+			&upgrade,
+			UpgradeState::AckFlushing,
+			msg.counterparty_latest_sequence_send,
+			msg.proof_upgrade,
+			msg.proof_height,
+		)?;
+		upgrade.counterparty_latest_sequence_send = msg.counterparty_latest_sequence_send;
+		Self::ensure_flushed(&path, &channel_end, &upgrade)?;
+
+		Self::open_upgraded_channel(path, channel_end, upgrade);
+		Ok(())
+	}
+
+	/// The channel end the counterparty of `path` stores when running over `connection_end`
+	/// with `ordering` and `version`.
+	fn counterparty_channel_end(
+		path: &ChannelEndsPath,
+		connection_end: &ConnectionEnd,
+		ordering: Order,
+		version: Version,
+	) -> Result<ChannelEnd, Error<T>> {
+		let connection_id = connection_end
+			.counterparty()
+			.connection_id()
+			.ok_or(Error::<T>::InvalidUpgrade)?;
+		Ok(ChannelEnd::new(
+			State::Open,
+			ordering,
+			Counterparty::new(path.0.clone(), Some(path.1.clone())),
+			vec![connection_id.clone()],
+			version,
+		))
+	}
+
+	/// Verify the proof of `expected` being the counterparty channel end of `channel_end` at
+	/// `proof_height`, returning the timestamp of the counterparty at that height.
+	fn verify_counterparty_channel(
+		channel_end: &ChannelEnd,
+		connection_end: &ConnectionEnd,
+		expected: &ChannelEnd,
+		proof: Vec<u8>,
+		proof_height: Option<RawHeight>,
+	) -> Result<(Height, u64), Error<T>> {
+		let proof_height = Self::proof_height(proof_height)?;
+		let proof = CommitmentProofBytes::try_from(proof).map_err(|_| Error::<T>::InvalidProof)?;
+		let counterparty_path = Self::counterparty_path(channel_end)?;
+
+		let client_id = connection_end.client_id();
+		let client_state = <Context<T> as ChannelReaderInterface>::client_state(client_id)
+			.map_err(|_| Error::<T>::ClientNotFound)?;
+		let consensus_state = <Context<T> as ChannelReaderInterface>::client_consensus_state(
+			client_id,
+			&proof_height,
+		)
+		.map_err(|_| Error::<T>::ConsensusStateNotFound)?;
+		client_state
+			.verify_channel_state(
+				proof_height,
+				connection_end.counterparty().prefix(),
+				&proof,
+				consensus_state.root(),
+				&counterparty_path.0,
+				&counterparty_path.1,
+				expected,
+			)
+			.map_err(|_| Error::<T>::InvalidProof)?;
+
+		Ok((proof_height, consensus_state.timestamp().nanoseconds()))
+	}
+
+	fn chan_upgrade_open(msg: MsgChannelUpgradeOpen) -> Result<(), Error<T>> {
+		let (path, channel_end, upgrade) = Self::upgrade_of(&msg.port_id, &msg.channel_id)?;
+		ensure!(upgrade.state == UpgradeState::AckFlushing, Error::<T>::InvalidUpgradeState);
+		Self::ensure_flushed(&path, &channel_end, &upgrade)?;
+
+		let connection_id =
+			upgrade.fields.connection_hops.first().ok_or(Error::<T>::InvalidUpgrade)?;
+		let connection_end = Self::open_connection(connection_id)?;
+		let expected = Self::counterparty_channel_end(
+			&path,
+			&connection_end,
+			upgrade.fields.ordering,
+			upgrade.fields.version.clone(),
+		)?;
+		Self::verify_counterparty_channel(
+			&channel_end,
+			&connection_end,
+			&expected,
+			msg.proof_channel,
+			msg.proof_height,
+		)?;
+
+		Self::open_upgraded_channel(path, channel_end, upgrade);
+		Ok(())
+	}
+
+	fn chan_upgrade_timeout(msg: MsgChannelUpgradeTimeout) -> Result<(), Error<T>> {
+		let (path, channel_end, upgrade) = Self::upgrade_of(&msg.port_id, &msg.channel_id)?;
+		let connection_id =
+			channel_end.connection_hops().first().ok_or(Error::<T>::InvalidUpgrade)?;
+		let connection_end = Self::open_connection(connection_id)?;
+		let expected = Self::counterparty_channel_end(
+			&path,
+			&connection_end,
+			*channel_end.ordering(),
+			channel_end.version().clone(),
+		)?;
+		let (proof_height, timestamp) = Self::verify_counterparty_channel(
+			&channel_end,
+			&connection_end,
+			&expected,
+			msg.proof_channel,
+			msg.proof_height,
+		)?;
+
+		let timed_out = upgrade.timeout.height.map_or(false, |height| proof_height >= height) ||
+			(upgrade.timeout.timestamp != 0 && timestamp >= upgrade.timeout.timestamp);
+		ensure!(timed_out, Error::<T>::UpgradeNotTimedOut);
+
+		Self::restore_channel(&path, upgrade.sequence);
+		Self::deposit_event(Event::ChannelUpgradeTimeout {
+			port_id: path.0,
+			channel_id: path.1,
+			upgrade_sequence: upgrade.sequence,
+		});
+		Ok(())
+	}
+
+	fn chan_upgrade_cancel(msg: MsgChannelUpgradeCancel) -> Result<(), Error<T>> {
+		let (path, channel_end, upgrade) = Self::upgrade_of(&msg.port_id, &msg.channel_id)?;
+		ensure!(
+			msg.error_receipt_sequence >= upgrade.sequence,
+			Error::<T>::UpgradeSequenceMismatch
+		);
+		let connection_id =
+			channel_end.connection_hops().first().ok_or(Error::<T>::InvalidUpgrade)?;
+		let connection_end = Self::open_connection(connection_id)?;
+		let counterparty_path = Self::counterparty_path(&channel_end)?;
+		Self::verify_counterparty_membership(
+			&connection_end,
+			msg.proof_error_receipt,
+			Self::proof_height(msg.proof_height)?,
+			ChannelUpgradeErrorPath::from(&counterparty_path).to_string(),
+			msg.error_receipt_sequence.encode(),
+		)?;
+
+		Self::restore_channel(&path, upgrade.sequence);
+		Self::deposit_event(Event::ChannelUpgradeCancelled {
+			port_id: path.0,
+			channel_id: path.1,
+			upgrade_sequence: upgrade.sequence,
+		});
+		Ok(())
+	}
+
+	fn decode_msg<M: Message + Default>(msg: &Any) -> Result<M, Error<T>> {
+		M::decode(msg.value.as_slice()).map_err(|_| Error::<T>::InvalidUpgradeMessage)
+	}
+
+	/// Handle `msg` if it is a channel upgrade message, `None` otherwise. The message is handled
+	/// in a storage transaction, rolled back if it fails.
+	pub(crate) fn deliver_channel_upgrade(msg: &Any) -> Option<Result<(), DispatchError>> {
+		let handle: fn(&Any) -> Result<(), Error<T>> = match msg.type_url.as_str() {
+			MSG_CHANNEL_UPGRADE_TRY_TYPE_URL =>
+				|msg| Self::decode_msg(msg).and_then(Self::chan_upgrade_try),
+			MSG_CHANNEL_UPGRADE_ACK_TYPE_URL =>
+				|msg| Self::decode_msg(msg).and_then(Self::chan_upgrade_ack),
+			MSG_CHANNEL_UPGRADE_CONFIRM_TYPE_URL =>
+				|msg| Self::decode_msg(msg).and_then(Self::chan_upgrade_confirm),
+			MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL =>
+				|msg| Self::decode_msg(msg).and_then(Self::chan_upgrade_open),
+			MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL =>
+				|msg| Self::decode_msg(msg).and_then(Self::chan_upgrade_timeout),
+			MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL =>
+				|msg| Self::decode_msg(msg).and_then(Self::chan_upgrade_cancel),
+			_ => return None,
+		};
+		Some(with_transaction(|| match handle(msg) {
+			Ok(()) => TransactionOutcome::Commit(Ok(())),
+			Err(error) => TransactionOutcome::Rollback(Err(error.into())),
+		}))
+	}
+}
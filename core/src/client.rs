@@ -1,6 +1,12 @@
 use crate::{
-	context::Context, ClientCounter, ClientProcessedHeights, ClientProcessedTimes, ClientStates,
-	Clients, Config, ConsensusStates, MOCK_CLIENT_TYPE, TENDERMINT_CLIENT_TYPE,
+	context::Context,
+	localhost_client::{
+		client_state::LocalhostClientState, consensus_state::LocalhostConsensusState,
+	},
+	wasm_client::{client_state::WasmClientState, consensus_state::WasmConsensusState},
+	ClientCounter, ClientProcessedHeights, ClientProcessedTimes, ClientStates, Clients, Config,
+	ConsensusStates, Pallet, LOCALHOST_CLIENT_TYPE, MOCK_CLIENT_TYPE, TENDERMINT_CLIENT_TYPE,
+	WASM_CLIENT_TYPE,
 };
 pub use alloc::{
 	format,
@@ -43,6 +49,8 @@ where
 		match data.as_str() {
 			TENDERMINT_CLIENT_TYPE => Ok(ClientType::new(TENDERMINT_CLIENT_TYPE.into())),
 			MOCK_CLIENT_TYPE => Ok(ClientType::new(MOCK_CLIENT_TYPE.into())),
+			WASM_CLIENT_TYPE => Ok(ClientType::new(WASM_CLIENT_TYPE.into())),
+			LOCALHOST_CLIENT_TYPE => Ok(ClientType::new(LOCALHOST_CLIENT_TYPE.into())),
 			unimplemented => {
 				return Err(ClientError::UnknownClientStateType {
 					client_state_type: unimplemented.to_string(),
@@ -70,6 +78,20 @@ where
 					})?;
 				Ok(Box::new(result))
 			},
+			WASM_CLIENT_TYPE => {
+				let result: WasmClientState =
+					Protobuf::<Any>::decode_vec(&data).map_err(|e| ClientError::Other {
+						description: format!("Decode WasmClientState failed: {:?}", e),
+					})?;
+				Ok(Box::new(result.with_host(Pallet::<T>::wasm_host())))
+			},
+			LOCALHOST_CLIENT_TYPE => {
+				let result: LocalhostClientState =
+					Protobuf::<Any>::decode_vec(&data).map_err(|e| ClientError::Other {
+						description: format!("Decode LocalhostClientState failed: {:?}", e),
+					})?;
+				Ok(Box::new(result.with_store(Pallet::<T>::local_store(), self.host_height()?)))
+			},
 			unimplemented => Err(ClientError::UnknownClientStateType {
 				client_state_type: unimplemented.to_string(),
 			}),
@@ -80,7 +102,10 @@ where
 		if let Ok(client_state) = Ics07ClientState::try_from(client_state.clone()) {
 			return Ok(client_state.into_box());
 		}
-		#[cfg(test)]
+		if let Ok(client_state) = WasmClientState::try_from(client_state.clone()) {
+			return Ok(client_state.with_host(Pallet::<T>::wasm_host()).into_box());
+		}
+		#[cfg(any(test, feature = "mock-client"))]
 		if let Ok(client_state) = MockClientState::try_from(client_state.clone()) {
 			return Ok(client_state.into_box());
 		}
@@ -92,6 +117,10 @@ where
 		client_id: &ClientId,
 		height: &Height,
 	) -> Result<Box<dyn ConsensusState>, ClientError> {
+		// the localhost client has the host timestamp as consensus state at every height
+		if self.client_type(client_id)?.as_str() == LOCALHOST_CLIENT_TYPE {
+			return Ok(Box::new(LocalhostConsensusState::new(self.host_timestamp()?)));
+		}
 		let data = <ConsensusStates<T>>::get(ClientConsensusStatePath {
 			client_id: client_id.clone(),
 			epoch: height.revision_number(),
@@ -116,6 +145,13 @@ where
 					})?;
 				Ok(Box::new(result))
 			},
+			WASM_CLIENT_TYPE => {
+				let result: WasmConsensusState =
+					Protobuf::<Any>::decode_vec(&data).map_err(|e| ClientError::Other {
+						description: format!("Decode WasmConsensusState failed: {:?}", e),
+					})?;
+				Ok(Box::new(result))
+			},
 			unimplemented => Err(ClientError::UnknownClientStateType {
 				client_state_type: unimplemented.to_string(),
 			}),
@@ -163,6 +199,13 @@ where
 							})?;
 						return Ok(Some(Box::new(result)));
 					},
+					WASM_CLIENT_TYPE => {
+						let result: WasmConsensusState = Protobuf::<Any>::decode_vec(&data)
+							.map_err(|e| ClientError::Other {
+								description: format!("Decode WasmConsensusState failed: {:?}", e),
+							})?;
+						return Ok(Some(Box::new(result)));
+					},
 					_ => {},
 				}
 			}
@@ -213,6 +256,13 @@ where
 							})?;
 						return Ok(Some(Box::new(result)));
 					},
+					WASM_CLIENT_TYPE => {
+						let result: WasmConsensusState = Protobuf::<Any>::decode_vec(&data)
+							.map_err(|e| ClientError::Other {
+								description: format!("Decode WasmConsensusState failed: {:?}", e),
+							})?;
+						return Ok(Some(Box::new(result)));
+					},
 					_ => {},
 				}
 			}
@@ -248,11 +298,11 @@ where
 		&self,
 		_height: &Height,
 	) -> Result<Box<dyn ConsensusState>, ClientError> {
-		#[cfg(not(test))]
+		#[cfg(not(any(test, feature = "mock-client")))]
 		{
 			Err(ClientError::ImplementationSpecific)
 		}
-		#[cfg(test)]
+		#[cfg(any(test, feature = "mock-client"))]
 		{
 			use ibc::mock::header::MockHeader;
 			let mock_header =
@@ -262,11 +312,11 @@ where
 	}
 
 	fn pending_host_consensus_state(&self) -> Result<Box<dyn ConsensusState>, ClientError> {
-		#[cfg(not(test))]
+		#[cfg(not(any(test, feature = "mock-client")))]
 		{
 			Err(ClientError::ImplementationSpecific)
 		}
-		#[cfg(test)]
+		#[cfg(any(test, feature = "mock-client"))]
 		{
 			use ibc::mock::header::MockHeader;
 			let mock_header =
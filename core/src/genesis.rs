@@ -0,0 +1,345 @@
+//! Genesis state of the IBC pallet, bootstrapping clients, connections and channels without
+//! replaying their handshakes.
+//!
+//! The localhost client and `connection-localhost` are always created, channels of the genesis
+//! config may run over it.
+use crate::{
+	context::Context,
+	localhost_client::{
+		client_state::LocalhostClientState, localhost_client_id, localhost_connection_id,
+		LOCALHOST_CLIENT_ID, LOCALHOST_CONNECTION_ID,
+	},
+	ChannelCounter, Channels, ChannelsConnection, ClientCounter, ClientProcessedHeights,
+	ClientProcessedTimes, ClientStates, Clients, Config, ConnectionClient, ConnectionCounter,
+	Connections, ConsensusStates, GenesisConfig, NextSequenceAck, NextSequenceRecv,
+	NextSequenceSend, Pallet, LOCALHOST_CLIENT_TYPE,
+};
+use alloc::{
+	format,
+	string::{String, ToString},
+};
+use frame_support::traits::{Get, UnixTime};
+use ibc::core::{
+	ics02_client::{client_type::ClientType, height::Height},
+	ics03_connection::{
+		connection::{ConnectionEnd, State as ConnectionState},
+		version::get_compatible_versions,
+	},
+	ics04_channel::{channel::ChannelEnd, packet::Sequence},
+	ics05_port::context::PortReader,
+	ics24_host::{
+		identifier::{ChannelId, ClientId, ConnectionId, PortId},
+		path::{
+			ChannelEndsPath, ClientConsensusStatePath, ClientStatePath, ClientTypePath,
+			ConnectionsPath, SeqAcksPath, SeqRecvsPath, SeqSendsPath,
+		},
+	},
+};
+use ibc_proto::{google::protobuf::Any, protobuf::Protobuf};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData, vec::Vec};
+
+/// A client with its states, each a prost-encoded `Any`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisClient {
+	pub client_id: ClientId,
+	pub client_type: String,
+	pub client_state: Vec<u8>,
+	/// `(height, consensus state)` pairs
+	pub consensus_states: Vec<(Height, Vec<u8>)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisConnection {
+	pub connection_id: ConnectionId,
+	pub connection_end: ConnectionEnd,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisChannel {
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+	pub channel_end: ChannelEnd,
+	pub next_sequence_send: u64,
+	pub next_sequence_recv: u64,
+	pub next_sequence_ack: u64,
+}
+
+/// Index of a generated identifier, e.g. 3 for `channel-3`.
+fn id_index(id: &str) -> Option<u64> {
+	id.rsplit_once('-')?.1.parse().ok()
+}
+
+/// Ensure the `counter` of the `kind` identifiers is above the index of all of `ids`, so that
+/// newly generated identifiers do not collide with the genesis ones.
+fn check_counter<'a>(
+	kind: &str,
+	counter: u64,
+	ids: impl Iterator<Item = &'a str>,
+) -> Result<(), String> {
+	for id in ids {
+		let index = id_index(id).ok_or_else(|| format!("{} id {} has no index", kind, id))?;
+		if index >= counter {
+			return Err(format!("{} counter {} does not exceed the index of {}", kind, counter, id));
+		}
+	}
+	Ok(())
+}
+
+impl<T: Config> GenesisConfig<T> {
+	/// Check that the channels run over genesis connections on bound ports, that the connections
+	/// run over genesis clients with a counterparty and supported versions, that the client
+	/// states are decodable and that the counters are above the genesis identifiers.
+	pub fn validate(&self) -> Result<(), String> {
+		for client in self.clients.iter() {
+			Any::decode(client.client_state.as_slice()).map_err(|_| {
+				format!("client {} has an undecodable client state", client.client_id)
+			})?;
+			if client.consensus_states.is_empty() {
+				return Err(format!("client {} has no consensus state", client.client_id));
+			}
+			for (height, consensus_state) in client.consensus_states.iter() {
+				Any::decode(consensus_state.as_slice()).map_err(|_| {
+					format!(
+						"client {} has an undecodable consensus state at {}",
+						client.client_id, height
+					)
+				})?;
+			}
+		}
+
+		for connection in self.connections.iter() {
+			let client_id = connection.connection_end.client_id();
+			if !self.clients.iter().any(|client| &client.client_id == client_id) {
+				return Err(format!(
+					"connection {} references unknown client {}",
+					connection.connection_id, client_id
+				));
+			}
+			let connection_end = &connection.connection_end;
+			if *connection_end.state() != ConnectionState::Init &&
+				connection_end.counterparty().connection_id().is_none()
+			{
+				return Err(format!(
+					"connection {} has no counterparty connection",
+					connection.connection_id
+				));
+			}
+			let compatible_versions = get_compatible_versions();
+			if connection_end.versions().is_empty() ||
+				connection_end
+					.versions()
+					.iter()
+					.any(|version| !compatible_versions.contains(version))
+			{
+				return Err(format!(
+					"connection {} has no or unsupported versions",
+					connection.connection_id
+				));
+			}
+		}
+
+		let ctx = Context::<T>::new();
+
+		for channel in self.channels.iter() {
+			if ctx.lookup_module_by_port(&channel.port_id).is_err() {
+				return Err(format!(
+					"channel {}/{} is on unbound port {}",
+					channel.port_id, channel.channel_id, channel.port_id
+				));
+			}
+			let connection_id = channel.channel_end.connection_hops().first().ok_or_else(|| {
+				format!("channel {}/{} has no connection hop", channel.port_id, channel.channel_id)
+			})?;
+			if connection_id.as_str() != LOCALHOST_CONNECTION_ID &&
+				!self.connections.iter().any(|conn| &conn.connection_id == connection_id)
+			{
+				return Err(format!(
+					"channel {}/{} references unknown connection {}",
+					channel.port_id, channel.channel_id, connection_id
+				));
+			}
+		}
+
+		check_counter(
+			"client",
+			self.client_counter,
+			self.clients.iter().map(|client| client.client_id.as_str()),
+		)?;
+		check_counter(
+			"connection",
+			self.connection_counter,
+			self.connections.iter().map(|connection| connection.connection_id.as_str()),
+		)?;
+		check_counter(
+			"channel",
+			self.channel_counter,
+			self.channels.iter().map(|channel| channel.channel_id.as_str()),
+		)
+	}
+
+	/// Write the validated genesis state to storage.
+	pub(crate) fn store(&self) {
+		// genesis consensus states count as processed at the first block
+		let processed_time = T::TimeProvider::now().as_nanos() as u64;
+		let processed_height = Height::new(T::ChainVersion::get(), 1)
+			.expect("height 1 of the chain is a valid height; qed");
+
+		Self::store_localhost(processed_height);
+
+		for client in self.clients.iter() {
+			<Clients<T>>::insert(
+				ClientTypePath(client.client_id.clone()),
+				ClientType::new(client.client_type.clone()),
+			);
+			<ClientStates<T>>::insert(
+				ClientStatePath(client.client_id.clone()),
+				client.client_state.clone(),
+			);
+			for (height, consensus_state) in client.consensus_states.iter() {
+				<ConsensusStates<T>>::insert(
+					ClientConsensusStatePath {
+						client_id: client.client_id.clone(),
+						epoch: height.revision_number(),
+						height: height.revision_height(),
+					},
+					consensus_state.clone(),
+				);
+				<ClientProcessedTimes<T>>::insert(&client.client_id, height, processed_time);
+				<ClientProcessedHeights<T>>::insert(&client.client_id, height, processed_height);
+			}
+		}
+
+		for connection in self.connections.iter() {
+			<Connections<T>>::insert(
+				ConnectionsPath(connection.connection_id.clone()),
+				connection.connection_end.clone(),
+			);
+			<ConnectionClient<T>>::insert(
+				connection.connection_end.client_id(),
+				connection.connection_id.clone(),
+			);
+		}
+
+		for channel in self.channels.iter() {
+			let (port_id, channel_id) = (channel.port_id.clone(), channel.channel_id.clone());
+			<Channels<T>>::insert(
+				ChannelEndsPath(port_id.clone(), channel_id.clone()),
+				channel.channel_end.clone(),
+			);
+			for connection_id in channel.channel_end.connection_hops().iter() {
+				<ChannelsConnection<T>>::append(
+					connection_id,
+					(port_id.clone(), channel_id.clone()),
+				);
+			}
+			<NextSequenceSend<T>>::insert(
+				SeqSendsPath(port_id.clone(), channel_id.clone()),
+				Sequence::from(channel.next_sequence_send),
+			);
+			<NextSequenceRecv<T>>::insert(
+				SeqRecvsPath(port_id.clone(), channel_id.clone()),
+				Sequence::from(channel.next_sequence_recv),
+			);
+			<NextSequenceAck<T>>::insert(
+				SeqAcksPath(port_id, channel_id),
+				Sequence::from(channel.next_sequence_ack),
+			);
+		}
+
+		<ClientCounter<T>>::put(self.client_counter);
+		<ConnectionCounter<T>>::put(self.connection_counter);
+		<ChannelCounter<T>>::put(self.channel_counter);
+	}
+
+	/// Create the localhost client and `connection-localhost`.
+	fn store_localhost(height: Height) {
+		<Clients<T>>::insert(
+			ClientTypePath(localhost_client_id()),
+			ClientType::new(LOCALHOST_CLIENT_TYPE.to_string()),
+		);
+		let client_state = LocalhostClientState::new(height)
+			.encode_vec()
+			.expect("invalid pallet-ibc genesis: cannot encode the localhost client state");
+		<ClientStates<T>>::insert(ClientStatePath(localhost_client_id()), client_state);
+		<Connections<T>>::insert(
+			ConnectionsPath(localhost_connection_id()),
+			Pallet::<T>::localhost_connection_end(),
+		);
+		<ConnectionClient<T>>::insert(localhost_client_id(), localhost_connection_id());
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Dump the clients, connections, channels and counters in storage as a genesis config.
+	pub fn export_genesis() -> GenesisConfig<T> {
+		let mut consensus_states = BTreeMap::<ClientId, Vec<(Height, Vec<u8>)>>::new();
+		for (path, consensus_state) in <ConsensusStates<T>>::iter() {
+			if let Ok(height) = Height::new(path.epoch, path.height) {
+				consensus_states
+					.entry(path.client_id)
+					.or_default()
+					.push((height, consensus_state));
+			}
+		}
+
+		// the localhost client and connection are recreated by every genesis
+		let clients = <Clients<T>>::iter()
+			.filter(|(ClientTypePath(client_id), _)| client_id.as_str() != LOCALHOST_CLIENT_ID)
+			.filter_map(|(ClientTypePath(client_id), client_type)| {
+				let client_state = <ClientStates<T>>::get(ClientStatePath(client_id.clone()))?;
+				let consensus_states = consensus_states.remove(&client_id).unwrap_or_default();
+				Some(GenesisClient {
+					client_id,
+					client_type: client_type.as_str().to_string(),
+					client_state,
+					consensus_states,
+				})
+			})
+			.collect();
+
+		let connections = <Connections<T>>::iter()
+			.filter(|(ConnectionsPath(connection_id), _)| {
+				connection_id.as_str() != LOCALHOST_CONNECTION_ID
+			})
+			.map(|(ConnectionsPath(connection_id), connection_end)| GenesisConnection {
+				connection_id,
+				connection_end,
+			})
+			.collect();
+
+		let channels = <Channels<T>>::iter()
+			.map(|(ChannelEndsPath(port_id, channel_id), channel_end)| {
+				let sequence = |sequence: Option<Sequence>| sequence.map_or(1, u64::from);
+				GenesisChannel {
+					next_sequence_send: sequence(<NextSequenceSend<T>>::get(SeqSendsPath(
+						port_id.clone(),
+						channel_id.clone(),
+					))),
+					next_sequence_recv: sequence(<NextSequenceRecv<T>>::get(SeqRecvsPath(
+						port_id.clone(),
+						channel_id.clone(),
+					))),
+					next_sequence_ack: sequence(<NextSequenceAck<T>>::get(SeqAcksPath(
+						port_id.clone(),
+						channel_id.clone(),
+					))),
+					port_id,
+					channel_id,
+					channel_end,
+				}
+			})
+			.collect();
+
+		GenesisConfig {
+			clients,
+			connections,
+			channels,
+			client_counter: <ClientCounter<T>>::get(),
+			connection_counter: <ConnectionCounter<T>>::get(),
+			channel_counter: <ChannelCounter<T>>::get(),
+			_marker: PhantomData,
+		}
+	}
+}
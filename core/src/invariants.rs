@@ -0,0 +1,145 @@
+//! Consistency of the sequences and packet state of open channels, checked by `try_state`.
+//!
+//! For every open channel:
+//! - its send, receive and acknowledgement sequences are stored and start at 1;
+//! - packet commitments are only stored for sent sequences, and `InFlightPackets` counts them;
+//! - on ordered channels, no sequence is acknowledged before it is sent, commitments are only
+//!   stored for unacknowledged sequences, and receipts and acknowledgements only for received
+//!   sequences, receipts being left by an upgrade from an unordered channel;
+//! - on unordered channels, every acknowledgement has a receipt.
+//!
+//! `InFlightPackets` only counts the commitments stored before it existed once
+//! `migrations::v2::MigrateToV2` ran, which the runtime upgrade must include.
+use crate::{
+	Acknowledgements, Channels, Config, InFlightPackets, NextSequenceAck, NextSequenceRecv,
+	NextSequenceSend, PacketCommitment as PacketCommitStore, PacketReceipt, Pallet,
+};
+use ibc::core::{
+	ics04_channel::{
+		channel::{ChannelEnd, Order, State},
+		packet::Sequence,
+	},
+	ics24_host::{
+		identifier::{ChannelId, PortId},
+		path::{ChannelEndsPath, ReceiptsPath, SeqAcksPath, SeqRecvsPath, SeqSendsPath},
+	},
+};
+use sp_runtime::DispatchError;
+use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
+
+impl<T: Config> Pallet<T> {
+	/// Check the consistency of the sequences and packet state of every open channel.
+	pub fn do_try_state() -> Result<(), DispatchError> {
+		let mut commitments = BTreeMap::<ChannelEndsPath, Vec<u64>>::new();
+		for (path, _) in <PacketCommitStore<T>>::iter() {
+			commitments
+				.entry(ChannelEndsPath(path.port_id, path.channel_id))
+				.or_default()
+				.push(u64::from(path.sequence));
+		}
+
+		for (path, channel_end) in <Channels<T>>::iter() {
+			if !channel_end.state_matches(&State::Open) {
+				continue
+			}
+			let ChannelEndsPath(port_id, channel_id) = path.clone();
+			let next_send =
+				<NextSequenceSend<T>>::get(SeqSendsPath(port_id.clone(), channel_id.clone()))
+					.map(u64::from)
+					.ok_or("open channel without next send sequence")?;
+			let next_recv =
+				<NextSequenceRecv<T>>::get(SeqRecvsPath(port_id.clone(), channel_id.clone()))
+					.map(u64::from)
+					.ok_or("open channel without next receive sequence")?;
+			let next_ack =
+				<NextSequenceAck<T>>::get(SeqAcksPath(port_id.clone(), channel_id.clone()))
+					.map(u64::from)
+					.ok_or("open channel without next acknowledgement sequence")?;
+			ensure(next_send >= 1 && next_recv >= 1 && next_ack >= 1, "channel sequence below 1")?;
+
+			let sent = commitments.remove(&path).unwrap_or_default();
+			ensure(
+				sent.iter().all(|sequence| *sequence < next_send),
+				"packet commitment for a sequence not sent yet",
+			)?;
+			ensure(
+				<InFlightPackets<T>>::get(&path) == sent.len() as u64,
+				"in-flight packets differ from the stored packet commitments",
+			)?;
+
+			if channel_end.ordering() == &Order::Ordered {
+				ensure(next_ack <= next_send, "sequence acknowledged before it was sent")?;
+				ensure(
+					sent.iter().all(|sequence| *sequence >= next_ack),
+					"packet commitment for an acknowledged sequence",
+				)?;
+			}
+		}
+
+		for (path, _) in <PacketReceipt<T>>::iter() {
+			Self::ensure_received(
+				&path.port_id,
+				&path.channel_id,
+				path.sequence,
+				"packet receipt for a sequence not received yet",
+			)?;
+		}
+		for (path, _) in <Acknowledgements<T>>::iter() {
+			Self::ensure_received(
+				&path.port_id,
+				&path.channel_id,
+				path.sequence,
+				"acknowledgement for a sequence not received yet",
+			)?;
+			let unordered =
+				<Channels<T>>::get(ChannelEndsPath(path.port_id.clone(), path.channel_id.clone()))
+					.map_or(false, |channel_end| {
+						channel_end.state_matches(&State::Open) &&
+							channel_end.ordering() == &Order::Unordered
+					});
+			if unordered {
+				ensure(
+					<PacketReceipt<T>>::contains_key(ReceiptsPath {
+						port_id: path.port_id,
+						channel_id: path.channel_id,
+						sequence: path.sequence,
+					}),
+					"acknowledgement without a packet receipt",
+				)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// The open ordered channel, `None` if it is not open or unordered.
+	fn open_ordered_channel(port_id: &PortId, channel_id: &ChannelId) -> Option<ChannelEnd> {
+		<Channels<T>>::get(ChannelEndsPath(port_id.clone(), channel_id.clone()))
+			.filter(|channel_end| channel_end.state_matches(&State::Open))
+			.filter(|channel_end| channel_end.ordering() == &Order::Ordered)
+	}
+
+	/// Ensure `sequence` was received on the channel if it is open and ordered.
+	fn ensure_received(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		message: &'static str,
+	) -> Result<(), DispatchError> {
+		if Self::open_ordered_channel(port_id, channel_id).is_none() {
+			return Ok(())
+		}
+		let next_recv =
+			<NextSequenceRecv<T>>::get(SeqRecvsPath(port_id.clone(), channel_id.clone()))
+				.map_or(1, u64::from);
+		ensure(u64::from(sequence) < next_recv, message)
+	}
+}
+
+fn ensure(condition: bool, message: &'static str) -> Result<(), DispatchError> {
+	if condition {
+		Ok(())
+	} else {
+		Err(DispatchError::Other(message))
+	}
+}
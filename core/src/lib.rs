@@ -13,7 +13,10 @@ extern crate core;
 
 pub use pallet::*;
 
-use frame_support::{pallet_prelude::*, traits::UnixTime};
+use frame_support::{
+	pallet_prelude::*,
+	traits::{BuildGenesisConfig, UnixTime},
+};
 use frame_system::{ensure_signed, pallet_prelude::*};
 use ibc::{
 	core::{
@@ -32,14 +35,15 @@ use ibc::{
 			path::{
 				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
 				ClientTypePath, CommitmentsPath, ConnectionsPath, ReceiptsPath, SeqAcksPath,
-				SeqRecvsPath, SeqSendsPath,
+				SeqRecvsPath, SeqSendsPath, UpgradeClientPath,
 			},
 		},
 		ics26_routing::handler::MsgReceipt,
 	},
 	events::IbcEvent,
 };
-use pallet_ibc_utils::module::AddModule;
+use pallet_ibc_utils::module::{AddModule, ChannelUpgradeModule};
+use simulation::DeliveryError;
 use sp_std::{fmt::Debug, vec, vec::Vec};
 
 pub mod channel;
@@ -47,8 +51,22 @@ pub mod client;
 pub mod connection;
 pub mod context;
 pub mod errors;
+pub mod genesis;
+pub mod invariants;
+pub mod localhost_client;
+pub mod migrations;
+pub mod misbehaviour;
 pub mod port;
+pub mod pruning;
+pub mod recovery;
+pub mod redundant_relay;
+pub mod relayer_policy;
 pub mod routing;
+pub mod runtime_api;
+pub mod simulation;
+pub mod status;
+pub mod upgrade;
+pub mod wasm_client;
 
 pub use crate::context::Context;
 pub use alloc::{
@@ -61,6 +79,10 @@ pub use weights::WeightInfo;
 pub const LOG_TARGET: &str = "runtime::pallet-ibc";
 pub const TENDERMINT_CLIENT_TYPE: &'static str = "07-tendermint";
 pub const MOCK_CLIENT_TYPE: &'static str = "9999-mock";
+pub const WASM_CLIENT_TYPE: &'static str = "08-wasm";
+pub const LOCALHOST_CLIENT_TYPE: &'static str = "09-localhost";
+/// Type url prefix shared by all ICS core messages delivered by this pallet.
+pub const IBC_CORE_TYPE_URL_PREFIX: &str = "/ibc.core.";
 
 #[cfg(any(test, feature = "runtime-benchmarks"))]
 mod mock;
@@ -98,11 +120,38 @@ pub mod pallet {
 
 		type IbcModule: AddModule;
 
+		/// The origin allowed to recover clients and schedule upgrades
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Handler of the accounts whose misbehaviour evidence froze a client, e.g.
+		/// `misbehaviour::RewardFromPot` to reward them
+		type OnMisbehaviour: misbehaviour::OnMisbehaviour<Self::AccountId>;
+
+		/// Callbacks of the modules whose channels can be upgraded
+		type UpgradeModule: ChannelUpgradeModule;
+
+		/// Maximum number of packet sequences whose receipt and acknowledgement are pruned per
+		/// block
+		#[pallet::constant]
+		type MaxPrunedPerBlock: Get<u32>;
+
+		/// Gas available to each call of an 08-wasm light client contract
+		#[pallet::constant]
+		type WasmClientGasLimit: Get<u64>;
+
+		/// Maximum size in bytes of the bytecode of an 08-wasm light client contract
+		#[pallet::constant]
+		type MaxWasmCodeSize: Get<u32>;
+
 		/// benchmarking weight info
 		type WeightInfo: WeightInfo<Self>;
 	}
 
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(3);
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(_);
 
@@ -204,13 +253,202 @@ pub mod pallet {
 
 	#[pallet::storage]
 	/// key: height
-	/// value: Ibc event height
-	pub type IbcEventStore<T: Config> = StorageMap<_, Blake2_128Concat, u64, IbcEvent>;
+	/// value: Ibc events emitted at the height, only kept until the next block
+	pub type IbcEventStore<T: Config> = StorageMap<_, Blake2_128Concat, u64, Vec<IbcEvent>>;
 
 	#[pallet::storage]
 	/// Previous host block height
 	pub type OldHeight<T: Config> = StorageValue<_, u64, ValueQuery>;
 
+	#[pallet::storage]
+	/// key: UpgradeClientPath, i.e. `upgradedIBCState/{height}/upgradedClient`
+	/// value: upgraded ClientState
+	pub type UpgradedClientStates<T: Config> =
+		StorageMap<_, Blake2_128Concat, UpgradeClientPath, Vec<u8>>;
+
+	#[pallet::storage]
+	/// key: UpgradeClientPath, i.e. `upgradedIBCState/{height}/upgradedConsState`
+	/// value: upgraded ConsensusState
+	pub type UpgradedConsensusStates<T: Config> =
+		StorageMap<_, Blake2_128Concat, UpgradeClientPath, Vec<u8>>;
+
+	#[pallet::storage]
+	/// Height of the scheduled upgrade of this chain
+	pub type ScheduledUpgrade<T: Config> = StorageValue<_, u64>;
+
+	#[pallet::storage]
+	/// key: ClientId
+	/// value: last recorded status of the client
+	pub type ClientStatuses<T: Config> =
+		StorageMap<_, Blake2_128Concat, ClientId, status::ClientStatus>;
+
+	#[pallet::storage]
+	/// Client whose status was refreshed last by `on_idle`
+	pub type ClientStatusCursor<T: Config> = StorageValue<_, ClientId>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: upgrade of the channel in progress
+	pub type ChannelUpgrades<T: Config> =
+		StorageMap<_, Blake2_128Concat, ChannelEndsPath, channel::upgrade::ChannelUpgrade>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: sequence of the last upgrade proposed for the channel
+	pub type ChannelUpgradeSequences<T: Config> =
+		StorageMap<_, Blake2_128Concat, ChannelEndsPath, u64, ValueQuery>;
+
+	#[pallet::storage]
+	/// key: ChannelUpgradePath
+	/// value: upgrade of the channel in progress, committed for the counterparty to prove
+	pub type UpgradeCommitments<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		channel::upgrade::ChannelUpgradePath,
+		channel::upgrade::UpgradeCommitment,
+	>;
+
+	#[pallet::storage]
+	/// key: ChannelUpgradeErrorPath
+	/// value: sequence of the last upgrade of the channel which was cancelled or timed out
+	pub type UpgradeErrorReceipts<T: Config> =
+		StorageMap<_, Blake2_128Concat, channel::upgrade::ChannelUpgradeErrorPath, u64>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: number of packets sent on the channel with a commitment still stored
+	pub type InFlightPackets<T: Config> =
+		StorageMap<_, Blake2_128Concat, ChannelEndsPath, u64, ValueQuery>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: (), present for the channels opened with the `ORDERED_ALLOW_TIMEOUT` ordering
+	pub type OrderedAllowTimeoutChannels<T: Config> =
+		StorageMap<_, Blake2_128Concat, ChannelEndsPath, ()>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: receipts and acknowledgements stored for the channel
+	pub type PacketStateUse<T: Config> =
+		StorageMap<_, Blake2_128Concat, ChannelEndsPath, pruning::PacketState, ValueQuery>;
+
+	#[pallet::storage]
+	/// Progress of counting the receipts and acknowledgements stored before `PacketStateUse`,
+	/// removed once they are all counted
+	pub type PacketStateMigrationCursor<T: Config> = StorageValue<_, pruning::PacketStateMigration>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: sequence below which receipts and acknowledgements are to be pruned
+	pub type PruningWatermarks<T: Config> = StorageMap<_, Blake2_128Concat, ChannelEndsPath, u64>;
+
+	#[pallet::storage]
+	/// key: ChannelEndsPath
+	/// value: next sequence to prune
+	pub type PruningCursors<T: Config> =
+		StorageMap<_, Blake2_128Concat, ChannelEndsPath, u64, ValueQuery>;
+
+	#[pallet::storage]
+	/// key: RelayerScope
+	/// value: (), present for the scopes restricted to their allowed relayers
+	pub type RestrictedScopes<T: Config> =
+		StorageMap<_, Blake2_128Concat, relayer_policy::RelayerScope, ()>;
+
+	#[pallet::storage]
+	/// key: (RelayerScope, AccountId)
+	/// value: (), present for the relayers allowed in the scope
+	pub type AllowedRelayers<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		relayer_policy::RelayerScope,
+		Blake2_128Concat,
+		T::AccountId,
+		(),
+	>;
+
+	#[pallet::storage]
+	/// value: who may create clients through `deliver`
+	pub type ClientCreation<T: Config> =
+		StorageValue<_, relayer_policy::ClientCreationPolicy, ValueQuery>;
+
+	#[pallet::storage]
+	/// value: number of client creation policies set, windows of earlier policies are stale
+	pub type ClientCreationEpoch<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::storage]
+	/// key: AccountId
+	/// value: (policy epoch, start of the rate limit window, clients created in it)
+	pub type ClientCreationWindows<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (u32, BlockNumberFor<T>, u32)>;
+
+	#[pallet::storage]
+	/// key: sha256 checksum of the bytecode
+	/// value: bytecode of an 08-wasm light client contract
+	pub type WasmCodes<T: Config> = StorageMap<_, Blake2_128Concat, [u8; 32], Vec<u8>>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T>
+	where
+		u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+	{
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			// the events of the previous block remain readable from its state
+			<IbcEventStore<T>>::remove(u64::from(n).saturating_sub(1));
+			T::DbWeight::get().writes(1)
+		}
+
+		fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			let used = migrations::v3::count_packet_state::<T>(remaining_weight);
+			let used = used
+				.saturating_add(Self::prune_packet_state(remaining_weight.saturating_sub(used)));
+			used.saturating_add(Self::refresh_recorded_client_statuses(
+				remaining_weight.saturating_sub(used),
+			))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_try_state()
+		}
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub clients: Vec<genesis::GenesisClient>,
+		pub connections: Vec<genesis::GenesisConnection>,
+		pub channels: Vec<genesis::GenesisChannel>,
+		pub client_counter: u64,
+		pub connection_counter: u64,
+		pub channel_counter: u64,
+		#[serde(skip)]
+		pub _marker: PhantomData<T>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				clients: Vec::new(),
+				connections: Vec::new(),
+				channels: Vec::new(),
+				client_counter: 0,
+				connection_counter: 0,
+				channel_counter: 0,
+				_marker: PhantomData,
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			if let Err(e) = self.validate() {
+				panic!("invalid pallet-ibc genesis: {}", e);
+			}
+			self.store();
+		}
+	}
+
 	/// Substrate IBC event list
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -219,6 +457,56 @@ pub mod pallet {
 		IbcEvents { events: Vec<IbcEvent> },
 		/// Ibc errors
 		IbcErrors { errors: Vec<errors::IbcError> },
+		/// A frozen or expired client was recovered from a substitute client
+		ClientRecovered { subject_client_id: ClientId, substitute_client_id: ClientId },
+		/// An upgrade of this chain was scheduled at the given height
+		UpgradeScheduled { height: u64 },
+		/// The upgrade of this chain scheduled at the given height was cancelled
+		UpgradeCancelled { height: u64 },
+		/// The status of a client changed
+		ClientStatusChanged { client_id: ClientId, status: status::ClientStatus },
+		/// Misbehaviour evidence submitted by the reporter froze a client
+		MisbehaviourReported { client_id: ClientId, reporter: T::AccountId },
+		/// An upgrade of a channel was proposed
+		ChannelUpgradeInit { port_id: PortId, channel_id: ChannelId, upgrade_sequence: u64 },
+		/// A channel started flushing after the counterparty proposed the same upgrade
+		ChannelUpgradeTry { port_id: PortId, channel_id: ChannelId, upgrade_sequence: u64 },
+		/// A channel started flushing after the counterparty accepted the upgrade
+		ChannelUpgradeAck { port_id: PortId, channel_id: ChannelId, upgrade_sequence: u64 },
+		/// A channel switched to its upgraded fields
+		ChannelUpgradeOpen { port_id: PortId, channel_id: ChannelId, upgrade_sequence: u64 },
+		/// The upgrade of a channel timed out on the counterparty and was abandoned
+		ChannelUpgradeTimeout { port_id: PortId, channel_id: ChannelId, upgrade_sequence: u64 },
+		/// The upgrade of a channel was cancelled
+		ChannelUpgradeCancelled { port_id: PortId, channel_id: ChannelId, upgrade_sequence: u64 },
+		/// A channel upgrade message failed
+		ChannelUpgradeFailed { type_url: Vec<u8>, error: DispatchError },
+		/// A packet relayed after its timeout was skipped on an `ORDERED_ALLOW_TIMEOUT` channel
+		PacketReceiveSkipped { port_id: PortId, channel_id: ChannelId, sequence: u64 },
+		/// A packet skipped by the counterparty timed out on an `ORDERED_ALLOW_TIMEOUT` channel
+		SkippedPacketTimedOut { port_id: PortId, channel_id: ChannelId, sequence: u64 },
+		/// A packet message on an `ORDERED_ALLOW_TIMEOUT` channel failed
+		AllowTimeoutPacketFailed { type_url: Vec<u8>, error: DispatchError },
+		/// Receipts and acknowledgements of a channel below the watermark are to be pruned
+		PruningScheduled { port_id: PortId, channel_id: ChannelId, watermark: u64 },
+		/// Receipts and acknowledgements of a channel below the given sequence were pruned
+		PacketStatePruned { port_id: PortId, channel_id: ChannelId, below: u64 },
+		/// Packet messages already relayed by someone else were skipped
+		RedundantMessagesSkipped { messages: u32 },
+		/// A scope was restricted to its allowed relayers
+		RelayerScopeRestricted { scope: relayer_policy::RelayerScope },
+		/// A scope was opened to all relayers
+		RelayerScopeUnrestricted { scope: relayer_policy::RelayerScope },
+		/// A relayer was allowed in a scope
+		RelayerAllowed { scope: relayer_policy::RelayerScope, relayer: T::AccountId },
+		/// A relayer was removed from the allowed relayers of a scope
+		RelayerRevoked { scope: relayer_policy::RelayerScope, relayer: T::AccountId },
+		/// The client creation policy changed
+		ClientCreationPolicySet { policy: relayer_policy::ClientCreationPolicy },
+		/// The bytecode of an 08-wasm light client was stored under its checksum
+		WasmCodeStored { checksum: [u8; 32] },
+		/// The bytecode of an 08-wasm light client was removed
+		WasmCodeRemoved { checksum: [u8; 32] },
 	}
 
 	/// Errors in MMR verification informing users that something went wrong.
@@ -244,6 +532,84 @@ pub mod pallet {
 		InvalidVersion,
 		/// Invalid module id
 		InvalidModuleId,
+		/// client not found
+		ClientNotFound,
+		/// client state cannot be decoded
+		InvalidClientState,
+		/// consensus state not found
+		ConsensusStateNotFound,
+		/// subject and substitute are the same client
+		SameSubjectAndSubstitute,
+		/// subject client is neither frozen nor expired
+		SubjectClientActive,
+		/// substitute client is frozen or expired
+		SubstituteClientNotActive,
+		/// substitute client is not ahead of the subject client
+		SubstituteHeightTooLow,
+		/// subject and substitute client parameters differ
+		ClientParamsMismatch,
+		/// consensus state cannot be decoded
+		InvalidConsensusState,
+		/// upgrade height is not in the future
+		InvalidUpgradeHeight,
+		/// no upgrade is scheduled
+		NoScheduledUpgrade,
+		/// channel not found
+		ChannelNotFound,
+		/// channel is not open
+		ChannelNotOpen,
+		/// an upgrade of the channel is already in progress
+		UpgradeInProgress,
+		/// no upgrade of the channel is in progress
+		NoUpgradeInProgress,
+		/// the channel upgrade is not in the state required by the message
+		InvalidUpgradeState,
+		/// counterparty upgrade fields differ from the proposed ones
+		UpgradeFieldsMismatch,
+		/// counterparty upgrade sequence differs from the proposed one
+		UpgradeSequenceMismatch,
+		/// the channel module refused the upgrade
+		UpgradeRefused,
+		/// packets sent before the upgrade are still in flight
+		FlushInProgress,
+		/// proof of the counterparty channel end or upgrade failed to verify
+		InvalidProof,
+		/// upgrade timeout is not reached on the counterparty
+		UpgradeNotTimedOut,
+		/// upgrade fields are invalid or do not change the channel
+		InvalidUpgrade,
+		/// upgrade timeout has neither height nor timestamp
+		InvalidUpgradeTimeout,
+		/// channel upgrade message cannot be decoded
+		InvalidUpgradeMessage,
+		/// packet cannot be decoded or does not match its channel
+		InvalidPacket,
+		/// packet sequence is not the next one to receive or acknowledge
+		PacketSequenceMismatch,
+		/// packet commitment is missing or differs from the packet
+		PacketCommitmentMismatch,
+		/// the module bound to the port failed to handle the packet
+		ModuleCallbackFailed,
+		/// pruning watermark is not above the pruned sequences
+		InvalidWatermark,
+		/// channel is not closed
+		ChannelNotClosed,
+		/// the sender is not allowed to relay in the scope of a message
+		RelayerNotAllowed,
+		/// clients can only be created by `AdminOrigin`
+		ClientCreationNotAllowed,
+		/// the sender created too many clients in the current window
+		ClientCreationRateLimited,
+		/// the message is not a `MsgCreateClient`
+		NotClientCreation,
+		/// the bytecode is not a valid wasm module
+		InvalidWasmCode,
+		/// the bytecode is larger than `MaxWasmCodeSize`
+		WasmCodeTooLarge,
+		/// bytecode with the same checksum is already stored
+		WasmCodeExists,
+		/// no bytecode is stored under the checksum
+		WasmCodeNotFound,
 		///
 		Other,
 	}
@@ -259,7 +625,9 @@ pub mod pallet {
 		/// This function acts as an entry for most of the IBC request.
 		/// I.e., create clients, update clients, handshakes to create channels, ...etc
 		///
-		/// The origin must be Signed and the sender must have sufficient funds fee.
+		/// The origin must be Signed and the sender must have sufficient funds fee. The sender
+		/// must be allowed in the restricted scopes of the messages, and may create clients only
+		/// as permitted by the client creation policy.
 		///
 		/// Parameters:
 		/// - `messages`: The arbitrary ICS message's representation in Substrate, which contains an
@@ -267,14 +635,363 @@ pub mod pallet {
 		///  a serialized protocol buffer message. The URL name that uniquely identifies the type of
 		/// the serialized protocol buffer message.
 		///
-		/// The relevant events are emitted when successful.
+		/// The relevant events are emitted when successful, and `MisbehaviourReported` for each
+		/// client frozen by misbehaviour evidence of the sender. Packet messages already relayed
+		/// are skipped without charging their weight, emitting `RedundantMessagesSkipped`.
 		#[pallet::call_index(0)]
-		#[pallet::weight(0)]
+		#[pallet::weight(weights::deliver::<T>(messages))]
 		pub fn deliver(origin: OriginFor<T>, messages: Vec<Any>) -> DispatchResultWithPostInfo {
-			let _ = ensure_signed(origin)?;
+			let who = ensure_signed(origin)?;
+
+			let messages = Self::admit_messages(&who, messages)?;
+			let actual_weight = weights::deliver::<T>(&messages);
+			let misbehaving_clients = Self::unfrozen_misbehaving_clients(&messages);
+			Self::deliver_messages(messages)?;
+			Self::report_misbehaviour(&who, misbehaving_clients);
+			Ok(Some(actual_weight).into())
+		}
+
+		/// Recover a frozen or expired client by substituting it with a healthy client, like
+		/// ibc-go's `MsgRecoverClient`.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `subject_client_id`: The frozen or expired client to recover.
+		/// - `substitute_client_id`: An active client of the same type and parameters whose latest
+		///   consensus state is copied to the subject.
+		///
+		/// Emits `ClientRecovered` when successful.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(15, 5))]
+		pub fn recover_client(
+			origin: OriginFor<T>,
+			subject_client_id: ClientId,
+			substitute_client_id: ClientId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Self::do_recover_client(&subject_client_id, &substitute_client_id)?;
+
+			Self::deposit_event(Event::ClientRecovered { subject_client_id, substitute_client_id });
+			Ok(())
+		}
 
-			<pallet::Pallet<T> as pallet_ibc_utils::Router>::dispatch(messages)?;
-			Ok(().into())
+		/// Schedule an upgrade of this chain at `height`, committing the upgraded client and
+		/// consensus states that counterparties prove in `MsgUpgradeClient`. A previously scheduled
+		/// upgrade is replaced.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `height`: The future block height of the upgrade.
+		/// - `upgraded_client_state`: The client state of this chain after the upgrade.
+		/// - `upgraded_consensus_state`: The consensus state of this chain after the upgrade.
+		///
+		/// Emits `UpgradeScheduled` when successful.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 5))]
+		pub fn schedule_upgrade(
+			origin: OriginFor<T>,
+			height: u64,
+			upgraded_client_state: Any,
+			upgraded_consensus_state: Any,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Self::do_schedule_upgrade(height, upgraded_client_state, upgraded_consensus_state)?;
+
+			Self::deposit_event(Event::UpgradeScheduled { height });
+			Ok(())
+		}
+
+		/// Cancel the scheduled upgrade of this chain before its height is reached.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `UpgradeCancelled` when successful.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 3))]
+		pub fn cancel_upgrade(origin: OriginFor<T>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let height = Self::do_cancel_upgrade()?;
+
+			Self::deposit_event(Event::UpgradeCancelled { height });
+			Ok(())
+		}
+
+		/// Propose an upgrade of an open channel, like ibc-go's `MsgChannelUpgradeInit` but not
+		/// interoperable with it. The governance of the counterparty chain must propose the same
+		/// upgrade fields.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `port_id`, `channel_id`: The channel to upgrade.
+		/// - `fields`: The ordering, connection hops and version of the upgraded channel.
+		/// - `timeout`: The counterparty height or timestamp after which the upgrade can be timed
+		///   out.
+		///
+		/// Emits `ChannelUpgradeInit` when successful.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(4, 3))]
+		pub fn chan_upgrade_init(
+			origin: OriginFor<T>,
+			port_id: PortId,
+			channel_id: ChannelId,
+			fields: channel::upgrade::UpgradeFields,
+			timeout: channel::upgrade::UpgradeTimeout,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let upgrade_sequence =
+				Self::do_chan_upgrade_init(port_id.clone(), channel_id.clone(), fields, timeout)?;
+
+			Self::deposit_event(Event::ChannelUpgradeInit {
+				port_id,
+				channel_id,
+				upgrade_sequence,
+			});
+			Ok(())
+		}
+
+		/// Cancel the upgrade of a channel in progress, which keeps its current fields. An error
+		/// receipt is written for `MsgChannelUpgradeCancel` to cancel the upgrade on the
+		/// counterparty.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `ChannelUpgradeCancelled` when successful.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 3))]
+		pub fn chan_upgrade_cancel(
+			origin: OriginFor<T>,
+			port_id: PortId,
+			channel_id: ChannelId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let upgrade_sequence =
+				Self::do_chan_upgrade_cancel(port_id.clone(), channel_id.clone())?;
+
+			Self::deposit_event(Event::ChannelUpgradeCancelled {
+				port_id,
+				channel_id,
+				upgrade_sequence,
+			});
+			Ok(())
+		}
+
+		/// Prune the receipts and acknowledgements of a channel below `watermark` in the idle
+		/// time of the next blocks.
+		///
+		/// The origin must be `AdminOrigin`, which must make sure the counterparty holds no packet
+		/// commitment below `watermark` anymore.
+		///
+		/// Emits `PruningScheduled` when successful, and `PacketStatePruned` once pruned.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+		pub fn set_pruning_watermark(
+			origin: OriginFor<T>,
+			port_id: PortId,
+			channel_id: ChannelId,
+			watermark: u64,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Self::do_set_pruning_watermark(port_id.clone(), channel_id.clone(), watermark)?;
+
+			Self::deposit_event(Event::PruningScheduled { port_id, channel_id, watermark });
+			Ok(())
+		}
+
+		/// Prune all receipts and acknowledgements of a closed channel, and drop its upgrade
+		/// state. The channel end and its sequences are kept for the proofs of the counterparty.
+		/// Up to `MaxPrunedPerBlock` sequences are pruned right away, the others in the idle time
+		/// of the next blocks.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `PruningScheduled` when successful, and `PacketStatePruned` once pruned.
+		#[pallet::call_index(7)]
+		#[pallet::weight(pruning::prune_closed_channel_weight::<T>())]
+		pub fn prune_closed_channel(
+			origin: OriginFor<T>,
+			port_id: PortId,
+			channel_id: ChannelId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let watermark = Self::do_prune_closed_channel(port_id.clone(), channel_id.clone())?;
+
+			Self::deposit_event(Event::PruningScheduled {
+				port_id: port_id.clone(),
+				channel_id: channel_id.clone(),
+				watermark,
+			});
+			Self::prune_scheduled_sequences(port_id, channel_id);
+			Ok(())
+		}
+
+		/// Restrict the messages of `scope` to the relayers allowed in it.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `RelayerScopeRestricted` when successful.
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn restrict_scope(
+			origin: OriginFor<T>,
+			scope: relayer_policy::RelayerScope,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<RestrictedScopes<T>>::insert(&scope, ());
+
+			Self::deposit_event(Event::RelayerScopeRestricted { scope });
+			Ok(())
+		}
+
+		/// Open the messages of `scope` to all relayers. The allowed relayers are kept for a later
+		/// restriction.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `RelayerScopeUnrestricted` when successful.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn unrestrict_scope(
+			origin: OriginFor<T>,
+			scope: relayer_policy::RelayerScope,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<RestrictedScopes<T>>::remove(&scope);
+
+			Self::deposit_event(Event::RelayerScopeUnrestricted { scope });
+			Ok(())
+		}
+
+		/// Allow `relayer` to deliver the messages of `scope` while it is restricted.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `RelayerAllowed` when successful.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn allow_relayer(
+			origin: OriginFor<T>,
+			scope: relayer_policy::RelayerScope,
+			relayer: T::AccountId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<AllowedRelayers<T>>::insert(&scope, &relayer, ());
+
+			Self::deposit_event(Event::RelayerAllowed { scope, relayer });
+			Ok(())
+		}
+
+		/// Remove `relayer` from the relayers allowed in `scope`.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `RelayerRevoked` when successful.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn revoke_relayer(
+			origin: OriginFor<T>,
+			scope: relayer_policy::RelayerScope,
+			relayer: T::AccountId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<AllowedRelayers<T>>::remove(&scope, &relayer);
+
+			Self::deposit_event(Event::RelayerRevoked { scope, relayer });
+			Ok(())
+		}
+
+		/// Set who may create clients through `deliver`. Rate limit windows in progress restart
+		/// from scratch, as they are overwritten on the next client creation of their account.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Emits `ClientCreationPolicySet` when successful.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 2))]
+		pub fn set_client_creation_policy(
+			origin: OriginFor<T>,
+			policy: relayer_policy::ClientCreationPolicy,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<ClientCreationEpoch<T>>::mutate(|epoch| *epoch = epoch.wrapping_add(1));
+			<ClientCreation<T>>::put(policy);
+
+			Self::deposit_event(Event::ClientCreationPolicySet { policy });
+			Ok(())
+		}
+
+		/// Create a client whatever the client creation policy.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `message`: A `MsgCreateClient`.
+		///
+		/// The `CreateClient` ibc event is emitted when successful.
+		#[pallet::call_index(13)]
+		#[pallet::weight(weights::deliver::<T>(core::slice::from_ref(message)))]
+		pub fn create_client(origin: OriginFor<T>, message: Any) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(
+				message.type_url == relayer_policy::MSG_CREATE_CLIENT_TYPE_URL,
+				Error::<T>::NotClientCreation
+			);
+
+			Self::deliver_messages(vec![message])
+		}
+
+		/// Store the bytecode of an 08-wasm light client contract under its sha256 checksum,
+		/// which 08-wasm client states name to be verified by it.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `code`: the wasm bytecode of the contract
+		///
+		/// Emits `WasmCodeStored` when successful.
+		#[pallet::call_index(14)]
+		#[pallet::weight(wasm_client::store_wasm_code_weight::<T>(code.len()))]
+		pub fn store_wasm_code(origin: OriginFor<T>, code: Vec<u8>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let checksum = Self::do_store_wasm_code(code)?;
+
+			Self::deposit_event(Event::WasmCodeStored { checksum });
+			Ok(())
+		}
+
+		/// Remove the bytecode of an 08-wasm light client contract. Clients verified by it can
+		/// no longer be updated nor verify proofs.
+		///
+		/// The origin must be `AdminOrigin`.
+		///
+		/// Parameters:
+		/// - `checksum`: the sha256 checksum of the bytecode
+		///
+		/// Emits `WasmCodeRemoved` when successful.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove_wasm_code(origin: OriginFor<T>, checksum: [u8; 32]) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Self::do_remove_wasm_code(checksum)?;
+
+			Self::deposit_event(Event::WasmCodeRemoved { checksum });
+			Ok(())
 		}
 	}
 }
@@ -283,7 +1000,24 @@ impl<T: Config> pallet_ibc_utils::Router for Pallet<T>
 where
 	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
 {
+	fn handles(type_url: &str) -> bool {
+		type_url.starts_with(IBC_CORE_TYPE_URL_PREFIX) ||
+			type_url.starts_with(channel::upgrade::CHANNEL_UPGRADE_TYPE_URL_PREFIX)
+	}
+
 	fn dispatch(messages: Vec<Any>) -> DispatchResult {
+		Self::deliver_messages(Self::skip_redundant_messages(messages))
+	}
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	fn deliver_messages(messages: Vec<Any>) -> DispatchResult {
+		if messages.is_empty() {
+			return Ok(());
+		}
 		let mut ctx = Context::<T>::new();
 		log::info!(
 			"☀️ ibc messages type: {:?}",
@@ -293,12 +1027,17 @@ where
 		let (events, logs, errors) = messages.into_iter().fold(
 			(vec![], vec![], vec![]),
 			|(mut events, mut logs, mut errors), msg| {
-				match ibc::core::ics26_routing::handler::deliver(&mut ctx, msg) {
+				let type_url = msg.type_url.as_bytes().to_vec();
+				match Self::deliver_message(&mut ctx, msg) {
 					Ok(MsgReceipt { events: temp_events, log: temp_logs }) => {
 						events.extend(temp_events);
 						logs.extend(temp_logs);
 					},
-					Err(e) => errors.push(e),
+					Err(DeliveryError::Router(e)) => errors.push(e),
+					Err(DeliveryError::ChannelUpgrade(error)) =>
+						Self::deposit_event(Event::ChannelUpgradeFailed { type_url, error }),
+					Err(DeliveryError::AllowTimeout(error)) =>
+						Self::deposit_event(Event::AllowTimeoutPacketFailed { type_url, error }),
 				}
 				(events, logs, errors)
 			},
@@ -313,8 +1052,9 @@ where
 		let block_height = <frame_system::Pallet<T>>::block_number();
 
 		for event in events.clone() {
-			<IbcEventStore<T>>::insert(u64::from(block_height), event);
+			<IbcEventStore<T>>::append(u64::from(block_height), event);
 		}
+		Self::refresh_client_statuses(&events);
 		Self::deposit_event(Event::IbcEvents { events });
 		if !errors.is_empty() {
 			Self::deposit_event(errors.into());
@@ -0,0 +1,296 @@
+use super::{
+	consensus_state::LocalhostConsensusState, LocalStore, LOCALHOST_CLIENT_STATE_TYPE_URL,
+};
+use crate::LOCALHOST_CLIENT_TYPE;
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use core::time::Duration;
+use ibc::{
+	core::{
+		ics02_client::{
+			client_state::{ClientState, UpdatedState, UpgradeOptions},
+			client_type::ClientType,
+			consensus_state::ConsensusState,
+			context::ClientReader,
+			error::ClientError,
+		},
+		ics03_connection::connection::ConnectionEnd,
+		ics04_channel::{
+			channel::ChannelEnd,
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			context::ChannelReader,
+			packet::Sequence,
+		},
+		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+		ics24_host::{
+			identifier::{ChainId, ClientId},
+			path::{
+				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
+				CommitmentsPath, ConnectionsPath, Path, ReceiptsPath, SeqRecvsPath,
+			},
+		},
+	},
+	Height,
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::{client::v1::Height as RawHeight, commitment::v1::MerkleProof as RawMerkleProof},
+	protobuf::Protobuf,
+};
+use prost::Message;
+use serde::Serialize;
+
+/// `ibc.lightclients.localhost.v2.ClientState`
+#[derive(Clone, PartialEq, Message)]
+pub struct RawLocalhostClientState {
+	#[prost(message, optional, tag = "1")]
+	pub latest_height: Option<RawHeight>,
+}
+
+/// State of the localhost client, verifying proofs against the commitment store of this chain.
+#[derive(Clone, Debug, Serialize)]
+pub struct LocalhostClientState {
+	pub latest_height: Height,
+	/// Set when read from the store, absent on states decoded elsewhere
+	#[serde(skip)]
+	pub store: Option<LocalStore>,
+}
+
+impl PartialEq for LocalhostClientState {
+	fn eq(&self, other: &Self) -> bool {
+		self.latest_height == other.latest_height
+	}
+}
+
+impl Eq for LocalhostClientState {}
+
+fn localhost_error(description: &str) -> ClientError {
+	ClientError::Other { description: format!("09-localhost client: {}", description) }
+}
+
+impl LocalhostClientState {
+	pub fn new(latest_height: Height) -> Self {
+		Self { latest_height, store: None }
+	}
+
+	/// Attach the commitment store of this chain, at its current height.
+	pub fn with_store(self, store: LocalStore, host_height: Height) -> Self {
+		Self { latest_height: host_height, store: Some(store) }
+	}
+
+	/// Check that the commitment store holds `value` at `path`, or nothing if `value` is `None`.
+	fn verify(&self, path: Path, value: Option<Vec<u8>>) -> Result<(), ClientError> {
+		let store = self.store.ok_or_else(|| localhost_error("no commitment store"))?;
+		if (store.read)(&path) != value {
+			return Err(localhost_error(&format!("unexpected value at {}", path)));
+		}
+		Ok(())
+	}
+}
+
+impl ClientState for LocalhostClientState {
+	fn chain_id(&self) -> ChainId {
+		ChainId::new(LOCALHOST_CLIENT_TYPE.to_string(), self.latest_height.revision_number())
+	}
+
+	fn client_type(&self) -> ClientType {
+		ClientType::new(LOCALHOST_CLIENT_TYPE.to_string())
+	}
+
+	fn latest_height(&self) -> Height {
+		self.latest_height
+	}
+
+	fn frozen_height(&self) -> Option<Height> {
+		None
+	}
+
+	fn upgrade(
+		&mut self,
+		_upgrade_height: Height,
+		_upgrade_options: &dyn UpgradeOptions,
+		_chain_id: ChainId,
+	) {
+	}
+
+	fn expired(&self, _elapsed: Duration) -> bool {
+		false
+	}
+
+	fn initialise(&self, consensus_state: Any) -> Result<Box<dyn ConsensusState>, ClientError> {
+		LocalhostConsensusState::try_from(consensus_state).map(LocalhostConsensusState::into_box)
+	}
+
+	fn check_header_and_update_state(
+		&self,
+		_ctx: &dyn ClientReader,
+		_client_id: ClientId,
+		_header: Any,
+	) -> Result<UpdatedState, ClientError> {
+		Err(localhost_error("the client follows the host height and takes no updates"))
+	}
+
+	fn check_misbehaviour_and_update_state(
+		&self,
+		_ctx: &dyn ClientReader,
+		_client_id: ClientId,
+		_misbehaviour: Any,
+	) -> Result<Box<dyn ClientState>, ClientError> {
+		Err(localhost_error("the client cannot misbehave"))
+	}
+
+	fn verify_upgrade_client(
+		&self,
+		_upgraded_client_state: Any,
+		_upgraded_consensus_state: Any,
+		_proof_upgrade_client: RawMerkleProof,
+		_proof_upgrade_consensus_state: RawMerkleProof,
+		_root: &CommitmentRoot,
+	) -> Result<(), ClientError> {
+		Err(localhost_error("upgrades are not supported"))
+	}
+
+	fn update_state_with_upgrade_client(
+		&self,
+		_upgraded_client_state: Any,
+		_upgraded_consensus_state: Any,
+	) -> Result<UpdatedState, ClientError> {
+		Err(localhost_error("upgrades are not supported"))
+	}
+
+	fn verify_client_consensus_state(
+		&self,
+		_proof_height: Height,
+		_prefix: &CommitmentPrefix,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		client_cons_state_path: &ClientConsensusStatePath,
+		expected_consensus_state: &dyn ConsensusState,
+	) -> Result<(), ClientError> {
+		let value = expected_consensus_state
+			.encode_vec()
+			.map_err(|_| localhost_error("cannot encode the consensus state"))?;
+		self.verify(Path::ClientConsensusState(client_cons_state_path.clone()), Some(value))
+	}
+
+	fn verify_connection_state(
+		&self,
+		_proof_height: Height,
+		_prefix: &CommitmentPrefix,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		counterparty_conn_path: &ConnectionsPath,
+		expected_counterparty_connection_end: &ConnectionEnd,
+	) -> Result<(), ClientError> {
+		let value = expected_counterparty_connection_end
+			.encode_vec()
+			.map_err(|_| localhost_error("cannot encode the connection end"))?;
+		self.verify(Path::Connections(counterparty_conn_path.clone()), Some(value))
+	}
+
+	fn verify_channel_state(
+		&self,
+		_proof_height: Height,
+		_prefix: &CommitmentPrefix,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		counterparty_chan_end_path: &ChannelEndsPath,
+		expected_counterparty_channel_end: &ChannelEnd,
+	) -> Result<(), ClientError> {
+		let value = expected_counterparty_channel_end
+			.encode_vec()
+			.map_err(|_| localhost_error("cannot encode the channel end"))?;
+		self.verify(Path::ChannelEnds(counterparty_chan_end_path.clone()), Some(value))
+	}
+
+	fn verify_client_full_state(
+		&self,
+		_proof_height: Height,
+		_prefix: &CommitmentPrefix,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		client_state_path: &ClientStatePath,
+		expected_client_state: Any,
+	) -> Result<(), ClientError> {
+		let value = expected_client_state.encode_to_vec();
+		self.verify(Path::ClientState(client_state_path.clone()), Some(value))
+	}
+
+	fn verify_packet_data(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		_connection_end: &ConnectionEnd,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		commitment_path: &CommitmentsPath,
+		commitment: PacketCommitment,
+	) -> Result<(), ClientError> {
+		self.verify(Path::Commitments(commitment_path.clone()), Some(commitment.into_vec()))
+	}
+
+	fn verify_packet_acknowledgement(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		_connection_end: &ConnectionEnd,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		ack_path: &AcksPath,
+		ack: AcknowledgementCommitment,
+	) -> Result<(), ClientError> {
+		self.verify(Path::Acks(ack_path.clone()), Some(ack.into_vec()))
+	}
+
+	fn verify_next_sequence_recv(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		_connection_end: &ConnectionEnd,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		seq_recv_path: &SeqRecvsPath,
+		sequence: Sequence,
+	) -> Result<(), ClientError> {
+		let value = u64::from(sequence).to_be_bytes().to_vec();
+		self.verify(Path::SeqRecvs(seq_recv_path.clone()), Some(value))
+	}
+
+	fn verify_packet_receipt_absence(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		_connection_end: &ConnectionEnd,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		receipt_path: &ReceiptsPath,
+	) -> Result<(), ClientError> {
+		self.verify(Path::Receipts(receipt_path.clone()), None)
+	}
+}
+
+impl Protobuf<Any> for LocalhostClientState {}
+
+impl TryFrom<Any> for LocalhostClientState {
+	type Error = ClientError;
+
+	fn try_from(any: Any) -> Result<Self, Self::Error> {
+		if any.type_url != LOCALHOST_CLIENT_STATE_TYPE_URL {
+			return Err(ClientError::UnknownClientStateType { client_state_type: any.type_url });
+		}
+		let raw = RawLocalhostClientState::decode(any.value.as_slice())
+			.map_err(|_| localhost_error("cannot decode the client state"))?;
+		let latest_height = raw
+			.latest_height
+			.ok_or(ClientError::MissingRawClientState)
+			.and_then(|height| Height::try_from(height))?;
+		Ok(Self::new(latest_height))
+	}
+}
+
+impl From<LocalhostClientState> for Any {
+	fn from(client_state: LocalhostClientState) -> Self {
+		let raw =
+			RawLocalhostClientState { latest_height: Some(client_state.latest_height.into()) };
+		Any { type_url: LOCALHOST_CLIENT_STATE_TYPE_URL.to_string(), value: raw.encode_to_vec() }
+	}
+}
@@ -0,0 +1,73 @@
+use super::LOCALHOST_CONSENSUS_STATE_TYPE_URL;
+use alloc::string::ToString;
+use ibc::{
+	core::{
+		ics02_client::{consensus_state::ConsensusState, error::ClientError},
+		ics23_commitment::commitment::CommitmentRoot,
+	},
+	timestamp::Timestamp,
+};
+use ibc_proto::{google::protobuf::Any, protobuf::Protobuf};
+use prost::Message;
+use serde::Serialize;
+
+/// `ibc.lightclients.localhost.v2.ConsensusState`, only known to this host.
+#[derive(Clone, PartialEq, Message)]
+pub struct RawLocalhostConsensusState {
+	#[prost(uint64, tag = "1")]
+	pub timestamp: u64,
+}
+
+/// Consensus state of the localhost client at any height, never stored: the host timestamp with
+/// an empty root, since proofs are not checked against it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LocalhostConsensusState {
+	pub root: CommitmentRoot,
+	pub timestamp: Timestamp,
+}
+
+impl LocalhostConsensusState {
+	pub fn new(timestamp: Timestamp) -> Self {
+		Self { root: CommitmentRoot::from_bytes(&[]), timestamp }
+	}
+}
+
+impl ConsensusState for LocalhostConsensusState {
+	fn root(&self) -> &CommitmentRoot {
+		&self.root
+	}
+
+	fn timestamp(&self) -> Timestamp {
+		self.timestamp
+	}
+}
+
+impl Protobuf<Any> for LocalhostConsensusState {}
+
+impl TryFrom<Any> for LocalhostConsensusState {
+	type Error = ClientError;
+
+	fn try_from(any: Any) -> Result<Self, Self::Error> {
+		if any.type_url != LOCALHOST_CONSENSUS_STATE_TYPE_URL {
+			return Err(ClientError::UnknownConsensusStateType {
+				consensus_state_type: any.type_url,
+			});
+		}
+		let raw = RawLocalhostConsensusState::decode(any.value.as_slice()).map_err(|_| {
+			ClientError::Other {
+				description: "09-localhost client: cannot decode the consensus state".to_string(),
+			}
+		})?;
+		let timestamp = Timestamp::from_nanoseconds(raw.timestamp).map_err(|_| {
+			ClientError::Other { description: "09-localhost client: invalid timestamp".to_string() }
+		})?;
+		Ok(Self::new(timestamp))
+	}
+}
+
+impl From<LocalhostConsensusState> for Any {
+	fn from(consensus_state: LocalhostConsensusState) -> Self {
+		let raw = RawLocalhostConsensusState { timestamp: consensus_state.timestamp.nanoseconds() };
+		Any { type_url: LOCALHOST_CONSENSUS_STATE_TYPE_URL.to_string(), value: raw.encode_to_vec() }
+	}
+}
@@ -0,0 +1,88 @@
+//! The 09-localhost client, letting modules of this chain open channels with each other.
+//!
+//! There is a single localhost client, `09-localhost`, and a single connection over it,
+//! `connection-localhost`, both created at genesis. The client always tracks the current height
+//! of this chain, has a consensus state at every height, and verifies a proof by reading the
+//! proven path in this chain's own commitment store, ignoring the proof bytes.
+use crate::{
+	Acknowledgements, Channels, ClientStates, Config, Connections, ConsensusStates,
+	NextSequenceRecv, PacketCommitment, PacketReceipt, Pallet,
+};
+use alloc::{vec, vec::Vec};
+use core::{str::FromStr, time::Duration};
+use ibc::core::{
+	ics03_connection::{
+		connection::{ConnectionEnd, Counterparty, State},
+		version::get_compatible_versions,
+	},
+	ics23_commitment::commitment::CommitmentPrefix,
+	ics24_host::{
+		identifier::{ClientId, ConnectionId},
+		path::Path,
+	},
+};
+use ibc_proto::protobuf::Protobuf;
+
+pub mod client_state;
+pub mod consensus_state;
+
+/// Identifier of the localhost client, the same as its client type
+pub const LOCALHOST_CLIENT_ID: &str = crate::LOCALHOST_CLIENT_TYPE;
+/// Identifier of the connection over the localhost client
+pub const LOCALHOST_CONNECTION_ID: &str = "connection-localhost";
+
+pub const LOCALHOST_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.localhost.v2.ClientState";
+pub const LOCALHOST_CONSENSUS_STATE_TYPE_URL: &str =
+	"/ibc.lightclients.localhost.v2.ConsensusState";
+
+/// Access of the localhost client state to the commitment store of this chain.
+#[derive(Clone, Copy, Debug)]
+pub struct LocalStore {
+	/// The value committed at a path, encoded as counterparties expect it in proofs
+	pub read: fn(&Path) -> Option<Vec<u8>>,
+}
+
+pub fn localhost_client_id() -> ClientId {
+	ClientId::from_str(LOCALHOST_CLIENT_ID).expect("09-localhost is a valid client id; qed")
+}
+
+pub fn localhost_connection_id() -> ConnectionId {
+	ConnectionId::from_str(LOCALHOST_CONNECTION_ID)
+		.expect("connection-localhost is a valid connection id; qed")
+}
+
+impl<T: Config> Pallet<T> {
+	/// Commitment store of this chain, read by the localhost client.
+	pub fn local_store() -> LocalStore {
+		LocalStore { read: Self::read_commitment }
+	}
+
+	fn read_commitment(path: &Path) -> Option<Vec<u8>> {
+		match path {
+			Path::ClientState(path) => <ClientStates<T>>::get(path),
+			Path::ClientConsensusState(path) => <ConsensusStates<T>>::get(path),
+			Path::Connections(path) => <Connections<T>>::get(path)?.encode_vec().ok(),
+			Path::ChannelEnds(path) => <Channels<T>>::get(path)?.encode_vec().ok(),
+			Path::SeqRecvs(path) => <NextSequenceRecv<T>>::get(path)
+				.map(|sequence| u64::from(sequence).to_be_bytes().to_vec()),
+			Path::Commitments(path) =>
+				<PacketCommitment<T>>::get(path).map(|commitment| commitment.into_vec()),
+			Path::Acks(path) => <Acknowledgements<T>>::get(path).map(|ack| ack.into_vec()),
+			Path::Receipts(path) => <PacketReceipt<T>>::get(path).map(|_| vec![1]),
+			_ => None,
+		}
+	}
+
+	/// The open `connection-localhost`, whose counterparty is itself.
+	pub fn localhost_connection_end() -> ConnectionEnd {
+		let prefix =
+			CommitmentPrefix::try_from(T::IBC_COMMITMENT_PREFIX.to_vec()).unwrap_or_default();
+		ConnectionEnd::new(
+			State::Open,
+			localhost_client_id(),
+			Counterparty::new(localhost_client_id(), Some(localhost_connection_id()), prefix),
+			get_compatible_versions(),
+			Duration::ZERO,
+		)
+	}
+}
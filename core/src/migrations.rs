@@ -0,0 +1,255 @@
+//! Storage migrations of the IBC pallet.
+use crate::{Config, IbcEventStore, InFlightPackets, PacketCommitment, Pallet, LOG_TARGET};
+use core::marker::PhantomData;
+use frame_support::{
+	traits::{Get, OnRuntimeUpgrade, StorageVersion},
+	weights::Weight,
+};
+use ibc::{core::ics24_host::path::ChannelEndsPath, events::IbcEvent};
+use sp_std::{vec, vec::Vec};
+
+pub mod v1 {
+	use super::*;
+
+	/// Migrates the storage from version 0 to 1: `IbcEventStore` holds all the events of a block
+	/// instead of the last one, every previously stored event becomes a single event list.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() != 0 {
+				log::info!(target: LOG_TARGET, "MigrateToV1 should be removed, storage is up to date");
+				return T::DbWeight::get().reads(1);
+			}
+
+			let mut translated = 0u64;
+			<IbcEventStore<T>>::translate::<IbcEvent, _>(|_, event| {
+				translated += 1;
+				Some(vec![event])
+			});
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+			log::info!(target: LOG_TARGET, "migrated {} event lists to version 1", translated);
+			T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+			use codec::Encode;
+			Ok((<IbcEventStore<T>>::iter_keys().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			use codec::Decode;
+			frame_support::ensure!(
+				StorageVersion::get::<Pallet<T>>() == 1,
+				"storage version was not updated to 1"
+			);
+			let heights = u32::decode(&mut state.as_slice())
+				.map_err(|_| "cannot decode the number of event heights")?;
+			let mut migrated = 0u32;
+			for (_, events) in <IbcEventStore<T>>::iter() {
+				frame_support::ensure!(events.len() == 1, "event list was not migrated");
+				migrated += 1;
+			}
+			frame_support::ensure!(migrated == heights, "event heights were lost");
+			Ok(())
+		}
+	}
+}
+
+pub mod v2 {
+	use super::*;
+
+	/// Maximum number of packet commitments counted by the migration, which runs within a single
+	/// block.
+	pub const MAX_PACKET_COMMITMENTS: u32 = 10_000;
+
+	/// Migrates the storage from version 1 to 2: seeds the `InFlightPackets` counter of each
+	/// channel with its packet commitments stored before the counter existed, which the flushing
+	/// of channel upgrades relies on.
+	///
+	/// The upgrade fails if the storage holds more than `MAX_PACKET_COMMITMENTS` packet
+	/// commitments, so that the new runtime never runs on storage of version 1.
+	pub struct MigrateToV2<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() != 1 {
+				log::info!(target: LOG_TARGET, "MigrateToV2 should be removed, storage is up to date");
+				return T::DbWeight::get().reads(1);
+			}
+
+			let mut commitments = 0u64;
+			for path in <PacketCommitment<T>>::iter_keys() {
+				commitments += 1;
+				if commitments > MAX_PACKET_COMMITMENTS as u64 {
+					panic!(
+						"more than {} packet commitments to count, refusing to run on storage of \
+						 version 1",
+						MAX_PACKET_COMMITMENTS
+					);
+				}
+				<InFlightPackets<T>>::mutate(ChannelEndsPath(path.port_id, path.channel_id), |n| {
+					*n += 1
+				});
+			}
+
+			StorageVersion::new(2).put::<Pallet<T>>();
+			log::info!(
+				target: LOG_TARGET,
+				"counted {} packets in flight, migrated storage to version 2",
+				commitments
+			);
+			T::DbWeight::get().reads_writes(2 * commitments + 1, commitments + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+			use codec::Encode;
+			let commitments = <PacketCommitment<T>>::iter_keys().count() as u32;
+			frame_support::ensure!(
+				commitments <= MAX_PACKET_COMMITMENTS,
+				"too many packet commitments to count in a single block"
+			);
+			Ok(commitments.encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			use codec::Decode;
+			frame_support::ensure!(
+				StorageVersion::get::<Pallet<T>>() == 2,
+				"storage version was not updated to 2"
+			);
+			let commitments = u32::decode(&mut state.as_slice())
+				.map_err(|_| "cannot decode the number of packet commitments")?;
+			let counted = <InFlightPackets<T>>::iter_values().sum::<u64>();
+			frame_support::ensure!(
+				counted == commitments as u64,
+				"packets in flight do not match the packet commitments"
+			);
+			Ok(())
+		}
+	}
+}
+
+pub mod v3 {
+	use super::*;
+	use crate::{
+		pruning::PacketStateMigration, Acknowledgements, PacketReceipt, PacketStateMigrationCursor,
+	};
+	use ibc::core::ics24_host::path::{AcksPath, ReceiptsPath};
+
+	/// Computation weight of counting a receipt or acknowledgement, on top of its storage
+	/// accesses.
+	pub const COUNT_PACKET_STATE_WEIGHT: u64 = 2_000_000;
+
+	/// Migrates the storage from version 2 to 3: starts counting the receipts and
+	/// acknowledgements stored before `PacketStateUse` existed.
+	///
+	/// They are counted by `count_packet_state` in `on_idle`, over as many blocks as needed. The
+	/// receipts and acknowledgements stored or removed meanwhile are counted as they are stored or
+	/// removed once it passed their storage key, and as it finds them otherwise.
+	pub struct MigrateToV3<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() != 2 {
+				log::info!(target: LOG_TARGET, "MigrateToV3 should be removed, storage is up to date");
+				return T::DbWeight::get().reads(1);
+			}
+
+			<PacketStateMigrationCursor<T>>::put(PacketStateMigration::Receipts(None));
+			StorageVersion::new(3).put::<Pallet<T>>();
+			log::info!(
+				target: LOG_TARGET,
+				"migrated storage to version 3, counting the packet state in the next blocks"
+			);
+			T::DbWeight::get().reads_writes(1, 2)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			frame_support::ensure!(
+				StorageVersion::get::<Pallet<T>>() == 3,
+				"storage version was not updated to 3"
+			);
+			frame_support::ensure!(
+				<PacketStateMigrationCursor<T>>::exists(),
+				"counting of the packet state was not started"
+			);
+			Ok(())
+		}
+	}
+
+	fn next_receipt<T: Config>(last: Option<Vec<u8>>) -> Option<ReceiptsPath> {
+		match last {
+			Some(last) => <PacketReceipt<T>>::iter_keys_from(last).next(),
+			None => <PacketReceipt<T>>::iter_keys().next(),
+		}
+	}
+
+	fn next_acknowledgement<T: Config>(last: Option<Vec<u8>>) -> Option<AcksPath> {
+		match last {
+			Some(last) => <Acknowledgements<T>>::iter_keys_from(last).next(),
+			None => <Acknowledgements<T>>::iter_keys().next(),
+		}
+	}
+
+	/// Count the receipts and acknowledgements stored before version 3 within
+	/// `remaining_weight`, returning the weight used.
+	pub(crate) fn count_packet_state<T: Config>(remaining_weight: Weight) -> Weight {
+		let db_weight = T::DbWeight::get();
+		let mut used = db_weight.reads_writes(1, 1);
+		if !remaining_weight.all_gte(used) {
+			return Weight::zero()
+		}
+		let Some(mut cursor) = <PacketStateMigrationCursor<T>>::get() else {
+			return db_weight.reads(1)
+		};
+		let per_entry = Weight::from_parts(COUNT_PACKET_STATE_WEIGHT, 0)
+			.saturating_add(db_weight.reads_writes(2, 1));
+
+		while remaining_weight.all_gte(used.saturating_add(per_entry)) {
+			used = used.saturating_add(per_entry);
+			cursor = match cursor {
+				PacketStateMigration::Receipts(last) => match next_receipt::<T>(last) {
+					Some(path) => {
+						let key = <PacketReceipt<T>>::hashed_key_for(&path);
+						Pallet::<T>::count_packet_state(
+							&path.port_id,
+							&path.channel_id,
+							path.sequence,
+							true,
+						);
+						PacketStateMigration::Receipts(Some(key))
+					},
+					None => PacketStateMigration::Acknowledgements(None),
+				},
+				PacketStateMigration::Acknowledgements(last) =>
+					match next_acknowledgement::<T>(last) {
+						Some(path) => {
+							let key = <Acknowledgements<T>>::hashed_key_for(&path);
+							Pallet::<T>::count_packet_state(
+								&path.port_id,
+								&path.channel_id,
+								path.sequence,
+								false,
+							);
+							PacketStateMigration::Acknowledgements(Some(key))
+						},
+						None => {
+							<PacketStateMigrationCursor<T>>::kill();
+							log::info!(target: LOG_TARGET, "counted the packet state of version 2");
+							return used
+						},
+					},
+			};
+		}
+
+		<PacketStateMigrationCursor<T>>::put(cursor);
+		used
+	}
+}
@@ -0,0 +1,118 @@
+//! Reporting of misbehaving counterparties.
+//!
+//! Evidence is submitted with `MsgSubmitMisbehaviour` and verified by the light client of the
+//! misbehaving chain, which freezes the client on valid evidence. For Tendermint, that is either
+//! two conflicting headers signed at the same height (duplicate vote) or two headers whose times
+//! are not monotonic with their heights (time violation). The account which submitted evidence
+//! freezing a client is handed over to `Config::OnMisbehaviour`, e.g. to reward it with
+//! `RewardFromPot`.
+use crate::{status::ClientStatus, Config, Event, Pallet};
+use core::marker::PhantomData;
+use frame_support::traits::{Currency, ExistenceRequirement, Get};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::core::ics24_host::identifier::ClientId;
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::{
+		core::client::v1::MsgSubmitMisbehaviour as RawMsgSubmitMisbehaviour,
+		lightclients::tendermint::v1::{Header as RawTmHeader, Misbehaviour as RawTmMisbehaviour},
+	},
+};
+use prost::Message;
+use sp_std::{str::FromStr, vec::Vec};
+
+pub const MSG_SUBMIT_MISBEHAVIOUR_TYPE_URL: &str = "/ibc.core.client.v1.MsgSubmitMisbehaviour";
+pub const TENDERMINT_MISBEHAVIOUR_TYPE_URL: &str = "/ibc.lightclients.tendermint.v1.Misbehaviour";
+
+/// Handler of the accounts whose evidence froze a client.
+pub trait OnMisbehaviour<AccountId> {
+	/// `reporter` submitted the evidence which froze `client_id`.
+	fn on_misbehaviour(client_id: &ClientId, reporter: &AccountId);
+}
+
+impl<AccountId> OnMisbehaviour<AccountId> for () {
+	fn on_misbehaviour(_client_id: &ClientId, _reporter: &AccountId) {}
+}
+
+/// Reward the reporter with `Reward` paid from the `Pot` account, as long as the pot can afford
+/// it.
+pub struct RewardFromPot<C, Pot, Reward>(PhantomData<(C, Pot, Reward)>);
+
+impl<AccountId, C, Pot, Reward> OnMisbehaviour<AccountId> for RewardFromPot<C, Pot, Reward>
+where
+	C: Currency<AccountId>,
+	Pot: Get<AccountId>,
+	Reward: Get<C::Balance>,
+{
+	fn on_misbehaviour(client_id: &ClientId, reporter: &AccountId) {
+		if let Err(e) =
+			C::transfer(&Pot::get(), reporter, Reward::get(), ExistenceRequirement::KeepAlive)
+		{
+			log::warn!(
+				target: "pallet_ibc",
+				"[misbehaviour]: no reward paid for freezing client {}: {:?}",
+				client_id,
+				e
+			);
+		}
+	}
+}
+
+/// Ids of the clients targeted by the `MsgSubmitMisbehaviour` among `messages`.
+pub fn misbehaving_client_ids(messages: &[Any]) -> Vec<ClientId> {
+	messages
+		.iter()
+		.filter(|msg| msg.type_url == MSG_SUBMIT_MISBEHAVIOUR_TYPE_URL)
+		.filter_map(|msg| RawMsgSubmitMisbehaviour::decode(msg.value.as_slice()).ok())
+		.filter_map(|msg| ClientId::from_str(&msg.client_id).ok())
+		.collect()
+}
+
+/// Number of commit signatures carried by Tendermint misbehaviour evidence, which drives the
+/// cost of verifying both headers.
+pub fn tendermint_signatures(misbehaviour: &Any) -> Option<u64> {
+	if misbehaviour.type_url != TENDERMINT_MISBEHAVIOUR_TYPE_URL {
+		return None;
+	}
+	let misbehaviour = RawTmMisbehaviour::decode(misbehaviour.value.as_slice()).ok()?;
+	let signatures = |header: &Option<RawTmHeader>| {
+		header
+			.as_ref()
+			.and_then(|header| header.signed_header.as_ref())
+			.and_then(|signed_header| signed_header.commit.as_ref())
+			.map_or(0, |commit| commit.signatures.len() as u64)
+	};
+	Some(signatures(&misbehaviour.header_1) + signatures(&misbehaviour.header_2))
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// The clients targeted by misbehaviour evidence in `messages` which are not frozen yet.
+	pub(crate) fn unfrozen_misbehaving_clients(messages: &[Any]) -> Vec<ClientId> {
+		let mut client_ids = Vec::new();
+		for client_id in misbehaving_client_ids(messages) {
+			if !client_ids.contains(&client_id) &&
+				Self::client_status(&client_id) != ClientStatus::Frozen
+			{
+				client_ids.push(client_id);
+			}
+		}
+		client_ids
+	}
+
+	/// Hand `reporter` over to `Config::OnMisbehaviour` for each of `client_ids` it froze.
+	pub(crate) fn report_misbehaviour(reporter: &T::AccountId, client_ids: Vec<ClientId>) {
+		for client_id in client_ids {
+			if Self::client_status(&client_id) != ClientStatus::Frozen {
+				continue;
+			}
+			T::OnMisbehaviour::on_misbehaviour(&client_id, reporter);
+			Self::deposit_event(Event::MisbehaviourReported {
+				client_id,
+				reporter: reporter.clone(),
+			});
+		}
+	}
+}
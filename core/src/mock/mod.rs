@@ -4,7 +4,8 @@ use crate as pallet_ibc;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstU128, ConstU16, ConstU32, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo,
+		ConstU128, ConstU16, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness,
+		StorageInfo,
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
@@ -13,6 +14,7 @@ pub use frame_support::{
 	StorageValue,
 };
 use frame_system as system;
+use ibc::core::ics04_channel::{channel::Order, error::ChannelError, Version};
 use pallet_ibc_utils::module::DefaultRouter;
 use sp_runtime::{
 	generic,
@@ -144,6 +146,72 @@ impl pallet_ibc_utils::module::AddModule for Test {
 	}
 }
 
+/// Records the client and reporter of the last misbehaviour for the tests.
+pub struct RecordMisbehaviour;
+impl misbehaviour::OnMisbehaviour<AccountId> for RecordMisbehaviour {
+	fn on_misbehaviour(client_id: &ClientId, reporter: &AccountId) {
+		sp_io::storage::set(b"misbehaviour", &(client_id, reporter).encode());
+	}
+}
+
+/// Accepts every channel upgrade except to the version `unsupported`. Tries are recorded under
+/// `upgrade_try`, and a try of `ics20-3` answers `ics20-2`.
+pub struct AcceptUpgrades;
+impl AcceptUpgrades {
+	fn check(version: &Version) -> Result<Version, ChannelError> {
+		if *version == Version::new("unsupported".to_string()) {
+			return Err(ChannelError::AppModule { description: "unsupported version".into() });
+		}
+		Ok(version.clone())
+	}
+}
+impl ChannelUpgradeModule for AcceptUpgrades {
+	fn handles(_port_id: &PortId) -> bool {
+		true
+	}
+
+	fn on_chan_upgrade_init(
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		version: &Version,
+	) -> Result<Version, ChannelError> {
+		Self::check(version)
+	}
+
+	fn on_chan_upgrade_try(
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		counterparty_version: &Version,
+	) -> Result<Version, ChannelError> {
+		sp_io::storage::set(b"upgrade_try", &[]);
+		if *counterparty_version == Version::new("ics20-3".to_string()) {
+			return Ok(Version::new("ics20-2".to_string()));
+		}
+		Self::check(counterparty_version)
+	}
+
+	fn on_chan_upgrade_ack(
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), ChannelError> {
+		Self::check(counterparty_version).map(|_| ())
+	}
+
+	fn on_chan_upgrade_open(
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_version: &Version,
+	) {
+	}
+}
+
 impl pallet::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type TimeProvider = pallet_timestamp::Pallet<Test>;
@@ -151,6 +219,12 @@ impl pallet::Config for Test {
 	const IBC_COMMITMENT_PREFIX: &'static [u8] = b"Ibc";
 	type ChainVersion = ChainVersion;
 	type IbcModule = DefaultRouter;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type OnMisbehaviour = RecordMisbehaviour;
+	type UpgradeModule = AcceptUpgrades;
+	type MaxPrunedPerBlock = ConstU32<10>;
+	type WasmClientGasLimit = ConstU64<10_000_000>;
+	type MaxWasmCodeSize = ConstU32<{ 3 * 1024 * 1024 }>;
 	type WeightInfo = ();
 }
 
@@ -6,17 +6,27 @@ use ibc::{
 	core::{
 		ics05_port::{context::PortReader, error::PortError},
 		ics24_host::identifier::PortId,
-		ics26_routing::context::ModuleId,
+		ics26_routing::context::{ModuleId, Router},
 	},
 };
 use sp_std::str::FromStr;
 
 impl<T: Config> PortReader for Context<T> {
+	/// The transfer port is bound to the transfer module, any other port to the module routed
+	/// under the same id, e.g. `icqhost`, or under the part of the port before the first `.`,
+	/// e.g. `contracts` for `contracts.my-port`.
 	fn lookup_module_by_port(&self, port_id: &PortId) -> Result<ModuleId, PortError> {
+		let routed = |module_id: &str| {
+			ModuleId::from_str(module_id)
+				.ok()
+				.filter(|module_id| self.router.has_route(module_id))
+		};
 		match port_id.as_str() {
 			TRANSFER_PORT_ID => Ok(ModuleId::from_str(TRANSFER_MODULE_ID)
 				.map_err(|_| PortError::ImplementationSpecific)?),
-			_ => Err(PortError::ImplementationSpecific),
+			port => routed(port)
+				.or_else(|| port.split_once('.').and_then(|(prefix, _)| routed(prefix)))
+				.ok_or(PortError::ImplementationSpecific),
 		}
 	}
 }
@@ -0,0 +1,276 @@
+//! Pruning of packet receipts and acknowledgements.
+//!
+//! Receipts and acknowledgement commitments are only needed while the counterparty may still
+//! relay or time out the matching packets, but ibc-rs never deletes them. `AdminOrigin` sets a
+//! per channel watermark below which the counterparty holds no packet commitments anymore, and
+//! the receipts and acknowledgements below it are removed in `on_idle`, at most
+//! `MaxPrunedPerBlock` sequences per block. Pruning above that point would let the counterparty
+//! prove the absence of a receipt for a packet this chain already received.
+//!
+//! Closed channels can be pruned up to the highest sequence they received, the first
+//! `MaxPrunedPerBlock` sequences by `prune_closed_channel` itself. Their channel end and
+//! sequences are kept so that the counterparty can still prove them in timeouts on close.
+//!
+//! `PacketStateUse` counts the receipts and acknowledgements of each channel. Those stored before
+//! it existed are counted over several blocks by `migrations::v3::MigrateToV3`, the counts are
+//! incomplete until then.
+use crate::{
+	channel::upgrade::ChannelUpgradeErrorPath, Acknowledgements, ChannelUpgradeSequences, Channels,
+	Config, Error, Event, InFlightPackets, OrderedAllowTimeoutChannels, PacketReceipt,
+	PacketStateMigrationCursor, PacketStateUse, Pallet, PruningCursors, PruningWatermarks,
+	UpgradeErrorReceipts,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{ensure, traits::Get, weights::Weight};
+use ibc::core::{
+	ics04_channel::{channel::State, packet::Sequence},
+	ics24_host::{
+		identifier::{ChannelId, PortId},
+		path::{AcksPath, ChannelEndsPath, ReceiptsPath},
+	},
+};
+use scale_info::TypeInfo;
+use sp_std::vec::Vec;
+
+/// Computation weight of pruning a sequence, on top of its storage accesses.
+pub const PRUNE_SEQUENCE_WEIGHT: u64 = 5_000_000;
+
+/// Weight of pruning the receipt and acknowledgement of a sequence.
+fn prune_sequence_weight<T: Config>() -> Weight {
+	Weight::from_parts(PRUNE_SEQUENCE_WEIGHT, 0)
+		.saturating_add(T::DbWeight::get().reads_writes(6, 4))
+}
+
+/// Weight of `prune_closed_channel`, which prunes up to `MaxPrunedPerBlock` sequences itself.
+pub(crate) fn prune_closed_channel_weight<T: Config>() -> Weight {
+	T::DbWeight::get().reads_writes(5, 8).saturating_add(
+		prune_sequence_weight::<T>().saturating_mul(T::MaxPrunedPerBlock::get().into()),
+	)
+}
+
+/// Receipts and acknowledgements stored for a channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo)]
+pub struct PacketState {
+	pub receipts: u64,
+	pub acknowledgements: u64,
+	/// Highest sequence with a receipt or acknowledgement stored
+	pub highest_sequence: u64,
+}
+
+/// Progress of `migrations::v3::MigrateToV3`, counting the receipts and acknowledgements stored
+/// before `PacketStateUse` existed in the order of their storage keys.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum PacketStateMigration {
+	/// Counting the receipts stored after the raw storage key, from the first one if `None`
+	Receipts(Option<Vec<u8>>),
+	/// Counting the acknowledgements stored after the raw storage key, from the first one if
+	/// `None`
+	Acknowledgements(Option<Vec<u8>>),
+}
+
+/// Storage used by the packet state of a channel, reported by the runtime API.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct ChannelStorageUse {
+	pub packet_commitments: u64,
+	pub packet_receipts: u64,
+	pub acknowledgements: u64,
+	/// Sequences below it were pruned
+	pub pruned_below: u64,
+	/// Sequences below it are waiting to be pruned
+	pub pruning_watermark: Option<u64>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether the receipt or acknowledgement of `sequence` on the channel is yet to be reached by
+	/// `migrations::v3::MigrateToV3`, which counts it then if it is still stored.
+	fn counted_by_migration(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		receipt: bool,
+	) -> bool {
+		let (port_id, channel_id) = (port_id.clone(), channel_id.clone());
+		match <PacketStateMigrationCursor<T>>::get() {
+			None => false,
+			Some(PacketStateMigration::Receipts(_)) if !receipt => true,
+			Some(PacketStateMigration::Receipts(last)) => last.map_or(true, |last| {
+				<PacketReceipt<T>>::hashed_key_for(ReceiptsPath { port_id, channel_id, sequence }) >
+					last
+			}),
+			Some(PacketStateMigration::Acknowledgements(_)) if receipt => false,
+			Some(PacketStateMigration::Acknowledgements(last)) => last.map_or(true, |last| {
+				<Acknowledgements<T>>::hashed_key_for(AcksPath { port_id, channel_id, sequence }) >
+					last
+			}),
+		}
+	}
+
+	/// Record a receipt or acknowledgement stored for `sequence` on the channel.
+	pub(crate) fn note_packet_state_stored(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		receipt: bool,
+	) {
+		if !Self::counted_by_migration(port_id, channel_id, sequence, receipt) {
+			Self::count_packet_state(port_id, channel_id, sequence, receipt);
+		}
+	}
+
+	/// Count a receipt or acknowledgement stored for `sequence` on the channel.
+	pub(crate) fn count_packet_state(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		receipt: bool,
+	) {
+		<PacketStateUse<T>>::mutate(
+			ChannelEndsPath(port_id.clone(), channel_id.clone()),
+			|state| {
+				if receipt {
+					state.receipts += 1;
+				} else {
+					state.acknowledgements += 1;
+				}
+				state.highest_sequence = state.highest_sequence.max(u64::from(sequence));
+			},
+		);
+	}
+
+	/// Record a receipt or acknowledgement removed for `sequence` from the channel.
+	pub(crate) fn note_packet_state_removed(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		receipt: bool,
+	) {
+		if Self::counted_by_migration(port_id, channel_id, sequence, receipt) {
+			return
+		}
+		<PacketStateUse<T>>::mutate(
+			ChannelEndsPath(port_id.clone(), channel_id.clone()),
+			|state| {
+				if receipt {
+					state.receipts = state.receipts.saturating_sub(1);
+				} else {
+					state.acknowledgements = state.acknowledgements.saturating_sub(1);
+				}
+			},
+		);
+	}
+
+	/// Storage used by the packet state of the channel.
+	pub fn channel_storage_use(port_id: PortId, channel_id: ChannelId) -> ChannelStorageUse {
+		let path = ChannelEndsPath(port_id, channel_id);
+		let state = <PacketStateUse<T>>::get(&path);
+		ChannelStorageUse {
+			packet_commitments: <InFlightPackets<T>>::get(&path),
+			packet_receipts: state.receipts,
+			acknowledgements: state.acknowledgements,
+			pruned_below: <PruningCursors<T>>::get(&path).max(1),
+			pruning_watermark: <PruningWatermarks<T>>::get(&path),
+		}
+	}
+
+	/// Prune the receipts and acknowledgements of the channel below `watermark`.
+	pub fn do_set_pruning_watermark(
+		port_id: PortId,
+		channel_id: ChannelId,
+		watermark: u64,
+	) -> Result<(), Error<T>> {
+		let path = ChannelEndsPath(port_id, channel_id);
+		ensure!(<Channels<T>>::contains_key(&path), Error::<T>::ChannelNotFound);
+		ensure!(watermark > <PruningCursors<T>>::get(&path).max(1), Error::<T>::InvalidWatermark);
+		<PruningWatermarks<T>>::insert(path, watermark);
+		Ok(())
+	}
+
+	/// Prune all receipts and acknowledgements of the closed channel and drop its upgrade state,
+	/// returning the pruning watermark.
+	pub fn do_prune_closed_channel(
+		port_id: PortId,
+		channel_id: ChannelId,
+	) -> Result<u64, Error<T>> {
+		let path = ChannelEndsPath(port_id, channel_id);
+		let channel_end = <Channels<T>>::get(&path).ok_or(Error::<T>::ChannelNotFound)?;
+		ensure!(channel_end.state_matches(&State::Closed), Error::<T>::ChannelNotClosed);
+
+		Self::remove_upgrade(&path);
+		<UpgradeErrorReceipts<T>>::remove(ChannelUpgradeErrorPath::from(&path));
+		<ChannelUpgradeSequences<T>>::remove(&path);
+		<OrderedAllowTimeoutChannels<T>>::remove(&path);
+
+		let watermark = <PacketStateUse<T>>::get(&path).highest_sequence + 1;
+		if watermark > <PruningCursors<T>>::get(&path).max(1) {
+			<PruningWatermarks<T>>::insert(&path, watermark);
+		}
+		Ok(watermark)
+	}
+
+	/// Prune sequences below the watermarks within `remaining_weight`, returning the weight used.
+	pub(crate) fn prune_packet_state(remaining_weight: Weight) -> Weight {
+		let per_sequence = prune_sequence_weight::<T>();
+		let used = T::DbWeight::get().reads_writes(2, 2);
+		if !remaining_weight.all_gte(used.saturating_add(per_sequence)) {
+			return Weight::zero();
+		}
+
+		let (path, watermark) = match <PruningWatermarks<T>>::iter().next() {
+			Some(next) => next,
+			None => return used,
+		};
+		let affordable = remaining_weight.saturating_sub(used).ref_time() / per_sequence.ref_time();
+		let budget = T::MaxPrunedPerBlock::get().min(affordable.try_into().unwrap_or(u32::MAX));
+		let pruned = Self::prune_channel(path, watermark, budget);
+		used.saturating_add(per_sequence.saturating_mul(pruned.into()))
+	}
+
+	/// Prune up to `MaxPrunedPerBlock` sequences of the channel below its watermark right away.
+	pub(crate) fn prune_scheduled_sequences(port_id: PortId, channel_id: ChannelId) {
+		let path = ChannelEndsPath(port_id, channel_id);
+		if let Some(watermark) = <PruningWatermarks<T>>::get(&path) {
+			Self::prune_channel(path, watermark, T::MaxPrunedPerBlock::get());
+		}
+	}
+
+	/// Prune up to `budget` sequences of the channel below `watermark`, returning the number of
+	/// sequences pruned.
+	fn prune_channel(path: ChannelEndsPath, watermark: u64, budget: u32) -> u32 {
+		let cursor = <PruningCursors<T>>::get(&path).max(1);
+		let pruned = watermark.saturating_sub(cursor).min(budget.into());
+		for sequence in cursor..cursor + pruned {
+			Self::prune_sequence(&path, Sequence::from(sequence));
+		}
+
+		let cursor = cursor + pruned;
+		<PruningCursors<T>>::insert(&path, cursor);
+		if cursor >= watermark {
+			<PruningWatermarks<T>>::remove(&path);
+			let ChannelEndsPath(port_id, channel_id) = path;
+			Self::deposit_event(Event::PacketStatePruned { port_id, channel_id, below: watermark });
+		}
+		pruned as u32
+	}
+
+	fn prune_sequence(path: &ChannelEndsPath, sequence: Sequence) {
+		let (port_id, channel_id) = (path.0.clone(), path.1.clone());
+		if <PacketReceipt<T>>::take(ReceiptsPath {
+			port_id: port_id.clone(),
+			channel_id: channel_id.clone(),
+			sequence,
+		})
+		.is_some()
+		{
+			Self::note_packet_state_removed(&port_id, &channel_id, sequence, true);
+		}
+		if <Acknowledgements<T>>::take(AcksPath {
+			port_id: port_id.clone(),
+			channel_id: channel_id.clone(),
+			sequence,
+		})
+		.is_some()
+		{
+			Self::note_packet_state_removed(&port_id, &channel_id, sequence, false);
+		}
+	}
+}
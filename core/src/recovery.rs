@@ -0,0 +1,177 @@
+//! Governance recovery of a frozen or expired client.
+//!
+//! Following ibc-go's `MsgRecoverClient`, the subject client takes over the latest consensus
+//! state of a healthy substitute client of the same type. Both clients must share the same
+//! parameters apart from the chain id, the trusting period, the latest height and the frozen
+//! height, which are copied over from the substitute.
+use crate::{
+	ClientProcessedHeights, ClientProcessedTimes, ClientStates, Clients, Config, ConsensusStates,
+	Error, Pallet, TENDERMINT_CLIENT_TYPE,
+};
+use frame_support::{ensure, traits::UnixTime};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::{
+	core::ics24_host::{
+		identifier::ClientId,
+		path::{ClientConsensusStatePath, ClientStatePath, ClientTypePath},
+	},
+	Height,
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::{
+		core::client::v1::Height as RawHeight,
+		lightclients::tendermint::v1::{
+			ClientState as RawTmClientState, ConsensusState as RawTmConsensusState,
+		},
+	},
+};
+use prost::Message;
+use sp_std::vec::Vec;
+
+pub const TENDERMINT_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.tendermint.v1.ClientState";
+
+fn consensus_state_path(client_id: &ClientId, height: &RawHeight) -> ClientConsensusStatePath {
+	ClientConsensusStatePath {
+		client_id: client_id.clone(),
+		epoch: height.revision_number,
+		height: height.revision_height,
+	}
+}
+
+fn latest_height(client_state: &RawTmClientState) -> RawHeight {
+	client_state.latest_height.clone().unwrap_or_default()
+}
+
+pub(crate) fn is_frozen(client_state: &RawTmClientState) -> bool {
+	client_state
+		.frozen_height
+		.as_ref()
+		.map_or(false, |height| height.revision_number != 0 || height.revision_height != 0)
+}
+
+/// The parameters that must be identical between a subject and its substitute.
+fn recovery_params(client_state: &RawTmClientState) -> RawTmClientState {
+	RawTmClientState {
+		chain_id: Default::default(),
+		trusting_period: None,
+		latest_height: None,
+		frozen_height: None,
+		..client_state.clone()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn tendermint_client_state(
+		client_id: &ClientId,
+	) -> Result<RawTmClientState, Error<T>> {
+		let client_type = <Clients<T>>::get(ClientTypePath(client_id.clone()))
+			.ok_or(Error::<T>::ClientNotFound)?;
+		ensure!(client_type.as_str() == TENDERMINT_CLIENT_TYPE, Error::<T>::UnknownClientType);
+
+		let data = <ClientStates<T>>::get(ClientStatePath(client_id.clone()))
+			.ok_or(Error::<T>::ClientNotFound)?;
+		let any = Any::decode(data.as_slice()).map_err(|_| Error::<T>::InvalidClientState)?;
+		ensure!(any.type_url == TENDERMINT_CLIENT_STATE_TYPE_URL, Error::<T>::InvalidClientState);
+		RawTmClientState::decode(any.value.as_slice()).map_err(|_| Error::<T>::InvalidClientState)
+	}
+
+	/// Returns true if the trusting period of the client elapsed since its latest consensus
+	/// state. A client without a readable latest consensus state is considered expired.
+	pub(crate) fn is_expired(client_id: &ClientId, client_state: &RawTmClientState) -> bool {
+		let consensus_state = <ConsensusStates<T>>::get(consensus_state_path(
+			client_id,
+			&latest_height(client_state),
+		))
+		.and_then(|data| Any::decode(data.as_slice()).ok())
+		.and_then(|any| RawTmConsensusState::decode(any.value.as_slice()).ok());
+		let (timestamp, trusting_period) =
+			match (consensus_state.and_then(|cs| cs.timestamp), &client_state.trusting_period) {
+				(Some(timestamp), Some(trusting_period)) => (timestamp, trusting_period),
+				_ => return true,
+			};
+
+		let to_nanos = |seconds: i64, nanos: i32| {
+			(seconds.max(0) as u128) * 1_000_000_000 + (nanos.max(0) as u128)
+		};
+		let now = T::TimeProvider::now().as_nanos();
+		let expiry = to_nanos(timestamp.seconds, timestamp.nanos) +
+			to_nanos(trusting_period.seconds, trusting_period.nanos);
+		now >= expiry
+	}
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Substitute the frozen or expired `subject_client_id` with the healthy
+	/// `substitute_client_id`. The status of the subject is refreshed afterwards.
+	pub fn do_recover_client(
+		subject_client_id: &ClientId,
+		substitute_client_id: &ClientId,
+	) -> Result<(), Error<T>> {
+		ensure!(subject_client_id != substitute_client_id, Error::<T>::SameSubjectAndSubstitute);
+
+		let subject = Self::tendermint_client_state(subject_client_id)?;
+		let substitute = Self::tendermint_client_state(substitute_client_id)?;
+
+		ensure!(
+			is_frozen(&subject) || Self::is_expired(subject_client_id, &subject),
+			Error::<T>::SubjectClientActive
+		);
+		ensure!(
+			!is_frozen(&substitute) && !Self::is_expired(substitute_client_id, &substitute),
+			Error::<T>::SubstituteClientNotActive
+		);
+
+		let substitute_height = latest_height(&substitute);
+		let subject_height = latest_height(&subject);
+		ensure!(
+			(substitute_height.revision_number, substitute_height.revision_height) >
+				(subject_height.revision_number, subject_height.revision_height),
+			Error::<T>::SubstituteHeightTooLow
+		);
+		ensure!(
+			recovery_params(&subject) == recovery_params(&substitute),
+			Error::<T>::ClientParamsMismatch
+		);
+
+		let consensus_state = <ConsensusStates<T>>::get(consensus_state_path(
+			substitute_client_id,
+			&substitute_height,
+		))
+		.ok_or(Error::<T>::ConsensusStateNotFound)?;
+
+		let recovered = RawTmClientState {
+			chain_id: substitute.chain_id.clone(),
+			trusting_period: substitute.trusting_period.clone(),
+			latest_height: Some(substitute_height.clone()),
+			frozen_height: Some(RawHeight::default()),
+			..subject
+		};
+		let data: Vec<u8> = Any {
+			type_url: TENDERMINT_CLIENT_STATE_TYPE_URL.into(),
+			value: recovered.encode_to_vec(),
+		}
+		.encode_to_vec();
+		<ClientStates<T>>::insert(ClientStatePath(subject_client_id.clone()), data);
+		<ConsensusStates<T>>::insert(
+			consensus_state_path(subject_client_id, &substitute_height),
+			consensus_state,
+		);
+
+		let height =
+			Height::new(substitute_height.revision_number, substitute_height.revision_height)
+				.map_err(|_| Error::<T>::InvalidHeight)?;
+		if let Some(time) = <ClientProcessedTimes<T>>::get(substitute_client_id, height) {
+			<ClientProcessedTimes<T>>::insert(subject_client_id, height, time);
+		}
+		if let Some(host_height) = <ClientProcessedHeights<T>>::get(substitute_client_id, height) {
+			<ClientProcessedHeights<T>>::insert(subject_client_id, height, host_height);
+		}
+		Self::refresh_client_status(subject_client_id);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,191 @@
+//! Detection of redundant packet messages, like ibc-go's redundant relay decorator.
+//!
+//! Several relayers usually race to relay the same packet. Only the first `MsgRecvPacket`,
+//! `MsgAcknowledgement` or `MsgTimeout` to land does anything, the later ones fail in the
+//! handlers after verifying their proofs. They are detected from the channel state alone:
+//!
+//! - a packet is already received when its sequence is below `NextSequenceRecv` of an ordered
+//!   channel, or when a receipt is stored for it on an unordered channel;
+//! - a packet is already acknowledged or timed out when its commitment is gone.
+//!
+//! `deliver` drops redundant messages before handling the others and does not charge their
+//! weight. `CheckRedundantRelay` rejects transactions made of redundant messages only from the
+//! transaction pool.
+use crate::{
+	channel::allow_timeout::{MSG_RECV_PACKET_TYPE_URL, MSG_TIMEOUT_TYPE_URL},
+	Call, Channels, Config, Event, NextSequenceRecv, PacketCommitment, PacketReceipt, Pallet,
+};
+use codec::{Decode, Encode};
+use core::{marker::PhantomData, str::FromStr};
+use frame_support::traits::IsSubType;
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::core::{
+	ics04_channel::{channel::Order, packet::Sequence},
+	ics24_host::{
+		identifier::{ChannelId, PortId},
+		path::{ChannelEndsPath, CommitmentsPath, ReceiptsPath, SeqRecvsPath},
+	},
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::channel::v1::{
+		MsgAcknowledgement as RawMsgAcknowledgement, MsgRecvPacket as RawMsgRecvPacket,
+		MsgTimeout as RawMsgTimeout, MsgTimeoutOnClose as RawMsgTimeoutOnClose,
+		Packet as RawPacket,
+	},
+};
+use prost::Message;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+use sp_std::vec::Vec;
+
+pub const MSG_ACKNOWLEDGEMENT_TYPE_URL: &str = "/ibc.core.channel.v1.MsgAcknowledgement";
+pub const MSG_TIMEOUT_ON_CLOSE_TYPE_URL: &str = "/ibc.core.channel.v1.MsgTimeoutOnClose";
+
+fn decode_packet<M: Message + Default>(
+	msg: &Any,
+	packet: impl FnOnce(M) -> Option<RawPacket>,
+) -> Option<RawPacket> {
+	M::decode(msg.value.as_slice()).ok().and_then(packet)
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Returns true if `msg` relays a packet already received, acknowledged or timed out.
+	pub fn is_redundant(msg: &Any) -> bool {
+		match msg.type_url.as_str() {
+			MSG_RECV_PACKET_TYPE_URL => decode_packet(msg, |raw: RawMsgRecvPacket| raw.packet)
+				.map_or(false, |packet| {
+					Self::is_received(
+						&packet.destination_port,
+						&packet.destination_channel,
+						packet.sequence,
+					)
+				}),
+			MSG_ACKNOWLEDGEMENT_TYPE_URL =>
+				decode_packet(msg, |raw: RawMsgAcknowledgement| raw.packet)
+					.map_or(false, |packet| Self::is_settled(&packet)),
+			MSG_TIMEOUT_TYPE_URL => decode_packet(msg, |raw: RawMsgTimeout| raw.packet)
+				.map_or(false, |packet| Self::is_settled(&packet)),
+			MSG_TIMEOUT_ON_CLOSE_TYPE_URL =>
+				decode_packet(msg, |raw: RawMsgTimeoutOnClose| raw.packet)
+					.map_or(false, |packet| Self::is_settled(&packet)),
+			_ => false,
+		}
+	}
+
+	/// Drop the redundant messages of `messages`, emitting `RedundantMessagesSkipped` if any.
+	pub(crate) fn skip_redundant_messages(messages: Vec<Any>) -> Vec<Any> {
+		let count = messages.len();
+		let messages: Vec<Any> =
+			messages.into_iter().filter(|msg| !Self::is_redundant(msg)).collect();
+		if messages.len() < count {
+			Self::deposit_event(Event::RedundantMessagesSkipped {
+				messages: (count - messages.len()) as u32,
+			});
+		}
+		messages
+	}
+
+	/// The channel identified by `port_id` and `channel_id` and its ordering, if it exists.
+	fn channel_ordering(port_id: &str, channel_id: &str) -> Option<(PortId, ChannelId, Order)> {
+		let port_id = PortId::from_str(port_id).ok()?;
+		let channel_id = ChannelId::from_str(channel_id).ok()?;
+		let channel_end = <Channels<T>>::get(ChannelEndsPath(port_id.clone(), channel_id.clone()))?;
+		Some((port_id, channel_id, *channel_end.ordering()))
+	}
+
+	fn is_received(port_id: &str, channel_id: &str, sequence: u64) -> bool {
+		match Self::channel_ordering(port_id, channel_id) {
+			Some((port_id, channel_id, Order::Ordered)) =>
+				<NextSequenceRecv<T>>::get(SeqRecvsPath(port_id, channel_id))
+					.map_or(false, |next| sequence < u64::from(next)),
+			Some((port_id, channel_id, Order::Unordered)) =>
+				<PacketReceipt<T>>::contains_key(ReceiptsPath {
+					port_id,
+					channel_id,
+					sequence: Sequence::from(sequence),
+				}),
+			_ => false,
+		}
+	}
+
+	/// Returns true if the commitment of a packet sent over an existing channel is gone.
+	fn is_settled(packet: &RawPacket) -> bool {
+		match Self::channel_ordering(&packet.source_port, &packet.source_channel) {
+			Some((port_id, channel_id, _)) =>
+				!<PacketCommitment<T>>::contains_key(CommitmentsPath {
+					port_id,
+					channel_id,
+					sequence: Sequence::from(packet.sequence),
+				}),
+			None => false,
+		}
+	}
+}
+
+/// Rejects `deliver` transactions made of redundant packet messages only from the transaction
+/// pool. Those included in a block anyway are handled as no-ops by `deliver`.
+#[derive(Encode, Decode, Clone, Debug, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckRedundantRelay<T: Config>(PhantomData<T>);
+
+impl<T: Config> CheckRedundantRelay<T> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config> Default for CheckRedundantRelay<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config> SignedExtension for CheckRedundantRelay<T>
+where
+	<T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckRedundantRelay";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if let Some(Call::deliver { messages }) = call.is_sub_type() {
+			if !messages.is_empty() && messages.iter().all(Pallet::<T>::is_redundant) {
+				return Err(InvalidTransaction::Stale.into());
+			}
+		}
+		Ok(ValidTransaction::default())
+	}
+
+	fn pre_dispatch(
+		self,
+		_who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+}
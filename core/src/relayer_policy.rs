@@ -0,0 +1,242 @@
+//! Optional policies on the relayers delivering messages.
+//!
+//! `AdminOrigin` can restrict a scope, a category of messages or the messages of a connection,
+//! to an allow-list of relayer accounts. A message is delivered only when the sender is allowed
+//! in every restricted scope it belongs to. Misbehaviour evidence is never restricted, anyone
+//! can freeze a misbehaving client.
+//!
+//! Client creation follows a separate policy: permissionless, rate limited per account over a
+//! window of blocks, or reserved to `AdminOrigin` through `create_client`. All policies are off
+//! by default.
+use crate::{
+	channel::{
+		allow_timeout::{
+			MSG_CHANNEL_OPEN_INIT_TYPE_URL, MSG_CHANNEL_OPEN_TRY_TYPE_URL,
+			MSG_RECV_PACKET_TYPE_URL, MSG_TIMEOUT_TYPE_URL,
+		},
+		upgrade::{
+			MsgChannelUpgradeAck, MsgChannelUpgradeCancel, MsgChannelUpgradeOpen,
+			MsgChannelUpgradeTry, MSG_CHANNEL_UPGRADE_ACK_TYPE_URL,
+			MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL, MSG_CHANNEL_UPGRADE_CONFIRM_TYPE_URL,
+			MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL, MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL,
+			MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+		},
+	},
+	misbehaviour::MSG_SUBMIT_MISBEHAVIOUR_TYPE_URL,
+	redundant_relay::{MSG_ACKNOWLEDGEMENT_TYPE_URL, MSG_TIMEOUT_ON_CLOSE_TYPE_URL},
+	AllowedRelayers, Channels, ClientCreation, ClientCreationEpoch, ClientCreationWindows, Config,
+	Error, Pallet, RestrictedScopes,
+};
+use codec::{Decode, Encode};
+use core::str::FromStr;
+use frame_support::ensure;
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::core::ics24_host::{
+	identifier::{ChannelId, ConnectionId, PortId},
+	path::ChannelEndsPath,
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::{
+		channel::v1::{
+			MsgAcknowledgement as RawMsgAcknowledgement,
+			MsgChannelCloseConfirm as RawMsgChannelCloseConfirm,
+			MsgChannelCloseInit as RawMsgChannelCloseInit,
+			MsgChannelOpenAck as RawMsgChannelOpenAck,
+			MsgChannelOpenConfirm as RawMsgChannelOpenConfirm,
+			MsgChannelOpenInit as RawMsgChannelOpenInit, MsgChannelOpenTry as RawMsgChannelOpenTry,
+			MsgRecvPacket as RawMsgRecvPacket, MsgTimeout as RawMsgTimeout,
+			MsgTimeoutOnClose as RawMsgTimeoutOnClose,
+		},
+		connection::v1::{
+			MsgConnectionOpenAck as RawMsgConnectionOpenAck,
+			MsgConnectionOpenConfirm as RawMsgConnectionOpenConfirm,
+		},
+	},
+};
+use prost::Message;
+use scale_info::TypeInfo;
+use sp_runtime::traits::Saturating;
+use sp_std::{string::String, vec::Vec};
+
+pub const MSG_CREATE_CLIENT_TYPE_URL: &str = "/ibc.core.client.v1.MsgCreateClient";
+const CLIENT_TYPE_URL_PREFIX: &str = "/ibc.core.client.";
+const CONNECTION_TYPE_URL_PREFIX: &str = "/ibc.core.connection.";
+const CHANNEL_TYPE_URL_PREFIX: &str = "/ibc.core.channel.";
+const MSG_CHANNEL_OPEN_ACK_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenAck";
+const MSG_CHANNEL_OPEN_CONFIRM_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelOpenConfirm";
+const MSG_CHANNEL_CLOSE_INIT_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelCloseInit";
+const MSG_CHANNEL_CLOSE_CONFIRM_TYPE_URL: &str = "/ibc.core.channel.v1.MsgChannelCloseConfirm";
+const MSG_CONNECTION_OPEN_ACK_TYPE_URL: &str = "/ibc.core.connection.v1.MsgConnectionOpenAck";
+const MSG_CONNECTION_OPEN_CONFIRM_TYPE_URL: &str =
+	"/ibc.core.connection.v1.MsgConnectionOpenConfirm";
+
+/// Category of the messages delivered by relayers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum MessageCategory {
+	/// Client creations, updates and upgrades
+	Client,
+	/// Connection handshakes
+	Connection,
+	/// Channel handshakes, closings and upgrades
+	Channel,
+	/// Packets received, acknowledged or timed out
+	Packet,
+}
+
+/// Scope of a relayer allow-list.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum RelayerScope {
+	/// All the messages of a category
+	Category(MessageCategory),
+	/// The messages of an existing connection and its channels
+	Connection(ConnectionId),
+}
+
+/// Who may create clients through `deliver`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum ClientCreationPolicy {
+	/// Any account
+	#[default]
+	Permissionless,
+	/// Any account, at most `max_clients` clients per `period` blocks
+	RateLimited { max_clients: u32, period: u32 },
+	/// Only `AdminOrigin`, through `create_client`
+	AdminOnly,
+}
+
+fn decode<M: Message + Default>(msg: &Any) -> Option<M> {
+	M::decode(msg.value.as_slice()).ok()
+}
+
+/// The category of `msg`, `None` for misbehaviour evidence and messages which are not ICS core
+/// messages.
+pub fn message_category(msg: &Any) -> Option<MessageCategory> {
+	let type_url = msg.type_url.as_str();
+	match type_url {
+		MSG_SUBMIT_MISBEHAVIOUR_TYPE_URL => None,
+		MSG_RECV_PACKET_TYPE_URL |
+		MSG_ACKNOWLEDGEMENT_TYPE_URL |
+		MSG_TIMEOUT_TYPE_URL |
+		MSG_TIMEOUT_ON_CLOSE_TYPE_URL => Some(MessageCategory::Packet),
+		_ if type_url.starts_with(CLIENT_TYPE_URL_PREFIX) => Some(MessageCategory::Client),
+		_ if type_url.starts_with(CONNECTION_TYPE_URL_PREFIX) => Some(MessageCategory::Connection),
+		_ if type_url.starts_with(CHANNEL_TYPE_URL_PREFIX) => Some(MessageCategory::Channel),
+		_ => None,
+	}
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// The connection of the channel identified by `port_id` and `channel_id`.
+	fn channel_connection(port_id: &str, channel_id: &str) -> Option<ConnectionId> {
+		let path =
+			ChannelEndsPath(PortId::from_str(port_id).ok()?, ChannelId::from_str(channel_id).ok()?);
+		<Channels<T>>::get(path)?.connection_hops().first().cloned()
+	}
+
+	/// The connection `msg` is delivered over, `None` for client messages and the handshake
+	/// messages opening a connection.
+	pub fn message_connection(msg: &Any) -> Option<ConnectionId> {
+		let first_hop =
+			|hops: Vec<String>| hops.first().and_then(|hop| ConnectionId::from_str(hop).ok());
+		match msg.type_url.as_str() {
+			MSG_CONNECTION_OPEN_ACK_TYPE_URL => decode::<RawMsgConnectionOpenAck>(msg)
+				.and_then(|raw| ConnectionId::from_str(&raw.connection_id).ok()),
+			MSG_CONNECTION_OPEN_CONFIRM_TYPE_URL => decode::<RawMsgConnectionOpenConfirm>(msg)
+				.and_then(|raw| ConnectionId::from_str(&raw.connection_id).ok()),
+			MSG_CHANNEL_OPEN_INIT_TYPE_URL => decode::<RawMsgChannelOpenInit>(msg)
+				.and_then(|raw| raw.channel)
+				.and_then(|channel| first_hop(channel.connection_hops)),
+			MSG_CHANNEL_OPEN_TRY_TYPE_URL => decode::<RawMsgChannelOpenTry>(msg)
+				.and_then(|raw| raw.channel)
+				.and_then(|channel| first_hop(channel.connection_hops)),
+			MSG_CHANNEL_OPEN_ACK_TYPE_URL => decode::<RawMsgChannelOpenAck>(msg)
+				.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_OPEN_CONFIRM_TYPE_URL => decode::<RawMsgChannelOpenConfirm>(msg)
+				.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_CLOSE_INIT_TYPE_URL => decode::<RawMsgChannelCloseInit>(msg)
+				.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_CLOSE_CONFIRM_TYPE_URL => decode::<RawMsgChannelCloseConfirm>(msg)
+				.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_UPGRADE_TRY_TYPE_URL => decode::<MsgChannelUpgradeTry>(msg)
+				.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_UPGRADE_ACK_TYPE_URL | MSG_CHANNEL_UPGRADE_CONFIRM_TYPE_URL =>
+				decode::<MsgChannelUpgradeAck>(msg)
+					.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL | MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL =>
+				decode::<MsgChannelUpgradeOpen>(msg)
+					.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL => decode::<MsgChannelUpgradeCancel>(msg)
+				.and_then(|raw| Self::channel_connection(&raw.port_id, &raw.channel_id)),
+			MSG_RECV_PACKET_TYPE_URL =>
+				decode::<RawMsgRecvPacket>(msg).and_then(|raw| raw.packet).and_then(|packet| {
+					Self::channel_connection(&packet.destination_port, &packet.destination_channel)
+				}),
+			MSG_ACKNOWLEDGEMENT_TYPE_URL => decode::<RawMsgAcknowledgement>(msg)
+				.and_then(|raw| raw.packet)
+				.and_then(|packet| {
+					Self::channel_connection(&packet.source_port, &packet.source_channel)
+				}),
+			MSG_TIMEOUT_TYPE_URL =>
+				decode::<RawMsgTimeout>(msg).and_then(|raw| raw.packet).and_then(|packet| {
+					Self::channel_connection(&packet.source_port, &packet.source_channel)
+				}),
+			MSG_TIMEOUT_ON_CLOSE_TYPE_URL => decode::<RawMsgTimeoutOnClose>(msg)
+				.and_then(|raw| raw.packet)
+				.and_then(|packet| {
+					Self::channel_connection(&packet.source_port, &packet.source_channel)
+				}),
+			_ => None,
+		}
+	}
+
+	fn ensure_allowed(relayer: &T::AccountId, scope: RelayerScope) -> Result<(), Error<T>> {
+		if <RestrictedScopes<T>>::contains_key(&scope) {
+			ensure!(
+				<AllowedRelayers<T>>::contains_key(scope, relayer),
+				Error::<T>::RelayerNotAllowed
+			);
+		}
+		Ok(())
+	}
+
+	/// Count a client created by `relayer` against the client creation policy.
+	fn note_client_creation(relayer: &T::AccountId) -> Result<(), Error<T>> {
+		match <ClientCreation<T>>::get() {
+			ClientCreationPolicy::Permissionless => Ok(()),
+			ClientCreationPolicy::AdminOnly => Err(Error::<T>::ClientCreationNotAllowed),
+			ClientCreationPolicy::RateLimited { max_clients, period } => {
+				let now = <frame_system::Pallet<T>>::block_number();
+				let epoch = <ClientCreationEpoch<T>>::get();
+				let (window_start, created) = <ClientCreationWindows<T>>::get(relayer)
+					.filter(|(window_epoch, window_start, _)| {
+						*window_epoch == epoch &&
+							u64::from(now.saturating_sub(*window_start)) < u64::from(period)
+					})
+					.map_or((now, 0), |(_, window_start, created)| (window_start, created));
+				ensure!(created < max_clients, Error::<T>::ClientCreationRateLimited);
+				<ClientCreationWindows<T>>::insert(relayer, (epoch, window_start, created + 1));
+				Ok(())
+			},
+		}
+	}
+
+	/// Ensure `relayer` may deliver `messages` under the relayer and client creation policies.
+	pub fn ensure_relayer_policy(relayer: &T::AccountId, messages: &[Any]) -> Result<(), Error<T>> {
+		for msg in messages {
+			if msg.type_url == MSG_CREATE_CLIENT_TYPE_URL {
+				Self::note_client_creation(relayer)?;
+			}
+			if let Some(category) = message_category(msg) {
+				Self::ensure_allowed(relayer, RelayerScope::Category(category))?;
+			}
+			if let Some(connection_id) = Self::message_connection(msg) {
+				Self::ensure_allowed(relayer, RelayerScope::Connection(connection_id))?;
+			}
+		}
+		Ok(())
+	}
+}
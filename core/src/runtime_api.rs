@@ -0,0 +1,34 @@
+//! Runtime API of the IBC pallet.
+//!
+//! A runtime implements it by forwarding to the matching `Pallet` functions, e.g.
+//! `IbcApi::client_status` to `pallet_ibc::Pallet::<Runtime>::client_status`.
+use crate::{pruning::ChannelStorageUse, simulation::MessageSimulation, status::ClientStatus};
+use codec::Codec;
+use ibc::core::ics24_host::identifier::{ChannelId, ClientId, PortId};
+use ibc_proto::google::protobuf::Any;
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Queries of the IBC state hosted by this chain.
+	#[api_version(3)]
+	pub trait IbcApi<AccountId>
+	where
+		AccountId: Codec,
+	{
+		/// Current status of a client, computed at the queried block.
+		fn client_status(client_id: ClientId) -> ClientStatus;
+
+		/// Storage used by the packet state of a channel and its pruning progress.
+		#[api_version(2)]
+		fn channel_storage_use(port_id: PortId, channel_id: ChannelId) -> ChannelStorageUse;
+
+		/// Dry-run of `relayer` delivering `messages` at the queried block, one outcome per
+		/// message admitted under the relayer policy.
+		#[api_version(3)]
+		fn simulate_deliver(
+			relayer: AccountId,
+			messages: Vec<Any>,
+		) -> Result<Vec<MessageSimulation>, DispatchError>;
+	}
+}
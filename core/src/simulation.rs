@@ -0,0 +1,138 @@
+//! Dry-run of message delivery.
+//!
+//! Relayers simulate a batch of messages before submitting it in `deliver`, like an `eth_call`.
+//! The batch is admitted like in `deliver`, under the relayer policy of the sender and without
+//! the packet messages already relayed, and then goes through the same handlers on a fresh
+//! `Context`. All of it runs inside a storage transaction that is always rolled back, so later
+//! messages of the batch observe the effects of earlier ones but nothing is persisted.
+//! Misbehaviour reporting of the sender is not simulated.
+use crate::{context::Context, errors::IbcError, weights, Config, Error, Pallet};
+use codec::{Decode, Encode};
+use frame_support::{
+	pallet_prelude::DispatchError,
+	storage::{with_transaction, TransactionOutcome},
+	weights::Weight,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::{
+	core::ics26_routing::{error::RouterError, handler::MsgReceipt},
+	events::IbcEvent,
+};
+use ibc_proto::google::protobuf::Any;
+use scale_info::TypeInfo;
+use sp_std::{vec, vec::Vec};
+
+/// Reason a single message could not be delivered.
+pub(crate) enum DeliveryError {
+	/// Rejected by the ibc-rs handlers
+	Router(RouterError),
+	/// Rejected by the channel upgrade handshake
+	ChannelUpgrade(DispatchError),
+	/// Rejected by the ORDERED_ALLOW_TIMEOUT packet handlers
+	AllowTimeout(DispatchError),
+}
+
+/// Error of a simulated message.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum SimulationError {
+	/// The ibc-rs handlers rejected the message
+	Ibc(IbcError),
+	/// A handler of the pallet rejected the message
+	Pallet(DispatchError),
+}
+
+/// Outcome of a simulated message, reported by the runtime API.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct MessageSimulation {
+	pub events: Vec<IbcEvent>,
+	pub logs: Vec<Vec<u8>>,
+	pub error: Option<SimulationError>,
+	/// Estimated weight of delivering the message
+	pub weight: Weight,
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Deliver a single message, routing channel upgrade and ORDERED_ALLOW_TIMEOUT packet
+	/// messages to the pallet handlers and everything else to ibc-rs.
+	pub(crate) fn deliver_message(
+		ctx: &mut Context<T>,
+		msg: Any,
+	) -> Result<MsgReceipt, DeliveryError> {
+		if let Some(result) = Self::deliver_channel_upgrade(&msg) {
+			return result
+				.map(|()| MsgReceipt { events: vec![], log: vec![] })
+				.map_err(DeliveryError::ChannelUpgrade);
+		}
+		if let Some(result) = Self::deliver_allow_timeout(ctx, &msg) {
+			return result
+				.map(|events| MsgReceipt { events, log: vec![] })
+				.map_err(DeliveryError::AllowTimeout);
+		}
+		let (msg, opened) = Self::allow_timeout_hook(msg);
+		let receipt =
+			ibc::core::ics26_routing::handler::deliver(ctx, msg).map_err(DeliveryError::Router)?;
+		Self::after_allow_timeout_hook(opened);
+		Ok(receipt)
+	}
+
+	/// Admit a batch of `relayer` for delivery: enforce the relayer and client creation
+	/// policies, then drop the packet messages already relayed.
+	pub(crate) fn admit_messages(
+		relayer: &T::AccountId,
+		messages: Vec<Any>,
+	) -> Result<Vec<Any>, Error<T>> {
+		Self::ensure_relayer_policy(relayer, &messages)?;
+		Ok(Self::skip_redundant_messages(messages))
+	}
+
+	/// Simulate the delivery of `messages` by `relayer`, without persisting any state change.
+	/// Fails if the batch is not admitted, and otherwise returns one outcome per admitted
+	/// message.
+	pub fn simulate_deliver(
+		relayer: T::AccountId,
+		messages: Vec<Any>,
+	) -> Result<Vec<MessageSimulation>, DispatchError> {
+		let simulate = || {
+			let messages = match Self::admit_messages(&relayer, messages) {
+				Ok(messages) => messages,
+				Err(error) => return TransactionOutcome::Rollback(Err(error.into())),
+			};
+			let mut ctx = Context::<T>::new();
+			let outcomes = messages
+				.into_iter()
+				.map(|msg| {
+					let weight = weights::deliver::<T>(&[msg.clone()]);
+					match Self::deliver_message(&mut ctx, msg) {
+						Ok(MsgReceipt { events, log }) => MessageSimulation {
+							events,
+							logs: log.into_iter().map(|log| log.into_bytes()).collect(),
+							error: None,
+							weight,
+						},
+						Err(error) => MessageSimulation {
+							events: vec![],
+							logs: vec![],
+							error: Some(error.into()),
+							weight,
+						},
+					}
+				})
+				.collect();
+			TransactionOutcome::Rollback(Ok(outcomes))
+		};
+		with_transaction(simulate)
+	}
+}
+
+impl From<DeliveryError> for SimulationError {
+	fn from(error: DeliveryError) -> Self {
+		match error {
+			DeliveryError::Router(error) => SimulationError::Ibc(error.into()),
+			DeliveryError::ChannelUpgrade(error) | DeliveryError::AllowTimeout(error) =>
+				SimulationError::Pallet(error),
+		}
+	}
+}
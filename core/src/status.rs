@@ -0,0 +1,149 @@
+//! Status of the light clients hosted by this chain.
+//!
+//! Like ibc-go, a client is `Frozen` once misbehaviour was submitted for it, `Expired` once the
+//! trusting period elapsed since its latest consensus state and `Active` otherwise. Channel and
+//! packet handlers refuse to operate over a client that is not active.
+//!
+//! The last observed status of every client is recorded, and `ClientStatusChanged` is emitted
+//! when it changes. Statuses are recorded by the handlers changing them: delivered client
+//! messages and client recovery. A client expires without any message, so the recorded statuses
+//! are also refreshed in turn with the weight left in `on_idle`. Reading a client never records
+//! its status.
+use crate::{
+	context::Context, recovery::is_frozen, ClientStatusCursor, ClientStatuses, Clients, Config,
+	Event, Pallet, TENDERMINT_CLIENT_TYPE,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{traits::Get, weights::Weight};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::{
+	core::{
+		ics02_client::context::ClientReader,
+		ics24_host::{identifier::ClientId, path::ClientTypePath},
+	},
+	events::IbcEvent,
+};
+use scale_info::TypeInfo;
+
+/// Computation weight of refreshing the status of a client, on top of its storage accesses.
+pub const REFRESH_STATUS_WEIGHT: u64 = 10_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo)]
+pub enum ClientStatus {
+	/// The client can be used to verify proofs
+	Active,
+	/// Misbehaviour was submitted for the client
+	Frozen,
+	/// The trusting period elapsed since the latest consensus state of the client
+	Expired,
+	/// The client does not exist or its state cannot be read
+	Unknown,
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Current status of `client_id`.
+	pub fn client_status(client_id: &ClientId) -> ClientStatus {
+		let client_type = match <Clients<T>>::get(ClientTypePath(client_id.clone())) {
+			Some(client_type) => client_type,
+			None => return ClientStatus::Unknown,
+		};
+
+		if client_type.as_str() == TENDERMINT_CLIENT_TYPE {
+			return match Self::tendermint_client_state(client_id) {
+				Ok(client_state) if is_frozen(&client_state) => ClientStatus::Frozen,
+				Ok(client_state) if Self::is_expired(client_id, &client_state) =>
+					ClientStatus::Expired,
+				Ok(_) => ClientStatus::Active,
+				Err(_) => ClientStatus::Unknown,
+			};
+		}
+
+		let ctx = Context::<T>::new();
+		let client_state = match ClientReader::client_state(&ctx, client_id) {
+			Ok(client_state) => client_state,
+			Err(_) => return ClientStatus::Unknown,
+		};
+		if client_state.is_frozen() {
+			return ClientStatus::Frozen;
+		}
+		let consensus_state =
+			match ClientReader::consensus_state(&ctx, client_id, &client_state.latest_height()) {
+				Ok(consensus_state) => consensus_state,
+				Err(_) => return ClientStatus::Expired,
+			};
+		let now = match ClientReader::host_timestamp(&ctx) {
+			Ok(now) => now,
+			Err(_) => return ClientStatus::Unknown,
+		};
+		match now.duration_since(&consensus_state.timestamp()) {
+			Some(elapsed) if client_state.expired(elapsed) => ClientStatus::Expired,
+			_ => ClientStatus::Active,
+		}
+	}
+
+	/// Compute the status of `client_id`, record it and emit `ClientStatusChanged` if it differs
+	/// from the last recorded one.
+	pub fn refresh_client_status(client_id: &ClientId) -> ClientStatus {
+		let status = Self::client_status(client_id);
+		if <ClientStatuses<T>>::get(client_id) != Some(status) {
+			<ClientStatuses<T>>::insert(client_id, status);
+			Self::deposit_event(Event::ClientStatusChanged {
+				client_id: client_id.clone(),
+				status,
+			});
+		}
+		status
+	}
+
+	/// Refresh the status of the clients created, updated, upgraded or frozen by `events`.
+	pub(crate) fn refresh_client_statuses(events: &[IbcEvent]) {
+		for event in events {
+			let client_id = match event {
+				IbcEvent::CreateClient(e) => e.client_id(),
+				IbcEvent::UpdateClient(e) => e.client_id(),
+				IbcEvent::UpgradeClient(e) => e.client_id(),
+				IbcEvent::ClientMisbehaviour(e) => e.client_id(),
+				_ => continue,
+			};
+			Self::refresh_client_status(client_id);
+		}
+	}
+
+	/// Refresh the recorded statuses within `remaining_weight`, resuming after the client
+	/// refreshed last, returning the weight used.
+	pub(crate) fn refresh_recorded_client_statuses(remaining_weight: Weight) -> Weight {
+		let db_weight = T::DbWeight::get();
+		let per_client = Weight::from_parts(REFRESH_STATUS_WEIGHT, 0)
+			.saturating_add(db_weight.reads_writes(4, 1));
+		let mut used = db_weight.reads_writes(1, 1);
+		if !remaining_weight.all_gte(used.saturating_add(per_client)) {
+			return Weight::zero();
+		}
+
+		let mut clients = match <ClientStatusCursor<T>>::get() {
+			Some(cursor) =>
+				<ClientStatuses<T>>::iter_keys_from(<ClientStatuses<T>>::hashed_key_for(cursor)),
+			None => <ClientStatuses<T>>::iter_keys(),
+		};
+		let mut cursor = None;
+		while remaining_weight.all_gte(used.saturating_add(per_client)) {
+			match clients.next() {
+				Some(client_id) => {
+					Self::refresh_client_status(&client_id);
+					cursor = Some(client_id);
+					used = used.saturating_add(per_client);
+				},
+				// start over from the first client in the next block
+				None => {
+					cursor = None;
+					break;
+				},
+			}
+		}
+		<ClientStatusCursor<T>>::set(cursor);
+		used
+	}
+}
@@ -0,0 +1,299 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		channel::allow_timeout::{
+			MSG_CHANNEL_OPEN_INIT_TYPE_URL, MSG_RECV_PACKET_TYPE_URL, MSG_TIMEOUT_TYPE_URL,
+			ORDER_ORDERED_ALLOW_TIMEOUT,
+		},
+		mock::{new_test_ext, AccountId, Ibc, RuntimeEvent, RuntimeOrigin, System, Test},
+		Acknowledgements, ChannelCounter, Channels, Context, Error, Event, NextSequenceAck,
+		NextSequenceRecv, OrderedAllowTimeoutChannels, PacketCommitment, Pallet,
+	};
+	use frame_support::assert_ok;
+	use ibc::{
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				context::{ChannelKeeper, ChannelReader},
+				packet::{Packet, Sequence},
+				Version,
+			},
+			ics24_host::{
+				identifier::{ChannelId, ClientId, ConnectionId, PortId},
+				path::{AcksPath, ChannelEndsPath, CommitmentsPath, SeqAcksPath, SeqRecvsPath},
+			},
+		},
+		mock::client_state::client_type as mock_client_type,
+		timestamp::ZERO_DURATION,
+		Height,
+	};
+	use ibc_proto::{
+		google::protobuf::Any,
+		ibc::core::{
+			channel::v1::{
+				Channel as RawChannel, MsgChannelOpenInit as RawMsgChannelOpenInit,
+				MsgRecvPacket as RawMsgRecvPacket, MsgTimeout as RawMsgTimeout,
+				Packet as RawPacket,
+			},
+			client::v1::Height as RawHeight,
+		},
+	};
+	use prost::Message;
+
+	const CLIENT_HEIGHT: u64 = 10;
+
+	fn relayer() -> AccountId {
+		AccountId::new([3u8; 32])
+	}
+
+	fn path() -> ChannelEndsPath {
+		ChannelEndsPath(PortId::transfer(), ChannelId::new(0))
+	}
+
+	/// An open mock client, connection and `ORDERED_ALLOW_TIMEOUT` channel.
+	fn open_channel() -> Context<Test> {
+		let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+		let connection_id = ConnectionId::new(0);
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::new(
+				client_id.clone(),
+				Some(ConnectionId::new(1)),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			Order::Ordered,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+			vec![connection_id.clone()],
+			Version::new("ics20-1".to_string()),
+		);
+		<OrderedAllowTimeoutChannels<Test>>::insert(path(), ());
+
+		Context::<Test>::new()
+			.with_client(&client_id, Height::new(0, CLIENT_HEIGHT).unwrap())
+			.with_connection(connection_id, connection_end)
+			.with_channel(PortId::transfer(), ChannelId::new(0), channel_end)
+			.with_recv_sequence(PortId::transfer(), ChannelId::new(0), Sequence::from(1))
+			.with_ack_sequence(PortId::transfer(), ChannelId::new(0), Sequence::from(1))
+			.with_send_sequence(PortId::transfer(), ChannelId::new(0), Sequence::from(1))
+	}
+
+	/// A packet from channel-1 to channel-0 timing out at height 5.
+	fn incoming_packet(sequence: u64) -> RawPacket {
+		RawPacket {
+			sequence,
+			source_port: "transfer".to_string(),
+			source_channel: "channel-1".to_string(),
+			destination_port: "transfer".to_string(),
+			destination_channel: "channel-0".to_string(),
+			data: vec![1, 2, 3],
+			timeout_height: Some(RawHeight { revision_number: 0, revision_height: 5 }),
+			timeout_timestamp: 0,
+		}
+	}
+
+	fn outgoing_packet(sequence: u64) -> RawPacket {
+		RawPacket {
+			source_channel: "channel-0".to_string(),
+			destination_channel: "channel-1".to_string(),
+			..incoming_packet(sequence)
+		}
+	}
+
+	fn proof_height() -> Option<RawHeight> {
+		Some(RawHeight { revision_number: 0, revision_height: CLIENT_HEIGHT })
+	}
+
+	fn recv_msg(packet: RawPacket) -> Any {
+		let msg = RawMsgRecvPacket {
+			packet: Some(packet),
+			proof_commitment: vec![1],
+			proof_height: proof_height(),
+			signer: "relayer".to_string(),
+		};
+		Any { type_url: MSG_RECV_PACKET_TYPE_URL.to_string(), value: msg.encode_to_vec() }
+	}
+
+	/// Time out `packet`, skipped by the counterparty.
+	fn timeout_msg(packet: RawPacket) -> Any {
+		let msg = RawMsgTimeout {
+			next_sequence_recv: packet.sequence + 1,
+			packet: Some(packet),
+			proof_unreceived: vec![1],
+			proof_height: proof_height(),
+			signer: "relayer".to_string(),
+		};
+		Any { type_url: MSG_TIMEOUT_TYPE_URL.to_string(), value: msg.encode_to_vec() }
+	}
+
+	fn failed(type_url: &str, error: Error<Test>) -> RuntimeEvent {
+		RuntimeEvent::Ibc(Event::AllowTimeoutPacketFailed {
+			type_url: type_url.as_bytes().to_vec(),
+			error: error.into(),
+		})
+	}
+
+	fn has_event(event: RuntimeEvent) -> bool {
+		System::events().iter().any(|record| record.event == event)
+	}
+
+	#[test]
+	fn open_init_is_rewritten_to_ordered() {
+		new_test_ext().execute_with(|| {
+			<ChannelCounter<Test>>::put(3);
+			let msg = RawMsgChannelOpenInit {
+				port_id: "transfer".to_string(),
+				channel: Some(RawChannel {
+					ordering: ORDER_ORDERED_ALLOW_TIMEOUT,
+					..Default::default()
+				}),
+				signer: "relayer".to_string(),
+			};
+			let (msg, opened) = Pallet::<Test>::allow_timeout_hook(Any {
+				type_url: MSG_CHANNEL_OPEN_INIT_TYPE_URL.to_string(),
+				value: msg.encode_to_vec(),
+			});
+
+			let raw = RawMsgChannelOpenInit::decode(msg.value.as_slice()).unwrap();
+			assert_eq!(raw.channel.unwrap().ordering, 2);
+			let path = ChannelEndsPath(PortId::transfer(), ChannelId::new(3));
+			assert_eq!(opened, Some(path.clone()));
+
+			// recorded once ibc-rs stored the channel
+			<Channels<Test>>::insert(&path, ChannelEnd::default());
+			Pallet::<Test>::after_allow_timeout_hook(opened);
+			assert!(<OrderedAllowTimeoutChannels<Test>>::contains_key(&path));
+		})
+	}
+
+	/// Store the commitment of an outgoing packet.
+	fn commit(ctx: &mut Context<Test>, packet: &RawPacket) {
+		let packet = Packet::try_from(packet.clone()).unwrap();
+		let commitment = ChannelReader::packet_commitment(
+			ctx,
+			&packet.data,
+			&packet.timeout_height_on_b,
+			&packet.timeout_timestamp_on_b,
+		);
+		ctx.store_packet_commitment(
+			PortId::transfer(),
+			ChannelId::new(0),
+			packet.sequence,
+			commitment,
+		)
+		.unwrap();
+	}
+
+	#[test]
+	fn timeout_is_handled_without_closing_the_channel() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = open_channel();
+			commit(&mut ctx, &outgoing_packet(1));
+
+			// the timeout is proven, then fails as no module is routed to the transfer port
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(relayer()),
+				vec![timeout_msg(outgoing_packet(1))]
+			));
+
+			assert!(has_event(failed(MSG_TIMEOUT_TYPE_URL, Error::InvalidModuleId)));
+			assert!(<Channels<Test>>::get(path()).unwrap().state_matches(&State::Open));
+			assert!(<PacketCommitment<Test>>::contains_key(CommitmentsPath {
+				port_id: PortId::transfer(),
+				channel_id: ChannelId::new(0),
+				sequence: Sequence::from(1),
+			}));
+			assert_eq!(
+				<NextSequenceAck<Test>>::get(SeqAcksPath(path().0, path().1)),
+				Some(Sequence::from(1))
+			);
+		})
+	}
+
+	#[test]
+	fn timed_out_packet_skips_the_receive_sequence() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(CLIENT_HEIGHT);
+			let _ctx = open_channel();
+
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(relayer()),
+				vec![recv_msg(incoming_packet(1))]
+			));
+
+			assert!(has_event(RuntimeEvent::Ibc(Event::PacketReceiveSkipped {
+				port_id: PortId::transfer(),
+				channel_id: ChannelId::new(0),
+				sequence: 1,
+			})));
+			assert_eq!(
+				<NextSequenceRecv<Test>>::get(SeqRecvsPath(path().0, path().1)),
+				Some(Sequence::from(2))
+			);
+			assert_eq!(
+				<Acknowledgements<Test>>::get(AcksPath {
+					port_id: PortId::transfer(),
+					channel_id: ChannelId::new(0),
+					sequence: Sequence::from(1),
+				}),
+				Some(Pallet::<Test>::timeout_receipt_commitment())
+			);
+
+			// the next packet must be received or skipped in order
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(relayer()),
+				vec![recv_msg(incoming_packet(3))]
+			));
+			assert!(has_event(failed(MSG_RECV_PACKET_TYPE_URL, Error::PacketSequenceMismatch)));
+			assert_ok!(Pallet::<Test>::do_try_state());
+		})
+	}
+
+	#[test]
+	fn skip_requires_a_matching_counterparty() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(CLIENT_HEIGHT);
+			let _ctx = open_channel();
+			let packet =
+				RawPacket { source_channel: "channel-7".to_string(), ..incoming_packet(1) };
+
+			assert_ok!(Ibc::deliver(RuntimeOrigin::signed(relayer()), vec![recv_msg(packet)]));
+
+			assert!(has_event(failed(MSG_RECV_PACKET_TYPE_URL, Error::InvalidPacket)));
+			assert_eq!(
+				<NextSequenceRecv<Test>>::get(SeqRecvsPath(path().0, path().1)),
+				Some(Sequence::from(1))
+			);
+		})
+	}
+
+	#[test]
+	fn timeout_of_skipped_packet_checks_the_commitment() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = open_channel();
+			let mut packet = outgoing_packet(1);
+			commit(&mut ctx, &packet);
+
+			packet.data = vec![4, 5, 6];
+			assert_ok!(Ibc::deliver(RuntimeOrigin::signed(relayer()), vec![timeout_msg(packet)]));
+			assert!(has_event(failed(MSG_TIMEOUT_TYPE_URL, Error::PacketCommitmentMismatch)));
+
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(relayer()),
+				vec![timeout_msg(outgoing_packet(2))]
+			));
+			assert!(has_event(failed(MSG_TIMEOUT_TYPE_URL, Error::PacketSequenceMismatch)));
+		})
+	}
+}
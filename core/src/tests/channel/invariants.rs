@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, Test},
+		Context, InFlightPackets, Pallet,
+	};
+	use frame_support::assert_ok;
+	use ibc::core::{
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, Order, State},
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			context::{ChannelKeeper, ChannelReader},
+			packet::{Receipt, Sequence},
+			Version,
+		},
+		ics24_host::{
+			identifier::{ChannelId, ConnectionId, PortId},
+			path::ChannelEndsPath,
+		},
+	};
+	use sp_runtime::DispatchError;
+
+	fn channel_id() -> ChannelId {
+		ChannelId::new(0)
+	}
+
+	/// An open channel with 4 packets sent and 2 received, the first of them acknowledged.
+	fn open_channel(ordering: Order) -> Context<Test> {
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			ordering,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+			vec![ConnectionId::new(0)],
+			Version::new("ics20-1".to_string()),
+		);
+		let mut ctx = Context::<Test>::new()
+			.with_channel(PortId::transfer(), channel_id(), channel_end)
+			.with_send_sequence(PortId::transfer(), channel_id(), Sequence::from(5))
+			.with_recv_sequence(PortId::transfer(), channel_id(), Sequence::from(3))
+			.with_ack_sequence(PortId::transfer(), channel_id(), Sequence::from(2));
+		for sequence in 2..5 {
+			commit(&mut ctx, sequence);
+		}
+		for sequence in 1..3 {
+			if ordering == Order::Unordered {
+				ctx.store_packet_receipt(
+					PortId::transfer(),
+					channel_id(),
+					Sequence::from(sequence),
+					Receipt::Ok,
+				)
+				.unwrap();
+			}
+			acknowledge(&mut ctx, sequence);
+		}
+		ctx
+	}
+
+	fn commit(ctx: &mut Context<Test>, sequence: u64) {
+		ctx.store_packet_commitment(
+			PortId::transfer(),
+			channel_id(),
+			Sequence::from(sequence),
+			PacketCommitment::from(vec![1]),
+		)
+		.unwrap();
+	}
+
+	fn acknowledge(ctx: &mut Context<Test>, sequence: u64) {
+		ctx.store_packet_acknowledgement(
+			PortId::transfer(),
+			channel_id(),
+			Sequence::from(sequence),
+			AcknowledgementCommitment::from(vec![1]),
+		)
+		.unwrap();
+	}
+
+	fn violation(message: &'static str) -> Result<(), DispatchError> {
+		Err(DispatchError::Other(message))
+	}
+
+	#[test]
+	fn next_sequence_send_is_not_the_ack_sequence() {
+		new_test_ext().execute_with(|| {
+			let ctx = open_channel(Order::Ordered);
+			assert_eq!(
+				ChannelReader::get_next_sequence_send(&ctx, &PortId::transfer(), &channel_id())
+					.unwrap(),
+				Sequence::from(5)
+			);
+		})
+	}
+
+	#[test]
+	fn consistent_channels_pass() {
+		new_test_ext().execute_with(|| {
+			let _ctx = open_channel(Order::Ordered);
+			assert_ok!(Pallet::<Test>::do_try_state());
+		});
+		new_test_ext().execute_with(|| {
+			let _ctx = open_channel(Order::Unordered);
+			assert_ok!(Pallet::<Test>::do_try_state());
+		});
+	}
+
+	#[test]
+	fn commitment_must_be_sent() {
+		for ordering in [Order::Ordered, Order::Unordered] {
+			new_test_ext().execute_with(|| {
+				let mut ctx = open_channel(ordering);
+				commit(&mut ctx, 5);
+				assert_eq!(
+					Pallet::<Test>::do_try_state(),
+					violation("packet commitment for a sequence not sent yet")
+				);
+			})
+		}
+	}
+
+	#[test]
+	fn in_flight_packets_must_match_commitments() {
+		new_test_ext().execute_with(|| {
+			let _ctx = open_channel(Order::Unordered);
+			<InFlightPackets<Test>>::insert(ChannelEndsPath(PortId::transfer(), channel_id()), 7);
+			assert_eq!(
+				Pallet::<Test>::do_try_state(),
+				violation("in-flight packets differ from the stored packet commitments")
+			);
+		})
+	}
+
+	#[test]
+	fn ordered_commitment_must_not_be_acknowledged() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = open_channel(Order::Ordered);
+			commit(&mut ctx, 1);
+			assert_eq!(
+				Pallet::<Test>::do_try_state(),
+				violation("packet commitment for an acknowledged sequence")
+			);
+		})
+	}
+
+	#[test]
+	fn ordered_ack_must_not_pass_send() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = open_channel(Order::Ordered);
+			ctx.store_next_sequence_ack(PortId::transfer(), channel_id(), Sequence::from(6))
+				.unwrap();
+			assert_eq!(
+				Pallet::<Test>::do_try_state(),
+				violation("sequence acknowledged before it was sent")
+			);
+		})
+	}
+
+	#[test]
+	fn ordered_acknowledgement_must_be_received() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = open_channel(Order::Ordered);
+			acknowledge(&mut ctx, 3);
+			assert_eq!(
+				Pallet::<Test>::do_try_state(),
+				violation("acknowledgement for a sequence not received yet")
+			);
+		})
+	}
+
+	#[test]
+	fn unordered_acknowledgement_needs_a_receipt() {
+		new_test_ext().execute_with(|| {
+			let mut ctx = open_channel(Order::Unordered);
+			acknowledge(&mut ctx, 9);
+			assert_eq!(
+				Pallet::<Test>::do_try_state(),
+				violation("acknowledgement without a packet receipt")
+			);
+		})
+	}
+}
@@ -1,4 +1,5 @@
 pub mod acknowledgement;
+pub mod allow_timeout;
 pub mod chan_close_confirm;
 pub mod chan_close_init;
 pub mod chan_open_ack;
@@ -6,9 +7,12 @@ pub mod chan_open_confirm;
 pub mod chan_open_init;
 pub mod chan_open_try;
 pub mod common;
+pub mod invariants;
 pub mod packet;
 pub mod recv_packet;
+pub mod redundant_relay;
 pub mod send_packet;
 pub mod timeout;
 pub mod timeout_on_close;
+pub mod upgrade;
 pub mod write_acknowledgement;
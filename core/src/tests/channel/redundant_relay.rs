@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		channel::allow_timeout::{MSG_RECV_PACKET_TYPE_URL, MSG_TIMEOUT_TYPE_URL},
+		mock::{
+			new_test_ext, AccountId, Ibc, RuntimeCall, RuntimeEvent, RuntimeOrigin, System, Test,
+		},
+		redundant_relay::{CheckRedundantRelay, MSG_ACKNOWLEDGEMENT_TYPE_URL},
+		Context, Event, Pallet,
+	};
+	use frame_support::dispatch::DispatchInfo;
+	use ibc::core::{
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, Order, State},
+			commitment::PacketCommitment,
+			context::ChannelKeeper,
+			packet::{Receipt, Sequence},
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+	};
+	use ibc_proto::{
+		google::protobuf::Any,
+		ibc::core::channel::v1::{
+			MsgAcknowledgement as RawMsgAcknowledgement, MsgRecvPacket as RawMsgRecvPacket,
+			MsgTimeout as RawMsgTimeout, Packet as RawPacket,
+		},
+	};
+	use prost::Message;
+	use sp_runtime::{
+		traits::SignedExtension,
+		transaction_validity::{InvalidTransaction, TransactionValidityError},
+	};
+
+	fn relayer() -> AccountId {
+		AccountId::new([3u8; 32])
+	}
+
+	/// An open channel `channel-0` with packets 1 and 2 received and packet 3 still committed.
+	fn open_channel(ordering: Order) {
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			ordering,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+			vec![ConnectionId::new(0)],
+			Version::new("ics20-1".to_string()),
+		);
+		let mut ctx = Context::<Test>::new()
+			.with_channel(PortId::transfer(), ChannelId::new(0), channel_end)
+			.with_recv_sequence(PortId::transfer(), ChannelId::new(0), Sequence::from(3));
+		for sequence in 1..3 {
+			ctx.store_packet_receipt(
+				PortId::transfer(),
+				ChannelId::new(0),
+				Sequence::from(sequence),
+				Receipt::Ok,
+			)
+			.unwrap();
+		}
+		ctx.store_packet_commitment(
+			PortId::transfer(),
+			ChannelId::new(0),
+			Sequence::from(3),
+			PacketCommitment::from(vec![1]),
+		)
+		.unwrap();
+	}
+
+	/// A packet sent by the counterparty if `incoming`, by this chain otherwise.
+	fn packet(sequence: u64, incoming: bool) -> RawPacket {
+		let (source, destination) =
+			if incoming { ("channel-1", "channel-0") } else { ("channel-0", "channel-1") };
+		RawPacket {
+			sequence,
+			source_port: "transfer".to_string(),
+			source_channel: source.to_string(),
+			destination_port: "transfer".to_string(),
+			destination_channel: destination.to_string(),
+			..Default::default()
+		}
+	}
+
+	fn recv(sequence: u64) -> Any {
+		let msg = RawMsgRecvPacket { packet: Some(packet(sequence, true)), ..Default::default() };
+		Any { type_url: MSG_RECV_PACKET_TYPE_URL.to_string(), value: msg.encode_to_vec() }
+	}
+
+	fn ack(sequence: u64) -> Any {
+		let msg =
+			RawMsgAcknowledgement { packet: Some(packet(sequence, false)), ..Default::default() };
+		Any { type_url: MSG_ACKNOWLEDGEMENT_TYPE_URL.to_string(), value: msg.encode_to_vec() }
+	}
+
+	fn timeout(sequence: u64) -> Any {
+		let msg = RawMsgTimeout { packet: Some(packet(sequence, false)), ..Default::default() };
+		Any { type_url: MSG_TIMEOUT_TYPE_URL.to_string(), value: msg.encode_to_vec() }
+	}
+
+	fn validate(messages: Vec<Any>) -> Result<(), TransactionValidityError> {
+		let call = RuntimeCall::Ibc(crate::Call::deliver { messages });
+		CheckRedundantRelay::<Test>::new()
+			.validate(&relayer(), &call, &DispatchInfo::default(), 0)
+			.map(|_| ())
+	}
+
+	#[test]
+	fn received_packets_are_redundant() {
+		new_test_ext().execute_with(|| {
+			open_channel(Order::Unordered);
+			assert!(Pallet::<Test>::is_redundant(&recv(2)));
+			assert!(!Pallet::<Test>::is_redundant(&recv(3)));
+		});
+		new_test_ext().execute_with(|| {
+			open_channel(Order::Ordered);
+			assert!(Pallet::<Test>::is_redundant(&recv(1)));
+			assert!(!Pallet::<Test>::is_redundant(&recv(3)));
+		});
+	}
+
+	#[test]
+	fn settled_packets_are_redundant() {
+		new_test_ext().execute_with(|| {
+			open_channel(Order::Unordered);
+			assert!(Pallet::<Test>::is_redundant(&ack(2)));
+			assert!(Pallet::<Test>::is_redundant(&timeout(2)));
+			assert!(!Pallet::<Test>::is_redundant(&ack(3)));
+			assert!(!Pallet::<Test>::is_redundant(&timeout(3)));
+		});
+	}
+
+	#[test]
+	fn packets_of_unknown_channels_are_not_redundant() {
+		new_test_ext().execute_with(|| {
+			assert!(!Pallet::<Test>::is_redundant(&recv(1)));
+			assert!(!Pallet::<Test>::is_redundant(&ack(1)));
+			assert!(!Pallet::<Test>::is_redundant(&Any {
+				type_url: MSG_RECV_PACKET_TYPE_URL.to_string(),
+				value: vec![0xff],
+			}));
+		});
+	}
+
+	#[test]
+	fn redundant_messages_are_skipped_without_weight() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			open_channel(Order::Unordered);
+
+			let post_info =
+				Ibc::deliver(RuntimeOrigin::signed(relayer()), vec![recv(1), ack(2)]).unwrap();
+
+			assert_eq!(post_info.actual_weight, Some(Default::default()));
+			assert_eq!(
+				System::events().into_iter().map(|record| record.event).collect::<Vec<_>>(),
+				vec![RuntimeEvent::Ibc(Event::RedundantMessagesSkipped { messages: 2 })]
+			);
+		});
+	}
+
+	#[test]
+	fn fully_redundant_deliveries_are_rejected_from_the_pool() {
+		new_test_ext().execute_with(|| {
+			open_channel(Order::Unordered);
+
+			assert_eq!(validate(vec![recv(1), timeout(2)]), Err(InvalidTransaction::Stale.into()));
+			assert_eq!(validate(vec![recv(1), recv(3)]), Ok(()));
+			assert_eq!(validate(vec![]), Ok(()));
+		});
+	}
+}
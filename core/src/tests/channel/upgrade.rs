@@ -0,0 +1,480 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		channel::upgrade::{
+			ChannelUpgradeErrorPath, ChannelUpgradePath, MsgChannelUpgradeAck,
+			MsgChannelUpgradeCancel, MsgChannelUpgradeOpen, MsgChannelUpgradeTry, UpgradeFields,
+			UpgradeState, UpgradeTimeout, MSG_CHANNEL_UPGRADE_ACK_TYPE_URL,
+			MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL, MSG_CHANNEL_UPGRADE_CONFIRM_TYPE_URL,
+			MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL, MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL,
+			MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+		},
+		mock::{new_test_ext, AccountId, Ibc, RuntimeEvent, RuntimeOrigin, System, Test},
+		ChannelUpgrades, Channels, Context, Error, Event, InFlightPackets, Pallet,
+		UpgradeCommitments, UpgradeErrorReceipts,
+	};
+	use frame_support::{assert_noop, assert_ok};
+	use ibc::{
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				commitment::PacketCommitment,
+				context::{ChannelKeeper, ChannelReader},
+				packet::Sequence,
+				Version,
+			},
+			ics24_host::{
+				identifier::{ChannelId, ClientId, ConnectionId, PortId},
+				path::ChannelEndsPath,
+			},
+		},
+		mock::client_state::client_type as mock_client_type,
+		timestamp::ZERO_DURATION,
+		Height,
+	};
+	use ibc_proto::{google::protobuf::Any, ibc::core::client::v1::Height as RawHeight};
+	use prost::Message;
+
+	const CLIENT_HEIGHT: u64 = 10;
+
+	fn relayer() -> AccountId {
+		AccountId::new([3u8; 32])
+	}
+
+	fn port_id() -> PortId {
+		PortId::transfer()
+	}
+
+	fn channel_id() -> ChannelId {
+		ChannelId::new(0)
+	}
+
+	fn path() -> ChannelEndsPath {
+		ChannelEndsPath(port_id(), channel_id())
+	}
+
+	/// An open mock client, connection and unordered `ics20-1` channel.
+	fn open_channel() -> Context<Test> {
+		let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+		let connection_id = ConnectionId::new(0);
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::new(
+				client_id.clone(),
+				Some(ConnectionId::new(1)),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(port_id(), Some(ChannelId::new(1))),
+			vec![connection_id.clone()],
+			Version::new("ics20-1".to_string()),
+		);
+
+		Context::<Test>::new()
+			.with_client(&client_id, Height::new(0, CLIENT_HEIGHT).unwrap())
+			.with_connection(connection_id, connection_end)
+			.with_channel(port_id(), channel_id(), channel_end)
+			.with_send_sequence(port_id(), channel_id(), Sequence::from(4))
+			.with_recv_sequence(port_id(), channel_id(), Sequence::from(1))
+			.with_ack_sequence(port_id(), channel_id(), Sequence::from(1))
+	}
+
+	fn upgrade_fields(version: &str) -> UpgradeFields {
+		UpgradeFields {
+			ordering: Order::Unordered,
+			connection_hops: vec![ConnectionId::new(0)],
+			version: Version::new(version.to_string()),
+		}
+	}
+
+	fn init(timeout: UpgradeTimeout) {
+		assert_ok!(Ibc::chan_upgrade_init(
+			RuntimeOrigin::root(),
+			port_id(),
+			channel_id(),
+			upgrade_fields("ics20-2"),
+			timeout,
+		));
+	}
+
+	fn height_timeout(height: u64) -> UpgradeTimeout {
+		UpgradeTimeout { height: Some(Height::new(0, height).unwrap()), timestamp: 0 }
+	}
+
+	fn deliver<M: Message>(type_url: &str, msg: M) {
+		assert_ok!(Ibc::deliver(
+			RuntimeOrigin::signed(relayer()),
+			vec![Any { type_url: type_url.to_string(), value: msg.encode_to_vec() }],
+		));
+	}
+
+	fn proof_height() -> Option<RawHeight> {
+		Some(RawHeight { revision_number: 0, revision_height: CLIENT_HEIGHT })
+	}
+
+	fn try_msg() -> MsgChannelUpgradeTry {
+		MsgChannelUpgradeTry {
+			port_id: port_id().to_string(),
+			channel_id: channel_id().to_string(),
+			counterparty_upgrade_sequence: 1,
+			proof_upgrade: vec![1],
+			proof_height: proof_height(),
+			signer: String::new(),
+		}
+	}
+
+	/// Also the layout of `MsgChannelUpgradeConfirm`.
+	fn ack_msg() -> MsgChannelUpgradeAck {
+		MsgChannelUpgradeAck {
+			port_id: port_id().to_string(),
+			channel_id: channel_id().to_string(),
+			counterparty_upgrade_sequence: 1,
+			counterparty_latest_sequence_send: 7,
+			proof_upgrade: vec![1],
+			proof_height: proof_height(),
+			signer: String::new(),
+		}
+	}
+
+	fn cancel_msg(error_receipt_sequence: u64) -> MsgChannelUpgradeCancel {
+		MsgChannelUpgradeCancel {
+			port_id: port_id().to_string(),
+			channel_id: channel_id().to_string(),
+			error_receipt_sequence,
+			proof_error_receipt: vec![1],
+			proof_height: proof_height(),
+			signer: String::new(),
+		}
+	}
+
+	fn proof_msg() -> MsgChannelUpgradeOpen {
+		MsgChannelUpgradeOpen {
+			port_id: port_id().to_string(),
+			channel_id: channel_id().to_string(),
+			proof_channel: vec![1],
+			proof_height: proof_height(),
+			signer: String::new(),
+		}
+	}
+
+	fn has_event(event: Event<Test>) -> bool {
+		System::events()
+			.iter()
+			.any(|record| record.event == RuntimeEvent::Ibc(event.clone()))
+	}
+
+	fn failed(type_url: &str, error: Error<Test>) -> Event<Test> {
+		Event::ChannelUpgradeFailed { type_url: type_url.as_bytes().to_vec(), error: error.into() }
+	}
+
+	fn channel_version() -> Version {
+		<Channels<Test>>::get(path()).unwrap().version().clone()
+	}
+
+	#[test]
+	fn upgrade_through_try_and_confirm() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let _ctx = open_channel();
+
+			init(height_timeout(100));
+			assert!(has_event(Event::ChannelUpgradeInit {
+				port_id: port_id(),
+				channel_id: channel_id(),
+				upgrade_sequence: 1,
+			}));
+
+			deliver(MSG_CHANNEL_UPGRADE_TRY_TYPE_URL, try_msg());
+			let upgrade = <ChannelUpgrades<Test>>::get(path()).unwrap();
+			assert_eq!(upgrade.state, UpgradeState::TryFlushing);
+			assert_eq!(upgrade.latest_sequence_send, 3);
+			let commitment =
+				<UpgradeCommitments<Test>>::get(ChannelUpgradePath::from(&path())).unwrap();
+			assert_eq!(commitment.state, UpgradeState::TryFlushing);
+			assert_eq!(commitment.latest_sequence_send, 3);
+
+			deliver(MSG_CHANNEL_UPGRADE_CONFIRM_TYPE_URL, ack_msg());
+			assert!(has_event(Event::ChannelUpgradeOpen {
+				port_id: port_id(),
+				channel_id: channel_id(),
+				upgrade_sequence: 1,
+			}));
+			assert_eq!(channel_version(), Version::new("ics20-2".to_string()));
+			assert!(<ChannelUpgrades<Test>>::get(path()).is_none());
+			assert!(<UpgradeCommitments<Test>>::get(ChannelUpgradePath::from(&path())).is_none());
+			assert_ok!(Pallet::<Test>::do_try_state());
+		})
+	}
+
+	#[test]
+	fn upgrade_through_ack_and_open_waits_for_flush() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let mut ctx = open_channel();
+			ctx.store_packet_commitment(
+				port_id(),
+				channel_id(),
+				Sequence::from(3),
+				PacketCommitment::from(vec![1, 2, 3]),
+			)
+			.unwrap();
+			assert_eq!(<InFlightPackets<Test>>::get(path()), 1);
+
+			init(height_timeout(100));
+			deliver(MSG_CHANNEL_UPGRADE_ACK_TYPE_URL, ack_msg());
+			let upgrade = <ChannelUpgrades<Test>>::get(path()).unwrap();
+			assert_eq!(upgrade.state, UpgradeState::AckFlushing);
+			assert_eq!(upgrade.counterparty_latest_sequence_send, 7);
+
+			deliver(MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL, proof_msg());
+			assert!(has_event(failed(MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL, Error::FlushInProgress)));
+			assert_eq!(channel_version(), Version::new("ics20-1".to_string()));
+
+			ctx.delete_packet_commitment(&port_id(), &channel_id(), &Sequence::from(3))
+				.unwrap();
+			assert_eq!(<InFlightPackets<Test>>::get(path()), 0);
+
+			deliver(MSG_CHANNEL_UPGRADE_OPEN_TYPE_URL, proof_msg());
+			assert_eq!(channel_version(), Version::new("ics20-2".to_string()));
+			assert_ok!(Pallet::<Test>::do_try_state());
+		})
+	}
+
+	#[test]
+	fn flushing_channel_refuses_to_send() {
+		new_test_ext().execute_with(|| {
+			let ctx = open_channel();
+			init(height_timeout(100));
+			assert!(ctx.get_next_sequence_send(&port_id(), &channel_id()).is_ok());
+
+			deliver(MSG_CHANNEL_UPGRADE_TRY_TYPE_URL, try_msg());
+			assert!(ctx.get_next_sequence_send(&port_id(), &channel_id()).is_err());
+		})
+	}
+
+	#[test]
+	fn try_requires_a_proof_of_the_counterparty_upgrade() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let _ctx = open_channel();
+			init(height_timeout(100));
+
+			deliver(
+				MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+				MsgChannelUpgradeTry { counterparty_upgrade_sequence: 2, ..try_msg() },
+			);
+			assert!(has_event(failed(
+				MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+				Error::UpgradeSequenceMismatch
+			)));
+
+			deliver(
+				MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+				MsgChannelUpgradeTry { proof_upgrade: vec![], ..try_msg() },
+			);
+			assert!(has_event(failed(MSG_CHANNEL_UPGRADE_TRY_TYPE_URL, Error::InvalidProof)));
+
+			deliver(
+				MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+				MsgChannelUpgradeTry { proof_height: None, ..try_msg() },
+			);
+			assert!(has_event(failed(MSG_CHANNEL_UPGRADE_TRY_TYPE_URL, Error::InvalidHeight)));
+			assert_eq!(<ChannelUpgrades<Test>>::get(path()).unwrap().state, UpgradeState::Init);
+			assert_eq!(sp_io::storage::get(b"upgrade_try"), None);
+		})
+	}
+
+	#[test]
+	fn failed_message_is_rolled_back() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let _ctx = open_channel();
+			assert_ok!(Ibc::chan_upgrade_init(
+				RuntimeOrigin::root(),
+				port_id(),
+				channel_id(),
+				upgrade_fields("ics20-3"),
+				height_timeout(100),
+			));
+
+			// the module answers `ics20-2` after recording the try
+			deliver(MSG_CHANNEL_UPGRADE_TRY_TYPE_URL, try_msg());
+			assert!(has_event(failed(
+				MSG_CHANNEL_UPGRADE_TRY_TYPE_URL,
+				Error::UpgradeFieldsMismatch
+			)));
+			assert_eq!(sp_io::storage::get(b"upgrade_try"), None);
+			assert_eq!(<ChannelUpgrades<Test>>::get(path()).unwrap().state, UpgradeState::Init);
+			assert_eq!(
+				<UpgradeCommitments<Test>>::get(ChannelUpgradePath::from(&path()))
+					.unwrap()
+					.state,
+				UpgradeState::Init
+			);
+		})
+	}
+
+	#[test]
+	fn init_checks_the_proposal() {
+		new_test_ext().execute_with(|| {
+			let _ctx = open_channel();
+
+			assert_noop!(
+				Ibc::chan_upgrade_init(
+					RuntimeOrigin::signed(relayer()),
+					port_id(),
+					channel_id(),
+					upgrade_fields("ics20-2"),
+					height_timeout(100),
+				),
+				sp_runtime::DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Ibc::chan_upgrade_init(
+					RuntimeOrigin::root(),
+					port_id(),
+					channel_id(),
+					upgrade_fields("ics20-1"),
+					height_timeout(100),
+				),
+				Error::<Test>::InvalidUpgrade
+			);
+			assert_noop!(
+				Ibc::chan_upgrade_init(
+					RuntimeOrigin::root(),
+					port_id(),
+					channel_id(),
+					upgrade_fields("unsupported"),
+					height_timeout(100),
+				),
+				Error::<Test>::UpgradeRefused
+			);
+			assert_noop!(
+				Ibc::chan_upgrade_init(
+					RuntimeOrigin::root(),
+					port_id(),
+					channel_id(),
+					upgrade_fields("ics20-2"),
+					UpgradeTimeout { height: None, timestamp: 0 },
+				),
+				Error::<Test>::InvalidUpgradeTimeout
+			);
+
+			init(height_timeout(100));
+			assert_noop!(
+				Ibc::chan_upgrade_init(
+					RuntimeOrigin::root(),
+					port_id(),
+					channel_id(),
+					upgrade_fields("ics20-2"),
+					height_timeout(100),
+				),
+				Error::<Test>::UpgradeInProgress
+			);
+		})
+	}
+
+	#[test]
+	fn cancel_restores_the_channel() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let ctx = open_channel();
+			init(height_timeout(100));
+			deliver(MSG_CHANNEL_UPGRADE_TRY_TYPE_URL, try_msg());
+
+			assert_ok!(Ibc::chan_upgrade_cancel(RuntimeOrigin::root(), port_id(), channel_id()));
+			System::assert_last_event(RuntimeEvent::Ibc(Event::ChannelUpgradeCancelled {
+				port_id: port_id(),
+				channel_id: channel_id(),
+				upgrade_sequence: 1,
+			}));
+			assert!(<ChannelUpgrades<Test>>::get(path()).is_none());
+			assert_eq!(
+				<UpgradeErrorReceipts<Test>>::get(ChannelUpgradeErrorPath::from(&path())),
+				Some(1)
+			);
+			assert!(ctx.get_next_sequence_send(&port_id(), &channel_id()).is_ok());
+			assert_noop!(
+				Ibc::chan_upgrade_cancel(RuntimeOrigin::root(), port_id(), channel_id()),
+				Error::<Test>::NoUpgradeInProgress
+			);
+
+			// the next proposal gets a new upgrade sequence
+			init(height_timeout(100));
+			assert_eq!(<ChannelUpgrades<Test>>::get(path()).unwrap().sequence, 2);
+		})
+	}
+
+	#[test]
+	fn timeout_restores_the_channel_once_reached() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let _ctx = open_channel();
+			init(height_timeout(CLIENT_HEIGHT + 1));
+			deliver(MSG_CHANNEL_UPGRADE_ACK_TYPE_URL, ack_msg());
+
+			deliver(MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL, proof_msg());
+			assert!(has_event(failed(
+				MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL,
+				Error::UpgradeNotTimedOut
+			)));
+			assert!(<ChannelUpgrades<Test>>::get(path()).is_some());
+
+			<ChannelUpgrades<Test>>::mutate(path(), |upgrade| {
+				upgrade.as_mut().unwrap().timeout = height_timeout(CLIENT_HEIGHT)
+			});
+			deliver(MSG_CHANNEL_UPGRADE_TIMEOUT_TYPE_URL, proof_msg());
+			assert!(has_event(Event::ChannelUpgradeTimeout {
+				port_id: port_id(),
+				channel_id: channel_id(),
+				upgrade_sequence: 1,
+			}));
+			assert!(<ChannelUpgrades<Test>>::get(path()).is_none());
+			assert_eq!(channel_version(), Version::new("ics20-1".to_string()));
+		})
+	}
+
+	#[test]
+	fn cancel_msg_proves_a_counterparty_error_receipt() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let _ctx = open_channel();
+			init(height_timeout(100));
+			deliver(MSG_CHANNEL_UPGRADE_ACK_TYPE_URL, ack_msg());
+
+			// receipts of earlier upgrades do not cancel this one
+			deliver(MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL, cancel_msg(0));
+			assert!(has_event(failed(
+				MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL,
+				Error::UpgradeSequenceMismatch
+			)));
+			assert!(<ChannelUpgrades<Test>>::get(path()).is_some());
+
+			deliver(MSG_CHANNEL_UPGRADE_CANCEL_TYPE_URL, cancel_msg(1));
+			assert!(has_event(Event::ChannelUpgradeCancelled {
+				port_id: port_id(),
+				channel_id: channel_id(),
+				upgrade_sequence: 1,
+			}));
+			assert!(<ChannelUpgrades<Test>>::get(path()).is_none());
+			assert!(<UpgradeCommitments<Test>>::get(ChannelUpgradePath::from(&path())).is_none());
+			assert_eq!(
+				<UpgradeErrorReceipts<Test>>::get(ChannelUpgradeErrorPath::from(&path())),
+				Some(1)
+			);
+			assert_eq!(channel_version(), Version::new("ics20-1".to_string()));
+		})
+	}
+}
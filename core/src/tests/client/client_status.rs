@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, Ibc, PalletTimestamp, System, Test},
+		status::ClientStatus,
+		tests::common::{client_id, client_state, setup, store_client},
+		ClientStatuses, Context, Event,
+	};
+	use frame_support::{traits::Hooks, weights::Weight};
+	use ibc::{core::ics24_host::identifier::ClientId, Height};
+	use pallet_ibc_utils::traits::ChannelReaderInterface;
+
+	#[test]
+	fn tendermint_client_status() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let (active, frozen, expired) = (client_id(0), client_id(1), client_id(2));
+			store_client(&active, client_state("chain-0", 10, 0, 500), 900);
+			store_client(&frozen, client_state("chain-0", 10, 5, 500), 900);
+			// the trusting period elapsed at 600s
+			store_client(&expired, client_state("chain-0", 10, 0, 500), 100);
+
+			assert_eq!(Ibc::client_status(&active), ClientStatus::Active);
+			assert_eq!(Ibc::client_status(&frozen), ClientStatus::Frozen);
+			assert_eq!(Ibc::client_status(&expired), ClientStatus::Expired);
+			assert_eq!(Ibc::client_status(&client_id(3)), ClientStatus::Unknown);
+		})
+	}
+
+	#[test]
+	fn mock_client_status() {
+		new_test_ext().execute_with(|| {
+			let (active, expired) = (ClientId::default(), client_id(1));
+			let height = Height::new(0, 10).unwrap();
+			let _ = Context::<Test>::new().with_client(&active, height).with_client_parametrized(
+				&expired,
+				height,
+				None,
+				Some(Height::new(0, 5).unwrap()),
+			);
+
+			assert_eq!(Ibc::client_status(&active), ClientStatus::Active);
+			// a client without a consensus state at its latest height is expired
+			assert_eq!(Ibc::client_status(&expired), ClientStatus::Expired);
+		})
+	}
+
+	#[test]
+	fn status_change_emits_event() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let client_id = client_id(0);
+			store_client(&client_id, client_state("chain-0", 10, 0, 500), 900);
+
+			assert_eq!(Ibc::refresh_client_status(&client_id), ClientStatus::Active);
+			System::assert_last_event(
+				Event::<Test>::ClientStatusChanged {
+					client_id: client_id.clone(),
+					status: ClientStatus::Active,
+				}
+				.into(),
+			);
+
+			// an unchanged status is not reported again
+			let events = System::events().len();
+			Ibc::refresh_client_status(&client_id);
+			assert_eq!(System::events().len(), events);
+
+			PalletTimestamp::set_timestamp(1_500 * 1_000);
+			assert_eq!(Ibc::refresh_client_status(&client_id), ClientStatus::Expired);
+			assert_eq!(ClientStatuses::<Test>::get(&client_id), Some(ClientStatus::Expired));
+			System::assert_last_event(
+				Event::<Test>::ClientStatusChanged { client_id, status: ClientStatus::Expired }
+					.into(),
+			);
+		})
+	}
+
+	#[test]
+	fn inactive_client_is_refused_to_channels() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let (active, frozen) = (ClientId::default(), client_id(1));
+			let _ = Context::<Test>::new().with_client(&active, Height::new(0, 10).unwrap());
+			store_client(&frozen, client_state("chain-0", 10, 5, 500), 900);
+
+			assert!(<Context<Test> as ChannelReaderInterface>::client_state(&active).is_ok());
+			assert!(<Context<Test> as ChannelReaderInterface>::client_state(&frozen).is_err());
+			// reading a client does not record its status
+			assert_eq!(ClientStatuses::<Test>::get(&frozen), None);
+		})
+	}
+
+	#[test]
+	fn on_idle_records_expired_clients() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let (first, second) = (client_id(0), client_id(1));
+			store_client(&first, client_state("chain-0", 10, 0, 500), 900);
+			store_client(&second, client_state("chain-0", 10, 0, 500), 900);
+			Ibc::refresh_client_status(&first);
+			Ibc::refresh_client_status(&second);
+
+			// the trusting period of both clients elapsed at 1_400s
+			PalletTimestamp::set_timestamp(1_500 * 1_000);
+			Ibc::on_idle(System::block_number(), Weight::zero());
+			assert_eq!(ClientStatuses::<Test>::get(&first), Some(ClientStatus::Active));
+
+			Ibc::on_idle(System::block_number(), Weight::MAX);
+			assert_eq!(ClientStatuses::<Test>::get(&first), Some(ClientStatus::Expired));
+			assert_eq!(ClientStatuses::<Test>::get(&second), Some(ClientStatus::Expired));
+			System::assert_has_event(
+				Event::<Test>::ClientStatusChanged {
+					client_id: first,
+					status: ClientStatus::Expired,
+				}
+				.into(),
+			);
+		})
+	}
+}
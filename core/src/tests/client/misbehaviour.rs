@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		misbehaviour::{misbehaving_client_ids, TENDERMINT_MISBEHAVIOUR_TYPE_URL},
+		mock::{new_test_ext, AccountId, Ibc, RuntimeOrigin, System, Test},
+		status::ClientStatus,
+		tests::common::get_dummy_account_id,
+		Context, Event, WeightInfo,
+	};
+	use codec::Decode;
+	use frame_support::assert_ok;
+	use ibc::{
+		core::{
+			ics02_client::msgs::misbehaviour::MsgSubmitMisbehaviour,
+			ics24_host::identifier::ClientId,
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::client_type as mock_client_type, header::MockHeader,
+			misbehaviour::Misbehaviour as MockMisbehaviour,
+		},
+		timestamp::Timestamp,
+		tx_msg::Msg,
+		Height,
+	};
+	use ibc_proto::{
+		google::protobuf::Any,
+		ibc::lightclients::tendermint::v1::{
+			Header as RawTmHeader, Misbehaviour as RawTmMisbehaviour,
+		},
+	};
+	use prost::Message;
+
+	fn reporter() -> AccountId {
+		AccountId::new([7u8; 32])
+	}
+
+	fn recorded_misbehaviour() -> Option<(ClientId, AccountId)> {
+		sp_io::storage::get(b"misbehaviour").map(|data| Decode::decode(&mut &data[..]).unwrap())
+	}
+
+	/// Two mock headers at the same height with different timestamps.
+	fn conflicting_mock_headers(client_id: &ClientId, height: u64) -> Any {
+		let height = Height::new(0, height).unwrap();
+		let timestamp = Timestamp::from_nanoseconds(1_000_000_000).unwrap();
+		let misbehaviour = MockMisbehaviour {
+			client_id: client_id.clone(),
+			header1: MockHeader::new(height).with_timestamp(timestamp),
+			header2: MockHeader::new(height)
+				.with_timestamp(Timestamp::from_nanoseconds(2_000_000_000).unwrap()),
+		};
+		MsgSubmitMisbehaviour {
+			client_id: client_id.clone(),
+			misbehaviour: misbehaviour.into(),
+			signer: get_dummy_account_id(),
+		}
+		.to_any()
+	}
+
+	/// A Tendermint header at `height` signed for `block_hash` with `signatures` commit
+	/// signatures.
+	fn tendermint_header(height: i64, block_hash: u8, signatures: usize) -> RawTmHeader {
+		let mut header = RawTmHeader::default();
+		let signed_header = header.signed_header.get_or_insert_with(Default::default);
+		signed_header.header.get_or_insert_with(Default::default).height = height;
+		let commit = signed_header.commit.get_or_insert_with(Default::default);
+		commit.height = height;
+		commit.block_id.get_or_insert_with(Default::default).hash = vec![block_hash; 32];
+		commit.signatures = vec![Default::default(); signatures];
+		header
+	}
+
+	/// Duplicate vote evidence: two different blocks signed at the same height.
+	fn tendermint_duplicate_vote(client_id: &ClientId, signatures: usize) -> Any {
+		Any {
+			type_url: TENDERMINT_MISBEHAVIOUR_TYPE_URL.to_string(),
+			value: RawTmMisbehaviour {
+				client_id: client_id.to_string(),
+				header_1: Some(tendermint_header(20, 1, signatures)),
+				header_2: Some(tendermint_header(20, 2, signatures)),
+			}
+			.encode_to_vec(),
+		}
+	}
+
+	fn setup(client_id: &ClientId) {
+		System::set_block_number(1);
+		let _ = Context::<Test>::new().with_client(client_id, Height::new(0, 10).unwrap());
+	}
+
+	#[test]
+	fn misbehaviour_freezes_client_and_reports_submitter() {
+		new_test_ext().execute_with(|| {
+			let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+			setup(&client_id);
+			assert_eq!(Ibc::client_status(&client_id), ClientStatus::Active);
+
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(reporter()),
+				vec![conflicting_mock_headers(&client_id, 20)]
+			));
+
+			assert_eq!(Ibc::client_status(&client_id), ClientStatus::Frozen);
+			assert!(System::events().iter().any(|record| matches!(
+				&record.event,
+				crate::mock::RuntimeEvent::Ibc(Event::IbcEvents { events })
+					if matches!(events.as_slice(), [IbcEvent::ClientMisbehaviour(_)])
+			)));
+			System::assert_last_event(
+				Event::<Test>::MisbehaviourReported {
+					client_id: client_id.clone(),
+					reporter: reporter(),
+				}
+				.into(),
+			);
+			assert_eq!(recorded_misbehaviour(), Some((client_id, reporter())));
+		})
+	}
+
+	#[test]
+	fn frozen_client_is_not_reported_again() {
+		new_test_ext().execute_with(|| {
+			let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+			setup(&client_id);
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(reporter()),
+				vec![conflicting_mock_headers(&client_id, 20)]
+			));
+			sp_io::storage::clear(b"misbehaviour");
+
+			let other = AccountId::new([8u8; 32]);
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(other),
+				vec![conflicting_mock_headers(&client_id, 30)]
+			));
+
+			assert_eq!(recorded_misbehaviour(), None);
+			assert!(!System::events().iter().any(|record| matches!(
+				&record.event,
+				crate::mock::RuntimeEvent::Ibc(Event::MisbehaviourReported { reporter, .. })
+					if reporter != &self::reporter()
+			)));
+		})
+	}
+
+	#[test]
+	fn invalid_evidence_is_not_reported() {
+		new_test_ext().execute_with(|| {
+			let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+			setup(&client_id);
+
+			// evidence below the latest height of the client is rejected
+			assert_ok!(Ibc::deliver(
+				RuntimeOrigin::signed(reporter()),
+				vec![conflicting_mock_headers(&client_id, 5)]
+			));
+
+			assert_eq!(Ibc::client_status(&client_id), ClientStatus::Active);
+			assert_eq!(recorded_misbehaviour(), None);
+		})
+	}
+
+	#[test]
+	fn misbehaving_clients_are_read_from_messages() {
+		let client_id = ClientId::new(mock_client_type(), 3).unwrap();
+		let messages = vec![
+			Any { type_url: "/ibc.core.client.v1.MsgUpdateClient".to_string(), value: vec![] },
+			conflicting_mock_headers(&client_id, 20),
+		];
+
+		assert_eq!(misbehaving_client_ids(&messages), vec![client_id]);
+	}
+
+	#[test]
+	fn tendermint_misbehaviour_weight_grows_with_signatures() {
+		new_test_ext().execute_with(|| {
+			let client_id = ClientId::default();
+			let weight = |signatures| {
+				<() as WeightInfo<Test>>::misbehaviour(MsgSubmitMisbehaviour {
+					client_id: client_id.clone(),
+					misbehaviour: tendermint_duplicate_vote(&client_id, signatures),
+					signer: get_dummy_account_id(),
+				})
+			};
+
+			assert!(weight(4).ref_time() > weight(1).ref_time());
+			assert_eq!(
+				weight(4).ref_time() - weight(3).ref_time(),
+				weight(2).ref_time() - weight(1).ref_time()
+			);
+		})
+	}
+}
@@ -1,3 +1,7 @@
+mod client_status;
 mod create_client;
+mod misbehaviour;
+mod recover_client;
+mod schedule_upgrade;
 mod update_client;
 mod upgrade_client;
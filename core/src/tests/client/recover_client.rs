@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, AccountId, Ibc, RuntimeOrigin, System, Test},
+		status::ClientStatus,
+		tests::common::{client_id, client_state, setup, store_client},
+		ClientStates, ClientStatuses, ConsensusStates, Error, Event,
+	};
+	use frame_support::{assert_noop, assert_ok};
+	use ibc::core::ics24_host::{
+		identifier::ClientId,
+		path::{ClientConsensusStatePath, ClientStatePath},
+	};
+	use ibc_proto::{
+		google::protobuf::{Any, Duration},
+		ibc::{
+			core::client::v1::Height as RawHeight,
+			lightclients::tendermint::v1::ClientState as RawTmClientState,
+		},
+	};
+	use prost::Message;
+	use sp_runtime::DispatchError;
+
+	fn stored_client_state(client_id: &ClientId) -> RawTmClientState {
+		let data = ClientStates::<Test>::get(ClientStatePath(client_id.clone())).unwrap();
+		let any = Any::decode(data.as_slice()).unwrap();
+		RawTmClientState::decode(any.value.as_slice()).unwrap()
+	}
+
+	#[test]
+	fn recover_frozen_client() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let (subject, substitute) = (client_id(0), client_id(1));
+			store_client(&subject, client_state("chain-0", 10, 10, 500), 900);
+			store_client(&substitute, client_state("chain-1", 20, 0, 600), 900);
+
+			assert_ok!(Ibc::recover_client(
+				RuntimeOrigin::root(),
+				subject.clone(),
+				substitute.clone()
+			));
+
+			let recovered = stored_client_state(&subject);
+			assert_eq!(recovered.chain_id, "chain-1");
+			assert_eq!(recovered.trusting_period, Some(Duration { seconds: 600, nanos: 0 }));
+			assert_eq!(recovered.latest_height.unwrap().revision_height, 20);
+			assert_eq!(recovered.frozen_height, Some(RawHeight::default()));
+			assert!(ConsensusStates::<Test>::contains_key(ClientConsensusStatePath {
+				client_id: subject.clone(),
+				epoch: 0,
+				height: 20,
+			}));
+			System::assert_last_event(
+				Event::<Test>::ClientRecovered {
+					subject_client_id: subject,
+					substitute_client_id: substitute,
+				}
+				.into(),
+			);
+		})
+	}
+
+	#[test]
+	fn recover_expired_client() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let (subject, substitute) = (client_id(0), client_id(1));
+			// the trusting period of the subject elapsed at 600s
+			store_client(&subject, client_state("chain-0", 10, 0, 500), 100);
+			store_client(&substitute, client_state("chain-0", 20, 0, 500), 900);
+			assert_eq!(Ibc::refresh_client_status(&subject), ClientStatus::Expired);
+
+			assert_ok!(Ibc::recover_client(RuntimeOrigin::root(), subject.clone(), substitute));
+			assert_eq!(stored_client_state(&subject).latest_height.unwrap().revision_height, 20);
+			assert_eq!(ClientStatuses::<Test>::get(&subject), Some(ClientStatus::Active));
+			assert!(System::events().iter().any(|record| record.event ==
+				Event::<Test>::ClientStatusChanged {
+					client_id: subject.clone(),
+					status: ClientStatus::Active
+				}
+				.into()));
+		})
+	}
+
+	#[test]
+	fn recover_client_requires_admin_origin() {
+		new_test_ext().execute_with(|| {
+			setup();
+			assert_noop!(
+				Ibc::recover_client(
+					RuntimeOrigin::signed(AccountId::new([1u8; 32])),
+					client_id(0),
+					client_id(1)
+				),
+				DispatchError::BadOrigin
+			);
+		})
+	}
+
+	#[test]
+	fn recover_client_rejects_invalid_pairs() {
+		new_test_ext().execute_with(|| {
+			setup();
+			let (subject, substitute) = (client_id(0), client_id(1));
+
+			assert_noop!(
+				Ibc::recover_client(RuntimeOrigin::root(), subject.clone(), subject.clone()),
+				Error::<Test>::SameSubjectAndSubstitute
+			);
+			assert_noop!(
+				Ibc::recover_client(RuntimeOrigin::root(), subject.clone(), substitute.clone()),
+				Error::<Test>::ClientNotFound
+			);
+
+			// an active subject cannot be substituted
+			store_client(&subject, client_state("chain-0", 10, 0, 500), 900);
+			store_client(&substitute, client_state("chain-1", 20, 0, 500), 900);
+			assert_noop!(
+				Ibc::recover_client(RuntimeOrigin::root(), subject.clone(), substitute.clone()),
+				Error::<Test>::SubjectClientActive
+			);
+
+			// a frozen substitute is not healthy
+			store_client(&subject, client_state("chain-0", 10, 10, 500), 900);
+			store_client(&substitute, client_state("chain-1", 20, 20, 500), 900);
+			assert_noop!(
+				Ibc::recover_client(RuntimeOrigin::root(), subject.clone(), substitute.clone()),
+				Error::<Test>::SubstituteClientNotActive
+			);
+
+			// the substitute must be ahead of the subject
+			store_client(&substitute, client_state("chain-1", 5, 0, 500), 900);
+			assert_noop!(
+				Ibc::recover_client(RuntimeOrigin::root(), subject.clone(), substitute.clone()),
+				Error::<Test>::SubstituteHeightTooLow
+			);
+
+			// parameters other than chain id, trusting period and heights must match
+			let mut different = client_state("chain-1", 20, 0, 500);
+			different.unbonding_period = Some(Duration { seconds: 1, nanos: 0 });
+			store_client(&substitute, different, 900);
+			assert_noop!(
+				Ibc::recover_client(RuntimeOrigin::root(), subject, substitute),
+				Error::<Test>::ClientParamsMismatch
+			);
+		})
+	}
+}
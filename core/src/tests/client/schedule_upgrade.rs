@@ -0,0 +1,277 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, AccountId, Ibc, RuntimeOrigin, System, Test},
+		recovery::TENDERMINT_CLIENT_STATE_TYPE_URL,
+		Error, Event, ScheduledUpgrade, UpgradedClientStates, UpgradedConsensusStates,
+	};
+	use frame_support::{assert_noop, assert_ok};
+	use ibc::{
+		core::{ics23_commitment::specs::ProofSpecs, ics24_host::path::UpgradeClientPath},
+		mock::{
+			client_state::MockClientState, consensus_state::MockConsensusState, header::MockHeader,
+		},
+		Height,
+	};
+	use ibc_proto::{
+		google::protobuf::{Any, Duration, Timestamp},
+		ibc::{
+			core::{client::v1::Height as RawHeight, commitment::v1::MerkleRoot},
+			lightclients::tendermint::v1::{
+				ClientState as RawTmClientState, ConsensusState as RawTmConsensusState, Fraction,
+			},
+		},
+	};
+	use prost::Message;
+	use sp_runtime::DispatchError;
+
+	fn upgraded_states() -> (Any, Any) {
+		let height = Height::new(1, 1).unwrap();
+		(
+			MockClientState::new(MockHeader::new(height)).into(),
+			MockConsensusState::new(MockHeader::new(height)).into(),
+		)
+	}
+
+	fn tendermint_upgraded_states() -> (RawTmClientState, Any) {
+		let client_state = RawTmClientState {
+			chain_id: "chain-1".to_string(),
+			trust_level: Some(Fraction { numerator: 1, denominator: 3 }),
+			trusting_period: Some(Duration { seconds: 1_000, nanos: 0 }),
+			unbonding_period: Some(Duration { seconds: 2_000, nanos: 0 }),
+			max_clock_drift: Some(Duration { seconds: 3, nanos: 0 }),
+			latest_height: Some(RawHeight { revision_number: 1, revision_height: 1 }),
+			proof_specs: ProofSpecs::default().into(),
+			upgrade_path: vec!["upgrade".to_string(), "upgradedIBCState".to_string()],
+			..Default::default()
+		};
+		let consensus_state = RawTmConsensusState {
+			timestamp: Some(Timestamp { seconds: 1, nanos: 0 }),
+			root: Some(MerkleRoot { hash: vec![1; 32] }),
+			next_validators_hash: vec![1; 32],
+		};
+		(
+			client_state,
+			Any {
+				type_url: "/ibc.lightclients.tendermint.v1.ConsensusState".to_string(),
+				value: consensus_state.encode_to_vec(),
+			},
+		)
+	}
+
+	#[test]
+	fn schedule_upgrade_stores_upgraded_states() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, consensus_state) = upgraded_states();
+
+			assert_ok!(Ibc::schedule_upgrade(
+				RuntimeOrigin::root(),
+				100,
+				client_state.clone(),
+				consensus_state.clone()
+			));
+
+			assert_eq!(ScheduledUpgrade::<Test>::get(), Some(100));
+			assert_eq!(
+				UpgradedClientStates::<Test>::get(UpgradeClientPath::UpgradedClientState(100)),
+				Some(client_state.encode_to_vec())
+			);
+			assert_eq!(
+				UpgradedConsensusStates::<Test>::get(
+					UpgradeClientPath::UpgradedClientConsensusState(100)
+				),
+				Some(consensus_state.encode_to_vec())
+			);
+			System::assert_last_event(Event::<Test>::UpgradeScheduled { height: 100 }.into());
+		})
+	}
+
+	#[test]
+	fn schedule_upgrade_zeroes_custom_fields_of_tendermint_clients() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, consensus_state) = tendermint_upgraded_states();
+
+			assert_ok!(Ibc::schedule_upgrade(
+				RuntimeOrigin::root(),
+				100,
+				Any {
+					type_url: TENDERMINT_CLIENT_STATE_TYPE_URL.to_string(),
+					value: client_state.encode_to_vec(),
+				},
+				consensus_state
+			));
+
+			let stored =
+				UpgradedClientStates::<Test>::get(UpgradeClientPath::UpgradedClientState(100))
+					.unwrap();
+			let stored = Any::decode(stored.as_slice()).unwrap();
+			let stored = RawTmClientState::decode(stored.value.as_slice()).unwrap();
+			assert_eq!(
+				stored,
+				RawTmClientState {
+					chain_id: client_state.chain_id,
+					unbonding_period: client_state.unbonding_period,
+					latest_height: client_state.latest_height,
+					proof_specs: client_state.proof_specs,
+					upgrade_path: client_state.upgrade_path,
+					..Default::default()
+				}
+			);
+		})
+	}
+
+	#[test]
+	fn schedule_upgrade_requires_consensus_state_of_the_client_type() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, _) = tendermint_upgraded_states();
+			let (_, mock_consensus_state) = upgraded_states();
+
+			assert_noop!(
+				Ibc::schedule_upgrade(
+					RuntimeOrigin::root(),
+					100,
+					Any {
+						type_url: TENDERMINT_CLIENT_STATE_TYPE_URL.to_string(),
+						value: client_state.encode_to_vec(),
+					},
+					mock_consensus_state
+				),
+				Error::<Test>::InvalidConsensusState
+			);
+		})
+	}
+
+	#[test]
+	fn schedule_upgrade_replaces_previous_upgrade() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, consensus_state) = upgraded_states();
+
+			assert_ok!(Ibc::schedule_upgrade(
+				RuntimeOrigin::root(),
+				100,
+				client_state.clone(),
+				consensus_state.clone()
+			));
+			assert_ok!(Ibc::schedule_upgrade(
+				RuntimeOrigin::root(),
+				200,
+				client_state,
+				consensus_state
+			));
+
+			assert_eq!(ScheduledUpgrade::<Test>::get(), Some(200));
+			assert!(!UpgradedClientStates::<Test>::contains_key(
+				UpgradeClientPath::UpgradedClientState(100)
+			));
+			assert!(!UpgradedConsensusStates::<Test>::contains_key(
+				UpgradeClientPath::UpgradedClientConsensusState(100)
+			));
+			assert!(UpgradedClientStates::<Test>::contains_key(
+				UpgradeClientPath::UpgradedClientState(200)
+			));
+		})
+	}
+
+	#[test]
+	fn schedule_upgrade_rejects_invalid_requests() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, consensus_state) = upgraded_states();
+
+			assert_noop!(
+				Ibc::schedule_upgrade(
+					RuntimeOrigin::signed(AccountId::new([1u8; 32])),
+					100,
+					client_state.clone(),
+					consensus_state.clone()
+				),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Ibc::schedule_upgrade(
+					RuntimeOrigin::root(),
+					10,
+					client_state.clone(),
+					consensus_state.clone()
+				),
+				Error::<Test>::InvalidUpgradeHeight
+			);
+			assert_noop!(
+				Ibc::schedule_upgrade(
+					RuntimeOrigin::root(),
+					100,
+					consensus_state.clone(),
+					consensus_state.clone()
+				),
+				Error::<Test>::InvalidClientState
+			);
+			assert_noop!(
+				Ibc::schedule_upgrade(
+					RuntimeOrigin::root(),
+					100,
+					client_state.clone(),
+					client_state
+				),
+				Error::<Test>::InvalidConsensusState
+			);
+		})
+	}
+
+	#[test]
+	fn cancel_upgrade_removes_upgraded_states() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, consensus_state) = upgraded_states();
+
+			assert_noop!(
+				Ibc::cancel_upgrade(RuntimeOrigin::root()),
+				Error::<Test>::NoScheduledUpgrade
+			);
+
+			assert_ok!(Ibc::schedule_upgrade(
+				RuntimeOrigin::root(),
+				100,
+				client_state,
+				consensus_state
+			));
+			assert_noop!(
+				Ibc::cancel_upgrade(RuntimeOrigin::signed(AccountId::new([1u8; 32]))),
+				DispatchError::BadOrigin
+			);
+			assert_ok!(Ibc::cancel_upgrade(RuntimeOrigin::root()));
+
+			assert_eq!(ScheduledUpgrade::<Test>::get(), None);
+			assert!(!UpgradedClientStates::<Test>::contains_key(
+				UpgradeClientPath::UpgradedClientState(100)
+			));
+			assert!(!UpgradedConsensusStates::<Test>::contains_key(
+				UpgradeClientPath::UpgradedClientConsensusState(100)
+			));
+			System::assert_last_event(Event::<Test>::UpgradeCancelled { height: 100 }.into());
+		})
+	}
+
+	#[test]
+	fn cancel_upgrade_after_upgrade_height_fails() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			let (client_state, consensus_state) = upgraded_states();
+			assert_ok!(Ibc::schedule_upgrade(
+				RuntimeOrigin::root(),
+				100,
+				client_state,
+				consensus_state
+			));
+
+			System::set_block_number(100);
+			assert_noop!(
+				Ibc::cancel_upgrade(RuntimeOrigin::root()),
+				Error::<Test>::InvalidUpgradeHeight
+			);
+		})
+	}
+}
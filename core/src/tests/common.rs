@@ -1,4 +1,31 @@
-use ibc::signer::Signer;
+use crate::{
+	mock::{PalletTimestamp, System, Test},
+	recovery::TENDERMINT_CLIENT_STATE_TYPE_URL,
+	ClientStates, Clients, ConsensusStates, TENDERMINT_CLIENT_TYPE,
+};
+use ibc::{
+	core::{
+		ics02_client::client_type::ClientType,
+		ics24_host::{
+			identifier::ClientId,
+			path::{ClientConsensusStatePath, ClientStatePath, ClientTypePath},
+		},
+	},
+	signer::Signer,
+};
+use ibc_proto::{
+	google::protobuf::{Any, Duration, Timestamp},
+	ibc::{
+		core::client::v1::Height as RawHeight,
+		lightclients::tendermint::v1::{
+			ClientState as RawTmClientState, ConsensusState as RawTmConsensusState,
+		},
+	},
+};
+use prost::Message;
+
+/// The host time set by `setup`, in seconds.
+pub const NOW_SECS: u64 = 1_000;
 
 pub fn get_dummy_proof() -> Vec<u8> {
 	"Y29uc2Vuc3VzU3RhdGUvaWJjb25lY2xpZW50LzIy".as_bytes().to_vec()
@@ -11,3 +38,57 @@ pub fn get_dummy_account_id() -> Signer {
 pub fn get_dummy_bech32_account() -> String {
 	"cosmos1wxeyh7zgn4tctjzs0vtqpc6p5cxq5t2muzl7ng".to_string()
 }
+
+pub fn setup() {
+	System::set_block_number(1);
+	PalletTimestamp::set_timestamp(NOW_SECS * 1_000);
+}
+
+pub fn client_id(index: u64) -> ClientId {
+	ClientId::new(ClientType::new(TENDERMINT_CLIENT_TYPE.into()), index).unwrap()
+}
+
+pub fn client_state(chain_id: &str, latest: u64, frozen: u64, trusting: i64) -> RawTmClientState {
+	RawTmClientState {
+		chain_id: chain_id.to_string(),
+		trusting_period: Some(Duration { seconds: trusting, nanos: 0 }),
+		unbonding_period: Some(Duration { seconds: 100_000, nanos: 0 }),
+		max_clock_drift: Some(Duration { seconds: 3, nanos: 0 }),
+		latest_height: Some(RawHeight { revision_number: 0, revision_height: latest }),
+		frozen_height: Some(RawHeight { revision_number: 0, revision_height: frozen }),
+		..Default::default()
+	}
+}
+
+/// Store a tendermint client whose latest consensus state has the given timestamp.
+pub fn store_client(client_id: &ClientId, client_state: RawTmClientState, timestamp: i64) {
+	let height = client_state.latest_height.clone().unwrap();
+	Clients::<Test>::insert(
+		ClientTypePath(client_id.clone()),
+		ClientType::new(TENDERMINT_CLIENT_TYPE.into()),
+	);
+	ClientStates::<Test>::insert(
+		ClientStatePath(client_id.clone()),
+		Any {
+			type_url: TENDERMINT_CLIENT_STATE_TYPE_URL.to_string(),
+			value: client_state.encode_to_vec(),
+		}
+		.encode_to_vec(),
+	);
+	ConsensusStates::<Test>::insert(
+		ClientConsensusStatePath {
+			client_id: client_id.clone(),
+			epoch: height.revision_number,
+			height: height.revision_height,
+		},
+		Any {
+			type_url: "/ibc.lightclients.tendermint.v1.ConsensusState".to_string(),
+			value: RawTmConsensusState {
+				timestamp: Some(Timestamp { seconds: timestamp, nanos: 0 }),
+				..Default::default()
+			}
+			.encode_to_vec(),
+		}
+		.encode_to_vec(),
+	);
+}
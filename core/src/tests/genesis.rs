@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		genesis::{GenesisChannel, GenesisClient, GenesisConnection},
+		mock::{new_test_ext, Test},
+		ChannelCounter, Context, GenesisConfig, NextSequenceSend, Pallet, MOCK_CLIENT_TYPE,
+	};
+	use frame_support::traits::BuildGenesisConfig;
+	use ibc::{
+		core::{
+			ics02_client::{client_state::ClientState, context::ClientReader},
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				context::ConnectionReader,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State as ChannelState},
+				packet::Sequence,
+				Version,
+			},
+			ics24_host::{
+				identifier::{ChannelId, ClientId, ConnectionId, PortId},
+				path::SeqSendsPath,
+			},
+		},
+		mock::{
+			client_state::{client_type as mock_client_type, MockClientState},
+			consensus_state::MockConsensusState,
+			header::MockHeader,
+		},
+		timestamp::ZERO_DURATION,
+		Height,
+	};
+	use ibc_proto::google::protobuf::Any;
+	use pallet_ibc_utils::traits::ChannelReaderInterface;
+	use prost::Message;
+	use sp_std::{marker::PhantomData, str::FromStr};
+
+	fn genesis() -> GenesisConfig<Test> {
+		let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+		let height = Height::new(0, 10).unwrap();
+		let connection_id = ConnectionId::new(0);
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::new(
+				client_id.clone(),
+				Some(connection_id.clone()),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			ChannelState::Open,
+			Order::Unordered,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(0))),
+			vec![connection_id.clone()],
+			Version::new("ics20-1".to_string()),
+		);
+
+		GenesisConfig {
+			clients: vec![GenesisClient {
+				client_id,
+				client_type: MOCK_CLIENT_TYPE.to_string(),
+				client_state: Any::from(MockClientState::new(MockHeader::new(height)))
+					.encode_to_vec(),
+				consensus_states: vec![(
+					height,
+					Any::from(MockConsensusState::new(MockHeader::new(height))).encode_to_vec(),
+				)],
+			}],
+			connections: vec![GenesisConnection { connection_id, connection_end }],
+			channels: vec![GenesisChannel {
+				port_id: PortId::transfer(),
+				channel_id: ChannelId::new(0),
+				channel_end,
+				next_sequence_send: 5,
+				next_sequence_recv: 3,
+				next_sequence_ack: 2,
+			}],
+			client_counter: 1,
+			connection_counter: 1,
+			channel_counter: 1,
+			_marker: PhantomData,
+		}
+	}
+
+	#[test]
+	fn genesis_bootstraps_open_channel() {
+		new_test_ext().execute_with(|| {
+			genesis().build();
+
+			let ctx = Context::<Test>::new();
+			let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+			assert_eq!(
+				ClientReader::client_state(&ctx, &client_id).unwrap().latest_height(),
+				Height::new(0, 10).unwrap()
+			);
+			assert!(ConnectionReader::connection_end(&ctx, &ConnectionId::new(0)).is_ok());
+			assert_eq!(
+				<Context<Test> as ChannelReaderInterface>::connection_channels(&ConnectionId::new(
+					0
+				))
+				.unwrap(),
+				vec![(PortId::transfer(), ChannelId::new(0))]
+			);
+			assert_eq!(
+				NextSequenceSend::<Test>::get(SeqSendsPath(PortId::transfer(), ChannelId::new(0))),
+				Some(Sequence::from(5))
+			);
+			assert_eq!(ChannelCounter::<Test>::get(), 1);
+		})
+	}
+
+	#[test]
+	fn export_genesis_round_trip() {
+		new_test_ext().execute_with(|| {
+			let config = genesis();
+			config.build();
+
+			let exported = Pallet::<Test>::export_genesis();
+			assert_eq!(exported.clients, config.clients);
+			assert_eq!(exported.connections, config.connections);
+			assert_eq!(exported.channels, config.channels);
+			assert_eq!(
+				(exported.client_counter, exported.connection_counter, exported.channel_counter),
+				(1, 1, 1)
+			);
+		})
+	}
+
+	#[test]
+	fn channel_over_unknown_connection_is_rejected() {
+		let mut config = genesis();
+		config.connections.clear();
+
+		assert_eq!(
+			config.validate(),
+			Err("channel transfer/channel-0 references unknown connection connection-0".to_string())
+		);
+	}
+
+	#[test]
+	fn connection_over_unknown_client_is_rejected() {
+		let mut config = genesis();
+		config.clients.clear();
+
+		assert_eq!(
+			config.validate(),
+			Err("connection connection-0 references unknown client 9999-mock-0".to_string())
+		);
+	}
+
+	#[test]
+	fn connection_without_counterparty_connection_is_rejected() {
+		let mut config = genesis();
+		let connection_end = &mut config.connections[0].connection_end;
+		connection_end.set_counterparty(ConnectionCounterparty::new(
+			ClientId::new(mock_client_type(), 0).unwrap(),
+			None,
+			Default::default(),
+		));
+
+		assert_eq!(
+			config.validate(),
+			Err("connection connection-0 has no counterparty connection".to_string())
+		);
+	}
+
+	#[test]
+	fn connection_without_versions_is_rejected() {
+		let mut config = genesis();
+		let client_id = ClientId::new(mock_client_type(), 0).unwrap();
+		config.connections[0].connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::new(client_id, Some(ConnectionId::new(0)), Default::default()),
+			vec![],
+			ZERO_DURATION,
+		);
+
+		assert_eq!(
+			config.validate(),
+			Err("connection connection-0 has no or unsupported versions".to_string())
+		);
+	}
+
+	#[test]
+	fn channel_on_unbound_port_is_rejected() {
+		let mut config = genesis();
+		config.channels[0].port_id = PortId::from_str("unbound").unwrap();
+
+		assert_eq!(
+			config.validate(),
+			Err("channel unbound/channel-0 is on unbound port unbound".to_string())
+		);
+	}
+
+	#[test]
+	fn counter_below_genesis_ids_is_rejected() {
+		let mut config = genesis();
+		config.channel_counter = 0;
+
+		assert_eq!(
+			config.validate(),
+			Err("channel counter 0 does not exceed the index of channel-0".to_string())
+		);
+	}
+
+	#[test]
+	#[should_panic(expected = "invalid pallet-ibc genesis")]
+	fn invalid_genesis_panics() {
+		new_test_ext().execute_with(|| {
+			let mut config = genesis();
+			config.clients.clear();
+			config.build();
+		})
+	}
+}
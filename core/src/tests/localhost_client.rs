@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		genesis::GenesisChannel,
+		localhost_client::{localhost_client_id, localhost_connection_id},
+		mock::{new_test_ext, System, Test},
+		status::ClientStatus,
+		Context, GenesisConfig, Pallet,
+	};
+	use frame_support::traits::BuildGenesisConfig;
+	use ibc::{
+		core::{
+			ics02_client::{client_state::ClientState, context::ClientReader},
+			ics03_connection::{connection::State as ConnectionState, context::ConnectionReader},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State as ChannelState},
+				commitment::PacketCommitment,
+				context::ChannelKeeper,
+				packet::{Receipt, Sequence},
+				Version,
+			},
+			ics23_commitment::commitment::{CommitmentProofBytes, CommitmentRoot},
+			ics24_host::{
+				identifier::{ChannelId, PortId},
+				path::{ChannelEndsPath, CommitmentsPath, ConnectionsPath, ReceiptsPath},
+			},
+		},
+		Height,
+	};
+
+	fn proof() -> CommitmentProofBytes {
+		CommitmentProofBytes::try_from(vec![1]).unwrap()
+	}
+
+	fn root() -> CommitmentRoot {
+		CommitmentRoot::from_bytes(&[])
+	}
+
+	#[test]
+	fn genesis_creates_localhost_connection() {
+		new_test_ext().execute_with(|| {
+			GenesisConfig::<Test>::default().build();
+			System::set_block_number(5);
+
+			let ctx = Context::<Test>::new();
+			let connection_end =
+				ConnectionReader::connection_end(&ctx, &localhost_connection_id()).unwrap();
+			assert_eq!(connection_end.state(), &ConnectionState::Open);
+			assert_eq!(connection_end.client_id(), &localhost_client_id());
+			assert_eq!(
+				connection_end.counterparty().connection_id(),
+				Some(&localhost_connection_id())
+			);
+
+			// the client follows the host height, with a consensus state at every height
+			let host_height = ClientReader::host_height(&ctx).unwrap();
+			let client_state = ClientReader::client_state(&ctx, &localhost_client_id()).unwrap();
+			assert_eq!(client_state.latest_height(), host_height);
+			assert!(ClientReader::consensus_state(
+				&ctx,
+				&localhost_client_id(),
+				&Height::new(0, 3).unwrap()
+			)
+			.is_ok());
+			assert_eq!(Pallet::<Test>::client_status(&localhost_client_id()), ClientStatus::Active);
+		})
+	}
+
+	#[test]
+	fn localhost_client_verifies_own_store() {
+		new_test_ext().execute_with(|| {
+			GenesisConfig::<Test>::default().build();
+			System::set_block_number(5);
+
+			let (port_id, channel_id) = (PortId::transfer(), ChannelId::new(0));
+			let sequence = Sequence::from(1);
+			let mut ctx = Context::<Test>::new();
+			ctx.store_packet_commitment(
+				port_id.clone(),
+				channel_id.clone(),
+				sequence,
+				PacketCommitment::from(vec![1, 2, 3]),
+			)
+			.unwrap();
+
+			let client_state = ClientReader::client_state(&ctx, &localhost_client_id()).unwrap();
+			let connection_end =
+				ConnectionReader::connection_end(&ctx, &localhost_connection_id()).unwrap();
+			let height = client_state.latest_height();
+			let commitment_path = CommitmentsPath {
+				port_id: port_id.clone(),
+				channel_id: channel_id.clone(),
+				sequence,
+			};
+			let verify_packet = |commitment: Vec<u8>| {
+				client_state.verify_packet_data(
+					&ctx,
+					height,
+					&connection_end,
+					&proof(),
+					&root(),
+					&commitment_path,
+					PacketCommitment::from(commitment),
+				)
+			};
+			assert!(verify_packet(vec![1, 2, 3]).is_ok());
+			assert!(verify_packet(vec![4, 5, 6]).is_err());
+
+			assert!(client_state
+				.verify_connection_state(
+					height,
+					connection_end.counterparty().prefix(),
+					&proof(),
+					&root(),
+					&ConnectionsPath(localhost_connection_id()),
+					&connection_end,
+				)
+				.is_ok());
+
+			let receipt_path =
+				ReceiptsPath { port_id: port_id.clone(), channel_id: channel_id.clone(), sequence };
+			let verify_absence = |ctx: &Context<Test>| {
+				client_state.verify_packet_receipt_absence(
+					ctx,
+					height,
+					&connection_end,
+					&proof(),
+					&root(),
+					&receipt_path,
+				)
+			};
+			assert!(verify_absence(&ctx).is_ok());
+			let mut ctx = Context::<Test>::new();
+			ctx.store_packet_receipt(port_id, channel_id, sequence, Receipt::Ok).unwrap();
+			assert!(verify_absence(&ctx).is_err());
+		})
+	}
+
+	#[test]
+	fn localhost_channels_in_genesis() {
+		new_test_ext().execute_with(|| {
+			let channel_end = ChannelEnd::new(
+				ChannelState::Open,
+				Order::Unordered,
+				Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+				vec![localhost_connection_id()],
+				Version::new("ics20-1".to_string()),
+			);
+			let mut config = GenesisConfig::<Test>::default();
+			config.channels = vec![GenesisChannel {
+				port_id: PortId::transfer(),
+				channel_id: ChannelId::new(0),
+				channel_end: channel_end.clone(),
+				next_sequence_send: 1,
+				next_sequence_recv: 1,
+				next_sequence_ack: 1,
+			}];
+			config.channel_counter = 1;
+			assert_eq!(config.validate(), Ok(()));
+			config.build();
+
+			let ctx = Context::<Test>::new();
+			let client_state = ClientReader::client_state(&ctx, &localhost_client_id()).unwrap();
+			let connection_end =
+				ConnectionReader::connection_end(&ctx, &localhost_connection_id()).unwrap();
+			assert!(client_state
+				.verify_channel_state(
+					client_state.latest_height(),
+					connection_end.counterparty().prefix(),
+					&proof(),
+					&root(),
+					&ChannelEndsPath(PortId::transfer(), ChannelId::new(0)),
+					&channel_end,
+				)
+				.is_ok());
+
+			// the localhost client and connection are not exported, the next genesis recreates them
+			let exported = Pallet::<Test>::export_genesis();
+			assert!(exported.clients.is_empty());
+			assert!(exported.connections.is_empty());
+			assert_eq!(exported.validate(), Ok(()));
+		})
+	}
+}
@@ -0,0 +1,246 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		migrations::{v1::MigrateToV1, v2::MigrateToV2, v3::MigrateToV3},
+		mock::{new_test_ext, Ibc, System, Test},
+		pruning::PacketState,
+		Acknowledgements, Context, IbcEventStore, InFlightPackets, PacketCommitment, PacketReceipt,
+		PacketStateMigrationCursor, PacketStateUse,
+	};
+	use codec::Encode;
+	use frame_support::{
+		traits::{Hooks, OnRuntimeUpgrade, StorageVersion},
+		weights::Weight,
+	};
+	use ibc::{
+		core::{
+			ics02_client::events::CreateClient,
+			ics04_channel::{
+				commitment::{AcknowledgementCommitment, PacketCommitment as IbcPacketCommitment},
+				context::ChannelKeeper,
+				packet::{Receipt, Sequence},
+			},
+			ics24_host::{
+				identifier::{ChannelId, ClientId, PortId},
+				path::{AcksPath, ChannelEndsPath, CommitmentsPath, ReceiptsPath},
+			},
+		},
+		events::IbcEvent,
+		mock::client_state::client_type as mock_client_type,
+		Height,
+	};
+
+	fn create_client_event(index: u64) -> IbcEvent {
+		IbcEvent::CreateClient(CreateClient::new(
+			ClientId::new(mock_client_type(), index).unwrap(),
+			mock_client_type(),
+			Height::new(0, 1).unwrap(),
+		))
+	}
+
+	#[test]
+	fn migrate_event_store_to_v1() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(0).put::<Ibc>();
+			// version 0 stored a single event per height
+			for height in [5u64, 6] {
+				frame_support::storage::unhashed::put(
+					&IbcEventStore::<Test>::hashed_key_for(height),
+					&create_client_event(height),
+				);
+			}
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get::<Ibc>(), 1);
+			for height in [5u64, 6] {
+				assert_eq!(
+					IbcEventStore::<Test>::get(height).unwrap().encode(),
+					vec![create_client_event(height)].encode()
+				);
+			}
+		})
+	}
+
+	#[test]
+	fn migration_to_v1_runs_once() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(1).put::<Ibc>();
+			IbcEventStore::<Test>::insert(5, vec![create_client_event(0), create_client_event(1)]);
+
+			MigrateToV1::<Test>::on_runtime_upgrade();
+
+			assert_eq!(IbcEventStore::<Test>::get(5).unwrap().len(), 2);
+		})
+	}
+
+	fn store_commitment(channel: u64, sequence: u64) {
+		PacketCommitment::<Test>::insert(
+			CommitmentsPath {
+				port_id: PortId::transfer(),
+				channel_id: ChannelId::new(channel),
+				sequence: Sequence::from(sequence),
+			},
+			IbcPacketCommitment::from(vec![1, 2, 3]),
+		);
+	}
+
+	fn in_flight(channel: u64) -> u64 {
+		InFlightPackets::<Test>::get(ChannelEndsPath(PortId::transfer(), ChannelId::new(channel)))
+	}
+
+	#[test]
+	fn migrate_packets_in_flight_to_v2() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(1).put::<Ibc>();
+			// version 1 stored the commitments without counting them
+			store_commitment(0, 1);
+			store_commitment(0, 3);
+			store_commitment(1, 2);
+
+			MigrateToV2::<Test>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get::<Ibc>(), 2);
+			assert_eq!(in_flight(0), 2);
+			assert_eq!(in_flight(1), 1);
+			assert_eq!(in_flight(2), 0);
+		})
+	}
+
+	#[test]
+	fn migration_to_v2_runs_once() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(2).put::<Ibc>();
+			store_commitment(0, 1);
+
+			MigrateToV2::<Test>::on_runtime_upgrade();
+
+			assert_eq!(in_flight(0), 0);
+		})
+	}
+
+	/// Store a receipt and an acknowledgement of `sequence` like version 2, without counting them.
+	fn store_packet_state(channel: u64, sequence: u64) {
+		let (port_id, channel_id) = (PortId::transfer(), ChannelId::new(channel));
+		let sequence = Sequence::from(sequence);
+		PacketReceipt::<Test>::insert(
+			ReceiptsPath { port_id: port_id.clone(), channel_id: channel_id.clone(), sequence },
+			Receipt::Ok,
+		);
+		Acknowledgements::<Test>::insert(
+			AcksPath { port_id, channel_id, sequence },
+			AcknowledgementCommitment::from(vec![1]),
+		);
+	}
+
+	fn packet_state(channel: u64) -> PacketState {
+		PacketStateUse::<Test>::get(ChannelEndsPath(PortId::transfer(), ChannelId::new(channel)))
+	}
+
+	#[test]
+	fn migrate_packet_state_to_v3() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(2).put::<Ibc>();
+			for sequence in 1..=3 {
+				store_packet_state(0, sequence);
+			}
+			store_packet_state(1, 7);
+
+			MigrateToV3::<Test>::on_runtime_upgrade();
+			assert_eq!(StorageVersion::get::<Ibc>(), 3);
+			assert_eq!(packet_state(0), PacketState::default());
+
+			Ibc::on_idle(System::block_number(), Weight::MAX);
+
+			assert!(!PacketStateMigrationCursor::<Test>::exists());
+			assert_eq!(
+				packet_state(0),
+				PacketState { receipts: 3, acknowledgements: 3, highest_sequence: 3 }
+			);
+			assert_eq!(
+				packet_state(1),
+				PacketState { receipts: 1, acknowledgements: 1, highest_sequence: 7 }
+			);
+		})
+	}
+
+	#[test]
+	fn packet_state_changed_during_the_migration_is_counted_once() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(2).put::<Ibc>();
+			for sequence in 1..=4 {
+				store_packet_state(0, sequence);
+			}
+			MigrateToV3::<Test>::on_runtime_upgrade();
+
+			// count two entries per block, storing and removing some in between
+			let mut ctx = Context::<Test>::new();
+			let (port_id, channel_id) = (PortId::transfer(), ChannelId::new(0));
+			let mut sequence = 5;
+			while PacketStateMigrationCursor::<Test>::exists() {
+				Ibc::on_idle(System::block_number(), Weight::from_parts(5_000_000, 0));
+				let stored = Sequence::from(sequence);
+				ctx.store_packet_receipt(port_id.clone(), channel_id.clone(), stored, Receipt::Ok)
+					.unwrap();
+				ctx.store_packet_acknowledgement(
+					port_id.clone(),
+					channel_id.clone(),
+					stored,
+					AcknowledgementCommitment::from(vec![1]),
+				)
+				.unwrap();
+				ctx.delete_packet_acknowledgement(
+					&port_id,
+					&channel_id,
+					&Sequence::from(sequence - 4),
+				)
+				.unwrap();
+				sequence += 1;
+			}
+
+			assert_eq!(
+				packet_state(0),
+				PacketState {
+					receipts: PacketReceipt::<Test>::iter_keys().count() as u64,
+					acknowledgements: Acknowledgements::<Test>::iter_keys().count() as u64,
+					highest_sequence: sequence - 1,
+				}
+			);
+		})
+	}
+
+	#[test]
+	fn migration_to_v3_runs_once() {
+		new_test_ext().execute_with(|| {
+			StorageVersion::new(3).put::<Ibc>();
+
+			MigrateToV3::<Test>::on_runtime_upgrade();
+
+			assert!(!PacketStateMigrationCursor::<Test>::exists());
+		})
+	}
+
+	#[test]
+	fn events_of_a_height_are_appended() {
+		new_test_ext().execute_with(|| {
+			IbcEventStore::<Test>::append(5, create_client_event(0));
+			IbcEventStore::<Test>::append(5, create_client_event(1));
+
+			assert_eq!(
+				IbcEventStore::<Test>::get(5).unwrap().encode(),
+				vec![create_client_event(0), create_client_event(1)].encode()
+			);
+		})
+	}
+
+	#[test]
+	fn events_are_cleared_in_the_next_block() {
+		new_test_ext().execute_with(|| {
+			IbcEventStore::<Test>::append(5, create_client_event(0));
+
+			Ibc::on_initialize(6);
+
+			assert!(!IbcEventStore::<Test>::contains_key(5));
+		})
+	}
+}
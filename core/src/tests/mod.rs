@@ -3,6 +3,13 @@ pub mod client;
 pub mod commitment;
 pub mod common;
 pub mod connection;
+pub mod genesis;
+pub mod localhost_client;
+pub mod migrations;
+pub mod pruning;
+pub mod relayer_policy;
+pub mod simulation;
+pub mod wasm_client;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, Ibc, RuntimeEvent, RuntimeOrigin, System, Test},
+		pruning::ChannelStorageUse,
+		Context, Error, Event, Pallet, PruningWatermarks,
+	};
+	use frame_support::{assert_noop, assert_ok, traits::Hooks, weights::Weight};
+	use ibc::core::{
+		ics04_channel::{
+			channel::{ChannelEnd, State},
+			commitment::AcknowledgementCommitment,
+			context::ChannelKeeper,
+			packet::{Receipt, Sequence},
+		},
+		ics24_host::{
+			identifier::{ChannelId, PortId},
+			path::ChannelEndsPath,
+		},
+	};
+
+	fn channel_id() -> ChannelId {
+		ChannelId::new(0)
+	}
+
+	/// A channel in `state` with a receipt and an acknowledgement for sequences `1..=count`.
+	fn channel_with_packets(state: State, count: u64) {
+		let mut channel_end = ChannelEnd::default();
+		channel_end.set_state(state);
+		let mut ctx =
+			Context::<Test>::new().with_channel(PortId::transfer(), channel_id(), channel_end);
+		for sequence in 1..=count {
+			let sequence = Sequence::from(sequence);
+			ctx.store_packet_receipt(PortId::transfer(), channel_id(), sequence, Receipt::Ok)
+				.unwrap();
+			ctx.store_packet_acknowledgement(
+				PortId::transfer(),
+				channel_id(),
+				sequence,
+				AcknowledgementCommitment::from(vec![1]),
+			)
+			.unwrap();
+		}
+	}
+
+	fn storage_use() -> ChannelStorageUse {
+		Pallet::<Test>::channel_storage_use(PortId::transfer(), channel_id())
+	}
+
+	fn on_idle(weight: Weight) -> Weight {
+		Ibc::on_idle(System::block_number(), weight)
+	}
+
+	#[test]
+	fn storage_use_counts_packet_state() {
+		new_test_ext().execute_with(|| {
+			channel_with_packets(State::Open, 3);
+			let mut ctx = Context::<Test>::new();
+			ctx.delete_packet_acknowledgement(
+				&PortId::transfer(),
+				&channel_id(),
+				&Sequence::from(2),
+			)
+			.unwrap();
+
+			assert_eq!(
+				storage_use(),
+				ChannelStorageUse {
+					packet_commitments: 0,
+					packet_receipts: 3,
+					acknowledgements: 2,
+					pruned_below: 1,
+					pruning_watermark: None,
+				}
+			);
+		})
+	}
+
+	#[test]
+	fn prunes_below_the_watermark() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			channel_with_packets(State::Open, 5);
+
+			assert_ok!(Ibc::set_pruning_watermark(
+				RuntimeOrigin::root(),
+				PortId::transfer(),
+				channel_id(),
+				4
+			));
+			System::assert_last_event(RuntimeEvent::Ibc(Event::PruningScheduled {
+				port_id: PortId::transfer(),
+				channel_id: channel_id(),
+				watermark: 4,
+			}));
+
+			on_idle(Weight::MAX);
+			System::assert_last_event(RuntimeEvent::Ibc(Event::PacketStatePruned {
+				port_id: PortId::transfer(),
+				channel_id: channel_id(),
+				below: 4,
+			}));
+			let used = storage_use();
+			assert_eq!((used.packet_receipts, used.acknowledgements), (2, 2));
+			assert_eq!((used.pruned_below, used.pruning_watermark), (4, None));
+
+			assert_noop!(
+				Ibc::set_pruning_watermark(
+					RuntimeOrigin::root(),
+					PortId::transfer(),
+					channel_id(),
+					4
+				),
+				Error::<Test>::InvalidWatermark
+			);
+		})
+	}
+
+	#[test]
+	fn pruning_is_bounded_per_block() {
+		new_test_ext().execute_with(|| {
+			channel_with_packets(State::Open, 25);
+			assert_ok!(Ibc::set_pruning_watermark(
+				RuntimeOrigin::root(),
+				PortId::transfer(),
+				channel_id(),
+				26
+			));
+
+			// MaxPrunedPerBlock of the mock is 10
+			on_idle(Weight::MAX);
+			assert_eq!(storage_use().packet_receipts, 15);
+			assert_eq!(storage_use().pruned_below, 11);
+
+			// no sequence fits in the remaining weight
+			on_idle(Weight::zero());
+			assert_eq!(storage_use().packet_receipts, 15);
+
+			on_idle(Weight::MAX);
+			on_idle(Weight::MAX);
+			assert_eq!(storage_use().packet_receipts, 0);
+			assert!(!<PruningWatermarks<Test>>::contains_key(ChannelEndsPath(
+				PortId::transfer(),
+				channel_id()
+			)));
+		})
+	}
+
+	#[test]
+	fn prunes_closed_channels_only() {
+		new_test_ext().execute_with(|| {
+			channel_with_packets(State::Open, 3);
+			assert_noop!(
+				Ibc::prune_closed_channel(RuntimeOrigin::root(), PortId::transfer(), channel_id()),
+				Error::<Test>::ChannelNotClosed
+			);
+			assert_noop!(
+				Ibc::prune_closed_channel(
+					RuntimeOrigin::root(),
+					PortId::transfer(),
+					ChannelId::new(9)
+				),
+				Error::<Test>::ChannelNotFound
+			);
+
+			channel_with_packets(State::Closed, 15);
+			assert_ok!(Ibc::prune_closed_channel(
+				RuntimeOrigin::root(),
+				PortId::transfer(),
+				channel_id()
+			));
+			// the first MaxPrunedPerBlock sequences are pruned right away
+			let used = storage_use();
+			assert_eq!((used.packet_receipts, used.acknowledgements), (5, 5));
+			assert_eq!(used.pruning_watermark, Some(16));
+
+			on_idle(Weight::MAX);
+			let used = storage_use();
+			assert_eq!((used.packet_receipts, used.acknowledgements), (0, 0));
+		})
+	}
+}
@@ -0,0 +1,243 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, AccountId, Ibc, RuntimeOrigin, System, Test},
+		relayer_policy::{message_category, ClientCreationPolicy, MessageCategory, RelayerScope},
+		tests::common::get_dummy_account_id,
+		ClientCounter, Context, Error, Pallet,
+	};
+	use frame_support::{assert_noop, assert_ok};
+	use ibc::{
+		core::{
+			ics02_client::msgs::create_client::MsgCreateClient,
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				Version,
+			},
+			ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		},
+		mock::{
+			client_state::MockClientState, consensus_state::MockConsensusState, header::MockHeader,
+		},
+		tx_msg::Msg,
+		Height,
+	};
+	use ibc_proto::{
+		google::protobuf::Any,
+		ibc::core::{
+			channel::v1::{MsgAcknowledgement as RawMsgAcknowledgement, Packet as RawPacket},
+			client::v1::MsgSubmitMisbehaviour as RawMsgSubmitMisbehaviour,
+		},
+	};
+	use prost::Message;
+	use sp_runtime::DispatchError;
+
+	fn relayer() -> AccountId {
+		AccountId::new([3u8; 32])
+	}
+
+	fn other_relayer() -> AccountId {
+		AccountId::new([4u8; 32])
+	}
+
+	fn open_channel() {
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+			vec![ConnectionId::new(0)],
+			Version::new("ics20-1".to_string()),
+		);
+		let _ =
+			Context::<Test>::new().with_channel(PortId::transfer(), ChannelId::new(0), channel_end);
+	}
+
+	fn ack() -> Any {
+		let msg = RawMsgAcknowledgement {
+			packet: Some(RawPacket {
+				sequence: 1,
+				source_port: "transfer".to_string(),
+				source_channel: "channel-0".to_string(),
+				destination_port: "transfer".to_string(),
+				destination_channel: "channel-1".to_string(),
+				..Default::default()
+			}),
+			..Default::default()
+		};
+		Any {
+			type_url: "/ibc.core.channel.v1.MsgAcknowledgement".to_string(),
+			value: msg.encode_to_vec(),
+		}
+	}
+
+	fn misbehaviour() -> Any {
+		let msg =
+			RawMsgSubmitMisbehaviour { client_id: "9999-mock-0".to_string(), ..Default::default() };
+		Any {
+			type_url: "/ibc.core.client.v1.MsgSubmitMisbehaviour".to_string(),
+			value: msg.encode_to_vec(),
+		}
+	}
+
+	fn create_client() -> Any {
+		let height = Height::new(0, 42).unwrap();
+		MsgCreateClient::new(
+			MockClientState::new(MockHeader::new(height)).into(),
+			MockConsensusState::new(MockHeader::new(height)).into(),
+			get_dummy_account_id(),
+		)
+		.to_any()
+	}
+
+	fn check(relayer: AccountId, messages: &[Any]) -> Result<(), Error<Test>> {
+		Pallet::<Test>::ensure_relayer_policy(&relayer, messages)
+	}
+
+	#[test]
+	fn messages_are_categorized() {
+		assert_eq!(message_category(&ack()), Some(MessageCategory::Packet));
+		assert_eq!(message_category(&create_client()), Some(MessageCategory::Client));
+		assert_eq!(message_category(&misbehaviour()), None);
+	}
+
+	#[test]
+	fn restricted_category_needs_an_allowed_relayer() {
+		new_test_ext().execute_with(|| {
+			let scope = RelayerScope::Category(MessageCategory::Packet);
+			assert_ok!(check(relayer(), &[ack()]));
+
+			assert_ok!(Ibc::restrict_scope(RuntimeOrigin::root(), scope.clone()));
+			assert_ok!(Ibc::allow_relayer(RuntimeOrigin::root(), scope.clone(), relayer()));
+
+			assert_ok!(check(relayer(), &[ack()]));
+			assert_eq!(check(other_relayer(), &[ack()]), Err(Error::<Test>::RelayerNotAllowed));
+			assert_noop!(
+				Ibc::deliver(RuntimeOrigin::signed(other_relayer()), vec![ack()]),
+				Error::<Test>::RelayerNotAllowed
+			);
+			assert_ok!(check(other_relayer(), &[create_client()]));
+
+			assert_ok!(Ibc::revoke_relayer(RuntimeOrigin::root(), scope.clone(), relayer()));
+			assert_eq!(check(relayer(), &[ack()]), Err(Error::<Test>::RelayerNotAllowed));
+			assert_ok!(Ibc::unrestrict_scope(RuntimeOrigin::root(), scope));
+			assert_ok!(check(other_relayer(), &[ack()]));
+		});
+	}
+
+	#[test]
+	fn restricted_connection_covers_its_channels() {
+		new_test_ext().execute_with(|| {
+			open_channel();
+			let scope = RelayerScope::Connection(ConnectionId::new(0));
+			assert_eq!(Pallet::<Test>::message_connection(&ack()), Some(ConnectionId::new(0)));
+
+			assert_ok!(Ibc::restrict_scope(RuntimeOrigin::root(), scope.clone()));
+			assert_ok!(Ibc::allow_relayer(RuntimeOrigin::root(), scope, relayer()));
+
+			assert_ok!(check(relayer(), &[ack()]));
+			assert_eq!(check(other_relayer(), &[ack()]), Err(Error::<Test>::RelayerNotAllowed));
+			assert_ok!(check(other_relayer(), &[create_client()]));
+		});
+	}
+
+	#[test]
+	fn misbehaviour_is_never_restricted() {
+		new_test_ext().execute_with(|| {
+			let scope = RelayerScope::Category(MessageCategory::Client);
+			assert_ok!(Ibc::restrict_scope(RuntimeOrigin::root(), scope));
+
+			assert_ok!(check(other_relayer(), &[misbehaviour()]));
+			assert_eq!(
+				check(other_relayer(), &[create_client()]),
+				Err(Error::<Test>::RelayerNotAllowed)
+			);
+		});
+	}
+
+	#[test]
+	fn client_creation_is_rate_limited_per_account() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Ibc::set_client_creation_policy(
+				RuntimeOrigin::root(),
+				ClientCreationPolicy::RateLimited { max_clients: 2, period: 10 },
+			));
+
+			assert_ok!(check(relayer(), &[create_client(), create_client()]));
+			assert_eq!(
+				check(relayer(), &[create_client()]),
+				Err(Error::<Test>::ClientCreationRateLimited)
+			);
+			assert_ok!(check(other_relayer(), &[create_client()]));
+
+			System::set_block_number(11);
+			assert_ok!(check(relayer(), &[create_client()]));
+		});
+	}
+
+	#[test]
+	fn new_policy_restarts_the_rate_limit_windows() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let policy = ClientCreationPolicy::RateLimited { max_clients: 1, period: 10 };
+			assert_ok!(Ibc::set_client_creation_policy(RuntimeOrigin::root(), policy));
+			assert_ok!(check(relayer(), &[create_client()]));
+			assert_eq!(
+				check(relayer(), &[create_client()]),
+				Err(Error::<Test>::ClientCreationRateLimited)
+			);
+
+			assert_ok!(Ibc::set_client_creation_policy(RuntimeOrigin::root(), policy));
+			assert_ok!(check(relayer(), &[create_client()]));
+		});
+	}
+
+	#[test]
+	fn admin_only_client_creation() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(20);
+			assert_ok!(Ibc::set_client_creation_policy(
+				RuntimeOrigin::root(),
+				ClientCreationPolicy::AdminOnly,
+			));
+
+			assert_noop!(
+				Ibc::deliver(RuntimeOrigin::signed(relayer()), vec![create_client()]),
+				Error::<Test>::ClientCreationNotAllowed
+			);
+			assert_noop!(
+				Ibc::create_client(RuntimeOrigin::signed(relayer()), create_client()),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Ibc::create_client(RuntimeOrigin::root(), ack()),
+				Error::<Test>::NotClientCreation
+			);
+
+			assert_ok!(Ibc::create_client(RuntimeOrigin::root(), create_client()));
+			assert_eq!(ClientCounter::<Test>::get(), 1);
+		});
+	}
+
+	#[test]
+	fn policies_are_set_by_admin_origin() {
+		new_test_ext().execute_with(|| {
+			let scope = RelayerScope::Category(MessageCategory::Packet);
+			assert_noop!(
+				Ibc::restrict_scope(RuntimeOrigin::signed(relayer()), scope.clone()),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Ibc::allow_relayer(RuntimeOrigin::signed(relayer()), scope, relayer()),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Ibc::set_client_creation_policy(
+					RuntimeOrigin::signed(relayer()),
+					ClientCreationPolicy::AdminOnly,
+				),
+				DispatchError::BadOrigin
+			);
+		});
+	}
+}
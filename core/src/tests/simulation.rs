@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		channel::upgrade::{MsgChannelUpgradeTry, MSG_CHANNEL_UPGRADE_TRY_TYPE_URL},
+		errors::IbcError,
+		mock::{new_test_ext, AccountId, Ibc, RuntimeOrigin, System, Test},
+		redundant_relay::MSG_ACKNOWLEDGEMENT_TYPE_URL,
+		relayer_policy::ClientCreationPolicy,
+		simulation::SimulationError,
+		tests::common::get_dummy_account_id,
+		Channels, ClientCounter, Error, Pallet,
+	};
+	use frame_support::assert_ok;
+	use ibc::{
+		core::{
+			ics02_client::msgs::create_client::MsgCreateClient,
+			ics04_channel::channel::ChannelEnd,
+			ics24_host::{
+				identifier::{ChannelId, PortId},
+				path::ChannelEndsPath,
+			},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState, consensus_state::MockConsensusState, header::MockHeader,
+		},
+		tx_msg::Msg,
+		Height,
+	};
+	use ibc_proto::{
+		google::protobuf::Any,
+		ibc::core::channel::v1::{
+			MsgAcknowledgement as RawMsgAcknowledgement, Packet as RawPacket,
+		},
+	};
+	use prost::Message;
+
+	fn relayer() -> AccountId {
+		AccountId::new([3u8; 32])
+	}
+
+	fn create_client() -> Any {
+		let height = Height::new(0, 42).unwrap();
+		MsgCreateClient::new(
+			MockClientState::new(MockHeader::new(height)).into(),
+			MockConsensusState::new(MockHeader::new(height)).into(),
+			get_dummy_account_id(),
+		)
+		.to_any()
+	}
+
+	#[test]
+	fn simulation_reports_events_and_rolls_back() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(20);
+
+			let outcomes =
+				Pallet::<Test>::simulate_deliver(relayer(), vec![create_client(), create_client()])
+					.unwrap();
+
+			assert_eq!(outcomes.len(), 2);
+			for outcome in outcomes {
+				assert_eq!(outcome.error, None);
+				assert!(matches!(outcome.events[..], [IbcEvent::CreateClient(_)]));
+			}
+			assert_eq!(ClientCounter::<Test>::get(), 0);
+			assert!(System::events().is_empty());
+		});
+	}
+
+	#[test]
+	fn simulation_reports_errors_per_message() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(20);
+			let unknown =
+				Any { type_url: "/ibc.core.client.v1.MsgUnknown".to_string(), value: vec![] };
+			let upgrade_try = Any {
+				type_url: MSG_CHANNEL_UPGRADE_TRY_TYPE_URL.to_string(),
+				value: MsgChannelUpgradeTry {
+					port_id: "transfer".to_string(),
+					channel_id: "channel-0".to_string(),
+					..Default::default()
+				}
+				.encode_to_vec(),
+			};
+
+			let outcomes = Pallet::<Test>::simulate_deliver(
+				relayer(),
+				vec![unknown, upgrade_try, create_client()],
+			)
+			.unwrap();
+
+			assert!(matches!(
+				outcomes[0].error,
+				Some(SimulationError::Ibc(IbcError::UnknownMessageTypeUrl { .. }))
+			));
+			assert!(outcomes[0].events.is_empty());
+			assert!(matches!(outcomes[1].error, Some(SimulationError::Pallet(_))));
+			assert_eq!(outcomes[2].error, None);
+			assert_eq!(ClientCounter::<Test>::get(), 0);
+		});
+	}
+
+	#[test]
+	fn simulation_enforces_the_relayer_policy() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(20);
+			assert_ok!(Ibc::set_client_creation_policy(
+				RuntimeOrigin::root(),
+				ClientCreationPolicy::AdminOnly,
+			));
+
+			assert_eq!(
+				Pallet::<Test>::simulate_deliver(relayer(), vec![create_client()]),
+				Err(Error::<Test>::ClientCreationNotAllowed.into())
+			);
+		});
+	}
+
+	#[test]
+	fn simulation_skips_redundant_messages() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(20);
+			<Channels<Test>>::insert(
+				ChannelEndsPath(PortId::transfer(), ChannelId::new(0)),
+				ChannelEnd::default(),
+			);
+			// the commitment of the packet is gone, it was already acknowledged
+			let ack = Any {
+				type_url: MSG_ACKNOWLEDGEMENT_TYPE_URL.to_string(),
+				value: RawMsgAcknowledgement {
+					packet: Some(RawPacket {
+						sequence: 1,
+						source_port: "transfer".to_string(),
+						source_channel: "channel-0".to_string(),
+						..Default::default()
+					}),
+					..Default::default()
+				}
+				.encode_to_vec(),
+			};
+
+			let outcomes =
+				Pallet::<Test>::simulate_deliver(relayer(), vec![ack, create_client()]).unwrap();
+
+			assert_eq!(outcomes.len(), 1);
+			assert!(matches!(outcomes[0].events[..], [IbcEvent::CreateClient(_)]));
+			assert!(System::events().is_empty());
+		});
+	}
+}
@@ -0,0 +1,253 @@
+#[cfg(test)]
+mod tests {
+	use crate::{
+		mock::{new_test_ext, AccountId, Ibc, RuntimeEvent, RuntimeOrigin, System, Test},
+		wasm_client::{
+			client_state::{RawClientMessage, WasmClientState},
+			consensus_state::WasmConsensusState,
+			executor::{self, ExecutionError},
+			WASM_CLIENT_MESSAGE_TYPE_URL,
+		},
+		Config, Context, Error, Event, WasmCodes, WASM_CLIENT_TYPE,
+	};
+	use frame_support::{assert_noop, assert_ok, traits::Get};
+	use ibc::{
+		core::{
+			ics02_client::{
+				client_state::ClientState,
+				client_type::ClientType,
+				consensus_state::ConsensusState,
+				context::{ClientKeeper, ClientReader},
+			},
+			ics03_connection::connection::ConnectionEnd,
+			ics04_channel::packet::Sequence,
+			ics23_commitment::commitment::{CommitmentProofBytes, CommitmentRoot},
+			ics24_host::{
+				identifier::{ChannelId, ClientId, PortId},
+				path::SeqRecvsPath,
+			},
+		},
+		Height,
+	};
+	use ibc_proto::google::protobuf::Any;
+	use prost::Message;
+	use sp_runtime::DispatchError;
+
+	/// A light client accepting the values ending with 1, whose updates move it to height 0-2.
+	const CLIENT_WAT: &str = r#"
+		(module
+			(import "env" "set_output" (func $set_output (param i32 i32)))
+			(memory (export "memory") 1)
+			(data (i32.const 0) "\00\00\00\00\00\00\00\00\00\00\02\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00")
+			(global $next (mut i32) (i32.const 1024))
+			(func (export "alloc") (param $len i32) (result i32)
+				(local $ptr i32)
+				(local.set $ptr (global.get $next))
+				(global.set $next (i32.add (global.get $next) (local.get $len)))
+				(local.get $ptr))
+			(func (export "verify_membership") (param $ptr i32) (param $len i32) (result i32)
+				(i32.ne
+					(i32.load8_u (i32.sub (i32.add (local.get $ptr) (local.get $len)) (i32.const 1)))
+					(i32.const 1)))
+			(func (export "verify_non_membership") (param i32 i32) (result i32)
+				(i32.const 0))
+			(func (export "update_state") (param i32 i32) (result i32)
+				(call $set_output (i32.const 0) (i32.const 26))
+				(i32.const 0))
+			(func (export "check_for_misbehaviour") (param i32 i32) (result i32)
+				(i32.const 1)))
+	"#;
+
+	const LOOPING_WAT: &str = r#"
+		(module
+			(memory (export "memory") 1)
+			(func (export "alloc") (param i32) (result i32)
+				(i32.const 0))
+			(func (export "verify_membership") (param i32 i32) (result i32)
+				(loop $forever (br $forever))
+				(i32.const 0)))
+	"#;
+
+	/// Sets an output past its memory on updates and a large one on misbehaviour checks.
+	const OUTPUT_WAT: &str = r#"
+		(module
+			(import "env" "set_output" (func $set_output (param i32 i32)))
+			(memory (export "memory") 1)
+			(func (export "alloc") (param i32) (result i32)
+				(i32.const 0))
+			(func (export "update_state") (param i32 i32) (result i32)
+				(call $set_output (i32.const 0) (i32.const 0x7fffffff))
+				(i32.const 0))
+			(func (export "check_for_misbehaviour") (param i32 i32) (result i32)
+				(call $set_output (i32.const 0) (i32.const 60000))
+				(i32.const 0)))
+	"#;
+
+	fn client_code() -> Vec<u8> {
+		wat::parse_str(CLIENT_WAT).unwrap()
+	}
+
+	#[test]
+	fn store_wasm_code_by_checksum() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let code = client_code();
+			let checksum = sp_io::hashing::sha2_256(&code);
+
+			assert_noop!(
+				Ibc::store_wasm_code(
+					RuntimeOrigin::signed(AccountId::new([1u8; 32])),
+					code.clone()
+				),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Ibc::store_wasm_code(RuntimeOrigin::root(), b"not wasm".to_vec()),
+				Error::<Test>::InvalidWasmCode
+			);
+			let max_size = <Test as Config>::MaxWasmCodeSize::get() as usize;
+			assert_noop!(
+				Ibc::store_wasm_code(RuntimeOrigin::root(), vec![0u8; max_size + 1]),
+				Error::<Test>::WasmCodeTooLarge
+			);
+
+			assert_ok!(Ibc::store_wasm_code(RuntimeOrigin::root(), code.clone()));
+			System::assert_last_event(RuntimeEvent::Ibc(Event::WasmCodeStored { checksum }));
+			assert_eq!(<WasmCodes<Test>>::get(checksum), Some(code.clone()));
+			assert_noop!(
+				Ibc::store_wasm_code(RuntimeOrigin::root(), code),
+				Error::<Test>::WasmCodeExists
+			);
+		})
+	}
+
+	#[test]
+	fn remove_wasm_code_by_checksum() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let code = client_code();
+			let checksum = sp_io::hashing::sha2_256(&code);
+			assert_ok!(Ibc::store_wasm_code(RuntimeOrigin::root(), code));
+
+			assert_noop!(
+				Ibc::remove_wasm_code(RuntimeOrigin::signed(AccountId::new([1u8; 32])), checksum),
+				DispatchError::BadOrigin
+			);
+			assert_ok!(Ibc::remove_wasm_code(RuntimeOrigin::root(), checksum));
+			System::assert_last_event(RuntimeEvent::Ibc(Event::WasmCodeRemoved { checksum }));
+			assert!(!<WasmCodes<Test>>::contains_key(checksum));
+			assert_noop!(
+				Ibc::remove_wasm_code(RuntimeOrigin::root(), checksum),
+				Error::<Test>::WasmCodeNotFound
+			);
+		})
+	}
+
+	#[test]
+	fn executor_meters_contract_calls() {
+		let code = client_code();
+
+		let execution = executor::execute(&code, executor::VERIFY_MEMBERSHIP, &[7, 1], 10_000);
+		assert!(execution.unwrap().gas_used > 0);
+		assert_eq!(
+			executor::execute(&code, executor::VERIFY_MEMBERSHIP, &[7, 2], 10_000),
+			Err(ExecutionError::Rejected(1))
+		);
+		assert_eq!(
+			executor::execute(&code, "unknown", &[], 10_000),
+			Err(ExecutionError::MissingExport)
+		);
+		assert_eq!(
+			executor::execute(b"not wasm", executor::VERIFY_MEMBERSHIP, &[], 10_000),
+			Err(ExecutionError::InvalidCode)
+		);
+
+		let looping = wat::parse_str(LOOPING_WAT).unwrap();
+		assert_eq!(
+			executor::execute(&looping, executor::VERIFY_MEMBERSHIP, &[1], 10_000),
+			Err(ExecutionError::OutOfGas)
+		);
+	}
+
+	#[test]
+	fn executor_bounds_and_meters_outputs() {
+		let code = wat::parse_str(OUTPUT_WAT).unwrap();
+
+		assert_eq!(
+			executor::execute(&code, executor::UPDATE_STATE, &[1], 10_000),
+			Err(ExecutionError::Trap)
+		);
+		// copying the output costs one unit of gas per byte
+		assert_eq!(
+			executor::execute(&code, executor::CHECK_FOR_MISBEHAVIOUR, &[1], 10_000),
+			Err(ExecutionError::OutOfGas)
+		);
+		let execution =
+			executor::execute(&code, executor::CHECK_FOR_MISBEHAVIOUR, &[1], 100_000).unwrap();
+		assert_eq!(execution.output.map(|output| output.len()), Some(60000));
+		assert!(execution.gas_used >= 60000);
+	}
+
+	#[test]
+	fn client_reader_routes_wasm_clients() {
+		new_test_ext().execute_with(|| {
+			let code = client_code();
+			let checksum = sp_io::hashing::sha2_256(&code);
+			assert_ok!(Ibc::store_wasm_code(RuntimeOrigin::root(), code));
+
+			let client_type = ClientType::new(WASM_CLIENT_TYPE.into());
+			let client_id = ClientId::new(client_type.clone(), 0).unwrap();
+			let height = Height::new(0, 1).unwrap();
+			let client_state = WasmClientState {
+				data: vec![1],
+				checksum: checksum.to_vec(),
+				latest_height: height,
+				frozen_height: None,
+				host: None,
+			};
+			let consensus_state = WasmConsensusState::new(vec![2], 1).unwrap();
+
+			let mut ctx = Context::<Test>::new();
+			ctx.store_client_type(client_id.clone(), client_type.clone()).unwrap();
+			ctx.store_client_state(client_id.clone(), client_state.into_box()).unwrap();
+			ctx.store_consensus_state(client_id.clone(), height, consensus_state.into_box())
+				.unwrap();
+
+			assert_eq!(ctx.client_type(&client_id).unwrap(), client_type);
+			let stored = ctx.client_state(&client_id).unwrap();
+			assert_eq!(stored.latest_height(), height);
+			assert_eq!(
+				ctx.consensus_state(&client_id, &height).unwrap().root(),
+				&CommitmentRoot::from_bytes(&[2])
+			);
+
+			// proofs are verified by the stored contract
+			let verify_sequence = |sequence: u64| {
+				stored.verify_next_sequence_recv(
+					&ctx,
+					height,
+					&ConnectionEnd::default(),
+					&CommitmentProofBytes::try_from(vec![1]).unwrap(),
+					&CommitmentRoot::from_bytes(&[2]),
+					&SeqRecvsPath(PortId::transfer(), ChannelId::new(0)),
+					Sequence::from(sequence),
+				)
+			};
+			assert!(verify_sequence(1).is_ok());
+			assert!(verify_sequence(2).is_err());
+
+			// as are client updates
+			let header = Any {
+				type_url: WASM_CLIENT_MESSAGE_TYPE_URL.to_string(),
+				value: RawClientMessage { data: vec![3] }.encode_to_vec(),
+			};
+			let updated =
+				stored.check_header_and_update_state(&ctx, client_id.clone(), header).unwrap();
+			assert_eq!(updated.client_state.latest_height(), Height::new(0, 2).unwrap());
+
+			// clients without contract cannot verify anything
+			assert_ok!(Ibc::remove_wasm_code(RuntimeOrigin::root(), checksum));
+			assert!(verify_sequence(1).is_err());
+		})
+	}
+}
@@ -0,0 +1,121 @@
+//! Governance-scheduled upgrades of the IBC client of this chain.
+//!
+//! Counterparties tracking this chain upgrade their client with `MsgUpgradeClient`, proving the
+//! upgraded client and consensus states committed here under the ICS-24 upgrade paths
+//! `upgradedIBCState/{height}/upgradedClient` and `upgradedIBCState/{height}/upgradedConsState`.
+//! Only one upgrade is scheduled at a time, scheduling a new one replaces the previous one.
+//!
+//! Like ibc-go, the client specific fields of an upgraded tendermint client state (trust level,
+//! trusting period, clock drift and frozen height) are zeroed before being committed, since each
+//! counterparty chooses its own.
+use crate::{
+	context::Context, recovery::TENDERMINT_CLIENT_STATE_TYPE_URL,
+	wasm_client::consensus_state::WasmConsensusState, Config, Error, Pallet, ScheduledUpgrade,
+	UpgradedClientStates, UpgradedConsensusStates, MOCK_CLIENT_TYPE, TENDERMINT_CLIENT_TYPE,
+	WASM_CLIENT_TYPE,
+};
+use frame_support::ensure;
+use frame_system::pallet_prelude::BlockNumberFor;
+use ibc::{
+	clients::ics07_tendermint::consensus_state::ConsensusState as Ics07ConsensusState,
+	core::{
+		ics02_client::{client_type::ClientType, context::ClientReader},
+		ics24_host::path::UpgradeClientPath,
+	},
+	mock::consensus_state::MockConsensusState,
+};
+use ibc_proto::{
+	google::protobuf::Any, ibc::lightclients::tendermint::v1::ClientState as RawTmClientState,
+};
+use prost::Message;
+
+/// Zero the fields of a tendermint client state that are chosen by each counterparty, keeping
+/// the chain id, unbonding period, latest height, proof specs and upgrade path.
+fn zero_custom_fields(client_state: Any) -> Result<Any, prost::DecodeError> {
+	if client_state.type_url != TENDERMINT_CLIENT_STATE_TYPE_URL {
+		return Ok(client_state)
+	}
+	let client_state = RawTmClientState::decode(client_state.value.as_slice())?;
+	let zeroed = RawTmClientState {
+		chain_id: client_state.chain_id,
+		unbonding_period: client_state.unbonding_period,
+		latest_height: client_state.latest_height,
+		proof_specs: client_state.proof_specs,
+		upgrade_path: client_state.upgrade_path,
+		..Default::default()
+	};
+	Ok(Any { type_url: TENDERMINT_CLIENT_STATE_TYPE_URL.into(), value: zeroed.encode_to_vec() })
+}
+
+impl<T: Config> Pallet<T>
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
+	/// Returns true if `consensus_state` is a consensus state of the light client `client_type`.
+	/// Client types are only reached once their client state was accepted by the host, so the
+	/// mock client is only accepted when the host accepts it.
+	fn is_valid_consensus_state(client_type: &ClientType, consensus_state: &Any) -> bool {
+		match client_type.as_str() {
+			TENDERMINT_CLIENT_TYPE =>
+				Ics07ConsensusState::try_from(consensus_state.clone()).is_ok(),
+			WASM_CLIENT_TYPE => WasmConsensusState::try_from(consensus_state.clone()).is_ok(),
+			MOCK_CLIENT_TYPE => MockConsensusState::try_from(consensus_state.clone()).is_ok(),
+			_ => false,
+		}
+	}
+
+	/// Commit the upgraded client and consensus states for an upgrade at `height`, replacing any
+	/// previously scheduled upgrade.
+	pub fn do_schedule_upgrade(
+		height: u64,
+		upgraded_client_state: Any,
+		upgraded_consensus_state: Any,
+	) -> Result<(), Error<T>> {
+		let current_height = u64::from(<frame_system::Pallet<T>>::block_number());
+		ensure!(height > current_height, Error::<T>::InvalidUpgradeHeight);
+		let client_type =
+			ClientReader::decode_client_state(&Context::<T>::new(), upgraded_client_state.clone())
+				.map_err(|_| Error::<T>::InvalidClientState)?
+				.client_type();
+		ensure!(
+			Self::is_valid_consensus_state(&client_type, &upgraded_consensus_state),
+			Error::<T>::InvalidConsensusState
+		);
+		let upgraded_client_state = zero_custom_fields(upgraded_client_state)
+			.map_err(|_| Error::<T>::InvalidClientState)?;
+
+		if let Some(previous) = <ScheduledUpgrade<T>>::take() {
+			Self::remove_upgraded_states(previous);
+		}
+		<UpgradedClientStates<T>>::insert(
+			UpgradeClientPath::UpgradedClientState(height),
+			upgraded_client_state.encode_to_vec(),
+		);
+		<UpgradedConsensusStates<T>>::insert(
+			UpgradeClientPath::UpgradedClientConsensusState(height),
+			upgraded_consensus_state.encode_to_vec(),
+		);
+		<ScheduledUpgrade<T>>::put(height);
+
+		Ok(())
+	}
+
+	/// Remove the scheduled upgrade before its height is reached, returning the upgrade height.
+	pub fn do_cancel_upgrade() -> Result<u64, Error<T>> {
+		let height = <ScheduledUpgrade<T>>::get().ok_or(Error::<T>::NoScheduledUpgrade)?;
+		let current_height = u64::from(<frame_system::Pallet<T>>::block_number());
+		ensure!(height > current_height, Error::<T>::InvalidUpgradeHeight);
+
+		<ScheduledUpgrade<T>>::kill();
+		Self::remove_upgraded_states(height);
+
+		Ok(height)
+	}
+
+	fn remove_upgraded_states(height: u64) {
+		<UpgradedClientStates<T>>::remove(UpgradeClientPath::UpgradedClientState(height));
+		<UpgradedConsensusStates<T>>::remove(UpgradeClientPath::UpgradedClientConsensusState(
+			height,
+		));
+	}
+}
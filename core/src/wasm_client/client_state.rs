@@ -0,0 +1,438 @@
+use super::{
+	consensus_state::WasmConsensusState,
+	executor::{self, ClientMessageInput, MembershipInput, UpdatedStates},
+	WasmHost, WASM_CLIENT_MESSAGE_TYPE_URL, WASM_CLIENT_STATE_TYPE_URL,
+};
+use crate::WASM_CLIENT_TYPE;
+use alloc::{
+	boxed::Box,
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use core::time::Duration;
+use ibc::{
+	core::{
+		ics02_client::{
+			client_state::{ClientState, UpdatedState, UpgradeOptions},
+			client_type::ClientType,
+			consensus_state::ConsensusState,
+			context::ClientReader,
+			error::ClientError,
+		},
+		ics03_connection::connection::ConnectionEnd,
+		ics04_channel::{
+			channel::ChannelEnd,
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			context::ChannelReader,
+			packet::Sequence,
+		},
+		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+		ics24_host::{
+			identifier::{ChainId, ClientId},
+			path::{
+				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
+				CommitmentsPath, ConnectionsPath, ReceiptsPath, SeqRecvsPath,
+			},
+		},
+	},
+	Height,
+};
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::{client::v1::Height as RawHeight, commitment::v1::MerkleProof as RawMerkleProof},
+	protobuf::Protobuf,
+};
+use prost::Message;
+use serde::Serialize;
+
+/// `ibc.lightclients.wasm.v1.ClientState`, with the frozen height kept by the host.
+#[derive(Clone, PartialEq, Message)]
+pub struct RawWasmClientState {
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+	#[prost(bytes = "vec", tag = "2")]
+	pub checksum: Vec<u8>,
+	#[prost(message, optional, tag = "3")]
+	pub latest_height: Option<RawHeight>,
+	#[prost(message, optional, tag = "4")]
+	pub frozen_height: Option<RawHeight>,
+}
+
+/// `ibc.lightclients.wasm.v1.ClientMessage`
+#[derive(Clone, PartialEq, Message)]
+pub struct RawClientMessage {
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+}
+
+/// State of an 08-wasm client: the opaque state of the contract identified by `checksum`.
+///
+/// Verification runs in the contract, on the consensus state data found in the commitment
+/// root. Expiry is left to the contract, which rejects proofs against an expired state.
+#[derive(Clone, Debug, Serialize)]
+pub struct WasmClientState {
+	pub data: Vec<u8>,
+	pub checksum: Vec<u8>,
+	pub latest_height: Height,
+	pub frozen_height: Option<Height>,
+	/// Set when read from the store, absent on states decoded elsewhere
+	#[serde(skip)]
+	pub host: Option<WasmHost>,
+}
+
+impl PartialEq for WasmClientState {
+	fn eq(&self, other: &Self) -> bool {
+		self.data == other.data &&
+			self.checksum == other.checksum &&
+			self.latest_height == other.latest_height &&
+			self.frozen_height == other.frozen_height
+	}
+}
+
+impl Eq for WasmClientState {}
+
+fn wasm_error(error: impl core::fmt::Debug) -> ClientError {
+	ClientError::Other { description: format!("08-wasm client: {:?}", error) }
+}
+
+impl WasmClientState {
+	/// Attach the host running the contract of this client.
+	pub fn with_host(self, host: WasmHost) -> Self {
+		Self { host: Some(host), ..self }
+	}
+
+	fn code(&self) -> Result<(Vec<u8>, u64), ClientError> {
+		let host = self.host.ok_or_else(|| wasm_error("no contract executor"))?;
+		let code =
+			(host.load_code)(&self.checksum).ok_or_else(|| wasm_error("unknown checksum"))?;
+		Ok((code, host.gas_limit))
+	}
+
+	fn verify(
+		&self,
+		entry_point: &str,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		path: String,
+		value: Vec<u8>,
+	) -> Result<(), ClientError> {
+		if self.frozen_height.is_some() {
+			return Err(wasm_error("client is frozen"));
+		}
+		let (code, gas_limit) = self.code()?;
+		let input = MembershipInput {
+			client_state: self.data.clone(),
+			consensus_state: root.as_bytes().to_vec(),
+			proof: proof.clone().into(),
+			prefix: prefix.as_bytes().to_vec(),
+			path: path.into_bytes(),
+			value,
+		};
+		executor::execute(&code, entry_point, &codec::Encode::encode(&input), gas_limit)
+			.map(|_| ())
+			.map_err(wasm_error)
+	}
+
+	pub(crate) fn verify_membership(
+		&self,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		path: String,
+		value: Vec<u8>,
+	) -> Result<(), ClientError> {
+		self.verify(executor::VERIFY_MEMBERSHIP, prefix, proof, root, path, value)
+	}
+
+	fn consensus_data(
+		ctx: &dyn ClientReader,
+		client_id: &ClientId,
+		height: &Height,
+	) -> Result<Vec<u8>, ClientError> {
+		Ok(ctx.consensus_state(client_id, height)?.root().as_bytes().to_vec())
+	}
+
+	fn client_message(message: Any) -> Result<Vec<u8>, ClientError> {
+		if message.type_url != WASM_CLIENT_MESSAGE_TYPE_URL {
+			return Err(wasm_error(format!("unexpected client message {}", message.type_url)));
+		}
+		RawClientMessage::decode(message.value.as_slice())
+			.map(|message| message.data)
+			.map_err(wasm_error)
+	}
+}
+
+impl ClientState for WasmClientState {
+	fn chain_id(&self) -> ChainId {
+		ChainId::new(WASM_CLIENT_TYPE.to_string(), self.latest_height.revision_number())
+	}
+
+	fn client_type(&self) -> ClientType {
+		ClientType::new(WASM_CLIENT_TYPE.to_string())
+	}
+
+	fn latest_height(&self) -> Height {
+		self.latest_height
+	}
+
+	fn frozen_height(&self) -> Option<Height> {
+		self.frozen_height
+	}
+
+	fn upgrade(
+		&mut self,
+		_upgrade_height: Height,
+		_upgrade_options: &dyn UpgradeOptions,
+		_chain_id: ChainId,
+	) {
+	}
+
+	fn expired(&self, _elapsed: Duration) -> bool {
+		false
+	}
+
+	fn initialise(&self, consensus_state: Any) -> Result<Box<dyn ConsensusState>, ClientError> {
+		WasmConsensusState::try_from(consensus_state).map(WasmConsensusState::into_box)
+	}
+
+	fn check_header_and_update_state(
+		&self,
+		ctx: &dyn ClientReader,
+		client_id: ClientId,
+		header: Any,
+	) -> Result<UpdatedState, ClientError> {
+		let (code, gas_limit) = self.code()?;
+		let input = ClientMessageInput {
+			client_state: self.data.clone(),
+			consensus_state: Self::consensus_data(ctx, &client_id, &self.latest_height)?,
+			client_message: Self::client_message(header)?,
+		};
+		let updated: UpdatedStates = executor::query(
+			&code,
+			executor::UPDATE_STATE,
+			&codec::Encode::encode(&input),
+			gas_limit,
+		)
+		.map_err(wasm_error)?;
+
+		let height = Height::new(updated.height.0, updated.height.1)?;
+		let client_state = Self {
+			data: updated.client_state,
+			latest_height: self.latest_height.max(height),
+			..self.clone()
+		};
+		let consensus_state = WasmConsensusState::new(updated.consensus_state, updated.timestamp)?;
+		Ok(UpdatedState {
+			client_state: client_state.into_box(),
+			consensus_state: consensus_state.into_box(),
+		})
+	}
+
+	fn check_misbehaviour_and_update_state(
+		&self,
+		ctx: &dyn ClientReader,
+		client_id: ClientId,
+		misbehaviour: Any,
+	) -> Result<Box<dyn ClientState>, ClientError> {
+		let (code, gas_limit) = self.code()?;
+		let input = ClientMessageInput {
+			client_state: self.data.clone(),
+			consensus_state: Self::consensus_data(ctx, &client_id, &self.latest_height)?,
+			client_message: Self::client_message(misbehaviour)?,
+		};
+		executor::execute(
+			&code,
+			executor::CHECK_FOR_MISBEHAVIOUR,
+			&codec::Encode::encode(&input),
+			gas_limit,
+		)
+		.map_err(wasm_error)?;
+		Ok(Self { frozen_height: Some(self.latest_height), ..self.clone() }.into_box())
+	}
+
+	fn verify_upgrade_client(
+		&self,
+		_upgraded_client_state: Any,
+		_upgraded_consensus_state: Any,
+		_proof_upgrade_client: RawMerkleProof,
+		_proof_upgrade_consensus_state: RawMerkleProof,
+		_root: &CommitmentRoot,
+	) -> Result<(), ClientError> {
+		Err(wasm_error("upgrades are not supported"))
+	}
+
+	fn update_state_with_upgrade_client(
+		&self,
+		_upgraded_client_state: Any,
+		_upgraded_consensus_state: Any,
+	) -> Result<UpdatedState, ClientError> {
+		Err(wasm_error("upgrades are not supported"))
+	}
+
+	fn verify_client_consensus_state(
+		&self,
+		_proof_height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		client_cons_state_path: &ClientConsensusStatePath,
+		expected_consensus_state: &dyn ConsensusState,
+	) -> Result<(), ClientError> {
+		let value = expected_consensus_state.encode_vec().map_err(wasm_error)?;
+		self.verify_membership(prefix, proof, root, client_cons_state_path.to_string(), value)
+	}
+
+	fn verify_connection_state(
+		&self,
+		_proof_height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		counterparty_conn_path: &ConnectionsPath,
+		expected_counterparty_connection_end: &ConnectionEnd,
+	) -> Result<(), ClientError> {
+		let value = expected_counterparty_connection_end.encode_vec().map_err(wasm_error)?;
+		self.verify_membership(prefix, proof, root, counterparty_conn_path.to_string(), value)
+	}
+
+	fn verify_channel_state(
+		&self,
+		_proof_height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		counterparty_chan_end_path: &ChannelEndsPath,
+		expected_counterparty_channel_end: &ChannelEnd,
+	) -> Result<(), ClientError> {
+		let value = expected_counterparty_channel_end.encode_vec().map_err(wasm_error)?;
+		self.verify_membership(prefix, proof, root, counterparty_chan_end_path.to_string(), value)
+	}
+
+	fn verify_client_full_state(
+		&self,
+		_proof_height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		client_state_path: &ClientStatePath,
+		expected_client_state: Any,
+	) -> Result<(), ClientError> {
+		let value = expected_client_state.encode_to_vec();
+		self.verify_membership(prefix, proof, root, client_state_path.to_string(), value)
+	}
+
+	fn verify_packet_data(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		commitment_path: &CommitmentsPath,
+		commitment: PacketCommitment,
+	) -> Result<(), ClientError> {
+		self.verify_membership(
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			commitment_path.to_string(),
+			commitment.into_vec(),
+		)
+	}
+
+	fn verify_packet_acknowledgement(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		ack_path: &AcksPath,
+		ack: AcknowledgementCommitment,
+	) -> Result<(), ClientError> {
+		self.verify_membership(
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			ack_path.to_string(),
+			ack.into_vec(),
+		)
+	}
+
+	fn verify_next_sequence_recv(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		seq_recv_path: &SeqRecvsPath,
+		sequence: Sequence,
+	) -> Result<(), ClientError> {
+		self.verify_membership(
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			seq_recv_path.to_string(),
+			u64::from(sequence).to_be_bytes().to_vec(),
+		)
+	}
+
+	fn verify_packet_receipt_absence(
+		&self,
+		_ctx: &dyn ChannelReader,
+		_height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		receipt_path: &ReceiptsPath,
+	) -> Result<(), ClientError> {
+		self.verify(
+			executor::VERIFY_NON_MEMBERSHIP,
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			receipt_path.to_string(),
+			Vec::new(),
+		)
+	}
+}
+
+impl Protobuf<Any> for WasmClientState {}
+
+impl TryFrom<Any> for WasmClientState {
+	type Error = ClientError;
+
+	fn try_from(any: Any) -> Result<Self, Self::Error> {
+		if any.type_url != WASM_CLIENT_STATE_TYPE_URL {
+			return Err(ClientError::UnknownClientStateType { client_state_type: any.type_url });
+		}
+		let raw = RawWasmClientState::decode(any.value.as_slice()).map_err(wasm_error)?;
+		let latest_height = raw
+			.latest_height
+			.ok_or(ClientError::MissingRawClientState)
+			.and_then(|height| Height::try_from(height))?;
+		let frozen_height = raw.frozen_height.map(Height::try_from).transpose()?;
+		Ok(Self {
+			data: raw.data,
+			checksum: raw.checksum,
+			latest_height,
+			frozen_height,
+			host: None,
+		})
+	}
+}
+
+impl From<WasmClientState> for Any {
+	fn from(client_state: WasmClientState) -> Self {
+		let raw = RawWasmClientState {
+			data: client_state.data,
+			checksum: client_state.checksum,
+			latest_height: Some(client_state.latest_height.into()),
+			frozen_height: client_state.frozen_height.map(Into::into),
+		};
+		Any { type_url: WASM_CLIENT_STATE_TYPE_URL.to_string(), value: raw.encode_to_vec() }
+	}
+}
@@ -0,0 +1,74 @@
+use super::WASM_CONSENSUS_STATE_TYPE_URL;
+use alloc::{format, string::ToString, vec::Vec};
+use ibc::{
+	core::{
+		ics02_client::{consensus_state::ConsensusState, error::ClientError},
+		ics23_commitment::commitment::CommitmentRoot,
+	},
+	timestamp::Timestamp,
+};
+use ibc_proto::{google::protobuf::Any, protobuf::Protobuf};
+use prost::Message;
+use serde::Serialize;
+
+/// `ibc.lightclients.wasm.v1.ConsensusState`, with the timestamp kept by the host.
+#[derive(Clone, PartialEq, Message)]
+pub struct RawWasmConsensusState {
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+	#[prost(uint64, tag = "2")]
+	pub timestamp: u64,
+}
+
+/// Consensus state of an 08-wasm client, whose root is the opaque state of the contract.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WasmConsensusState {
+	pub root: CommitmentRoot,
+	pub timestamp: Timestamp,
+}
+
+impl WasmConsensusState {
+	pub fn new(data: Vec<u8>, timestamp: u64) -> Result<Self, ClientError> {
+		let timestamp = Timestamp::from_nanoseconds(timestamp).map_err(|e| ClientError::Other {
+			description: format!("08-wasm client: invalid timestamp {:?}", e),
+		})?;
+		Ok(Self { root: CommitmentRoot::from_bytes(&data), timestamp })
+	}
+}
+
+impl ConsensusState for WasmConsensusState {
+	fn root(&self) -> &CommitmentRoot {
+		&self.root
+	}
+
+	fn timestamp(&self) -> Timestamp {
+		self.timestamp
+	}
+}
+
+impl Protobuf<Any> for WasmConsensusState {}
+
+impl TryFrom<Any> for WasmConsensusState {
+	type Error = ClientError;
+
+	fn try_from(any: Any) -> Result<Self, Self::Error> {
+		if any.type_url != WASM_CONSENSUS_STATE_TYPE_URL {
+			return Err(ClientError::UnknownConsensusStateType {
+				consensus_state_type: any.type_url,
+			});
+		}
+		let raw = RawWasmConsensusState::decode(any.value.as_slice())
+			.map_err(|e| ClientError::Other { description: format!("08-wasm client: {:?}", e) })?;
+		Self::new(raw.data, raw.timestamp)
+	}
+}
+
+impl From<WasmConsensusState> for Any {
+	fn from(consensus_state: WasmConsensusState) -> Self {
+		let raw = RawWasmConsensusState {
+			data: consensus_state.root.into_vec(),
+			timestamp: consensus_state.timestamp.nanoseconds(),
+		};
+		Any { type_url: WASM_CONSENSUS_STATE_TYPE_URL.to_string(), value: raw.encode_to_vec() }
+	}
+}
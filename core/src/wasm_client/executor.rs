@@ -0,0 +1,177 @@
+//! Metered execution of 08-wasm light client contracts.
+//!
+//! A contract exports its `memory`, an `alloc(len: i32) -> i32` function and one entry point per
+//! operation, each taking the pointer and length of its SCALE encoded input and returning `0` on
+//! success. Entry points returning data pass it to the host through the imported
+//! `env.set_output(ptr: i32, len: i32)`, which traps if the output is out of the memory of the
+//! contract or longer than `MAX_OUTPUT_LEN`. Every instruction consumes one unit of gas, as does
+//! every byte of output copied to the host, and a call running out of gas fails.
+use alloc::{vec, vec::Vec};
+use codec::{Decode, Encode};
+use wasmi::{core::Trap, Caller, Config, Engine, Extern, Linker, Module, Store};
+
+/// Maximum length in bytes of the output of a contract call
+pub const MAX_OUTPUT_LEN: usize = 128 * 1024;
+
+/// Verify that `value` is stored at `path` under `prefix`
+pub const VERIFY_MEMBERSHIP: &str = "verify_membership";
+/// Verify that nothing is stored at `path` under `prefix`, `value` is empty
+pub const VERIFY_NON_MEMBERSHIP: &str = "verify_non_membership";
+/// Verify a client message and output the `UpdatedStates`
+pub const UPDATE_STATE: &str = "update_state";
+/// Succeeds if the client message is valid evidence of misbehaviour
+pub const CHECK_FOR_MISBEHAVIOUR: &str = "check_for_misbehaviour";
+
+/// Input of `VERIFY_MEMBERSHIP` and `VERIFY_NON_MEMBERSHIP`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct MembershipInput {
+	pub client_state: Vec<u8>,
+	pub consensus_state: Vec<u8>,
+	pub proof: Vec<u8>,
+	pub prefix: Vec<u8>,
+	pub path: Vec<u8>,
+	pub value: Vec<u8>,
+}
+
+/// Input of `UPDATE_STATE` and `CHECK_FOR_MISBEHAVIOUR`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ClientMessageInput {
+	pub client_state: Vec<u8>,
+	pub consensus_state: Vec<u8>,
+	pub client_message: Vec<u8>,
+}
+
+/// Output of `UPDATE_STATE`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct UpdatedStates {
+	pub client_state: Vec<u8>,
+	pub consensus_state: Vec<u8>,
+	/// (revision number, revision height) of the new consensus state
+	pub height: (u64, u64),
+	/// Timestamp in nanoseconds of the new consensus state
+	pub timestamp: u64,
+}
+
+/// Reason a contract call failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionError {
+	/// The bytecode is not a valid contract
+	InvalidCode,
+	/// The contract does not export the entry point, `memory` or `alloc`
+	MissingExport,
+	/// The call used more than the gas limit
+	OutOfGas,
+	/// The contract trapped
+	Trap,
+	/// The entry point returned this non-zero code
+	Rejected(i32),
+	/// The entry point did not set a decodable output
+	InvalidOutput,
+}
+
+#[derive(Default)]
+struct HostState {
+	output: Option<Vec<u8>>,
+}
+
+/// Outcome of a successful contract call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Execution {
+	pub output: Option<Vec<u8>>,
+	pub gas_used: u64,
+}
+
+/// Call `entry_point` of `code` with `input`, using at most `gas_limit` gas.
+pub fn execute(
+	code: &[u8],
+	entry_point: &str,
+	input: &[u8],
+	gas_limit: u64,
+) -> Result<Execution, ExecutionError> {
+	let mut config = Config::default();
+	config.consume_fuel(true);
+	let engine = Engine::new(&config);
+	let module = Module::new(&engine, code).map_err(|_| ExecutionError::InvalidCode)?;
+	let mut store = Store::new(&engine, HostState::default());
+	store.add_fuel(gas_limit).map_err(|_| ExecutionError::OutOfGas)?;
+
+	let mut linker = <Linker<HostState>>::new(&engine);
+	linker
+		.func_wrap(
+			"env",
+			"set_output",
+			move |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), Trap> {
+				let memory = caller
+					.get_export("memory")
+					.and_then(Extern::into_memory)
+					.ok_or_else(|| Trap::new("contract memory is not exported"))?;
+				let (ptr, len) = (ptr as u32 as usize, len as u32 as usize);
+				if len > MAX_OUTPUT_LEN || ptr.saturating_add(len) > memory.data_size(&caller) {
+					return Err(Trap::new("output is out of bounds"));
+				}
+				if caller.consume_fuel(len as u64).is_err() {
+					// use up the gas left, so that the call is reported out of gas
+					let used = caller.fuel_consumed().unwrap_or_default();
+					let _ = caller.consume_fuel(gas_limit.saturating_sub(used));
+					return Err(Trap::new("out of gas"));
+				}
+
+				let mut output = vec![0u8; len];
+				memory
+					.read(&caller, ptr, &mut output)
+					.map_err(|_| Trap::new("output is out of bounds"))?;
+				caller.data_mut().output = Some(output);
+				Ok(())
+			},
+		)
+		.map_err(|_| ExecutionError::InvalidCode)?;
+
+	let out_of_gas =
+		|store: &Store<HostState>| store.fuel_consumed().map_or(false, |used| used >= gas_limit);
+	let trapped = |store: &Store<HostState>| {
+		if out_of_gas(store) {
+			ExecutionError::OutOfGas
+		} else {
+			ExecutionError::Trap
+		}
+	};
+
+	let instance = linker
+		.instantiate(&mut store, &module)
+		.map_err(|_| ExecutionError::InvalidCode)?
+		.start(&mut store)
+		.map_err(|_| trapped(&store))?;
+	let memory = instance.get_memory(&store, "memory").ok_or(ExecutionError::MissingExport)?;
+	let alloc = instance
+		.get_typed_func::<i32, i32>(&store, "alloc")
+		.map_err(|_| ExecutionError::MissingExport)?;
+	let entry = instance
+		.get_typed_func::<(i32, i32), i32>(&store, entry_point)
+		.map_err(|_| ExecutionError::MissingExport)?;
+
+	let len = i32::try_from(input.len()).map_err(|_| ExecutionError::Trap)?;
+	let ptr = alloc.call(&mut store, len).map_err(|_| trapped(&store))?;
+	memory
+		.write(&mut store, ptr as usize, input)
+		.map_err(|_| ExecutionError::Trap)?;
+	match entry.call(&mut store, (ptr, len)).map_err(|_| trapped(&store))? {
+		0 => Ok(Execution {
+			output: store.data_mut().output.take(),
+			gas_used: store.fuel_consumed().unwrap_or_default(),
+		}),
+		code => Err(ExecutionError::Rejected(code)),
+	}
+}
+
+/// Call an entry point returning a SCALE encoded `O`.
+pub fn query<O: Decode>(
+	code: &[u8],
+	entry_point: &str,
+	input: &[u8],
+	gas_limit: u64,
+) -> Result<O, ExecutionError> {
+	let output = execute(code, entry_point, input, gas_limit)?
+		.output
+		.ok_or(ExecutionError::InvalidOutput)?;
+	O::decode(&mut output.as_slice()).map_err(|_| ExecutionError::InvalidOutput)
+}
@@ -0,0 +1,70 @@
+//! Host of 08-wasm light clients.
+//!
+//! The bytecode of a light client contract is stored by `AdminOrigin` under its sha256 checksum,
+//! and an 08-wasm client state names the checksum of the contract verifying it. Client updates,
+//! misbehaviour and the proofs of every handshake and packet message are checked by calling the
+//! contract in `executor`, with at most `WasmClientGasLimit` gas per call. Stored bytecode is
+//! at most `MaxWasmCodeSize` bytes.
+use crate::{Config, Error, Pallet, WasmCodes};
+use alloc::vec::Vec;
+use frame_support::{ensure, traits::Get, weights::Weight};
+
+pub mod client_state;
+pub mod consensus_state;
+pub mod executor;
+
+pub const WASM_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientState";
+pub const WASM_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ConsensusState";
+pub const WASM_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientMessage";
+
+/// Reference time to validate, hash and store one byte of bytecode, an upper estimate of the
+/// linear cost of `wasmi::Module::new`, sha256 and the storage write.
+const WASM_CODE_BYTE_REF_TIME: u64 = 50_000;
+
+/// Weight of `store_wasm_code` with `len` bytes of bytecode: the checksum lookup and bytecode
+/// write, and the per byte validation and hashing.
+pub(crate) fn store_wasm_code_weight<T: Config>(len: usize) -> Weight {
+	let len = len as u64;
+	T::DbWeight::get()
+		.reads_writes(1, 1)
+		.saturating_add(Weight::from_parts(WASM_CODE_BYTE_REF_TIME.saturating_mul(len), len))
+}
+
+/// Access of an 08-wasm client state to the stored contracts.
+#[derive(Clone, Copy, Debug)]
+pub struct WasmHost {
+	/// Bytecode of the contract with the given checksum
+	pub load_code: fn(&[u8]) -> Option<Vec<u8>>,
+	/// Gas available to each contract call
+	pub gas_limit: u64,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Host running the contracts stored in `WasmCodes`.
+	pub fn wasm_host() -> WasmHost {
+		WasmHost { load_code: Self::load_wasm_code, gas_limit: T::WasmClientGasLimit::get() }
+	}
+
+	fn load_wasm_code(checksum: &[u8]) -> Option<Vec<u8>> {
+		<[u8; 32]>::try_from(checksum).ok().and_then(<WasmCodes<T>>::get)
+	}
+
+	/// Store the bytecode of a light client contract, returning its checksum.
+	pub(crate) fn do_store_wasm_code(code: Vec<u8>) -> Result<[u8; 32], Error<T>> {
+		ensure!(code.len() <= T::MaxWasmCodeSize::get() as usize, Error::<T>::WasmCodeTooLarge);
+		let engine = wasmi::Engine::default();
+		ensure!(wasmi::Module::new(&engine, code.as_slice()).is_ok(), Error::<T>::InvalidWasmCode);
+
+		let checksum = sp_io::hashing::sha2_256(&code);
+		ensure!(!<WasmCodes<T>>::contains_key(checksum), Error::<T>::WasmCodeExists);
+		<WasmCodes<T>>::insert(checksum, code);
+		Ok(checksum)
+	}
+
+	/// Remove the bytecode of a light client contract.
+	pub(crate) fn do_remove_wasm_code(checksum: [u8; 32]) -> Result<(), Error<T>> {
+		ensure!(<WasmCodes<T>>::contains_key(checksum), Error::<T>::WasmCodeNotFound);
+		<WasmCodes<T>>::remove(checksum);
+		Ok(())
+	}
+}
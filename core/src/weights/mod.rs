@@ -1,7 +1,10 @@
 mod mock_client_weight;
 
 use super::*;
-use crate::{weights::mock_client_weight::MockClientWeightInfo, MOCK_CLIENT_TYPE};
+use crate::{
+	misbehaviour::tendermint_signatures, weights::mock_client_weight::MockClientWeightInfo,
+	MOCK_CLIENT_TYPE,
+};
 use alloc::boxed::Box;
 use core::marker::PhantomData;
 use frame_support::pallet_prelude::Weight;
@@ -33,6 +36,25 @@ use ibc::core::{
 };
 use pallet_ibc_utils::CallbackWeight;
 
+// Hand-set estimates for the `misbehaviour_mock` and `ed25519_verify` benchmarks, kept out of the
+// generated `mock_client_weight` until the benchmark CLI is run for them and they are regenerated
+// there.
+
+// Storage: Ibc Clients (r:1 w:0)
+// Storage: Ibc ClientStates (r:1 w:1)
+// Storage: Ibc ConsensusStates (r:2 w:0)
+// Storage: System Account (r:2 w:2)
+fn misbehaviour_mock<T: frame_system::Config>() -> Weight {
+	Weight::from_parts(40_000_000, 0)
+		.saturating_add(T::DbWeight::get().reads(6))
+		.saturating_add(T::DbWeight::get().writes(3))
+}
+
+/// Verification of `s` ed25519 signatures, `s` ranging over `[1, 300]`.
+fn ed25519_verify<T: frame_system::Config>(s: u32) -> Weight {
+	Weight::from_parts(50_000_000, 0).saturating_mul(s.into())
+}
+
 pub trait WeightInfo<T> {
 	fn create_client(msg_create_client: MsgCreateClient) -> Weight;
 	fn misbehaviour(msg_misbehaviour: MsgSubmitMisbehaviour) -> Weight;
@@ -78,8 +100,13 @@ where
 		}
 	}
 
-	fn misbehaviour(_msg_misbehaviour: MsgSubmitMisbehaviour) -> Weight {
-		Weight::default()
+	fn misbehaviour(msg_misbehaviour: MsgSubmitMisbehaviour) -> Weight {
+		// every commit signature of both conflicting headers is verified
+		match tendermint_signatures(&msg_misbehaviour.misbehaviour) {
+			Some(signatures) => misbehaviour_mock::<T>()
+				.saturating_add(ed25519_verify::<T>(signatures.min(u32::MAX as u64) as u32)),
+			None => misbehaviour_mock::<T>(),
+		}
 	}
 
 	fn update_client(msg_update_client: MsgUpdateClient) -> Weight {
@@ -477,10 +504,10 @@ pub fn channel_client<T: Config>(
 	Err(Error::<T>::Other)
 }
 
-#[allow(dead_code)]
-pub(crate) fn deliver<T: Config + Send + Sync>(
-	msgs: &[ibc_proto::google::protobuf::Any],
-) -> Weight {
+pub(crate) fn deliver<T: Config>(msgs: &[ibc_proto::google::protobuf::Any]) -> Weight
+where
+	u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
+{
 	msgs.into_iter()
 		.filter_map(|msg| {
 			let msg: Option<MsgEnvelope> = msg.clone().try_into().ok();
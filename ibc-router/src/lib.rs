@@ -8,12 +8,14 @@ extern crate alloc;
 pub use pallet::*;
 
 use frame_support::pallet_prelude::*;
-use frame_system::pallet_prelude::BlockNumberFor;
-use frame_system::pallet_prelude::*;
+use frame_system::pallet_prelude::{BlockNumberFor, *};
 use ibc_proto::google::protobuf::Any;
+use pallet_ibc_utils::Router;
 use scale_info::prelude::vec;
 use sp_std::{fmt::Debug, vec::Vec};
 
+pub const LOG_TARGET: &str = "runtime::pallet-ibc-router";
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
@@ -31,16 +33,28 @@ pub mod pallet {
 			+ From<Event<Self>>
 			+ Debug
 			+ IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Type url routing table, e.g. `(pallet_ibc::Pallet<Runtime>,
+		/// (pallet_ics20_transfer::Pallet<Runtime>, ()))`. Each message goes to the first router
+		/// declaring its type url.
+		type Routers: Router;
 	}
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/main-docs/build/events-errors/
 	#[pallet::event]
-	pub enum Event<T: Config> {}
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A batch of messages was dispatched by the router at index `route`.
+		MessagesRouted { route: u32, messages: u32 },
+	}
 
 	// Errors inform users that something went wrong.
 	#[pallet::error]
-	pub enum Error<T> {}
+	pub enum Error<T> {
+		/// No configured router handles the message type url.
+		UnknownTypeUrl,
+	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
 	// These functions materialize as "extrinsics", which are often compared to transactions.
@@ -50,30 +64,59 @@ pub mod pallet {
 	where
 		u64: From<<T as pallet_timestamp::Config>::Moment> + From<BlockNumberFor<T>>,
 	{
+		/// Dispatches `messages` to the routers handling their type urls.
+		///
+		/// The origin must be Signed, and the sender allowed to deliver the messages by the
+		/// relayer policies of pallet-ibc.
+		///
+		/// Consecutive messages for the same router are dispatched as one batch, in order. The
+		/// first failing batch fails the call with its error, reverting every batch.
+		///
+		/// Parameters:
+		/// - `messages`: the protobuf encoded messages.
+		///
+		/// Emits `MessagesRouted` per batch.
 		#[pallet::call_index(0)]
 		#[pallet::weight(0)]
 		pub fn dispatch(origin: OriginFor<T>, messages: Vec<Any>) -> DispatchResultWithPostInfo {
-			let _ = ensure_signed(origin)?;
+			let who = ensure_signed(origin)?;
 
-			for message in messages {
-				match message.type_url.as_str() {
-					"/ibc.applications.transfer.v1.MsgTransfer" => {
-						#[cfg(feature = "ics20")]
-						{
-							<pallet_ics20::Pallet<T> as pallet_ibc_utils::Router>::dispatch(vec![
-								message,
-							])?;
-						}
-					},
-					_ => {
-						<pallet_ibc::Pallet<T> as pallet_ibc_utils::Router>::dispatch(vec![
-							message,
-						])?;
-					},
-				}
+			pallet_ibc::Pallet::<T>::ensure_relayer_policy(&who, &messages)?;
+			for (route, batch) in Self::route_messages(messages)? {
+				let count = batch.len() as u32;
+				T::Routers::dispatch_to(route, batch).map_err(|error| {
+					log::warn!(
+						target: LOG_TARGET,
+						"route {} failed to dispatch {} messages: {:?}",
+						route,
+						count,
+						error
+					);
+					error
+				})?;
+				Self::deposit_event(Event::MessagesRouted { route, messages: count });
 			}
 
 			Ok(().into())
 		}
 	}
 }
+
+impl<T: Config> Pallet<T> {
+	/// Groups consecutive messages by the route handling them, failing on the first message no
+	/// router handles.
+	pub fn route_messages(messages: Vec<Any>) -> Result<Vec<(u32, Vec<Any>)>, Error<T>> {
+		let mut batches: Vec<(u32, Vec<Any>)> = vec![];
+		for message in messages {
+			let route = T::Routers::route(&message.type_url).ok_or_else(|| {
+				log::warn!(target: LOG_TARGET, "no route for type url {}", message.type_url);
+				Error::<T>::UnknownTypeUrl
+			})?;
+			match batches.last_mut() {
+				Some((last, batch)) if *last == route => batch.push(message),
+				_ => batches.push((route, vec![message])),
+			}
+		}
+		Ok(batches)
+	}
+}
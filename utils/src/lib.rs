@@ -5,9 +5,11 @@ use sp_std::vec::Vec;
 pub mod traits;
 pub mod weights;
 pub use weights::*;
+pub mod context;
 pub mod module;
+pub mod packet;
 
-use frame_support::dispatch::DispatchResult;
+use frame_support::{dispatch::DispatchResult, ensure, pallet_prelude::DispatchError};
 use ibc_proto::google::protobuf::Any;
 
 /// A trait handling asset ID and name
@@ -21,5 +23,64 @@ pub trait AssetIdAndNameProvider<AssetId> {
 
 /// for ibc router
 pub trait Router {
+	/// Number of routes this router is made of, one per handling pallet.
+	const ROUTES: u32 = 1;
+
+	/// Whether messages with `type_url` are dispatched by this router.
+	fn handles(type_url: &str) -> bool;
+
+	/// Index of the route handling `type_url`, if any.
+	fn route(type_url: &str) -> Option<u32> {
+		Self::handles(type_url).then_some(0)
+	}
+
+	/// Dispatches `messages` to the route at index `route`.
+	fn dispatch_to(route: u32, messages: Vec<Any>) -> DispatchResult {
+		ensure!(route < Self::ROUTES, DispatchError::Other("unknown ibc route"));
+		Self::dispatch(messages)
+	}
+
 	fn dispatch(messages: Vec<Any>) -> DispatchResult;
 }
+
+impl Router for () {
+	const ROUTES: u32 = 0;
+
+	fn handles(_type_url: &str) -> bool {
+		false
+	}
+
+	fn dispatch(_messages: Vec<Any>) -> DispatchResult {
+		Err(DispatchError::Other("no ibc route"))
+	}
+}
+
+/// Routes a message to `A` when it handles the type url, otherwise to `B`.
+impl<A: Router, B: Router> Router for (A, B) {
+	const ROUTES: u32 = A::ROUTES + B::ROUTES;
+
+	fn handles(type_url: &str) -> bool {
+		A::handles(type_url) || B::handles(type_url)
+	}
+
+	fn route(type_url: &str) -> Option<u32> {
+		A::route(type_url).or_else(|| B::route(type_url).map(|route| route + A::ROUTES))
+	}
+
+	fn dispatch_to(route: u32, messages: Vec<Any>) -> DispatchResult {
+		if route < A::ROUTES {
+			A::dispatch_to(route, messages)
+		} else {
+			B::dispatch_to(route - A::ROUTES, messages)
+		}
+	}
+
+	fn dispatch(messages: Vec<Any>) -> DispatchResult {
+		for message in messages {
+			let route = Self::route(&message.type_url)
+				.ok_or(DispatchError::Other("no ibc route for type url"))?;
+			Self::dispatch_to(route, sp_std::vec![message])?;
+		}
+		Ok(())
+	}
+}
@@ -1,4 +1,8 @@
-use ibc::core::ics26_routing::context::{Module, ModuleId};
+use ibc::core::{
+	ics04_channel::{channel::Order, error::ChannelError, Version},
+	ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+	ics26_routing::context::{Module, ModuleId},
+};
 use scale_info::prelude::{format, string::String};
 use sp_std::{
 	borrow::{Borrow, ToOwned},
@@ -52,3 +56,178 @@ impl ibc::core::ics26_routing::context::Router for Router {
 		self.0.get(module_id.borrow()).is_some()
 	}
 }
+
+/// Callbacks of the application bound to a port when one of its channels is upgraded, called
+/// in addition to the `Module` callbacks of the application.
+pub trait ChannelUpgradeModule {
+	/// Returns true if the application bound to `port_id` supports upgrades of its channels.
+	fn handles(port_id: &PortId) -> bool;
+
+	/// Validate the upgrade proposed on this chain and return the version to upgrade to.
+	fn on_chan_upgrade_init(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		version: &Version,
+	) -> Result<Version, ChannelError>;
+
+	/// Validate the upgrade proposed by the counterparty and return the version to upgrade to.
+	fn on_chan_upgrade_try(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		counterparty_version: &Version,
+	) -> Result<Version, ChannelError>;
+
+	/// Validate the version the counterparty agreed to upgrade to.
+	fn on_chan_upgrade_ack(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), ChannelError>;
+
+	/// The channel switched to the upgraded fields.
+	fn on_chan_upgrade_open(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		version: &Version,
+	);
+
+	/// The upgrade was cancelled or timed out, the channel keeps its previous fields.
+	fn on_chan_upgrade_restore(_port_id: &PortId, _channel_id: &ChannelId) {}
+}
+
+/// No application supports channel upgrades.
+impl ChannelUpgradeModule for () {
+	fn handles(_port_id: &PortId) -> bool {
+		false
+	}
+
+	fn on_chan_upgrade_init(
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_version: &Version,
+	) -> Result<Version, ChannelError> {
+		Err(upgrades_not_supported(port_id))
+	}
+
+	fn on_chan_upgrade_try(
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_counterparty_version: &Version,
+	) -> Result<Version, ChannelError> {
+		Err(upgrades_not_supported(port_id))
+	}
+
+	fn on_chan_upgrade_ack(
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty_version: &Version,
+	) -> Result<(), ChannelError> {
+		Err(upgrades_not_supported(port_id))
+	}
+
+	fn on_chan_upgrade_open(
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_version: &Version,
+	) {
+	}
+}
+
+/// Routes the callbacks to `A` for the ports it handles, and to `B` otherwise.
+impl<A: ChannelUpgradeModule, B: ChannelUpgradeModule> ChannelUpgradeModule for (A, B) {
+	fn handles(port_id: &PortId) -> bool {
+		A::handles(port_id) || B::handles(port_id)
+	}
+
+	fn on_chan_upgrade_init(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		version: &Version,
+	) -> Result<Version, ChannelError> {
+		if A::handles(port_id) {
+			A::on_chan_upgrade_init(port_id, channel_id, order, connection_hops, version)
+		} else {
+			B::on_chan_upgrade_init(port_id, channel_id, order, connection_hops, version)
+		}
+	}
+
+	fn on_chan_upgrade_try(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		counterparty_version: &Version,
+	) -> Result<Version, ChannelError> {
+		if A::handles(port_id) {
+			A::on_chan_upgrade_try(
+				port_id,
+				channel_id,
+				order,
+				connection_hops,
+				counterparty_version,
+			)
+		} else {
+			B::on_chan_upgrade_try(
+				port_id,
+				channel_id,
+				order,
+				connection_hops,
+				counterparty_version,
+			)
+		}
+	}
+
+	fn on_chan_upgrade_ack(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), ChannelError> {
+		if A::handles(port_id) {
+			A::on_chan_upgrade_ack(port_id, channel_id, counterparty_version)
+		} else {
+			B::on_chan_upgrade_ack(port_id, channel_id, counterparty_version)
+		}
+	}
+
+	fn on_chan_upgrade_open(
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		version: &Version,
+	) {
+		if A::handles(port_id) {
+			A::on_chan_upgrade_open(port_id, channel_id, order, connection_hops, version)
+		} else {
+			B::on_chan_upgrade_open(port_id, channel_id, order, connection_hops, version)
+		}
+	}
+
+	fn on_chan_upgrade_restore(port_id: &PortId, channel_id: &ChannelId) {
+		if A::handles(port_id) {
+			A::on_chan_upgrade_restore(port_id, channel_id)
+		} else {
+			B::on_chan_upgrade_restore(port_id, channel_id)
+		}
+	}
+}
+
+fn upgrades_not_supported(port_id: &PortId) -> ChannelError {
+	ChannelError::AppModule {
+		description: format!("channel upgrades are not supported on port {}", port_id),
+	}
+}
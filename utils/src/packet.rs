@@ -0,0 +1,26 @@
+//! Packets sent by the applications on their own channels.
+use ibc::{
+	core::ics04_channel::{
+		context::{ChannelKeeper, ChannelReader},
+		error::PacketError,
+		handler::send_packet::send_packet as process_send_packet,
+		packet::Packet,
+	},
+	events::IbcEvent,
+	handler::HandlerOutput,
+};
+use sp_std::vec::Vec;
+
+/// Send `packet` through the ICS04 send handler of ibc-rs and store its result.
+///
+/// The handler checks the channel is not closed, the counterparty, the client of the connection
+/// and its timeouts, and the sequence of the packet. The packet commitment and the next send
+/// sequence it computes are then stored through `ctx`, and the `SendPacket` events returned.
+pub fn send_packet<Ctx>(ctx: &mut Ctx, packet: Packet) -> Result<Vec<IbcEvent>, PacketError>
+where
+	Ctx: ChannelReader + ChannelKeeper,
+{
+	let HandlerOutput { result, events, .. } = process_send_packet(ctx, packet)?;
+	ctx.store_packet_result(result)?;
+	Ok(events)
+}